@@ -1,6 +1,10 @@
 //! Command line argument parsing for the fan curve application
 
+use crate::fan_monitor::{ReadingSource, SensorOptions};
+use crate::temperature::TempUnit;
 use clap::{Parser, Subcommand};
+use std::net::SocketAddr;
+use std::path::PathBuf;
 
 /// Fan Curve Control Application
 ///
@@ -18,14 +22,68 @@ pub struct Args {
     #[arg(long)]
     pub gui: bool,
 
+    /// Per-target log levels, e.g. "info,zbus=off" (defaults to $FAN_APP_LOG)
+    #[arg(long, global = true, value_name = "FILTER")]
+    pub log_filter: Option<String>,
+
+    /// Read the CPU temperature from /sys/class/thermal/thermal_zone<N>
+    #[arg(long, global = true, value_name = "N")]
+    pub thermal_zone: Option<u32>,
+
+    /// Degrees to subtract from AMD Tctl readings when no Tdie sensor exists
+    #[arg(long, global = true, value_name = "DEGREES", default_value_t = 0.0)]
+    pub tctl_offset: f32,
+
+    /// Where local temperatures and fan speeds are read from
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "SOURCE",
+        default_value = "sysfs"
+    )]
+    pub source: ReadingSource,
+
+    /// Unit to show temperatures in; curves are always stored in Celsius
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "UNIT",
+        default_value = "c"
+    )]
+    pub units: TempUnit,
+
+    /// Show a desktop notification when the CPU passes the config's `warn_temp` and recovers
+    #[arg(long, global = true)]
+    pub notifications: bool,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
 
+impl Args {
+    /// Sensor overrides given on the command line
+    pub fn sensor_options(&self) -> SensorOptions {
+        SensorOptions {
+            thermal_zone: self.thermal_zone,
+            tctl_offset: self.tctl_offset,
+            source: self.source,
+        }
+    }
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
     /// Run the daemon
-    Daemon,
+    Daemon {
+        /// Serve Prometheus metrics at this address (e.g. 127.0.0.1:9184)
+        #[arg(long, value_name = "ADDR")]
+        metrics_addr: Option<SocketAddr>,
+        /// Return the fans to automatic control when the daemon stops
+        #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+        restore_auto_on_exit: bool,
+    },
     /// Fan curve management
     FanCurve {
         #[command(subcommand)]
@@ -36,7 +94,14 @@ pub enum Commands {
 #[derive(Subcommand)]
 pub enum FanCurveCommands {
     /// List available fan curves
-    List,
+    List {
+        /// Only list curves with this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Warn about curves that may not cool a hot CPU enough
+        #[arg(long)]
+        lint: bool,
+    },
     /// Get current fan curve
     Get,
     /// Set fan curve by name
@@ -44,6 +109,97 @@ pub enum FanCurveCommands {
         /// Name of the fan curve to set
         name: String,
     },
+    /// Apply a fan curve once at the current temperature
+    Apply {
+        /// Name of the fan curve to apply
+        name: String,
+        /// Log what would be written instead of changing fan settings
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Apply a fan curve once and exit, for cron jobs and systemd timers
+    ///
+    /// The fans return to automatic control if nothing keeps writing a duty,
+    /// so schedule this regularly.
+    Oneshot {
+        /// Name of the fan curve to apply
+        #[arg(long)]
+        name: String,
+    },
+    /// Print the interpolated duty of a fan curve over a temperature range
+    Preview {
+        /// Name of the fan curve to preview
+        name: String,
+        /// First temperature in Celsius
+        #[arg(long, allow_negative_numbers = true, default_value_t = 20)]
+        from: i16,
+        /// Last temperature in Celsius
+        #[arg(long, allow_negative_numbers = true, default_value_t = 100)]
+        to: i16,
+        /// Degrees between rows
+        #[arg(long, default_value_t = 5)]
+        step: i16,
+    },
+    /// Print the duty a fan curve gives at one temperature, without touching the fans
+    Simulate {
+        /// Name of the fan curve
+        #[arg(long)]
+        name: String,
+        /// Temperature in Celsius
+        #[arg(long, allow_negative_numbers = true)]
+        temp: f32,
+    },
+    /// Shift or scale a whole fan curve and save it
+    Adjust {
+        /// Name of the fan curve to adjust
+        #[arg(long)]
+        name: String,
+        /// Percentage points to add to every duty (e.g. -5)
+        #[arg(long, allow_negative_numbers = true, default_value_t = 0)]
+        duty_offset: i32,
+        /// Factor to multiply every duty by (e.g. 0.9)
+        #[arg(long, default_value_t = 1.0)]
+        duty_scale: f32,
+        /// Degrees to add to every temperature (e.g. +2)
+        #[arg(long, allow_negative_numbers = true, default_value_t = 0)]
+        temp_offset: i16,
+    },
+    /// Shift or scale every unlocked fan curve and save them
+    AdjustAll {
+        /// Percentage points to add to every duty (e.g. 10)
+        #[arg(long, allow_negative_numbers = true, default_value_t = 0)]
+        duty_offset: i32,
+        /// Factor to multiply every duty by (e.g. 1.1)
+        #[arg(long, default_value_t = 1.0)]
+        duty_scale: f32,
+    },
+    /// Restore a built-in preset's points, undoing edits to it
+    Reset {
+        /// Name of the preset curve, e.g. Standard
+        #[arg(long)]
+        name: String,
+    },
+    /// Save a new curve running the louder of two curves at every temperature
+    Merge {
+        /// First curve (a saved curve or preset name)
+        #[arg(long)]
+        a: String,
+        /// Second curve (a saved curve or preset name)
+        #[arg(long)]
+        b: String,
+        /// Name of the merged curve
+        #[arg(long)]
+        out: String,
+    },
+    /// Run the fans at a fixed duty for a while, then return to the curve
+    Boost {
+        /// Fan duty percentage (0-100)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=100), default_value_t = 100)]
+        percent: u8,
+        /// How long to boost for
+        #[arg(long, default_value_t = 60)]
+        seconds: u32,
+    },
     /// Set default fan curve
     SetDefault {
         /// Name of the fan curve to set as default
@@ -51,7 +207,8 @@ pub enum FanCurveCommands {
     },
     /// Add a new fan curve point
     AddPoint {
-        /// Temperature in Celsius
+        /// Temperature in Celsius (may be negative)
+        #[arg(allow_negative_numbers = true)]
         temp: i16,
         /// Fan duty percentage (0-100)
         duty: u16,
@@ -62,10 +219,16 @@ pub enum FanCurveCommands {
     Save,
     /// Load configuration from file
     Load,
-    /// Test fan curve with monitoring
+    /// Test a fan curve with monitoring
     Test {
+        /// Name of the fan curve to test
+        name: String,
         /// Duration of test in seconds
+        #[arg(long, default_value_t = 60)]
         duration: u64,
+        /// Time between samples in milliseconds (defaults to the config file setting)
+        #[arg(long)]
+        interval_ms: Option<u64>,
     },
     /// Test D-Bus integration with system76-power daemon
     TestDbus,
@@ -73,7 +236,72 @@ pub enum FanCurveCommands {
     TestMonitor {
         /// Duration of monitoring test in seconds
         duration: u64,
+        /// Time between samples in milliseconds (defaults to the config file setting)
+        #[arg(long)]
+        interval_ms: Option<u64>,
     },
     /// Test GUI integration with daemon
     TestGui,
+    /// Show detected CPU sensor and fans
+    Info,
+    /// Check vendor detection, temperature, fans, PWM access, the daemon and the config
+    ///
+    /// Prints a pass/fail summary to paste into bug reports; works without the daemon.
+    Doctor,
+    /// Show whether the fans are under automatic, manual, full-speed or mixed control
+    Mode,
+    /// List every hwmon temperature sensor with its current reading
+    Temps,
+    /// Check the fan duty reported by system76-power against what the active curve calls for
+    ///
+    /// Exits with an error if they differ by more than the tolerance.
+    Verify {
+        /// Allowed difference in percentage points
+        #[arg(long, default_value_t = 5.0)]
+        tolerance: f32,
+    },
+    /// Interactive terminal view of temperature and fans; arrow keys switch curves
+    #[cfg(feature = "tui")]
+    Tui,
+    /// Find the lowest duty each fan keeps spinning at, to choose `spin_up_duty`
+    ///
+    /// The fans are ramped down from full speed and returned to automatic control afterwards.
+    /// Refuses to run while the daemon is controlling the fans.
+    Calibrate {
+        /// Only calibrate this fan number
+        #[arg(long)]
+        fan: Option<u8>,
+    },
+    /// Print the version and build metadata
+    Version {
+        /// Ask the running daemon instead, to see which build is actually serving
+        #[arg(long)]
+        daemon: bool,
+    },
+    /// Save the current readings, active curve, hardware, PWM files and config as JSON
+    Snapshot {
+        /// File to write; printed to stdout if not given
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+    },
+    /// Print the daemon's whole configuration as JSON
+    Dump,
+    /// Replace the daemon's configuration with one printed by `dump`
+    Restore {
+        /// JSON file written by `fan-curve dump`
+        path: PathBuf,
+    },
+    /// Set all fans to a fixed duty
+    SetDuty {
+        /// Fan duty percentage (0-100, 0 means automatic)
+        #[arg(
+            value_parser = clap::value_parser!(u8).range(0..=100),
+            required_unless_present = "auto",
+            conflicts_with = "auto"
+        )]
+        percent: Option<u8>,
+        /// Return the fans to automatic control
+        #[arg(long)]
+        auto: bool,
+    },
 }
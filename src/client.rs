@@ -2,16 +2,31 @@
 
 use crate::{
     args::{Args, Commands, FanCurveCommands},
-    errors::{FanCurveError, Result},
-    fan_monitor,
+    cpu_temp::CpuTempDetector,
+    duty::Duty,
+    errors::{DaemonError, FanCurveError, Result},
+    fan::{FanCurve, FanCurveConfig, FanCurveDto},
+    fan_detector::FanDetector,
+    fan_monitor::{self, FanDataDto, FanDataPoint, FanMonitor, SensorOptions},
+    hardware::HardwareInfo,
+    system76_power_client::System76PowerClient,
+    temperature::TempUnit,
+    BuildInfo, DBUS_INTERFACE_NAME, DBUS_OBJECT_PATH, DBUS_SERVICE_NAME,
 };
-use log::{debug, error, info};
-use zbus::Connection;
+use futures_util::stream::{Stream, StreamExt};
+use log::{debug, error, info, warn};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+use zbus::{Connection, MatchRule, MessageStream};
+use zvariant::OwnedValue;
 
 /// Client for communicating with the fan curve daemon
 pub struct FanCurveClient {
-    #[allow(dead_code)]
     connection: Connection,
+    sensor_options: SensorOptions,
+    temp_unit: TempUnit,
+    notifications: bool,
 }
 
 impl FanCurveClient {
@@ -19,13 +34,43 @@ impl FanCurveClient {
     pub async fn new() -> Result<Self> {
         let connection = Connection::system().await.map_err(FanCurveError::DBus)?;
 
-        Ok(Self { connection })
+        Ok(Self {
+            connection,
+            sensor_options: SensorOptions::default(),
+            temp_unit: TempUnit::default(),
+            notifications: false,
+        })
+    }
+
+    /// Sensor overrides for locally run monitors
+    pub fn set_sensor_options(&mut self, options: SensorOptions) {
+        self.sensor_options = options;
+    }
+
+    /// Unit temperatures are printed in
+    pub fn set_temp_unit(&mut self, unit: TempUnit) {
+        self.temp_unit = unit;
+    }
+
+    /// Show desktop notifications from locally run monitors when the CPU overheats
+    pub fn set_notifications(&mut self, enabled: bool) {
+        self.notifications = enabled;
+    }
+
+    /// Warning temperature for local monitors, if notifications are on
+    fn overheat_warning(&self, config: &FanCurveConfig) -> Option<f32> {
+        self.notifications.then_some(config.warn_temp)
+    }
+
+    /// Fan data the daemon publishes every poll interval, see [`fan_data_updates`]
+    pub async fn subscribe_fan_data(&self) -> Result<impl Stream<Item = FanDataPoint>> {
+        fan_data_updates(&self.connection).await
     }
 
     /// Handle CLI commands
     pub async fn handle_args(&self, args: Args) -> Result<()> {
         match args.command {
-            Some(Commands::Daemon) => {
+            Some(Commands::Daemon { .. }) => {
                 error!("Daemon command should not be handled by client");
                 Err(FanCurveError::Unknown(
                     "Invalid command for client".to_string(),
@@ -42,32 +87,102 @@ impl FanCurveClient {
     /// Handle fan curve commands
     async fn handle_fan_curve_command(&self, command: FanCurveCommands) -> Result<()> {
         match command {
-            FanCurveCommands::List => self.list_fan_curves().await,
+            FanCurveCommands::List { tag, lint } => self.list_fan_curves(tag.as_deref(), lint).await,
             FanCurveCommands::Get => self.get_current_fan_curve().await,
             FanCurveCommands::Set { name } => self.set_fan_curve_by_name(&name).await,
+            FanCurveCommands::Apply { name, dry_run } => self.apply_fan_curve(&name, dry_run).await,
+            FanCurveCommands::Oneshot { name } => self.oneshot(&name).await,
+            FanCurveCommands::Preview {
+                name,
+                from,
+                to,
+                step,
+            } => self.preview_fan_curve(&name, from, to, step).await,
+            FanCurveCommands::Simulate { name, temp } => self.simulate_duty(&name, temp).await,
+            FanCurveCommands::Adjust {
+                name,
+                duty_offset,
+                duty_scale,
+                temp_offset,
+            } => {
+                self.adjust_fan_curve(&name, duty_offset, duty_scale, temp_offset)
+                    .await
+            }
+            FanCurveCommands::AdjustAll {
+                duty_offset,
+                duty_scale,
+            } => self.adjust_all_fan_curves(duty_offset, duty_scale).await,
+            FanCurveCommands::Reset { name } => self.reset_fan_curve(&name).await,
+            FanCurveCommands::Merge { a, b, out } => self.merge_fan_curves(&a, &b, &out).await,
+            FanCurveCommands::Boost { percent, seconds } => self.boost(percent, seconds).await,
             FanCurveCommands::SetDefault { name } => self.set_default_fan_curve(&name).await,
             FanCurveCommands::AddPoint { temp, duty } => self.add_fan_curve_point(temp, duty).await,
             FanCurveCommands::RemovePoint => self.remove_fan_curve_point().await,
             FanCurveCommands::Save => self.save_config().await,
             FanCurveCommands::Load => self.load_config().await,
-            FanCurveCommands::Test { duration } => self.test_fan_curve(duration).await,
+            FanCurveCommands::Test {
+                name,
+                duration,
+                interval_ms,
+            } => self.test_fan_curve(&name, duration, interval_ms).await,
             FanCurveCommands::TestDbus => self.test_dbus_integration().await,
-            FanCurveCommands::TestMonitor { duration } => self.test_fan_monitor_integration(duration).await,
+            FanCurveCommands::TestMonitor {
+                duration,
+                interval_ms,
+            } => self.test_fan_monitor_integration(duration, interval_ms).await,
             FanCurveCommands::TestGui => self.test_gui_integration().await,
+            FanCurveCommands::Info => self.show_hardware_info().await,
+            // Run by main without a client, so an unreachable DBus is reported instead of fatal
+            FanCurveCommands::Doctor => {
+                error!("Doctor command should not be handled by client");
+                Err(FanCurveError::Unknown(
+                    "Invalid command for client".to_string(),
+                ))
+            }
+            FanCurveCommands::Mode => self.show_control_mode().await,
+            FanCurveCommands::Temps => self.show_temperatures().await,
+            FanCurveCommands::Verify { tolerance } => self.verify(tolerance).await,
+            #[cfg(feature = "tui")]
+            FanCurveCommands::Tui => self.run_tui().await,
+            FanCurveCommands::Calibrate { fan } => self.calibrate(fan).await,
+            FanCurveCommands::Version { daemon } => self.show_version(daemon).await,
+            FanCurveCommands::Snapshot { out } => self.snapshot(out.as_deref()).await,
+            FanCurveCommands::Dump => self.dump_config().await,
+            FanCurveCommands::Restore { path } => self.restore_config(&path).await,
+            FanCurveCommands::SetDuty { percent, auto } => {
+                self.set_duty(if auto { None } else { percent }).await
+            }
         }
     }
 
     /// List all fan curves
-    async fn list_fan_curves(&self) -> Result<()> {
-        debug!("Listing fan curves");
+    async fn list_fan_curves(&self, tag: Option<&str>, lint: bool) -> Result<()> {
+        debug!("Listing fan curves (tag: {:?})", tag);
 
-        // For now, we'll use a simple approach since we don't have the zbus proxy yet
-        // In a real implementation, we'd use the generated proxy
-        println!("Available fan curves:");
-        println!("  - Standard");
-        println!("  - Threadripper 2");
-        println!("  - HEDT");
-        println!("  - Xeon");
+        let config = FanCurveConfig::load_from_file(&FanCurveConfig::get_config_path())
+            .unwrap_or_default();
+        let curves: Vec<&FanCurve> = match tag {
+            Some(tag) => config.curves.with_tag(tag).collect(),
+            None => config.curves.iter().collect(),
+        };
+
+        match tag {
+            Some(tag) if curves.is_empty() => println!("No fan curves tagged '{}'", tag),
+            Some(tag) => println!("Fan curves tagged '{}':", tag),
+            None => println!("Available fan curves:"),
+        }
+        for curve in curves {
+            if curve.tags().is_empty() {
+                println!("  - {}", curve.name());
+            } else {
+                println!("  - {} [{}]", curve.name(), curve.tags().join(", "));
+            }
+            if lint {
+                for warning in curve.safety_lint() {
+                    println!("      warning: {}", warning);
+                }
+            }
+        }
 
         Ok(())
     }
@@ -92,6 +207,286 @@ impl FanCurveClient {
         Ok(())
     }
 
+    /// Apply a stored fan curve once at the current temperature
+    async fn apply_fan_curve(&self, name: &str, dry_run: bool) -> Result<()> {
+        self.apply_once(name, dry_run, false).await
+    }
+
+    /// Apply a stored fan curve once, failing if no temperature sensor or fans were found
+    async fn oneshot(&self, name: &str) -> Result<()> {
+        self.apply_once(name, false, true).await
+    }
+
+    /// Run a single fan curve iteration; `require_fans` rejects machines without detected fans
+    async fn apply_once(&self, name: &str, dry_run: bool, require_fans: bool) -> Result<()> {
+        debug!("Applying fan curve {} (dry run: {})", name, dry_run);
+
+        let config = FanCurveConfig::load_from_file(&FanCurveConfig::get_config_path())
+            .unwrap_or_default();
+        let curve = config.curve(name)?.clone();
+
+        let mut monitor = FanMonitor::new();
+        monitor.set_sensor_options(self.sensor_options);
+        monitor.configure(&config);
+        monitor.set_overheat_warning(self.overheat_warning(&config));
+        monitor.set_dry_run(dry_run);
+        monitor.initialize()?;
+        if let Err(e) = monitor.initialize_system76_power().await {
+            warn!("System76 Power daemon unavailable, using direct PWM control: {}", e);
+        }
+        monitor.set_fan_curve(curve);
+
+        if require_fans && monitor.fan_detector().fan_count() == 0 {
+            return Err(FanCurveError::Config("No controllable fans found".to_string()));
+        }
+
+        let temperature = monitor.read_temperature()?;
+        monitor.apply_fan_curve(temperature).await?;
+
+        if dry_run {
+            println!("Dry run: fan curve '{}' at {}, no fan settings changed", name, self.temp_unit.format(temperature, 1));
+        } else {
+            println!("Applied fan curve '{}' at {}", name, self.temp_unit.format(temperature, 1));
+        }
+
+        Ok(())
+    }
+
+    /// Run the terminal UI, starting on the active curve
+    #[cfg(feature = "tui")]
+    async fn run_tui(&self) -> Result<()> {
+        let config = FanCurveConfig::load_from_file(&FanCurveConfig::get_config_path())
+            .unwrap_or_default();
+
+        let mut monitor = FanMonitor::new();
+        monitor.set_sensor_options(self.sensor_options);
+        monitor.configure(&config);
+        monitor.set_overheat_warning(self.overheat_warning(&config));
+        monitor.initialize()?;
+        if let Err(e) = monitor.initialize_system76_power().await {
+            warn!("System76 Power daemon unavailable, using direct PWM control: {}", e);
+        }
+
+        crate::tui::run(
+            monitor,
+            config.curves.to_vec(),
+            config.initial_curve_index(),
+            self.temp_unit,
+        )
+        .await
+    }
+
+    /// Print a stored curve's duty every `step` degrees, with a bar per row
+    async fn preview_fan_curve(&self, name: &str, from: i16, to: i16, step: i16) -> Result<()> {
+        if step <= 0 {
+            return Err(FanCurveError::Config(format!(
+                "Step must be at least 1°C, got {}",
+                step
+            )));
+        }
+
+        let config = FanCurveConfig::load_from_file(&FanCurveConfig::get_config_path())
+            .unwrap_or_default();
+        let curve = config.curve(name)?;
+
+        println!("Fan curve '{}':", name);
+        for (temp, duty) in curve.sample(from, to, step) {
+            let duty = Duty::from_ten_thousandths(duty);
+            let bar = "#".repeat(usize::from(duty.as_whole_percent() / 2));
+            println!("  {:>6} {:>6} {}", self.temp_unit.format(f32::from(temp), 0), duty.to_string(), bar);
+        }
+
+        Ok(())
+    }
+
+    /// Print the duty a curve gives at `temp` °C, asking the daemon and falling back to the config file
+    /// only when the daemon can't be reached
+    async fn simulate_duty(&self, name: &str, temp: f32) -> Result<()> {
+        let duty = match self.simulate_duty_via_daemon(name, temp).await {
+            Ok(duty) => duty,
+            // The daemon's config is the one in use, so don't look further
+            Err(e) if e.dbus_error_name() == Some(DaemonError::CURVE_NOT_FOUND) => {
+                return Err(FanCurveError::FanCurveNotFound { name: name.to_string() });
+            }
+            Err(e) if e.is_daemon_reply() => return Err(e),
+            Err(e) => {
+                warn!("Failed to simulate via D-Bus ({}), using the local config", e);
+                let config = FanCurveConfig::load_from_file(&FanCurveConfig::get_config_path())
+                    .unwrap_or_default();
+                config.curve(name)?.calculate_duty_f32(temp)
+            }
+        };
+
+        println!("Fan curve '{}' at {}: {:.1}%", name, self.temp_unit.format(temp, 1), duty);
+        Ok(())
+    }
+
+    /// Print every temperature sensor, asking the daemon and falling back to reading hwmon
+    async fn show_temperatures(&self) -> Result<()> {
+        let temperatures = match self.temperatures_via_daemon().await {
+            Ok(temperatures) => temperatures,
+            Err(e) => {
+                warn!("Failed to read temperatures via D-Bus ({}), reading hwmon", e);
+                CpuTempDetector::new().read_all_temperatures()?
+            }
+        };
+
+        if temperatures.is_empty() {
+            println!("No temperature sensors found");
+        }
+        for (label, temp) in temperatures {
+            println!("  {:<32} {:>8}", label, self.temp_unit.format(temp, 1));
+        }
+        Ok(())
+    }
+
+    async fn temperatures_via_daemon(&self) -> Result<Vec<(String, f32)>> {
+        let response = self
+            .connection
+            .call_method(
+                Some(DBUS_SERVICE_NAME),
+                DBUS_OBJECT_PATH,
+                Some(DBUS_INTERFACE_NAME),
+                "GetAllTemperatures",
+                &(),
+            )
+            .await?;
+        Ok(response.body::<Vec<(String, f32)>>()?)
+    }
+
+    async fn simulate_duty_via_daemon(&self, name: &str, temp: f32) -> Result<f32> {
+        let response = self
+            .connection
+            .call_method(
+                Some(DBUS_SERVICE_NAME),
+                DBUS_OBJECT_PATH,
+                Some(DBUS_INTERFACE_NAME),
+                "SimulateDuty",
+                &(name, temp),
+            )
+            .await?;
+        Ok(response.body::<f32>()?)
+    }
+
+    /// Shift and scale a stored curve, then save it back to the config file
+    /// Scaling is applied before the duty offset
+    async fn adjust_fan_curve(
+        &self,
+        name: &str,
+        duty_offset: i32,
+        duty_scale: f32,
+        temp_offset: i16,
+    ) -> Result<()> {
+        debug!(
+            "Adjusting fan curve {}: duty {:+}%, scale {}, temp {:+}°C",
+            name, duty_offset, duty_scale, temp_offset
+        );
+
+        Self::edit_config_file(|config| {
+            let index = config
+                .curves
+                .position(name)
+                .ok_or_else(|| FanCurveError::FanCurveNotFound {
+                    name: name.to_string(),
+                })?;
+
+            let mut curve = config.curves[index].clone();
+            curve.ensure_unlocked()?;
+            curve.scale_duty(duty_scale);
+            curve.offset_duty(duty_offset.saturating_mul(100));
+            curve.offset_temp(temp_offset);
+            curve.validate()?;
+
+            println!("Adjusted fan curve '{}':", name);
+            for point in curve.iter() {
+                println!("  {} -> {}", self.temp_unit.format(f32::from(point.temp), 0), Duty::from_ten_thousandths(point.duty));
+            }
+            config.curves.replace(curve)?;
+            Ok(())
+        })
+    }
+
+    /// Load the config file, change it with `edit` and save it back
+    /// A missing file starts from the defaults; one that fails to load is left untouched
+    fn edit_config_file<T>(edit: impl FnOnce(&mut FanCurveConfig) -> Result<T>) -> Result<T> {
+        let config_path = FanCurveConfig::get_config_path();
+        let mut config = FanCurveConfig::load_or_default(&config_path)
+            .map_err(|e| e.context(format!("Failed to load {}", config_path.display())))?;
+        let result = edit(&mut config)?;
+
+        if let Some(parent) = config_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        config.save_to_file(&config_path)?;
+        Ok(result)
+    }
+
+    /// Shift and scale every unlocked curve, then save them to the config file
+    /// Scaling is applied before the duty offset, as with `adjust`
+    async fn adjust_all_fan_curves(&self, duty_offset: i32, duty_scale: f32) -> Result<()> {
+        debug!(
+            "Adjusting all fan curves: duty {:+}%, scale {}",
+            duty_offset, duty_scale
+        );
+
+        Self::edit_config_file(|config| {
+            let adjusted = config.map_all(|curve| {
+                curve.scale_duty(duty_scale);
+                curve.offset_duty(duty_offset.saturating_mul(100));
+            })?;
+
+            println!("Adjusted {} fan curves: {}", adjusted.len(), adjusted.join(", "));
+            let skipped: Vec<&str> = config.curves.iter().filter(|c| c.is_locked()).map(|c| c.name()).collect();
+            if !skipped.is_empty() {
+                println!("Skipped locked curves: {}", skipped.join(", "));
+            }
+            Ok(())
+        })
+    }
+
+    /// Reset a preset-named curve to the built-in points and save the config file
+    async fn reset_fan_curve(&self, name: &str) -> Result<()> {
+        debug!("Resetting fan curve {} to its preset", name);
+
+        Self::edit_config_file(|config| {
+            config.reset_to_preset(name)?;
+
+            println!("Reset fan curve '{}' to the built-in preset:", name);
+            for point in config.curve(name)?.iter() {
+                println!("  {} -> {}", self.temp_unit.format(f32::from(point.temp), 0), Duty::from_ten_thousandths(point.duty));
+            }
+            Ok(())
+        })
+    }
+
+    /// Merge two curves into a new one taking the higher duty, then save it to the config file
+    async fn merge_fan_curves(&self, a: &str, b: &str, out: &str) -> Result<()> {
+        debug!("Merging fan curves {} and {} into {}", a, b, out);
+
+        Self::edit_config_file(|config| {
+            let lookup = |name: &str| {
+                config
+                    .curve(name)
+                    .cloned()
+                    .or_else(|e| FanCurve::preset_by_name(name).ok_or(e))
+            };
+            let merged = lookup(a)?.max_merge(&lookup(b)?, out.to_string());
+            merged.validate()?;
+
+            if config.curves.contains(out) {
+                config.curves.replace(merged.clone())?;
+            } else {
+                config.curves.insert(merged.clone())?;
+            }
+
+            println!("Merged '{}' and '{}' into '{}':", a, b, out);
+            for point in &merged {
+                println!("  {} -> {}", self.temp_unit.format(f32::from(point.temp), 0), Duty::from_ten_thousandths(point.duty));
+            }
+            Ok(())
+        })
+    }
+
     /// Set default fan curve
     async fn set_default_fan_curve(&self, name: &str) -> Result<()> {
         debug!("Setting default fan curve to: {}", name);
@@ -142,14 +537,376 @@ impl FanCurveClient {
         Ok(())
     }
 
+    /// Print this binary's build metadata, or the running daemon's with `daemon`
+    async fn show_version(&self, daemon: bool) -> Result<()> {
+        let info = if daemon {
+            daemon_build_info(&self.connection).await?
+        } else {
+            BuildInfo::current()
+        };
+        println!("Version: {}", info.version);
+        println!("Git hash: {}", info.git_hash);
+        println!("Build time: {}", info.build_time);
+        Ok(())
+    }
+
+    /// Print the daemon's configuration as JSON
+    async fn dump_config(&self) -> Result<()> {
+        debug!("Dumping configuration");
+
+        let response = self
+            .connection
+            .call_method(
+                Some(DBUS_SERVICE_NAME),
+                DBUS_OBJECT_PATH,
+                Some(DBUS_INTERFACE_NAME),
+                "ExportConfig",
+                &(),
+            )
+            .await?;
+        println!("{}", response.body::<String>()?);
+        Ok(())
+    }
+
+    /// Send a configuration written by `dump` to the daemon
+    async fn restore_config(&self, path: &Path) -> Result<()> {
+        debug!("Restoring configuration from {}", path.display());
+
+        let json = std::fs::read_to_string(path)?;
+        // Check locally first for a clearer error than the daemon's
+        FanCurveConfig::from_json(&json)?.validate()?;
+
+        self.connection
+            .call_method(
+                Some(DBUS_SERVICE_NAME),
+                DBUS_OBJECT_PATH,
+                Some(DBUS_INTERFACE_NAME),
+                "ImportConfig",
+                &(json.as_str(),),
+            )
+            .await?;
+        println!("Configuration restored from {}", path.display());
+        Ok(())
+    }
+
+    /// Set all fans to a fixed duty percentage, or back to automatic control with None
+    async fn set_duty(&self, percent: Option<u8>) -> Result<()> {
+        let percent = percent.filter(|p| *p > 0);
+        let pwm_value = match percent {
+            Some(p) => fan_monitor::percent_to_pwm(p)?,
+            None => 0,
+        };
+        debug!("Setting fan duty: {:?}% -> PWM {}", percent, pwm_value);
+
+        if let Err(e) = self.set_duty_via_daemon(pwm_value).await {
+            warn!("Failed to set fan duty via D-Bus ({}), falling back to direct PWM", e);
+            let mut detector = FanDetector::new();
+            detector.initialize()?;
+            detector.set_duty(percent.map(|_| pwm_value))?;
+        }
+
+        match percent {
+            Some(p) => println!("Fan duty set to {}% (PWM: {})", p, pwm_value),
+            None => println!("Fans returned to automatic control (PWM: {})", pwm_value),
+        }
+
+        Ok(())
+    }
+
+    /// Send a PWM duty to the system76-power daemon
+    async fn set_duty_via_daemon(&self, pwm_value: u8) -> Result<()> {
+        let client = System76PowerClient::new().await?;
+        client.set_fan_duty(pwm_value).await
+    }
+
+    /// Print the hardware detected by the daemon, or by a local scan if it isn't running
+    async fn show_hardware_info(&self) -> Result<()> {
+        debug!("Getting hardware info");
+
+        let info = match self.get_hardware_info_via_daemon().await {
+            Ok(info) => info,
+            Err(e) => {
+                warn!("Failed to get hardware info via D-Bus ({}), scanning locally", e);
+                tokio::task::spawn_blocking(HardwareInfo::detect)
+                    .await
+                    .map_err(|e| FanCurveError::Task(e).context("Hardware scan failed"))?
+            }
+        };
+
+        println!("{}", info);
+        Ok(())
+    }
+
+    /// Find and print the lowest duty each fan (or just `fan`) still spins at
+    async fn calibrate(&self, fan: Option<u8>) -> Result<()> {
+        if self.daemon_is_running().await? {
+            return Err(FanCurveError::Config(
+                "The fan curve daemon is controlling the fans; stop it before calibrating".to_string(),
+            ));
+        }
+
+        let results = tokio::task::spawn_blocking(move || -> Result<Vec<(u8, String, Result<u8>)>> {
+            let mut detector = FanDetector::new();
+            detector.initialize()?;
+            let fans: Vec<_> = detector
+                .get_fans()
+                .iter()
+                .filter(|f| fan.map_or(true, |n| n == f.fan_number))
+                .map(|f| (f.fan_number, f.fan_label.clone()))
+                .collect();
+            if fans.is_empty() {
+                return Err(FanCurveError::Config(match fan {
+                    Some(n) => format!("Fan {} not found", n),
+                    None => "No controllable fans found".to_string(),
+                }));
+            }
+
+            println!("Ramping fans down from full speed, this takes a minute or two per fan...");
+            Ok(fans
+                .into_iter()
+                .map(|(number, label)| (number, label, detector.calibrate_min_duty(number)))
+                .collect())
+        })
+        .await
+        .map_err(|e| FanCurveError::Task(e).context("Calibration failed"))??;
+
+        let mut failed = false;
+        for (number, label, result) in results {
+            match result {
+                Ok(pwm) => {
+                    let duty = Duty::from_pwm(pwm);
+                    println!(
+                        "Fan {} ({}): lowest spinning PWM {} ({}), spin_up_duty {}",
+                        number,
+                        label,
+                        pwm,
+                        duty,
+                        duty.as_ten_thousandths()
+                    );
+                }
+                Err(e) => {
+                    println!("Fan {} ({}): calibration failed: {}", number, label, e);
+                    failed = true;
+                }
+            }
+        }
+
+        if failed {
+            return Err(FanCurveError::Config("Calibration failed for some fans".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Whether the fan curve daemon currently owns its bus name
+    async fn daemon_is_running(&self) -> Result<bool> {
+        let response = self
+            .connection
+            .call_method(
+                Some("org.freedesktop.DBus"),
+                "/org/freedesktop/DBus",
+                Some("org.freedesktop.DBus"),
+                "NameHasOwner",
+                &(DBUS_SERVICE_NAME,),
+            )
+            .await?;
+        Ok(response.body::<bool>()?)
+    }
+
+    /// Print the fan control mode reported by the daemon, or read it locally
+    async fn show_control_mode(&self) -> Result<()> {
+        debug!("Getting fan control mode");
+
+        let mode = match self.get_control_mode_via_daemon().await {
+            Ok(mode) => mode,
+            Err(e) => {
+                warn!("Failed to get control mode via D-Bus ({}), reading locally", e);
+                tokio::task::spawn_blocking(|| {
+                    let mut detector = FanDetector::new();
+                    if let Err(e) = detector.initialize() {
+                        warn!("Fan detection failed: {}", e);
+                    }
+                    detector.control_mode().to_string()
+                })
+                .await
+                .map_err(|e| FanCurveError::Task(e).context("Fan scan failed"))?
+            }
+        };
+
+        println!("{}", mode);
+        Ok(())
+    }
+
+    /// Compare the live fan duty with what the active curve calls for at the live temperature
+    async fn verify(&self, tolerance: f32) -> Result<()> {
+        debug!("Verifying fan duty against the active curve (tolerance {}%)", tolerance);
+
+        // The daemon's curve, which may differ from the local config
+        let curve = self.current_curve_via_daemon().await?;
+        if curve.is_auto() {
+            println!("Fan curve '{}' leaves the fans to the firmware, nothing to verify", curve.name());
+            return Ok(());
+        }
+
+        // The duty from the daemon's whole pipeline, including the idle floor and ramp-down hold
+        let (temperature, expected) = self.target_duty_via_daemon().await?;
+        let expected = Duty::from_ten_thousandths(expected);
+        let power = System76PowerClient::new().await?;
+        let actual = Duty::from_pwm(power.get_current_duty_from_daemon().await?);
+
+        let delta = actual.as_percent() - expected.as_percent();
+        println!("Fan curve: {}", curve.name());
+        println!("Temperature: {}", self.temp_unit.format(temperature, 1));
+        println!("Expected duty: {}", expected);
+        println!("Actual duty: {}", actual);
+        println!("Delta: {:+.1}%", delta);
+
+        if delta.abs() > tolerance {
+            return Err(FanCurveError::Config(format!(
+                "Fan duty is {:+.1}% off the curve, more than the {:.1}% tolerance",
+                delta, tolerance
+            )));
+        }
+        Ok(())
+    }
+
+    /// Capture a diagnostic snapshot from local sensors and the daemon's active curve,
+    /// and write it to `out` or stdout
+    async fn snapshot(&self, out: Option<&Path>) -> Result<()> {
+        debug!("Capturing diagnostic snapshot");
+
+        let config = FanCurveConfig::load_from_file(&FanCurveConfig::get_config_path())
+            .unwrap_or_default();
+        let mut monitor = FanMonitor::new();
+        monitor.set_sensor_options(self.sensor_options);
+        monitor.configure(&config);
+        // The curve the daemon is driving the fans with, not the one the config starts on
+        let active_curve = self.current_curve_via_daemon().await;
+        if let Ok(curve) = &active_curve {
+            monitor.set_fan_curve(curve.clone());
+        }
+
+        let mut snapshot = tokio::task::spawn_blocking(move || monitor.capture_diagnostics())
+            .await
+            .map_err(|e| FanCurveError::Task(e).context("Snapshot failed"))?;
+        if let Err(e) = active_curve {
+            snapshot.errors.push(format!("Active curve: {}", e));
+        }
+        match daemon_build_info(&self.connection).await {
+            Ok(build) => snapshot.daemon = Some(build),
+            Err(e) => snapshot.errors.push(format!("Daemon version: {}", e)),
+        }
+        snapshot.config = Some(config);
+
+        match out {
+            Some(path) => {
+                snapshot.save_to_file(path)?;
+                println!("Snapshot written to {}", path.display());
+            }
+            None => println!("{}", serde_json::to_string_pretty(&snapshot)?),
+        }
+        Ok(())
+    }
+
+    /// Ask the fan curve daemon for the temperature and duty its control loop last calculated
+    async fn target_duty_via_daemon(&self) -> Result<(f32, u16)> {
+        let response = self
+            .connection
+            .call_method(
+                Some(DBUS_SERVICE_NAME),
+                DBUS_OBJECT_PATH,
+                Some(DBUS_INTERFACE_NAME),
+                "GetTargetDuty",
+                &(),
+            )
+            .await?;
+        Ok(response.body::<(f32, u16)>()?)
+    }
+
+    /// Ask the fan curve daemon for the curve driving the fans
+    async fn current_curve_via_daemon(&self) -> Result<FanCurve> {
+        let response = self
+            .connection
+            .call_method(
+                Some(DBUS_SERVICE_NAME),
+                DBUS_OBJECT_PATH,
+                Some(DBUS_INTERFACE_NAME),
+                "GetCurrentFanCurve",
+                &(),
+            )
+            .await?;
+        Ok(response.body::<FanCurve>()?)
+    }
+
+    /// Ask the fan curve daemon how the fans are controlled
+    async fn get_control_mode_via_daemon(&self) -> Result<String> {
+        let response = self
+            .connection
+            .call_method(
+                Some(DBUS_SERVICE_NAME),
+                DBUS_OBJECT_PATH,
+                Some(DBUS_INTERFACE_NAME),
+                "GetControlMode",
+                &(),
+            )
+            .await?;
+        Ok(response.body::<String>()?)
+    }
+
+    /// Ask the fan curve daemon to boost the fans for a while
+    async fn boost(&self, percent: u8, seconds: u32) -> Result<()> {
+        debug!("Boosting fans to {}% for {}s", percent, seconds);
+
+        self.connection
+            .call_method(
+                Some(DBUS_SERVICE_NAME),
+                DBUS_OBJECT_PATH,
+                Some(DBUS_INTERFACE_NAME),
+                "Boost",
+                &(percent, seconds),
+            )
+            .await
+            .map_err(|e| FanCurveError::from(e).context("Boost not applied by the fan curve daemon"))?;
+
+        println!(
+            "Fans boosted to {}% for {} seconds, then back to the curve",
+            percent, seconds
+        );
+        Ok(())
+    }
+
+    /// Ask the fan curve daemon for its detected hardware
+    async fn get_hardware_info_via_daemon(&self) -> Result<HardwareInfo> {
+        let response = self
+            .connection
+            .call_method(
+                Some(DBUS_SERVICE_NAME),
+                DBUS_OBJECT_PATH,
+                Some(DBUS_INTERFACE_NAME),
+                "GetHardwareInfo",
+                &(),
+            )
+            .await?;
+        Ok(response.body::<HardwareInfo>()?)
+    }
+
+    /// Monitoring interval from `--interval-ms`, falling back to the config file setting
+    fn poll_interval(interval_ms: Option<u64>) -> Duration {
+        match interval_ms {
+            Some(ms) => Duration::from_millis(ms),
+            None => FanCurveConfig::load_from_file(&FanCurveConfig::get_config_path())
+                .unwrap_or_default()
+                .poll_interval(),
+        }
+    }
+
     /// Test fan curve with monitoring
-    async fn test_fan_curve(&self, duration: u64) -> Result<()> {
-        debug!("Testing fan curve for {} seconds", duration);
+    async fn test_fan_curve(&self, name: &str, duration: u64, interval_ms: Option<u64>) -> Result<()> {
+        debug!("Testing fan curve {} for {} seconds", name, duration);
 
         info!("Starting fan curve test for {} seconds", duration);
 
         // Run the fan curve test
-        fan_monitor::test_fan_curve("current", duration).await?;
+        fan_monitor::test_fan_curve(name, duration, Self::poll_interval(interval_ms)).await?;
 
         info!("Fan curve test completed");
         Ok(())
@@ -157,7 +914,6 @@ impl FanCurveClient {
 
     /// Test D-Bus integration with system76-power daemon
     async fn test_dbus_integration(&self) -> Result<()> {
-        use crate::system76_power_client::System76PowerClient;
         
         info!("Testing D-Bus integration with system76-power daemon...");
         
@@ -181,8 +937,7 @@ impl FanCurveClient {
         println!("🔍 Testing GetCurrentDuty...");
         match client.get_current_duty_from_daemon().await {
             Ok(duty) => {
-                let duty_percent = (duty as f32 / 255.0) * 100.0;
-                println!("✅ Duty: {} PWM ({:.1}%)", duty, duty_percent);
+                println!("✅ Duty: {} PWM ({})", duty, Duty::from_pwm(duty));
             }
             Err(e) => {
                 println!("❌ Duty failed: {}", e);
@@ -207,13 +962,12 @@ impl FanCurveClient {
         
         // Test 4: Get fan curve
         println!("🔍 Testing GetFanCurve...");
-        match client.get_fan_curve_from_daemon().await {
-            Ok(curve_points) => {
-                println!("✅ Fan curve points: {:?}", curve_points);
-                for (i, (temp, duty)) in curve_points.iter().enumerate() {
-                    let temp_celsius = *temp as f32 / 10.0; // Convert tenths to Celsius
-                    let duty_percent = (*duty as f32 / 10000.0) * 100.0; // Convert ten-thousandths to percent
-                    println!("   Point {}: {:.1}°C -> {:.1}%", i + 1, temp_celsius, duty_percent);
+        match client.get_fan_curve().await {
+            Ok(curve) => {
+                println!("✅ Fan curve '{}': {:?}", curve.name, curve.points);
+                for (i, (temp, duty)) in curve.points.iter().enumerate() {
+                    let duty = Duty::from_ten_thousandths(*duty);
+                    println!("   Point {}: {}°C -> {}", i + 1, temp, duty);
                 }
             }
             Err(e) => {
@@ -224,18 +978,21 @@ impl FanCurveClient {
         
         // Test 5: Set fan curve (test with a simple curve)
         println!("🔍 Testing SetFanCurve...");
-        let test_curve = vec![
-            (5000, 2000),  // 50°C -> 20%
-            (7000, 5000),  // 70°C -> 50%
-            (8000, 8000),  // 80°C -> 80%
-        ];
+        let test_curve = FanCurveDto {
+            name: "D-Bus Test".to_string(),
+            points: vec![
+                (50, 2000), // 50°C -> 20%
+                (70, 5000), // 70°C -> 50%
+                (80, 8000), // 80°C -> 80%
+            ],
+        };
         
-        match client.set_fan_curve_to_daemon(test_curve.clone()).await {
+        match client.set_fan_curve(&test_curve).await {
             Ok(()) => {
                 println!("✅ Fan curve set successfully");
                 
                 // Verify the curve was set by getting it back
-                match client.get_fan_curve_from_daemon().await {
+                match client.get_fan_curve().await {
                     Ok(current_curve) => {
                         if current_curve == test_curve {
                             println!("✅ Curve verification: Set curve matches retrieved curve");
@@ -261,10 +1018,9 @@ impl FanCurveClient {
     }
 
     /// Test full FanMonitor integration with daemon
-    async fn test_fan_monitor_integration(&self, duration: u64) -> Result<()> {
-        use crate::fan_monitor::FanMonitor;
-        use crate::system76_power_client::System76PowerClient;
-        
+    async fn test_fan_monitor_integration(&self, duration: u64, interval_ms: Option<u64>) -> Result<()> {
+        let poll_interval = Self::poll_interval(interval_ms);
+
         info!("Testing full FanMonitor integration with system76-power daemon...");
         println!("🔍 Testing FanMonitor integration for {} seconds...", duration);
         
@@ -273,6 +1029,11 @@ impl FanCurveClient {
         
         // Create FanMonitor and initialize it with the D-Bus client
         let mut monitor = FanMonitor::new();
+        monitor.set_sensor_options(self.sensor_options);
+        monitor.set_poll_interval(poll_interval);
+        let config = FanCurveConfig::load_from_file(&FanCurveConfig::get_config_path())
+            .unwrap_or_default();
+        monitor.set_overheat_warning(self.overheat_warning(&config));
         monitor.initialize_system76_power().await?;
         
         println!("✅ FanMonitor initialized with D-Bus client");
@@ -282,7 +1043,7 @@ impl FanCurveClient {
         match monitor.get_current_fan_data().await {
             Ok(data) => {
                 println!("✅ Fan data retrieved successfully:");
-                println!("   Temperature: {:.1}°C", data.temperature);
+                println!("   Temperature: {}", self.temp_unit.format(data.temperature, 1));
                 println!("   CPU Fan Speeds: {:?}", data.cpu_fan_speeds);
                 println!("   Fan Duty: {} ten-thousandths", data.fan_duty);
                 println!("   CPU Usage: {:.1}%", data.cpu_usage);
@@ -316,9 +1077,9 @@ impl FanCurveClient {
             match monitor.get_current_fan_data().await {
                 Ok(data) => {
                     sample_count += 1;
-                    println!("Sample {}: {:.1}°C -> {} duty, Fans: {:?}", 
+                    println!("Sample {}: {} -> {} duty, Fans: {:?}", 
                         sample_count, 
-                        data.temperature, 
+                        self.temp_unit.format(data.temperature, 1), 
                         data.fan_duty,
                         data.cpu_fan_speeds.iter().map(|(_, speed, _)| *speed).collect::<Vec<_>>()
                     );
@@ -328,8 +1089,7 @@ impl FanCurveClient {
                 }
             }
             
-            // Wait 2 seconds between samples
-            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+            tokio::time::sleep(monitor.poll_interval()).await;
         }
         
         println!("✅ Continuous monitoring completed: {} samples in {} seconds", 
@@ -362,10 +1122,98 @@ impl FanCurveClient {
         println!("   - 'Fan curve updated in daemon' - Confirms D-Bus curve setting");
         println!("");
         
-        crate::iced_gui::run_iced_gui()
+        crate::iced_gui::run_iced_gui(self.sensor_options, self.temp_unit, self.notifications)
             .map_err(|e| crate::errors::FanCurveError::Unknown(format!("GUI error: {}", e)))?;
 
         println!("🎉 GUI integration test completed!");
         Ok(())
     }
 }
+
+/// Version, git hash and build time of the daemon serving on `connection`
+pub async fn daemon_build_info(connection: &Connection) -> Result<BuildInfo> {
+    let response = connection
+        .call_method(
+            Some(DBUS_SERVICE_NAME),
+            DBUS_OBJECT_PATH,
+            Some("org.freedesktop.DBus.Properties"),
+            "GetAll",
+            &(DBUS_INTERFACE_NAME,),
+        )
+        .await?;
+    let properties: HashMap<String, OwnedValue> = response.body()?;
+    let property = |name: &str| -> Result<String> {
+        properties
+            .get(name)
+            .and_then(|value| <&str>::try_from(value).ok())
+            .map(str::to_string)
+            .ok_or_else(|| FanCurveError::Unknown(format!("Daemon did not report {}", name)))
+    };
+
+    Ok(BuildInfo {
+        version: property("Version")?,
+        git_hash: property("GitHash")?,
+        build_time: property("BuildTime")?,
+    })
+}
+
+/// Fan curves of the daemon on `connection`
+/// Daemons without `GetFanCurvesV2` are asked with `GetFanCurves`, which only has names
+/// and points, so the other curve settings come back as defaults
+pub async fn daemon_fan_curves(connection: &Connection) -> Result<Vec<FanCurve>> {
+    let call = |method| {
+        connection.call_method(
+            Some(DBUS_SERVICE_NAME),
+            DBUS_OBJECT_PATH,
+            Some(DBUS_INTERFACE_NAME),
+            method,
+            &(),
+        )
+    };
+
+    match call("GetFanCurvesV2").await {
+        Ok(response) => Ok(response.body::<Vec<FanCurve>>()?),
+        Err(e) => {
+            debug!("GetFanCurvesV2 failed ({}), falling back to GetFanCurves", e);
+            let curves = call("GetFanCurves").await?.body::<Vec<FanCurveDto>>()?;
+            Ok(curves.into_iter().map(FanCurve::from).collect())
+        }
+    }
+}
+
+/// Have the daemon on `connection` validate, activate and apply `curve` in one call
+/// The daemon only saves it with `persist`; returns the duty applied (ten-thousandths)
+pub async fn apply_fan_curve_inline(connection: &Connection, curve: &FanCurve, persist: bool) -> Result<u16> {
+    let response = connection
+        .call_method(
+            Some(DBUS_SERVICE_NAME),
+            DBUS_OBJECT_PATH,
+            Some(DBUS_INTERFACE_NAME),
+            "ApplyFanCurveInline",
+            &(FanCurveDto::from(curve), persist),
+        )
+        .await?;
+    Ok(response.body()?)
+}
+
+/// Stream of the `FanDataUpdated` signals the daemon emits on `connection`
+/// Malformed signals are logged and skipped
+pub async fn fan_data_updates(connection: &Connection) -> Result<impl Stream<Item = FanDataPoint>> {
+    let rule = MatchRule::builder()
+        .msg_type(zbus::MessageType::Signal)
+        .path(DBUS_OBJECT_PATH)?
+        .interface(DBUS_INTERFACE_NAME)?
+        .member("FanDataUpdated")?
+        .build();
+    let stream = MessageStream::for_match_rule(rule, connection, None).await?;
+
+    Ok(stream.filter_map(|msg| async move {
+        match msg.and_then(|msg| msg.body::<FanDataDto>()) {
+            Ok(data) => Some(FanDataPoint::from(data)),
+            Err(e) => {
+                warn!("Ignoring malformed fan data update: {}", e);
+                None
+            }
+        }
+    }))
+}
@@ -0,0 +1,227 @@
+//! Closed-loop fan control: fans follow a curve of target RPM instead of target duty
+//!
+//! A fixed duty cools less as fans wear or air paths clog. A fan set to the `rpm` control
+//! mode gets its target speed from an [`RpmTarget`], and a [`ClosedLoopController`] adjusts
+//! its duty each sample until `fanN_input` reads that speed.
+
+use crate::duty::Duty;
+use crate::errors::{FanCurveError, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Target fan speed at a temperature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpmPoint {
+    pub temp: i16,
+    pub rpm: u16,
+}
+
+impl RpmPoint {
+    pub fn new(temp: i16, rpm: u16) -> Self {
+        Self { temp, rpm }
+    }
+}
+
+/// Proportional and integral gains of a [`ClosedLoopController`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PiGains {
+    /// Duty percent added per RPM below the target
+    pub kp: f32,
+    /// Duty percent accumulated per RPM below the target, per second
+    pub ki: f32,
+}
+
+impl Default for PiGains {
+    fn default() -> Self {
+        Self { kp: 0.01, ki: 0.02 }
+    }
+}
+
+/// Target RPM curve of one fan, with the gains used to reach it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpmTarget {
+    /// Points in temperature order; the ends are held flat
+    pub points: Vec<RpmPoint>,
+    #[serde(default)]
+    pub gains: PiGains,
+}
+
+impl RpmTarget {
+    pub fn new(points: Vec<RpmPoint>) -> Self {
+        Self {
+            points,
+            gains: PiGains::default(),
+        }
+    }
+
+    /// Check for at least one point, increasing temperatures and usable gains
+    pub fn validate(&self) -> Result<()> {
+        if self.points.is_empty() {
+            return Err(FanCurveError::Config(
+                "Target RPM curve has no points".to_string(),
+            ));
+        }
+        if let Some(pair) = self
+            .points
+            .windows(2)
+            .find(|pair| pair[0].temp >= pair[1].temp)
+        {
+            return Err(FanCurveError::Config(format!(
+                "Target RPM points must have increasing temperatures, got {}°C after {}°C",
+                pair[1].temp, pair[0].temp
+            )));
+        }
+        let PiGains { kp, ki } = self.gains;
+        if !(kp.is_finite() && ki.is_finite() && kp >= 0.0 && ki >= 0.0) {
+            return Err(FanCurveError::Config(format!(
+                "Controller gains must be finite and not negative, got kp {} and ki {}",
+                kp, ki
+            )));
+        }
+        Ok(())
+    }
+
+    /// Target RPM at `temp` °C, interpolated between points; 0 without points
+    pub fn rpm_at(&self, temp: f32) -> u16 {
+        let (Some(first), Some(last)) = (self.points.first(), self.points.last()) else {
+            return 0;
+        };
+        if temp <= f32::from(first.temp) {
+            return first.rpm;
+        }
+        if temp >= f32::from(last.temp) {
+            return last.rpm;
+        }
+        self.points
+            .windows(2)
+            .find(|pair| temp <= f32::from(pair[1].temp))
+            .map(|pair| {
+                let (temp1, temp2) = (f32::from(pair[0].temp), f32::from(pair[1].temp));
+                let (rpm1, rpm2) = (f32::from(pair[0].rpm), f32::from(pair[1].rpm));
+                let factor = (temp - temp1) / (temp2 - temp1);
+                (rpm1 + factor * (rpm2 - rpm1)).round() as u16
+            })
+            .unwrap_or(last.rpm)
+    }
+}
+
+/// PI controller finding the duty that makes one fan spin at a target RPM
+///
+/// The integral is kept within 0-100% so a target the fan can't reach doesn't wind it up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosedLoopController {
+    gains: PiGains,
+    /// Accumulated integral term, in duty percent
+    integral: f32,
+}
+
+impl ClosedLoopController {
+    pub fn new(gains: PiGains) -> Self {
+        Self {
+            gains,
+            integral: 0.0,
+        }
+    }
+
+    pub fn gains(&self) -> PiGains {
+        self.gains
+    }
+
+    /// Duty (ten-thousandths) for the next `dt`, given the target and the RPM just measured
+    pub fn update(&mut self, target_rpm: u16, measured_rpm: u16, dt: Duration) -> u16 {
+        let error = f32::from(target_rpm) - f32::from(measured_rpm);
+        self.integral =
+            (self.integral + self.gains.ki * error * dt.as_secs_f32()).clamp(0.0, 100.0);
+        let percent = (self.gains.kp * error + self.integral).clamp(0.0, 100.0);
+        Duty::from_percent(percent).as_ten_thousandths()
+    }
+
+    /// Forget the accumulated integral, e.g. after the fan was driven some other way
+    pub fn reset(&mut self) {
+        self.integral = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fan reaching 25 RPM per duty percent above an 8% stall point, half-way each second
+    struct SimulatedFan {
+        rpm: f32,
+    }
+
+    impl SimulatedFan {
+        fn step(&mut self, duty: u16) -> u16 {
+            let percent = Duty::from_ten_thousandths(duty).as_percent();
+            let steady = (25.0 * percent - 200.0).max(0.0);
+            self.rpm += (steady - self.rpm) * 0.5;
+            self.rpm.round() as u16
+        }
+    }
+
+    fn run(
+        controller: &mut ClosedLoopController,
+        fan: &mut SimulatedFan,
+        target: u16,
+        seconds: usize,
+    ) -> u16 {
+        let mut rpm = fan.rpm.round() as u16;
+        for _ in 0..seconds {
+            let duty = controller.update(target, rpm, Duration::from_secs(1));
+            rpm = fan.step(duty);
+        }
+        rpm
+    }
+
+    #[test]
+    fn test_controller_converges() {
+        let mut controller = ClosedLoopController::new(PiGains::default());
+        let mut fan = SimulatedFan { rpm: 0.0 };
+
+        assert!(run(&mut controller, &mut fan, 1200, 30).abs_diff(1200) <= 12);
+        // Steady once there
+        assert!(run(&mut controller, &mut fan, 1200, 30).abs_diff(1200) <= 2);
+
+        // Follows a lower target down
+        assert!(run(&mut controller, &mut fan, 700, 40).abs_diff(700) <= 7);
+
+        // An unreachable target runs the fan flat out without winding up, so it
+        // comes back quickly once the target is reachable again
+        assert_eq!(run(&mut controller, &mut fan, 5000, 60), 2300);
+        assert!(run(&mut controller, &mut fan, 1000, 40).abs_diff(1000) <= 10);
+
+        // At target the integral alone holds the duty, until it is reset
+        assert!(controller.update(1000, 1000, Duration::from_secs(1)) > 0);
+        controller.reset();
+        assert_eq!(controller.update(1000, 1000, Duration::from_secs(1)), 0);
+
+        // A target of 0 stops the fan
+        assert_eq!(run(&mut controller, &mut fan, 0, 40), 0);
+    }
+
+    #[test]
+    fn test_rpm_target() {
+        let target = RpmTarget::new(vec![
+            RpmPoint::new(40, 600),
+            RpmPoint::new(70, 1500),
+            RpmPoint::new(90, 2400),
+        ]);
+        target.validate().unwrap();
+        assert_eq!(target.rpm_at(20.0), 600);
+        assert_eq!(target.rpm_at(55.0), 1050);
+        assert_eq!(target.rpm_at(80.0), 1950);
+        assert_eq!(target.rpm_at(100.0), 2400);
+
+        let json = r#"{"points": [{"temp": 40, "rpm": 600}]}"#;
+        let parsed: RpmTarget = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed.gains, PiGains::default());
+
+        assert!(RpmTarget::new(Vec::new()).validate().is_err());
+        let unordered = RpmTarget::new(vec![RpmPoint::new(70, 1500), RpmPoint::new(40, 600)]);
+        assert!(unordered.validate().is_err());
+        let mut negative = target.clone();
+        negative.gains.ki = -1.0;
+        assert!(negative.validate().is_err());
+    }
+}
@@ -0,0 +1,248 @@
+//! CPU model and core count from /proc/cpuinfo and the hybrid core lists in sysfs
+
+use std::fs;
+use std::sync::OnceLock;
+
+/// Where the kernel describes each logical CPU
+const CPUINFO_PATH: &str = "/proc/cpuinfo";
+
+/// Logical CPUs of each core type on hybrid Intel CPUs, with the suffix counting them in the model
+const CORE_TYPE_PATHS: [(&str, &str); 2] = [
+    ("/sys/devices/cpu_core/cpus", "P"),
+    ("/sys/devices/cpu_atom/cpus", "E"),
+];
+
+/// Model shown when /proc/cpuinfo has none
+pub const UNKNOWN_CPU_MODEL: &str = "Unknown CPU";
+
+/// CPU model and number of physical cores
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuInfo {
+    /// Model name; hybrid CPUs count each core type, e.g. "X (6P + 8E)", or list each model
+    /// name, fastest first, e.g. "X (4) + Y (4)"
+    pub model: String,
+    /// Number of physical cores, by `core id`; processors without one count once each
+    pub core_count: u32,
+}
+
+/// Parse a sysfs CPU list such as "0-11,16"; malformed ranges are skipped
+fn parse_cpu_list(list: &str) -> Vec<u32> {
+    list.trim()
+        .split(',')
+        .filter_map(|range| match range.split_once('-') {
+            Some((first, last)) => Some(first.parse().ok()?..=last.parse().ok()?),
+            None => range.parse().ok().map(|cpu| cpu..=cpu),
+        })
+        .flatten()
+        .collect()
+}
+
+impl CpuInfo {
+    /// Parse the contents of /proc/cpuinfo
+    /// `core_types` holds each hybrid core type's suffix and logical CPUs, see [`CORE_TYPE_PATHS`]
+    pub fn parse(cpuinfo: &str, core_types: &[(&str, Vec<u32>)]) -> Self {
+        // (model, cores, highest MHz) per core type, in the order first seen
+        let mut models: Vec<(String, u32, f32)> = Vec::new();
+        // (logical CPU, "physical id:core id") per processor; the core is None without a `core id`
+        let mut cores: Vec<(Option<u32>, Option<String>)> = Vec::new();
+        let mut fallback_model = None;
+
+        for block in cpuinfo.split("\n\n") {
+            let mut processor = None;
+            let mut physical_id = String::new();
+            let mut core_id = None;
+            let mut model = None;
+            let mut mhz = 0.0;
+            for (key, value) in block.lines().filter_map(|line| line.split_once(':')) {
+                let value = value.trim();
+                match key.trim() {
+                    "processor" => processor = Some(value.parse().ok()),
+                    "physical id" => physical_id = value.to_string(),
+                    "core id" => core_id = Some(value.to_string()),
+                    "model name" | "cpu model" => model = Some(value.to_string()),
+                    "cpu MHz" => mhz = value.parse().unwrap_or(0.0),
+                    // ARM boards name the SoC once, outside the processor entries
+                    "Hardware" | "Model" if !value.is_empty() => {
+                        fallback_model.get_or_insert_with(|| value.to_string());
+                    }
+                    _ => {}
+                }
+            }
+            let Some(processor) = processor else {
+                continue;
+            };
+            cores.push((
+                processor,
+                core_id.map(|core_id| format!("{}:{}", physical_id, core_id)),
+            ));
+
+            let Some(model) = model.filter(|model| !model.is_empty()) else {
+                continue;
+            };
+            match models.iter_mut().find(|(name, _, _)| *name == model) {
+                Some((_, cores, max_mhz)) => {
+                    *cores += 1;
+                    *max_mhz = max_mhz.max(mhz);
+                }
+                None => models.push((model, 1, mhz)),
+            }
+        }
+
+        // Physical cores among the processors `in_core_type` accepts
+        let count_cores = |in_core_type: &dyn Fn(Option<u32>) -> bool| {
+            let mut seen = Vec::new();
+            let mut count = 0;
+            for (_, core) in cores
+                .iter()
+                .filter(|(processor, _)| in_core_type(*processor))
+            {
+                match core {
+                    Some(core) if seen.contains(&core) => {}
+                    Some(core) => {
+                        seen.push(core);
+                        count += 1;
+                    }
+                    None => count += 1,
+                }
+            }
+            count
+        };
+        let core_count = count_cores(&|_| true);
+
+        // Hybrid Intel CPUs give every core the same model name; sysfs tells the types apart
+        let type_counts: Vec<String> = core_types
+            .iter()
+            .map(|(suffix, cpus)| {
+                let count =
+                    count_cores(&|processor| processor.is_some_and(|cpu| cpus.contains(&cpu)));
+                (count, suffix)
+            })
+            .filter(|&(count, _)| count > 0)
+            .map(|(count, suffix)| format!("{}{}", count, suffix))
+            .collect();
+
+        let model = match models.len() {
+            0 => fallback_model.unwrap_or_else(|| UNKNOWN_CPU_MODEL.to_string()),
+            1 if type_counts.len() > 1 => {
+                format!("{} ({})", models.remove(0).0, type_counts.join(" + "))
+            }
+            1 => models.remove(0).0,
+            _ => {
+                // Stable sort keeps the listed order for core types without a clock
+                models.sort_by(|a, b| b.2.total_cmp(&a.2));
+                models
+                    .iter()
+                    .map(|(name, cores, _)| format!("{} ({})", name, cores))
+                    .collect::<Vec<_>>()
+                    .join(" + ")
+            }
+        };
+
+        Self { model, core_count }
+    }
+
+    /// Read and parse /proc/cpuinfo; an unreadable file gives an unknown model and no cores
+    /// Core types whose sysfs list is missing, as on non-hybrid CPUs, are left out
+    pub fn read() -> Self {
+        let core_types: Vec<(&str, Vec<u32>)> = CORE_TYPE_PATHS
+            .iter()
+            .filter_map(|&(path, suffix)| {
+                Some((suffix, parse_cpu_list(&fs::read_to_string(path).ok()?)))
+            })
+            .collect();
+        Self::parse(
+            &fs::read_to_string(CPUINFO_PATH).unwrap_or_default(),
+            &core_types,
+        )
+    }
+}
+
+/// CPU info read once on first use, since it doesn't change while running
+pub fn cpu_info() -> &'static CpuInfo {
+    static CPU_INFO: OnceLock<CpuInfo> = OnceLock::new();
+    CPU_INFO.get_or_init(CpuInfo::read)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn processor(index: u32, model: &str, mhz: f32) -> String {
+        format!(
+            "processor\t: {}\nvendor_id\t: GenuineIntel\nmodel name\t: {}\ncpu MHz\t\t: {:.3}\nflags\t\t: fpu vme\n\n",
+            index, model, mhz
+        )
+    }
+
+    #[test]
+    fn test_parse_cpuinfo() {
+        let desktop: String = (0..16)
+            .map(|i| processor(i, "AMD Ryzen 9 5950X 16-Core Processor", 3400.0))
+            .collect();
+        assert_eq!(
+            CpuInfo::parse(&desktop, &[]),
+            CpuInfo {
+                model: "AMD Ryzen 9 5950X 16-Core Processor".to_string(),
+                core_count: 16,
+            }
+        );
+
+        // Efficiency cores are listed first here, but the faster cores are named first
+        let hybrid: String = (0..4)
+            .map(|i| processor(i, "Efficient Core", 2000.0))
+            .chain((4..6).map(|i| processor(i, "Performance Core", 4800.0)))
+            .collect();
+        let info = CpuInfo::parse(&hybrid, &[]);
+        assert_eq!(info.model, "Performance Core (2) + Efficient Core (4)");
+        assert_eq!(info.core_count, 6);
+
+        // ARM entries have no model name per processor
+        let arm = "processor\t: 0\nBogoMIPS\t: 108.00\n\nprocessor\t: 1\nBogoMIPS\t: 108.00\n\nHardware\t: BCM2835\nModel\t\t: Raspberry Pi 4 Model B\n";
+        assert_eq!(
+            CpuInfo::parse(arm, &[]),
+            CpuInfo {
+                model: "BCM2835".to_string(),
+                core_count: 2,
+            }
+        );
+
+        assert_eq!(
+            CpuInfo::parse("", &[]),
+            CpuInfo {
+                model: UNKNOWN_CPU_MODEL.to_string(),
+                core_count: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_intel_hybrid() {
+        // 6 hyper-threaded performance cores on CPUs 0-11, then 8 efficiency cores
+        let model = "12th Gen Intel(R) Core(TM) i7-12700H";
+        let cpuinfo: String = (0..20)
+            .map(|i| {
+                let core_id = if i < 12 { i / 2 } else { i - 4 };
+                format!(
+                    "processor\t: {}\nmodel name\t: {}\nphysical id\t: 0\ncore id\t\t: {}\n\n",
+                    i, model, core_id
+                )
+            })
+            .collect();
+        let core_types = [
+            ("P", parse_cpu_list("0-11\n")),
+            ("E", parse_cpu_list("12-19\n")),
+        ];
+
+        assert_eq!(
+            CpuInfo::parse(&cpuinfo, &core_types),
+            CpuInfo {
+                model: format!("{} (6P + 8E)", model),
+                core_count: 14,
+            }
+        );
+        // Without the sysfs lists the cores all look the same
+        assert_eq!(CpuInfo::parse(&cpuinfo, &[]).model, model);
+
+        assert_eq!(parse_cpu_list("0-2,5,x,8-7\n"), [0, 1, 2, 5]);
+    }
+}
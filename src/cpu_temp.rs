@@ -2,7 +2,18 @@ use crate::errors::FanCurveError;
 use crate::errors::Result;
 use log::{info, warn};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Intel sensor labels to use, most preferred first
+pub(crate) const INTEL_LABELS: &[&str] = &["Package id 0", "Core 0"];
+
+/// AMD sensor labels to use, most preferred first
+///
+/// `Tdie` is the real die temperature while `Tctl` may include a fan-control
+/// offset, so `Tdie` wins when both exist.
+pub(crate) const AMD_LABELS: &[&str] = &["Tdie", "Tctl"];
 
 /// CPU manufacturer types
 #[derive(Debug, Clone, PartialEq)]
@@ -20,18 +31,48 @@ pub struct CpuTempSensor {
     pub temp_input_path: String,
     pub temp_label_path: String,
     pub sensor_name: String,
+    /// Label of the chosen input, e.g. `Tdie` or `Tctl`
+    pub temp_label: String,
 }
 
 /// CPU temperature detector
 #[derive(Clone)]
 pub struct CpuTempDetector {
-    sensor: Option<CpuTempSensor>,
+    /// Usable sensors found by `initialize`, most preferred first
+    sensors: Vec<CpuTempSensor>,
+    /// Index of the sensor currently read, shared with clones
+    active: Arc<AtomicUsize>,
+    /// Degrees subtracted from AMD `Tctl` readings when no `Tdie` is available
+    tctl_offset: f32,
+    /// Directory holding the hwmon devices, normally /sys/class/hwmon
+    hwmon_root: PathBuf,
 }
 
 impl CpuTempDetector {
-    /// Create a new CPU temperature detector
+    /// Create a new CPU temperature detector for the hwmon tree from [`crate::hwmon_root`]
     pub fn new() -> Self {
-        Self { sensor: None }
+        Self::with_hwmon_root(crate::hwmon_root())
+    }
+
+    /// Detector that looks for hwmon devices in `root` instead of /sys/class/hwmon
+    pub fn with_hwmon_root(root: impl Into<PathBuf>) -> Self {
+        Self {
+            sensors: Vec::new(),
+            active: Arc::new(AtomicUsize::new(0)),
+            tctl_offset: 0.0,
+            hwmon_root: root.into(),
+        }
+    }
+
+    /// Subtract `offset` degrees from `Tctl` readings
+    /// Some Threadrippers report `Tctl` 27°C above the die temperature.
+    pub fn set_tctl_offset(&mut self, offset: f32) {
+        self.tctl_offset = offset;
+    }
+
+    /// Get the offset set with [`CpuTempDetector::set_tctl_offset`]
+    pub fn tctl_offset(&self) -> f32 {
+        self.tctl_offset
     }
 
     /// Initialize the detector by scanning for CPU temperature sensors
@@ -42,16 +83,20 @@ impl CpuTempDetector {
         let manufacturer = self.detect_cpu_manufacturer()?;
         info!("Detected CPU manufacturer: {:?}", manufacturer);
 
-        // Find the appropriate temperature sensor
-        let sensor = self.find_cpu_temp_sensor(&manufacturer)?;
-        self.sensor = Some(sensor);
+        // Find the temperature sensors, keeping the others as fallbacks
+        self.sensors = self.find_cpu_temp_sensors(&manufacturer)?;
+        self.active.store(0, Ordering::Relaxed);
 
-        info!("CPU temperature sensor initialized: {:?}", self.sensor);
+        info!(
+            "CPU temperature sensor initialized: {:?} ({} fallbacks)",
+            self.sensors[0],
+            self.sensors.len() - 1
+        );
         Ok(())
     }
 
     /// Detect CPU manufacturer by reading /proc/cpuinfo
-    fn detect_cpu_manufacturer(&self) -> Result<CpuManufacturer> {
+    pub fn detect_cpu_manufacturer(&self) -> Result<CpuManufacturer> {
         let cpuinfo = fs::read_to_string("/proc/cpuinfo")?;
 
         for line in cpuinfo.lines() {
@@ -78,23 +123,32 @@ impl CpuTempDetector {
         ))
     }
 
-    /// Find the CPU temperature sensor in /sys/class/hwmon
-    fn find_cpu_temp_sensor(&self, manufacturer: &CpuManufacturer) -> Result<CpuTempSensor> {
-        let hwmon_dir = Path::new("/sys/class/hwmon");
+    /// Find the CPU temperature sensors in the hwmon root
+    fn find_cpu_temp_sensors(&self, manufacturer: &CpuManufacturer) -> Result<Vec<CpuTempSensor>> {
+        self.find_cpu_temp_sensors_in(&self.hwmon_root, manufacturer)
+    }
 
+    /// Find the CPU temperature sensors among the hwmon devices in `hwmon_dir`
+    /// Ordered by device, then by label preference, so the first one is the best choice
+    fn find_cpu_temp_sensors_in(
+        &self,
+        hwmon_dir: &Path,
+        manufacturer: &CpuManufacturer,
+    ) -> Result<Vec<CpuTempSensor>> {
         if !hwmon_dir.exists() {
             return Err(FanCurveError::Config(
                 "Hardware monitoring directory not found".to_string(),
             ));
         }
 
-        // Read all hwmon directories
-        let entries = fs::read_dir(hwmon_dir)?;
-
-        for entry in entries {
-            let entry = entry?;
-            let hwmon_path = entry.path();
+        // Read all hwmon directories, in a stable order
+        let mut hwmon_paths = fs::read_dir(hwmon_dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        hwmon_paths.sort();
 
+        let mut sensors = Vec::new();
+        for hwmon_path in hwmon_paths {
             if !hwmon_path.is_dir() {
                 continue;
             }
@@ -115,78 +169,88 @@ impl CpuTempDetector {
                 };
 
                 if is_target_sensor {
-                    // Find the correct temperature input file
-                    if let Ok(temp_input_path) =
-                        self.find_temp_input_file(&hwmon_path, manufacturer)
+                    // Find the usable temperature input files
+                    for (temp_input_path, temp_label) in self
+                        .find_temp_input_files(&hwmon_path, manufacturer)
+                        .unwrap_or_default()
                     {
                         let temp_label_path =
                             self.find_temp_label_file(&hwmon_path, &temp_input_path)?;
 
-                        return Ok(CpuTempSensor {
+                        sensors.push(CpuTempSensor {
                             manufacturer: manufacturer.clone(),
                             hwmon_path: hwmon_path.to_string_lossy().to_string(),
                             temp_input_path,
                             temp_label_path,
-                            sensor_name,
+                            sensor_name: sensor_name.clone(),
+                            temp_label,
                         });
                     }
                 }
             }
         }
 
-        Err(FanCurveError::Config(format!(
-            "Could not find CPU temperature sensor for {:?}",
-            manufacturer
-        )))
+        if sensors.is_empty() {
+            return Err(FanCurveError::Config(format!(
+                "Could not find CPU temperature sensor for {:?}",
+                manufacturer
+            )));
+        }
+        Ok(sensors)
     }
 
-    /// Find the correct temperature input file
-    fn find_temp_input_file(
+    /// Find the temperature input files with a wanted label, returning their paths and labels
+    /// The most preferred label comes first
+    fn find_temp_input_files(
         &self,
         hwmon_path: &Path,
         manufacturer: &CpuManufacturer,
-    ) -> Result<String> {
-        let entries = fs::read_dir(hwmon_path)?;
-
-        for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
+    ) -> Result<Vec<(String, String)>> {
+        let wanted: Vec<&str> = match manufacturer {
+            CpuManufacturer::Intel => INTEL_LABELS.to_vec(),
+            CpuManufacturer::Amd => AMD_LABELS.to_vec(),
+            // Try both patterns
+            CpuManufacturer::Unknown => INTEL_LABELS.iter().chain(AMD_LABELS).copied().collect(),
+        };
+
+        // Collect every labelled input, then pick the most preferred label
+        let mut inputs = Vec::new();
+        for entry in fs::read_dir(hwmon_path)? {
+            let path = entry?.path();
 
             if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
                 if file_name.starts_with("temp") && file_name.ends_with("_input") {
-                    // Check if this is the right temperature sensor by reading the label
                     if let Ok(label_path) =
                         self.find_temp_label_file(hwmon_path, &path.to_string_lossy())
                     {
                         if let Ok(label_content) = fs::read_to_string(&label_path) {
-                            let label = label_content.trim();
-
-                            let is_correct_sensor = match manufacturer {
-                                CpuManufacturer::Intel => {
-                                    label.contains("Package id 0") || label.contains("Core 0")
-                                }
-                                CpuManufacturer::Amd => label.contains("Tctl"),
-                                CpuManufacturer::Unknown => {
-                                    // Try both patterns
-                                    label.contains("Package id 0")
-                                        || label.contains("Core 0")
-                                        || label.contains("Tctl")
-                                }
-                            };
-
-                            if is_correct_sensor {
-                                return Ok(path.to_string_lossy().to_string());
-                            }
+                            inputs.push((
+                                path.to_string_lossy().to_string(),
+                                label_content.trim().to_string(),
+                            ));
                         }
                     }
                 }
             }
         }
-
-        Err(FanCurveError::Config(format!(
-            "Could not find temperature input file for {:?}",
-            manufacturer
-        )))
+        inputs.sort();
+
+        let found: Vec<(String, String)> = wanted
+            .iter()
+            .filter_map(|pattern| {
+                inputs
+                    .iter()
+                    .find(|(_, label)| label.contains(pattern))
+                    .cloned()
+            })
+            .collect();
+        if found.is_empty() {
+            return Err(FanCurveError::Config(format!(
+                "Could not find temperature input file for {:?}",
+                manufacturer
+            )));
+        }
+        Ok(found)
     }
 
     /// Find the corresponding temperature label file
@@ -217,11 +281,42 @@ impl CpuTempDetector {
     }
 
     /// Read the current CPU temperature
+    /// If the sensor in use fails, the fallbacks found by `initialize` are tried in order and
+    /// the first one that works is used from then on
     pub fn read_temperature(&self) -> Result<f32> {
-        let sensor = self.sensor.as_ref().ok_or_else(|| {
-            FanCurveError::Config("CPU temperature sensor not initialized".to_string())
-        })?;
+        if self.sensors.is_empty() {
+            return Err(FanCurveError::Config(
+                "CPU temperature sensor not initialized".to_string(),
+            ));
+        }
 
+        let active = self.active.load(Ordering::Relaxed);
+        let mut first_error = None;
+        for index in (active..self.sensors.len()).chain(0..active) {
+            match self.read_sensor(&self.sensors[index]) {
+                Ok(temperature) => {
+                    if index != active {
+                        warn!(
+                            "CPU temperature sensor {} ({}) failed, switched to {} ({})",
+                            self.sensors[active].temp_label,
+                            self.sensors[active].temp_input_path,
+                            self.sensors[index].temp_label,
+                            self.sensors[index].temp_input_path
+                        );
+                        self.active.store(index, Ordering::Relaxed);
+                    }
+                    return Ok(temperature);
+                }
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        Err(first_error.unwrap())
+    }
+
+    /// Read one sensor in °C
+    fn read_sensor(&self, sensor: &CpuTempSensor) -> Result<f32> {
         let temp_content = fs::read_to_string(&sensor.temp_input_path)?;
 
         // Temperature is typically in millidegrees Celsius
@@ -231,7 +326,10 @@ impl CpuTempDetector {
             .map_err(|_| FanCurveError::Config("Failed to parse temperature".to_string()))?;
 
         // Convert to degrees Celsius
-        let temp_celsius = temp_millidegrees as f32 / 1000.0;
+        let mut temp_celsius = temp_millidegrees as f32 / 1000.0;
+        if sensor.temp_label.contains("Tctl") {
+            temp_celsius -= self.tctl_offset;
+        }
 
         // Validate temperature range (reasonable CPU temperature range)
         if !(-50.0..=200.0).contains(&temp_celsius) {
@@ -244,23 +342,76 @@ impl CpuTempDetector {
         Ok(temp_celsius)
     }
 
-    /// Get sensor information
+    /// Get information on the sensor currently in use
     pub fn get_sensor_info(&self) -> Option<&CpuTempSensor> {
-        self.sensor.as_ref()
+        self.sensors.get(self.active.load(Ordering::Relaxed))
+    }
+
+    /// Every usable sensor found, most preferred first
+    pub fn sensors(&self) -> &[CpuTempSensor] {
+        &self.sensors
     }
 
     /// Check if the detector is initialized
     pub fn is_initialized(&self) -> bool {
-        self.sensor.is_some()
+        !self.sensors.is_empty()
     }
 
     /// Get the detected CPU manufacturer
     pub fn manufacturer(&self) -> CpuManufacturer {
-        self.sensor
-            .as_ref()
+        self.get_sensor_info()
             .map(|s| s.manufacturer.clone())
             .unwrap_or(CpuManufacturer::Unknown)
     }
+
+    /// Read every hwmon temperature input, not just the CPU ones, for diagnostics
+    /// Returns (`<device name> <label>`, °C) pairs in device then input order. Inputs
+    /// without a label are named after their file, e.g. `acpitz temp1`; unreadable ones are skipped
+    pub fn read_all_temperatures(&self) -> Result<Vec<(String, f32)>> {
+        let mut hwmon_paths = fs::read_dir(&self.hwmon_root)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        hwmon_paths.sort();
+
+        let mut temperatures = Vec::new();
+        for hwmon_path in hwmon_paths {
+            let Ok(entries) = fs::read_dir(&hwmon_path) else {
+                continue;
+            };
+            let device = fs::read_to_string(hwmon_path.join("name"))
+                .map(|name| name.trim().to_string())
+                .unwrap_or_else(|_| {
+                    hwmon_path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string()
+                });
+
+            let mut inputs: Vec<(u32, String)> = entries
+                .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+                .filter_map(|file_name| {
+                    let number = file_name.strip_prefix("temp")?.strip_suffix("_input")?;
+                    Some((number.parse().ok()?, file_name))
+                })
+                .collect();
+            inputs.sort();
+
+            for (number, file_name) in inputs {
+                let Some(millidegrees) = fs::read_to_string(hwmon_path.join(&file_name))
+                    .ok()
+                    .and_then(|content| content.trim().parse::<f32>().ok())
+                else {
+                    continue;
+                };
+                let label = fs::read_to_string(hwmon_path.join(format!("temp{}_label", number)))
+                    .map(|label| label.trim().to_string())
+                    .unwrap_or_else(|_| format!("temp{}", number));
+                temperatures.push((format!("{} {}", device, label), millidegrees / 1000.0));
+            }
+        }
+        Ok(temperatures)
+    }
 }
 
 impl Default for CpuTempDetector {
@@ -272,6 +423,127 @@ impl Default for CpuTempDetector {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::TempDir;
+
+    /// hwmon root holding one k10temp device with the given (label, millidegrees) inputs
+    fn fake_k10temp(inputs: &[(&str, &str)]) -> TempDir {
+        let root = TempDir::new("k10temp");
+        root.write("hwmon0/name", "k10temp\n");
+        for (n, (label, millidegrees)) in inputs.iter().enumerate() {
+            root.write(
+                &format!("hwmon0/temp{}_label", n + 1),
+                &format!("{}\n", label),
+            );
+            root.write(
+                &format!("hwmon0/temp{}_input", n + 1),
+                &format!("{}\n", millidegrees),
+            );
+        }
+        root
+    }
+
+    fn detect(root: &TempDir, tctl_offset: f32) -> CpuTempDetector {
+        let mut detector = CpuTempDetector::new();
+        detector.set_tctl_offset(tctl_offset);
+        detector.sensors = detector
+            .find_cpu_temp_sensors_in(root.path(), &CpuManufacturer::Amd)
+            .unwrap();
+        detector
+    }
+
+    #[test]
+    fn test_amd_prefers_tdie() {
+        let root = fake_k10temp(&[("Tctl", "72000"), ("Tdie", "45000"), ("Tccd1", "40000")]);
+        let detector = detect(&root, 27.0);
+        assert_eq!(detector.get_sensor_info().unwrap().temp_label, "Tdie");
+        // The offset only applies to Tctl
+        assert_eq!(detector.read_temperature().unwrap(), 45.0);
+    }
+
+    #[test]
+    fn test_amd_tctl_offset() {
+        let root = fake_k10temp(&[("Tctl", "72000"), ("Tccd1", "40000")]);
+        let detector = detect(&root, 27.0);
+        assert_eq!(detector.get_sensor_info().unwrap().temp_label, "Tctl");
+        assert_eq!(detector.read_temperature().unwrap(), 45.0);
+
+        // No offset by default
+        assert_eq!(detect(&root, 0.0).read_temperature().unwrap(), 72.0);
+    }
+
+    #[test]
+    fn test_failover_to_next_sensor() {
+        let root = fake_k10temp(&[("Tctl", "60000"), ("Tdie", "45000")]);
+        let detector = detect(&root, 0.0);
+        assert_eq!(detector.sensors().len(), 2);
+        assert_eq!(detector.read_temperature().unwrap(), 45.0);
+
+        // The Tdie input disappears, e.g. during a driver reload
+        fs::remove_file(root.path().join("hwmon0/temp2_input")).unwrap();
+        assert_eq!(detector.read_temperature().unwrap(), 60.0);
+        assert_eq!(detector.get_sensor_info().unwrap().temp_label, "Tctl");
+
+        // The switch sticks even once the primary is back
+        root.write("hwmon0/temp2_input", "45000\n");
+        assert_eq!(detector.read_temperature().unwrap(), 60.0);
+
+        fs::remove_dir_all(root.path().join("hwmon0")).unwrap();
+        assert!(detector.read_temperature().is_err());
+    }
+
+    #[test]
+    fn test_initialize_from_fake_hwmon_root() {
+        // Both drivers are present, so detection works whatever CPU runs the test
+        let root = fake_k10temp(&[("Tctl", "51000")]);
+        root.write("hwmon1/name", "coretemp\n");
+        root.write("hwmon1/temp1_label", "Package id 0\n");
+        root.write("hwmon1/temp1_input", "51000\n");
+
+        let mut detector = CpuTempDetector::with_hwmon_root(root.path());
+        detector.initialize().unwrap();
+        assert!(detector
+            .get_sensor_info()
+            .unwrap()
+            .hwmon_path
+            .starts_with(&*root.path().to_string_lossy()));
+        assert_eq!(detector.read_temperature().unwrap(), 51.0);
+
+        let empty = TempDir::new("no-hwmon");
+        assert!(CpuTempDetector::with_hwmon_root(empty.path())
+            .initialize()
+            .is_err());
+    }
+
+    #[test]
+    fn test_read_all_temperatures() {
+        let root = fake_k10temp(&[("Tctl", "61500"), ("Tccd1", "48250")]);
+        root.write("hwmon1/name", "nvme\n");
+        root.write("hwmon1/temp1_label", "Composite\n");
+        root.write("hwmon1/temp1_input", "38850\n");
+        root.write("hwmon1/temp10_input", "41000\n");
+        root.write("hwmon1/temp2_input", "35000\n");
+        root.write("hwmon1/temp3_input", "unavailable\n");
+        root.write("hwmon2/name", "acpitz\n");
+        root.write("hwmon2/temp1_input", "27800\n");
+
+        let detector = CpuTempDetector::with_hwmon_root(root.path());
+        assert_eq!(
+            detector.read_all_temperatures().unwrap(),
+            [
+                ("k10temp Tctl".to_string(), 61.5),
+                ("k10temp Tccd1".to_string(), 48.25),
+                ("nvme Composite".to_string(), 38.85),
+                ("nvme temp2".to_string(), 35.0),
+                ("nvme temp10".to_string(), 41.0),
+                ("acpitz temp1".to_string(), 27.8),
+            ]
+        );
+
+        let missing = root.path().join("missing");
+        assert!(CpuTempDetector::with_hwmon_root(missing)
+            .read_all_temperatures()
+            .is_err());
+    }
 
     #[test]
     fn test_cpu_manufacturer_detection() {
@@ -0,0 +1,401 @@
+//! Fan curves keyed by name, kept in insertion order
+
+use crate::errors::{FanCurveError, Result};
+use crate::fan::FanCurve;
+use log::warn;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut, Index};
+
+/// Fan curves with unique names, in the order they were added
+///
+/// Serializes as a plain list, so configs written before the registry load unchanged.
+/// Curves should be renamed through [`CurveRegistry::rename`]; see [`CurveMut`] for renames
+/// made through mutable access.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(from = "Vec<FanCurve>")]
+pub struct CurveRegistry {
+    curves: Vec<FanCurve>,
+    index: HashMap<String, usize>,
+}
+
+impl CurveRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.curves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.curves.is_empty()
+    }
+
+    /// Add a curve at the end
+    /// Fails if a curve with the same name exists; use [`CurveRegistry::replace`] to change it
+    pub fn insert(&mut self, curve: FanCurve) -> Result<()> {
+        if self.contains(curve.name()) {
+            return Err(FanCurveError::CurveNameTaken {
+                name: curve.name().to_string(),
+            });
+        }
+        self.index
+            .insert(curve.name().to_string(), self.curves.len());
+        self.curves.push(curve);
+        Ok(())
+    }
+
+    /// Replace the curve with the same name in place, returning the old one
+    /// Fails if there is no such curve or it is locked
+    pub fn replace(&mut self, curve: FanCurve) -> Result<FanCurve> {
+        let position =
+            self.position(curve.name())
+                .ok_or_else(|| FanCurveError::FanCurveNotFound {
+                    name: curve.name().to_string(),
+                })?;
+        self.curves[position].ensure_unlocked()?;
+        Ok(std::mem::replace(&mut self.curves[position], curve))
+    }
+
+    /// Remove a curve by name; later curves move up one place
+    pub fn remove(&mut self, name: &str) -> Option<FanCurve> {
+        let position = self.index.remove(name)?;
+        let curve = self.curves.remove(position);
+        for index in self.index.values_mut() {
+            if *index > position {
+                *index -= 1;
+            }
+        }
+        Some(curve)
+    }
+
+    /// Rename a curve, keeping its position
+    /// Fails if `old` doesn't exist, is locked, or `new` is already taken
+    pub fn rename(&mut self, old: &str, new: &str) -> Result<()> {
+        let position = self
+            .position(old)
+            .ok_or_else(|| FanCurveError::FanCurveNotFound {
+                name: old.to_string(),
+            })?;
+        if old == new {
+            return Ok(());
+        }
+        if new.trim().is_empty() {
+            return Err(FanCurveError::Config(
+                "Fan curve name cannot be empty".to_string(),
+            ));
+        }
+        if self.index.contains_key(new) {
+            return Err(FanCurveError::CurveNameTaken {
+                name: new.to_string(),
+            });
+        }
+
+        let curve = &mut self.curves[position];
+        curve.ensure_unlocked()?;
+        curve.set_name(new.to_string());
+        self.index.remove(old);
+        self.index.insert(new.to_string(), position);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&FanCurve> {
+        self.position(name).map(|position| &self.curves[position])
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<CurveMut<'_>> {
+        let position = self.position(name)?;
+        self.get_index_mut(position)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.index.contains_key(name)
+    }
+
+    /// Position of a curve in insertion order
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.index.get(name).copied()
+    }
+
+    pub fn get_index(&self, position: usize) -> Option<&FanCurve> {
+        self.curves.get(position)
+    }
+
+    pub fn get_index_mut(&mut self, position: usize) -> Option<CurveMut<'_>> {
+        let name = self.curves.get(position)?.name().to_string();
+        Some(CurveMut {
+            registry: self,
+            position,
+            name,
+        })
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, FanCurve> {
+        self.curves.iter()
+    }
+
+    /// Curves tagged with `tag` (ignoring case), in order
+    pub fn with_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a FanCurve> + 'a {
+        self.curves.iter().filter(move |curve| curve.has_tag(tag))
+    }
+
+    /// Every tag used by some curve, see [`unique_tags`]
+    pub fn tags(&self) -> Vec<String> {
+        unique_tags(&self.curves)
+    }
+
+    /// Curve names in order, e.g. for a `pick_list`
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.curves.iter().map(|curve| curve.name())
+    }
+
+    pub fn as_slice(&self) -> &[FanCurve] {
+        &self.curves
+    }
+
+    pub fn to_vec(&self) -> Vec<FanCurve> {
+        self.curves.clone()
+    }
+
+    pub fn into_vec(self) -> Vec<FanCurve> {
+        self.curves
+    }
+}
+
+/// Tags used by `curves`, sorted and without case-insensitive duplicates
+pub fn unique_tags<'a>(curves: impl IntoIterator<Item = &'a FanCurve>) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in curves.into_iter().flat_map(|curve| curve.tags()) {
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.clone());
+        }
+    }
+    tags.sort_by_key(|tag| tag.to_lowercase());
+    tags
+}
+
+/// Mutable access to a curve in a [`CurveRegistry`]
+///
+/// A rename made through it is picked up when it is dropped: the name index follows the new
+/// name if it is free, otherwise the old name is put back.
+pub struct CurveMut<'a> {
+    registry: &'a mut CurveRegistry,
+    position: usize,
+    /// Name the curve had when it was borrowed
+    name: String,
+}
+
+impl Deref for CurveMut<'_> {
+    type Target = FanCurve;
+
+    fn deref(&self) -> &FanCurve {
+        &self.registry.curves[self.position]
+    }
+}
+
+impl DerefMut for CurveMut<'_> {
+    fn deref_mut(&mut self) -> &mut FanCurve {
+        &mut self.registry.curves[self.position]
+    }
+}
+
+impl Drop for CurveMut<'_> {
+    fn drop(&mut self) {
+        let curve = &mut self.registry.curves[self.position];
+        if curve.name() == self.name {
+            return;
+        }
+        let new = curve.name().to_string();
+        if new.trim().is_empty() || self.registry.index.contains_key(&new) {
+            warn!(
+                "Can't rename fan curve '{}' to '{}', keeping the old name",
+                self.name, new
+            );
+            curve.set_name(self.name.clone());
+            return;
+        }
+        self.registry.index.remove(&self.name);
+        self.registry.index.insert(new, self.position);
+    }
+}
+
+impl From<Vec<FanCurve>> for CurveRegistry {
+    /// Later curves with a duplicate name replace the earlier one in its position
+    fn from(curves: Vec<FanCurve>) -> Self {
+        let mut registry = Self::new();
+        for curve in curves {
+            match registry.position(curve.name()) {
+                Some(position) => {
+                    warn!(
+                        "Duplicate fan curve '{}', keeping the last one",
+                        curve.name()
+                    );
+                    registry.curves[position] = curve;
+                }
+                None => {
+                    registry
+                        .index
+                        .insert(curve.name().to_string(), registry.curves.len());
+                    registry.curves.push(curve);
+                }
+            }
+        }
+        registry
+    }
+}
+
+impl FromIterator<FanCurve> for CurveRegistry {
+    fn from_iter<I: IntoIterator<Item = FanCurve>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl Serialize for CurveRegistry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.curves.serialize(serializer)
+    }
+}
+
+impl Index<usize> for CurveRegistry {
+    type Output = FanCurve;
+
+    fn index(&self, position: usize) -> &FanCurve {
+        &self.curves[position]
+    }
+}
+
+impl<'a> IntoIterator for &'a CurveRegistry {
+    type Item = &'a FanCurve;
+    type IntoIter = std::slice::Iter<'a, FanCurve>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve(name: &str) -> FanCurve {
+        let mut curve = FanCurve::standard();
+        curve.set_name(name.to_string());
+        curve
+    }
+
+    #[test]
+    fn test_registry_keeps_insertion_order() {
+        let mut registry: CurveRegistry = ["B", "A", "C"].into_iter().map(curve).collect();
+        assert_eq!(registry.names().collect::<Vec<_>>(), ["B", "A", "C"]);
+
+        // Names stay unique; replacing keeps the position
+        let mut louder = curve("A");
+        louder.offset_duty(500);
+        assert!(matches!(
+            registry.insert(louder.clone()),
+            Err(FanCurveError::CurveNameTaken { .. })
+        ));
+        assert_eq!(registry.replace(louder.clone()).unwrap(), curve("A"));
+        assert_eq!(registry.position("A"), Some(1));
+        assert_eq!(registry.get("A"), Some(&louder));
+        assert!(registry.replace(curve("D")).is_err());
+        registry.get_mut("A").unwrap().set_locked(true);
+        assert!(matches!(
+            registry.replace(curve("A")),
+            Err(FanCurveError::CurveLocked { .. })
+        ));
+        registry.get_mut("A").unwrap().set_locked(false);
+
+        assert_eq!(registry.remove("B").unwrap().name(), "B");
+        assert!(registry.remove("B").is_none());
+        assert_eq!(registry.names().collect::<Vec<_>>(), ["A", "C"]);
+        assert_eq!(registry.position("C"), Some(1));
+        assert_eq!(registry[1].name(), "C");
+
+        // Round-trips through the plain list format
+        let json = serde_json::to_string(&registry).unwrap();
+        assert_eq!(json, serde_json::to_string(&registry.to_vec()).unwrap());
+        assert_eq!(
+            serde_json::from_str::<CurveRegistry>(&json).unwrap(),
+            registry
+        );
+    }
+
+    #[test]
+    fn test_registry_tag_filter() {
+        let mut registry: CurveRegistry = ["Quiet", "Gaming", "Render"]
+            .into_iter()
+            .map(curve)
+            .collect();
+        registry
+            .get_mut("Quiet")
+            .unwrap()
+            .set_tags(["silent", "Summer"]);
+        registry
+            .get_mut("Render")
+            .unwrap()
+            .set_tags(["summer", "work"]);
+
+        let names = |tag| {
+            registry
+                .with_tag(tag)
+                .map(FanCurve::name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names("summer"), ["Quiet", "Render"]);
+        assert_eq!(names("work"), ["Render"]);
+        assert!(names("winter").is_empty());
+        assert_eq!(registry.tags(), ["silent", "Summer", "work"]);
+    }
+
+    #[test]
+    fn test_registry_rename() {
+        let mut registry: CurveRegistry =
+            ["Quiet", "Loud", "Custom"].into_iter().map(curve).collect();
+
+        registry.rename("Loud", "Turbo").unwrap();
+        assert_eq!(
+            registry.names().collect::<Vec<_>>(),
+            ["Quiet", "Turbo", "Custom"]
+        );
+        assert!(!registry.contains("Loud"));
+        assert_eq!(registry.get("Turbo").unwrap().name(), "Turbo");
+
+        assert!(matches!(
+            registry.rename("Quiet", "Custom"),
+            Err(FanCurveError::CurveNameTaken { .. })
+        ));
+        assert!(matches!(
+            registry.rename("Loud", "Louder"),
+            Err(FanCurveError::FanCurveNotFound { .. })
+        ));
+        assert!(registry.rename("Quiet", " ").is_err());
+        registry.rename("Quiet", "Quiet").unwrap();
+
+        registry.get_mut("Custom").unwrap().set_locked(true);
+        assert!(matches!(
+            registry.rename("Custom", "Mine"),
+            Err(FanCurveError::CurveLocked { .. })
+        ));
+        assert_eq!(
+            registry.names().collect::<Vec<_>>(),
+            ["Quiet", "Turbo", "Custom"]
+        );
+
+        // Renames through mutable access keep the index in sync, or are undone on a clash
+        registry
+            .get_index_mut(0)
+            .unwrap()
+            .set_name("Silent".to_string());
+        assert_eq!(registry.position("Silent"), Some(0));
+        assert!(!registry.contains("Quiet"));
+        registry
+            .get_mut("Silent")
+            .unwrap()
+            .set_name("Turbo".to_string());
+        assert_eq!(registry.position("Silent"), Some(0));
+        assert_eq!(registry.position("Turbo"), Some(1));
+        assert_eq!(registry[0].name(), "Silent");
+    }
+}
@@ -0,0 +1,204 @@
+//! Prometheus text-format metrics endpoint for the daemon
+
+use crate::{duty::Duty, errors::Result, fan_monitor::FanDataPoint};
+use log::{debug, info, warn};
+use std::fmt::Write as _;
+use std::future::Future;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::{timeout, Duration};
+
+/// Path the metrics are served at
+pub const METRICS_PATH: &str = "/metrics";
+
+/// How long a client gets to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest request header accepted
+const MAX_REQUEST_SIZE: usize = 8192;
+
+/// Serve metrics on `listener`, calling `sample` for fresh data on every scrape
+///
+/// Requests are handled one at a time, which is plenty for a scraper polling
+/// every few seconds.
+pub async fn serve<F, Fut>(listener: TcpListener, sample: F) -> Result<()>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<FanDataPoint>>,
+{
+    info!(
+        "Serving metrics on http://{}{}",
+        listener.local_addr()?,
+        METRICS_PATH
+    );
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        debug!("Metrics request from {}", peer);
+        if let Err(e) = handle_connection(stream, &sample).await {
+            warn!("Failed to answer metrics request from {}: {}", peer, e);
+        }
+    }
+}
+
+/// Answer a single HTTP request
+async fn handle_connection<F, Fut>(mut stream: TcpStream, sample: &F) -> Result<()>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<FanDataPoint>>,
+{
+    let request = match timeout(REQUEST_TIMEOUT, read_request(&mut stream)).await {
+        Ok(request) => request?,
+        Err(_) => return Ok(()),
+    };
+
+    let mut parts = request.split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(METRICS_PATH)) => match sample().await {
+            Ok(data) => http_response("200 OK", &format_metrics(&data)),
+            Err(e) => http_response(
+                "503 Service Unavailable",
+                &format!("fan data unavailable: {}\n", e),
+            ),
+        },
+        (Some("GET"), Some(_)) => http_response("404 Not Found", "not found\n"),
+        _ => http_response("405 Method Not Allowed", "method not allowed\n"),
+    };
+
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Read the request head; the body, if any, is ignored
+async fn read_request(stream: &mut TcpStream) -> Result<String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") && buf.len() < MAX_REQUEST_SIZE {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+fn http_response(status: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+/// Escape a Prometheus label value
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Render a fan data sample in the Prometheus text exposition format
+pub fn format_metrics(data: &FanDataPoint) -> String {
+    let mut out = String::new();
+
+    let _ = writeln!(
+        out,
+        "# HELP fan_curve_temperature_celsius Temperature driving the fan curve\n\
+         # TYPE fan_curve_temperature_celsius gauge\n\
+         fan_curve_temperature_celsius {}",
+        data.temperature
+    );
+
+    let _ = writeln!(
+        out,
+        "# HELP fan_curve_fan_rpm Fan speed in revolutions per minute\n\
+         # TYPE fan_curve_fan_rpm gauge"
+    );
+    let fans = data
+        .cpu_fan_speeds
+        .iter()
+        .chain(&data.intake_fan_speeds)
+        .chain(&data.gpu_fan_speeds);
+    for (_, rpm, label) in fans {
+        let _ = writeln!(
+            out,
+            "fan_curve_fan_rpm{{fan=\"{}\"}} {}",
+            escape_label(label),
+            rpm
+        );
+    }
+
+    let _ = writeln!(
+        out,
+        "# HELP fan_curve_duty_ratio Fan duty from 0 to 1\n\
+         # TYPE fan_curve_duty_ratio gauge\n\
+         fan_curve_duty_ratio {}",
+        Duty::from_ten_thousandths(data.fan_duty).as_fraction()
+    );
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn sample_data() -> FanDataPoint {
+        FanDataPoint {
+            timestamp: chrono::Local::now(),
+            temperature: 47.5,
+            cpu_fan_speeds: vec![(1, 1250, "CPU Fan".to_string())],
+            intake_fan_speeds: vec![(2, 800, "Intake \"Front\"".to_string())],
+            gpu_fan_speeds: Vec::new(),
+            fan_duty: 4500,
+            cpu_usage: 12.0,
+            cpu_model: "Test CPU".to_string(),
+            cpu_core_count: 8,
+        }
+    }
+
+    async fn get(addr: std::net::SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).as_bytes())
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, || async { Ok(sample_data()) }));
+
+        let response = get(addr, METRICS_PATH).await;
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK"));
+
+        let samples: HashMap<&str, f64> = body
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| {
+                let (name, value) = line.rsplit_once(' ').unwrap();
+                (name, value.parse().unwrap())
+            })
+            .collect();
+        assert_eq!(samples["fan_curve_temperature_celsius"], 47.5);
+        assert_eq!(samples["fan_curve_fan_rpm{fan=\"CPU Fan\"}"], 1250.0);
+        assert_eq!(
+            samples["fan_curve_fan_rpm{fan=\"Intake \\\"Front\\\"\"}"],
+            800.0
+        );
+        assert_eq!(samples["fan_curve_duty_ratio"], 0.45);
+
+        assert!(get(addr, "/").await.starts_with("HTTP/1.1 404"));
+    }
+}
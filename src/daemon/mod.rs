@@ -1,29 +1,65 @@
 //! Daemon implementation for the fan curve application
 
+pub mod metrics;
+pub mod notify;
+
 use crate::{
-    errors::{zbus_error_from_display, FanCurveError, Result},
-    fan::{FanCurve, FanCurveConfig},
+    cpu_temp::CpuTempDetector,
+    duty::Duty,
+    errors::{DaemonError, FanCurveError, Result},
+    fan::{FanControlMode, FanCurve, FanCurveConfig, FanCurveDto, FanLink, MIN_POINT_TEMP},
+    fan_detector::FanDetector,
+    fan_monitor::{FanDataDto, FanDataPoint, FanMonitor},
+    hardware::HardwareInfo,
+    schedule::{self, ScheduleEntry, SCHEDULE_CHECK_INTERVAL},
+    temperature::TemperatureSource,
     thelio_io::ThelioIoClient,
     DBUS_OBJECT_PATH, DBUS_SERVICE_NAME,
 };
-use log::{debug, error, info};
+use chrono::{Local, NaiveTime};
+use futures_util::stream::StreamExt;
+use log::{debug, error, info, warn};
+use std::future::Future;
+use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
-use tokio::time::{sleep, Duration};
-use zbus::{dbus_interface, ConnectionBuilder, SignalContext};
+use std::time::Instant;
+use tokio::net::TcpListener;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::time::Duration;
+use zbus::{
+    dbus_interface, Connection, ConnectionBuilder, MatchRule, MessageStream, SignalContext,
+};
+
+/// Longest boost accepted over DBus, in seconds
+pub const MAX_BOOST_SECONDS: u32 = 3600;
 
 /// Main daemon structure
 pub struct FanCurveDaemon {
     config: Arc<Mutex<FanCurveConfig>>,
     current_curve_index: Arc<Mutex<usize>>,
+    /// Curve tried out with `ApplyFanCurveInline` without saving it, used instead of the
+    /// selected curve until another curve is selected
+    inline_curve: Arc<Mutex<Option<FanCurve>>>,
+    /// Drives the fans for the daemon's whole life, so spin-up, ramp-down, idle and RPM
+    /// state carry over between polls
+    monitor: Arc<tokio::sync::Mutex<FanMonitor>>,
     #[allow(dead_code)]
     thelio: Option<ThelioIoClient>,
+    metrics_addr: Option<SocketAddr>,
+    restore_auto_on_exit: bool,
+    /// Temperature used when applying a newly selected curve; local sensors when None
+    temperature_reader: Option<Arc<dyn TemperatureSource>>,
 }
 
 impl FanCurveDaemon {
     /// Create a new daemon instance
     pub fn new() -> Result<Self> {
-        let config = Arc::new(Mutex::new(Self::load_config()?));
-        let current_curve_index = Arc::new(Mutex::new(0));
+        let config = Self::load_config()?;
+        let current_curve_index = Arc::new(Mutex::new(config.initial_curve_index()));
+        let mut monitor = FanMonitor::new();
+        monitor.configure(&config);
+        let monitor = Arc::new(tokio::sync::Mutex::new(monitor));
+        let config = Arc::new(Mutex::new(config));
 
         // Thelio client is optional and non-fatal if unavailable
         let thelio = match ThelioIoClient::new() {
@@ -40,35 +76,187 @@ impl FanCurveDaemon {
         Ok(Self {
             config,
             current_curve_index,
+            inline_curve: Arc::default(),
+            monitor,
             thelio,
+            metrics_addr: None,
+            restore_auto_on_exit: true,
+            temperature_reader: None,
         })
     }
 
+    /// Serve Prometheus metrics on `addr` while running; off when None
+    pub fn set_metrics_addr(&mut self, addr: Option<SocketAddr>) {
+        self.metrics_addr = addr;
+    }
+
+    /// Return the fans to automatic control when the daemon is stopped (on by default)
+    pub fn set_restore_auto_on_exit(&mut self, restore: bool) {
+        self.restore_auto_on_exit = restore;
+    }
+
     /// Load configuration from file or create default
+    /// With `import_system76_curves` set, curves saved by system76-power are added too
     fn load_config() -> Result<FanCurveConfig> {
-        let config_path = FanCurveConfig::get_config_path();
+        let config_path = FanCurveConfig::get_daemon_config_path();
         if config_path.exists() {
-            FanCurveConfig::load_from_file(&config_path)
-                .map_err(|e| FanCurveError::Config(format!("Failed to load config: {}", e)))
+            let mut config = FanCurveConfig::load_from_file(&config_path)
+                .map_err(|e| e.context("Failed to load config"))?;
+            for curve in &config.curves {
+                for lint in curve.safety_lint() {
+                    warn!("Fan curve '{}': {}", curve.name(), lint);
+                }
+            }
+            if config.import_system76_curves {
+                for dir in FanCurveConfig::get_curve_export_dirs() {
+                    match config.import_system76_curves(&dir) {
+                        Ok(0) => {}
+                        Ok(added) => info!("Imported {} fan curves from {}", added, dir.display()),
+                        Err(e) => {
+                            warn!("Failed to import fan curves from {}: {}", dir.display(), e)
+                        }
+                    }
+                }
+            }
+            Ok(config)
         } else {
             let config = FanCurveConfig::new();
-            config.save_to_file(&config_path).map_err(|e| {
-                FanCurveError::Config(format!("Failed to save default config: {}", e))
-            })?;
+            config
+                .save_to_file(&config_path)
+                .map_err(|e| e.context("Failed to save default config"))?;
             Ok(config)
         }
     }
 
     /// Save configuration to file
     fn save_config_internal(&self) -> Result<()> {
-        let config = self.config.lock().unwrap();
-        let config_path = FanCurveConfig::get_config_path();
-        if let Some(parent) = config_path.parent() {
-            std::fs::create_dir_all(parent).map_err(FanCurveError::Io)?;
-        }
+        Self::save_config_file(&self.config.lock().unwrap())
+    }
+
+    /// Save `config` to the daemon config file and mirror its curves
+    fn save_config_file(config: &FanCurveConfig) -> Result<()> {
+        let config_path = FanCurveConfig::get_daemon_config_path();
         config
             .save_to_file(&config_path)
-            .map_err(|e| FanCurveError::Config(format!("Failed to save config: {}", e)))
+            .map_err(|e| e.context("Failed to save config"))?;
+
+        Self::export_curves(config);
+        Ok(())
+    }
+
+    /// Mirror curves into system76-power's curve directories
+    /// Failures are logged but never fatal, since these directories normally need root
+    fn export_curves(config: &FanCurveConfig) {
+        for dir in FanCurveConfig::get_curve_export_dirs() {
+            match config.export_system76_curves(&dir) {
+                Ok(()) => debug!("Exported fan curves to {}", dir.display()),
+                Err(e) if e.is_permission_denied() => {
+                    debug!(
+                        "No permission to write fan curves to {}, skipping",
+                        dir.display()
+                    );
+                }
+                Err(e) => warn!("Failed to export fan curves to {}: {}", dir.display(), e),
+            }
+        }
+    }
+
+    /// Switch to a curve and remember it as the active one across restarts
+    fn activate_curve(&self, index: usize) -> Result<()> {
+        Self::select_curve(
+            &self.config,
+            &self.current_curve_index,
+            &self.inline_curve,
+            index,
+        )
+    }
+
+    /// Select the curve at `index` and save it as the active one, ending any inline curve
+    /// Shared by DBus calls, power profiles and the schedule; the control loop applies it
+    fn select_curve(
+        config: &Mutex<FanCurveConfig>,
+        current_curve_index: &Mutex<usize>,
+        inline_curve: &Mutex<Option<FanCurve>>,
+        index: usize,
+    ) -> Result<()> {
+        *current_curve_index.lock().unwrap() = index;
+        *inline_curve.lock().unwrap() = None;
+        let mut config = config.lock().unwrap();
+        config.active_curve_index = Some(index);
+        Self::save_config_file(&config)
+    }
+
+    /// Drive the fans with the curve at `index` once, so a new selection takes effect right away
+    /// Firmware-controlled curves return the fans to automatic mode. Failures are only logged,
+    /// since the selection itself has already been saved
+    async fn apply_selected_curve(&self, index: usize) {
+        let Some(curve) = self.config.lock().unwrap().curves.get_index(index).cloned() else {
+            return;
+        };
+        match self.apply_curve(&curve).await {
+            Ok(_) => info!("Fan curve '{}' applied to the fans", curve.name()),
+            Err(e) => warn!("Failed to apply fan curve '{}': {}", curve.name(), e),
+        }
+    }
+
+    /// Pick up config changes in the monitor driving the fans
+    async fn reconfigure_monitor(&self) {
+        let mut monitor = self.monitor.lock().await;
+        monitor.configure(&self.config.lock().unwrap());
+    }
+
+    /// Reject `label` unless it is one of the detected fans
+    async fn check_fan_detected(&self, label: &str) -> std::result::Result<(), DaemonError> {
+        let monitor = self.monitor.lock().await;
+        let fans = monitor.fan_detector().get_fans();
+        if fans.iter().any(|fan| fan.fan_label == label) {
+            return Ok(());
+        }
+        let detected: Vec<&str> = fans.iter().map(|fan| fan.fan_label.as_str()).collect();
+        Err(DaemonError::InvalidArgument(format!(
+            "Fan '{}' not detected, the detected fans are: {}",
+            label,
+            detected.join(", ")
+        )))
+    }
+
+    /// The inline curve if one is being tried out, otherwise the curve at the selected index
+    fn active_curve(
+        config: &Mutex<FanCurveConfig>,
+        current_curve_index: &Mutex<usize>,
+        inline_curve: &Mutex<Option<FanCurve>>,
+    ) -> Option<FanCurve> {
+        if let Some(curve) = inline_curve.lock().unwrap().clone() {
+            return Some(curve);
+        }
+        let index = *current_curve_index.lock().unwrap();
+        config.lock().unwrap().curves.get_index(index).cloned()
+    }
+
+    /// Make `curve` the one `monitor` drives the fans with, unless it already is
+    fn use_curve(monitor: &mut FanMonitor, curve: FanCurve) {
+        if monitor.fan_curve() != Some(&curve) {
+            monitor.set_fan_curve(curve);
+        }
+    }
+
+    /// Drive the fans with `curve` at the current temperature, using the config's overrides
+    /// Returns the duty the curve calls for (ten-thousandths), 0 for firmware-controlled curves
+    async fn apply_curve(&self, curve: &FanCurve) -> Result<u16> {
+        let mut monitor = self.monitor.lock().await;
+        Self::use_curve(&mut monitor, curve.clone());
+
+        // Firmware curves don't look at the temperature
+        if curve.is_auto() {
+            monitor.apply_fan_curve(0.0).await?;
+            return Ok(0);
+        }
+        let temperature = match &self.temperature_reader {
+            Some(reader) => reader.read_celsius().await?,
+            None => monitor.read_temperature()?,
+        };
+        monitor.apply_fan_curve(temperature).await?;
+        Ok(monitor.expected_duty(temperature))
     }
 
     /// Send a fan curve changed signal
@@ -78,11 +266,216 @@ impl FanCurveDaemon {
         info!("Fan curve changed - signal would be sent to fan monitor");
     }
 
+    /// Switch to the curve mapped to a power profile, if a mapping exists
+    fn select_curve_for_profile(
+        config: &Mutex<FanCurveConfig>,
+        current_curve_index: &Mutex<usize>,
+        inline_curve: &Mutex<Option<FanCurve>>,
+        profile: &str,
+    ) {
+        let selected = {
+            let config = config.lock().unwrap();
+            config
+                .curve_index_for_profile(profile)
+                .map(|index| (index, config.curves[index].name().to_string()))
+        };
+        let Some((index, name)) = selected else {
+            debug!(
+                "No fan curve mapped to power profile '{}', keeping current curve",
+                profile
+            );
+            return;
+        };
+        if let Err(e) = Self::select_curve(config, current_curve_index, inline_curve, index) {
+            warn!("Failed to save active fan curve: {}", e);
+        }
+        info!(
+            "Power profile '{}' active, fan curve set to: {}",
+            profile, name
+        );
+    }
+
+    /// Listen for System76 Power profile switches and select the mapped curve
+    async fn watch_power_profile(
+        connection: Connection,
+        config: Arc<Mutex<FanCurveConfig>>,
+        current_curve_index: Arc<Mutex<usize>>,
+        inline_curve: Arc<Mutex<Option<FanCurve>>>,
+    ) -> Result<()> {
+        let match_rule = MatchRule::builder()
+            .msg_type(zbus::MessageType::Signal)
+            .sender("com.system76.PowerDaemon")?
+            .interface("com.system76.PowerDaemon")?
+            .member("PowerProfileSwitch")?
+            .build();
+
+        let mut stream = MessageStream::for_match_rule(match_rule, &connection, None).await?;
+        info!("Listening for power profile switches");
+
+        while let Some(msg) = stream.next().await {
+            let profile = match msg.and_then(|msg| msg.body::<String>()) {
+                Ok(profile) => profile,
+                Err(e) => {
+                    warn!("Failed to read power profile switch signal: {}", e);
+                    continue;
+                }
+            };
+            Self::select_curve_for_profile(&config, &current_curve_index, &inline_curve, &profile);
+        }
+
+        Ok(())
+    }
+
+    /// Select the scheduled curve if a different schedule window than `active` contains `time`
+    /// Only the start of a window switches curves, so a curve picked by hand inside a
+    /// window stays until the next one starts
+    fn select_curve_for_schedule(
+        config: &Mutex<FanCurveConfig>,
+        current_curve_index: &Mutex<usize>,
+        inline_curve: &Mutex<Option<FanCurve>>,
+        active: &mut Option<ScheduleEntry>,
+        time: NaiveTime,
+    ) {
+        let (entry, index) = {
+            let config = config.lock().unwrap();
+            let entry =
+                schedule::active_entry(&config.schedule, time).map(|i| config.schedule[i].clone());
+            if entry == *active {
+                return;
+            }
+            *active = entry.clone();
+
+            let Some(entry) = entry else {
+                return;
+            };
+            let index = config.curves.position(&entry.curve_name);
+            (entry, index)
+        };
+
+        let Some(index) = index else {
+            warn!(
+                "Schedule {} uses missing fan curve '{}', keeping current curve",
+                entry.window(),
+                entry.curve_name
+            );
+            return;
+        };
+        if let Err(e) = Self::select_curve(config, current_curve_index, inline_curve, index) {
+            warn!("Failed to save active fan curve: {}", e);
+        }
+        info!(
+            "Schedule {} started, fan curve set to: {}",
+            entry.window(),
+            entry.curve_name
+        );
+    }
+
+    /// Check the schedule every [`SCHEDULE_CHECK_INTERVAL`] against the local time
+    async fn watch_schedule(
+        config: Arc<Mutex<FanCurveConfig>>,
+        current_curve_index: Arc<Mutex<usize>>,
+        inline_curve: Arc<Mutex<Option<FanCurve>>>,
+    ) {
+        let mut active = None;
+        let mut interval = tokio::time::interval(SCHEDULE_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            Self::select_curve_for_schedule(
+                &config,
+                &current_curve_index,
+                &inline_curve,
+                &mut active,
+                Local::now().time(),
+            );
+        }
+    }
+
+    /// Serve the latest sample of the control loop, so the metrics show what drives the fans
+    async fn serve_metrics(
+        listener: TcpListener,
+        monitor: Arc<tokio::sync::Mutex<FanMonitor>>,
+    ) -> Result<()> {
+        metrics::serve(listener, || async {
+            let monitor = monitor.lock().await;
+            monitor
+                .history()
+                .back()
+                .cloned()
+                .ok_or_else(|| FanCurveError::Config("No fan data sampled yet".to_string()))
+        })
+        .await
+    }
+
+    /// Drive the fans with the active curve every `interval`, emitting `FanDataUpdated`
+    /// with each sample
+    /// A failed read is handled by [`FanMonitor::control_from_sample`], which falls back to
+    /// the last good reading and then to the critical duty
+    async fn control_fans(
+        signal_ctx: SignalContext<'static>,
+        interval: Duration,
+        monitor: Arc<tokio::sync::Mutex<FanMonitor>>,
+        config: Arc<Mutex<FanCurveConfig>>,
+        current_curve_index: Arc<Mutex<usize>>,
+        inline_curve: Arc<Mutex<Option<FanCurve>>>,
+    ) -> Result<()> {
+        Self::publish_fan_data(signal_ctx, interval, || {
+            let curve = Self::active_curve(&config, &current_curve_index, &inline_curve);
+            let monitor = monitor.clone();
+            async move {
+                let mut monitor = monitor.lock().await;
+                if let Some(curve) = curve {
+                    Self::use_curve(&mut monitor, curve);
+                }
+                let sample = monitor.get_current_fan_data_direct();
+                monitor.control_from_sample(sample, Instant::now()).await
+            }
+        })
+        .await
+    }
+
+    /// Emit `FanDataUpdated` with a fresh sample every `interval`
+    /// Samples that fail to read are skipped; stops once a signal can't be sent
+    async fn publish_fan_data<F, Fut>(
+        signal_ctx: SignalContext<'static>,
+        interval: Duration,
+        mut sample: F,
+    ) -> Result<()>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<FanDataPoint>>,
+    {
+        let mut ticks = tokio::time::interval(interval);
+        loop {
+            ticks.tick().await;
+            match sample().await {
+                Ok(data) => Self::fan_data_updated(&signal_ctx, FanDataDto::from(&data)).await?,
+                Err(e) => debug!("Skipping fan data update: {}", e),
+            }
+        }
+    }
+
     /// Run the daemon
     pub async fn run(self) -> Result<()> {
         info!("Starting fan curve daemon");
 
-        let _connection = ConnectionBuilder::system()?
+        let config = self.config.clone();
+        let current_curve_index = self.current_curve_index.clone();
+        let inline_curve = self.inline_curve.clone();
+        let monitor = self.monitor.clone();
+        let restore_auto_on_exit = self.restore_auto_on_exit;
+        let poll_interval = config.lock().unwrap().poll_interval();
+
+        if let Some(addr) = self.metrics_addr {
+            let listener = TcpListener::bind(addr).await?;
+            let monitor = monitor.clone();
+            tokio::spawn(async move {
+                if let Err(e) = Self::serve_metrics(listener, monitor).await {
+                    warn!("Metrics endpoint stopped: {}", e);
+                }
+            });
+        }
+
+        let connection = ConnectionBuilder::system()?
             .name(DBUS_SERVICE_NAME)?
             .serve_at(DBUS_OBJECT_PATH, self)?
             .build()
@@ -90,10 +483,114 @@ impl FanCurveDaemon {
 
         info!("Daemon started, listening on DBus");
 
-        // Keep the daemon running
+        tokio::spawn(Self::watch_schedule(
+            config.clone(),
+            current_curve_index.clone(),
+            inline_curve.clone(),
+        ));
+
+        if let Err(e) = monitor.lock().await.initialize() {
+            warn!("Fan monitor initialization failed: {}", e);
+        }
+        let signal_ctx = SignalContext::new(&connection, DBUS_OBJECT_PATH)?;
+        let control = {
+            let monitor = monitor.clone();
+            let config = config.clone();
+            let current_curve_index = current_curve_index.clone();
+            let inline_curve = inline_curve.clone();
+            tokio::spawn(async move {
+                if let Err(e) = Self::control_fans(
+                    signal_ctx,
+                    poll_interval,
+                    monitor,
+                    config,
+                    current_curve_index,
+                    inline_curve,
+                )
+                .await
+                {
+                    warn!("Fan control stopped: {}", e);
+                }
+            })
+        };
+
+        let notifier = notify::Notifier::from_env();
+        if let Some(notifier) = &notifier {
+            if let Err(e) = notifier.ready() {
+                warn!("Failed to notify systemd of readiness: {}", e);
+            }
+        }
+
+        tokio::spawn(async move {
+            if let Err(e) =
+                Self::watch_power_profile(connection, config, current_curve_index, inline_curve)
+                    .await
+            {
+                warn!("Power profile listener stopped: {}", e);
+            }
+        });
+
+        Self::run_until_shutdown(notifier).await?;
+        // Stop writing duties first, or the next poll would undo the restore
+        control.abort();
+        // The monitor's detector remembers the enable modes the fans had before
+        let detector = monitor.lock().await.fan_detector().clone();
+        Self::shutdown(restore_auto_on_exit, || Ok(detector))
+    }
+
+    /// Wait for a shutdown signal, pinging the systemd watchdog meanwhile if
+    /// running under systemd
+    async fn run_until_shutdown(notifier: Option<notify::Notifier>) -> Result<()> {
+        let Some(notifier) = notifier else {
+            return Self::wait_for_shutdown_signal().await;
+        };
+
+        let watchdog_usec = std::env::var(notify::WATCHDOG_USEC_ENV).ok();
+        let mut watchdog = tokio::time::interval(notify::ping_interval(watchdog_usec.as_deref()));
+        let shutdown = Self::wait_for_shutdown_signal();
+        tokio::pin!(shutdown);
+
         loop {
-            sleep(Duration::from_secs(1)).await;
+            tokio::select! {
+                result = &mut shutdown => return result,
+                _ = watchdog.tick() => {
+                    if let Err(e) = notifier.watchdog() {
+                        warn!("Failed to ping systemd watchdog: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Wait for SIGTERM or SIGINT
+    async fn wait_for_shutdown_signal() -> Result<()> {
+        let mut sigterm = signal(SignalKind::terminate())?;
+        tokio::select! {
+            _ = sigterm.recv() => info!("Received SIGTERM, shutting down"),
+            result = tokio::signal::ctrl_c() => {
+                result?;
+                info!("Received SIGINT, shutting down");
+            }
         }
+        Ok(())
+    }
+
+    /// Clean up before exiting, handing the fans back to automatic control if enabled
+    /// so they aren't left stuck at the last manual PWM value. Fans switched to manual by the
+    /// daemon get back the `pwmN_enable` mode they had before
+    fn shutdown<F>(restore_auto: bool, detect_fans: F) -> Result<()>
+    where
+        F: FnOnce() -> Result<FanDetector>,
+    {
+        if !restore_auto {
+            info!("Leaving fan control mode unchanged on exit");
+            return Ok(());
+        }
+
+        info!("Restoring automatic fan control before exit");
+        let detector = detect_fans()?;
+        detector.set_duty(None)?;
+        detector.restore_enable_mode()
     }
 }
 
@@ -105,37 +602,150 @@ impl FanCurveDaemon {
         info!("Emitting fan curve changed signal");
         Ok(())
     }
-    /// Get all available fan curves
-    async fn get_fan_curves(&self) -> zbus::fdo::Result<Vec<FanCurve>> {
+
+    /// Signal emitted when the fans should run at `duty` (ten-thousandths) for `seconds`
+    #[dbus_interface(signal)]
+    async fn boost_started(
+        signal_ctx: &SignalContext<'_>,
+        duty: u16,
+        seconds: u32,
+    ) -> zbus::Result<()>;
+
+    /// Signal emitted every poll interval with the latest temperature, duty and fan speeds
+    #[dbus_interface(signal)]
+    async fn fan_data_updated(signal_ctx: &SignalContext<'_>, data: FanDataDto)
+        -> zbus::Result<()>;
+
+    /// Crate version of the running daemon
+    #[dbus_interface(property)]
+    async fn version(&self) -> String {
+        crate::VERSION.to_string()
+    }
+
+    /// Git hash the running daemon was built from
+    #[dbus_interface(property)]
+    async fn git_hash(&self) -> String {
+        crate::GIT_HASH.to_string()
+    }
+
+    /// When the running daemon was built
+    #[dbus_interface(property)]
+    async fn build_time(&self) -> String {
+        crate::BUILD_TIME.to_string()
+    }
+
+    /// Run the fans at `percent` for `seconds`, after which they return to the curve
+    /// The daemon's own control loop applies the boost; `BoostStarted` tells other monitors
+    async fn boost(
+        &self,
+        #[zbus(signal_context)] signal_ctx: SignalContext<'_>,
+        percent: u8,
+        seconds: u32,
+    ) -> std::result::Result<(), DaemonError> {
+        debug!("Boosting fans to {}% for {}s", percent, seconds);
+
+        if percent > 100 || !(1..=MAX_BOOST_SECONDS).contains(&seconds) {
+            return Err(DaemonError::InvalidArgument(format!(
+                "Boost needs 0-100% for 1-{} seconds",
+                MAX_BOOST_SECONDS
+            )));
+        }
+
+        let duty = Duty::from_percent(f32::from(percent)).as_ten_thousandths();
+        self.monitor
+            .lock()
+            .await
+            .set_boost(duty, Duration::from_secs(seconds.into()));
+        Self::boost_started(&signal_ctx, duty, seconds).await?;
+
+        info!("Boosting fans to {}% for {}s", percent, seconds);
+        Ok(())
+    }
+
+    /// Get all available fan curves as names and points, signature `a(sa(nq))`
+    /// Kept unchanged for older clients; `GetFanCurvesV2` has every curve setting
+    async fn get_fan_curves(&self) -> std::result::Result<Vec<FanCurveDto>, DaemonError> {
         debug!("Getting fan curves");
         let config = self.config.lock().unwrap();
-        Ok(config.curves.clone())
+        Ok(config.curves.iter().map(FanCurveDto::from).collect())
     }
 
-    /// Get current fan curve
-    async fn get_current_fan_curve(&self) -> zbus::fdo::Result<FanCurve> {
+    /// Get all available fan curves with their settings, signature `a(sa(nq)qqbbas)`
+    #[dbus_interface(name = "GetFanCurvesV2")]
+    async fn get_fan_curves_v2(&self) -> std::result::Result<Vec<FanCurve>, DaemonError> {
+        debug!("Getting fan curves (v2)");
+        let config = self.config.lock().unwrap();
+        Ok(config.curves.to_vec())
+    }
+
+    /// Get the fan curves tagged with `tag`, ignoring case
+    async fn get_fan_curves_by_tag(
+        &self,
+        tag: &str,
+    ) -> std::result::Result<Vec<FanCurve>, DaemonError> {
+        debug!("Getting fan curves tagged '{}'", tag);
+        let config = self.config.lock().unwrap();
+        Ok(config.curves.with_tag(tag).cloned().collect())
+    }
+
+    /// Duty in percent the named curve gives at `temp_celsius`, without touching the fans
+    async fn simulate_duty(
+        &self,
+        curve_name: &str,
+        temp_celsius: f32,
+    ) -> std::result::Result<f32, DaemonError> {
+        debug!("Simulating fan curve {} at {}°C", curve_name, temp_celsius);
+        if !temp_celsius.is_finite() {
+            return Err(DaemonError::InvalidArgument(format!(
+                "Invalid temperature: {}",
+                temp_celsius
+            )));
+        }
+        let config = self.config.lock().unwrap();
+        let curve = config.curve(curve_name)?;
+        Ok(curve.calculate_duty_f32(temp_celsius))
+    }
+
+    /// Every hwmon temperature as (`<device> <label>`, °C), for picking or checking sensors
+    async fn get_all_temperatures(&self) -> std::result::Result<Vec<(String, f32)>, DaemonError> {
+        debug!("Reading all temperature sensors");
+        Ok(CpuTempDetector::new().read_all_temperatures()?)
+    }
+
+    /// Get current fan curve, the inline curve while one is being tried out
+    async fn get_current_fan_curve(&self) -> std::result::Result<FanCurve, DaemonError> {
         debug!("Getting current fan curve");
+        if let Some(curve) = self.inline_curve.lock().unwrap().clone() {
+            return Ok(curve);
+        }
         let config = self.config.lock().unwrap();
         let current_index = self.current_curve_index.lock().unwrap();
         Ok(config.curves[*current_index].clone())
     }
 
     /// Set current fan curve by index
-    async fn set_fan_curve(&self, index: u32) -> zbus::fdo::Result<()> {
+    async fn set_fan_curve(&self, index: u32) -> std::result::Result<(), DaemonError> {
         debug!("Setting fan curve to index {}", index);
         let curve_name = {
-            let mut current_index = self.current_curve_index.lock().unwrap();
             let config = self.config.lock().unwrap();
-
-            if index as usize >= config.curves.len() {
-                return Err(zbus_error_from_display("Invalid fan curve index"));
+            match config.curves.get_index(index as usize) {
+                Some(curve) => curve.name().to_string(),
+                None => {
+                    return Err(DaemonError::CurveNotFound(format!(
+                        "No fan curve at index {}",
+                        index
+                    )))
+                }
             }
-
-            *current_index = index as usize;
-            config.curves[*current_index].name().to_string()
         };
 
+        if let Err(e) = self.activate_curve(index as usize) {
+            error!("Failed to save active fan curve: {}", e);
+            return Err(e.context("Failed to save active fan curve").into());
+        }
+
         info!("Fan curve set to: {}", curve_name);
+        self.apply_selected_curve(index as usize).await;
 
         // Emit signal to notify fan monitor of the change
         self.send_fan_curve_changed_signal().await;
@@ -143,122 +753,187 @@ impl FanCurveDaemon {
         Ok(())
     }
 
+    /// Validate `curve`, make it the active curve and apply it to the fans in one call
+    /// With `persist` the curve is saved, replacing an unlocked curve with the same name in
+    /// place. Without it the config is left alone and the curve only drives the fans until
+    /// another curve is selected, so a curve being edited can be tried out first.
+    /// Returns the duty applied (ten-thousandths)
+    async fn apply_fan_curve_inline(
+        &self,
+        #[zbus(signal_context)] signal_ctx: SignalContext<'_>,
+        curve: FanCurveDto,
+        persist: bool,
+    ) -> std::result::Result<u16, DaemonError> {
+        debug!("Applying inline fan curve '{}'", curve.name);
+        let curve = FanCurve::from(curve);
+        curve.validate().map_err(|e| {
+            warn!("Rejected inline fan curve '{}': {}", curve.name(), e);
+            DaemonError::InvalidArgument(format!("Invalid fan curve: {}", e))
+        })?;
+
+        if persist {
+            let index = {
+                let mut config = self.config.lock().unwrap();
+                if config.curves.contains(curve.name()) {
+                    config.curves.replace(curve.clone())?;
+                } else {
+                    config.curves.insert(curve.clone())?;
+                }
+                config.curves.position(curve.name()).unwrap_or_default()
+            };
+            if let Err(e) = self.activate_curve(index) {
+                error!("Failed to save active fan curve: {}", e);
+                return Err(e.context("Failed to save active fan curve").into());
+            }
+        } else {
+            *self.inline_curve.lock().unwrap() = Some(curve.clone());
+        }
+
+        let duty = self.apply_curve(&curve).await.map_err(|e| {
+            warn!("Failed to apply fan curve '{}': {}", curve.name(), e);
+            e.context(format!("Failed to apply fan curve '{}'", curve.name()))
+        })?;
+        info!(
+            "Fan curve '{}' applied inline at {}{}",
+            curve.name(),
+            Duty::from_ten_thousandths(duty),
+            if persist { " and saved" } else { "" }
+        );
+        self.fan_curve_changed(&signal_ctx).await?;
+
+        Ok(duty)
+    }
+
     /// Set fan curve by name
-    async fn set_fan_curve_by_name(&self, name: &str) -> zbus::fdo::Result<()> {
+    async fn set_fan_curve_by_name(&self, name: &str) -> std::result::Result<(), DaemonError> {
         debug!("Setting fan curve to name: {}", name);
         let found = {
             let config = self.config.lock().unwrap();
-            config.curves.iter().position(|c| c.name() == name)
+            config.curves.position(name)
         };
 
         if let Some(index) = found {
-            {
-                let mut current_index = self.current_curve_index.lock().unwrap();
-                *current_index = index;
+            if let Err(e) = self.activate_curve(index) {
+                error!("Failed to save active fan curve: {}", e);
+                return Err(e.context("Failed to save active fan curve").into());
             }
             info!("Fan curve set to: {}", name);
+            self.apply_selected_curve(index).await;
 
             // Emit signal to notify fan monitor of the change
             self.send_fan_curve_changed_signal().await;
 
             Ok(())
         } else {
-            Err(zbus_error_from_display(format!(
-                "Fan curve not found: {}",
-                name
-            )))
+            Err(FanCurveError::FanCurveNotFound {
+                name: name.to_string(),
+            }
+            .into())
         }
     }
 
     /// Set default fan curve
-    async fn set_default_fan_curve(&self, name: &str) -> zbus::fdo::Result<()> {
+    async fn set_default_fan_curve(&self, name: &str) -> std::result::Result<(), DaemonError> {
         debug!("Setting default fan curve to: {}", name);
         let mut config = self.config.lock().unwrap();
 
-        if let Some(index) = config.curves.iter().position(|c| c.name() == name) {
+        if let Some(index) = config.curves.position(name) {
             config.default_curve_index = Some(index);
             drop(config);
 
             if let Err(e) = self.save_config_internal() {
                 error!("Failed to save config: {}", e);
-                return Err(zbus_error_from_display(format!(
-                    "Failed to save config: {}",
-                    e
-                )));
+                return Err(e.context("Failed to save config").into());
             }
 
             info!("Default fan curve set to: {}", name);
             Ok(())
         } else {
-            Err(zbus_error_from_display(format!(
-                "Fan curve not found: {}",
-                name
-            )))
+            Err(FanCurveError::FanCurveNotFound {
+                name: name.to_string(),
+            }
+            .into())
         }
     }
 
+    /// Rename a fan curve, keeping it active or default if it was
+    async fn rename_fan_curve(&self, old: &str, new: &str) -> std::result::Result<(), DaemonError> {
+        debug!("Renaming fan curve '{}' to '{}'", old, new);
+
+        self.config.lock().unwrap().rename_curve(old, new)?;
+
+        if let Err(e) = self.save_config_internal() {
+            error!("Failed to save config: {}", e);
+            return Err(e.context("Failed to save config").into());
+        }
+
+        info!("Fan curve '{}' renamed to '{}'", old, new);
+
+        // Emit signal to notify fan monitor of the change
+        self.send_fan_curve_changed_signal().await;
+
+        Ok(())
+    }
+
     /// Add a fan curve point
-    async fn add_fan_curve_point(&self, temp: i16, duty: u16) -> zbus::fdo::Result<()> {
+    async fn add_fan_curve_point(
+        &self,
+        temp: i16,
+        duty: u16,
+    ) -> std::result::Result<(), DaemonError> {
         debug!("Adding fan curve point: {}°C -> {}%", temp, duty);
 
-        if !(0..=100).contains(&temp) || duty > 100 {
-            return Err(zbus_error_from_display("Invalid fan curve point values"));
+        if !(MIN_POINT_TEMP..=100).contains(&temp) || duty > 100 {
+            return Err(FanCurveError::InvalidFanPoint { temp, duty }.into());
         }
 
-        let valid_index = {
+        {
             let mut config = self.config.lock().unwrap();
             let current_index = self.current_curve_index.lock().unwrap();
 
-            if *current_index < config.curves.len() {
-                config.curves[*current_index].add_point(temp, duty);
-                true
-            } else {
-                false
-            }
-        };
+            let Some(mut curve) = config.curves.get_index_mut(*current_index) else {
+                return Err(DaemonError::Failed(
+                    "Invalid current fan curve index".to_string(),
+                ));
+            };
+            curve.ensure_unlocked()?;
+            curve.add_point(temp, Duty::from_percent(duty as f32).as_ten_thousandths());
+        }
 
-        if valid_index {
-            if let Err(e) = self.save_config_internal() {
-                error!("Failed to save config: {}", e);
-                return Err(zbus_error_from_display(format!(
-                    "Failed to save config: {}",
-                    e
-                )));
-            }
+        if let Err(e) = self.save_config_internal() {
+            error!("Failed to save config: {}", e);
+            return Err(e.context("Failed to save config").into());
+        }
 
-            info!("Added fan curve point: {}°C -> {}%", temp, duty);
+        info!("Added fan curve point: {}°C -> {}%", temp, duty);
 
-            // Emit signal to notify fan monitor of the change
-            self.send_fan_curve_changed_signal().await;
+        // Emit signal to notify fan monitor of the change
+        self.send_fan_curve_changed_signal().await;
 
-            Ok(())
-        } else {
-            Err(zbus_error_from_display("Invalid current fan curve index"))
-        }
+        Ok(())
     }
 
     /// Remove last fan curve point
-    async fn remove_fan_curve_point(&self) -> zbus::fdo::Result<()> {
+    async fn remove_fan_curve_point(&self) -> std::result::Result<(), DaemonError> {
         debug!("Removing last fan curve point");
 
         let point_removed = {
             let mut config = self.config.lock().unwrap();
             let current_index = self.current_curve_index.lock().unwrap();
 
-            if *current_index < config.curves.len() {
-                config.curves[*current_index].remove_last_point().is_some()
-            } else {
-                return Err(zbus_error_from_display("Invalid current fan curve index"));
-            }
+            let Some(mut curve) = config.curves.get_index_mut(*current_index) else {
+                return Err(DaemonError::Failed(
+                    "Invalid current fan curve index".to_string(),
+                ));
+            };
+            curve.ensure_unlocked()?;
+            curve.remove_last_point().is_some()
         };
 
         if point_removed {
             if let Err(e) = self.save_config_internal() {
                 error!("Failed to save config: {}", e);
-                return Err(zbus_error_from_display(format!(
-                    "Failed to save config: {}",
-                    e
-                )));
+                return Err(e.context("Failed to save config").into());
             }
 
             info!("Removed last fan curve point");
@@ -268,23 +943,1257 @@ impl FanCurveDaemon {
 
             Ok(())
         } else {
-            Err(zbus_error_from_display("No points to remove"))
+            Err(DaemonError::Failed("No points to remove".to_string()))
+        }
+    }
+
+    /// Map a System76 power profile to a fan curve
+    async fn set_profile_curve_mapping(
+        &self,
+        profile: &str,
+        curve_name: &str,
+    ) -> std::result::Result<(), DaemonError> {
+        debug!(
+            "Mapping power profile '{}' to fan curve '{}'",
+            profile, curve_name
+        );
+
+        {
+            let mut config = self.config.lock().unwrap();
+            if !config.curves.contains(curve_name) {
+                return Err(FanCurveError::FanCurveNotFound {
+                    name: curve_name.to_string(),
+                }
+                .into());
+            }
+            config
+                .profile_curve_map
+                .insert(profile.to_string(), curve_name.to_string());
+        }
+
+        if let Err(e) = self.save_config_internal() {
+            error!("Failed to save config: {}", e);
+            return Err(e.context("Failed to save config").into());
+        }
+
+        info!(
+            "Power profile '{}' mapped to fan curve: {}",
+            profile, curve_name
+        );
+        Ok(())
+    }
+
+    /// Get the curve schedule as (start, end, curve name), times as `HH:MM`
+    async fn get_schedule(&self) -> std::result::Result<Vec<ScheduleEntry>, DaemonError> {
+        debug!("Getting schedule");
+        Ok(self.config.lock().unwrap().schedule.clone())
+    }
+
+    /// Replace the curve schedule; every entry must use an existing curve
+    async fn set_schedule(
+        &self,
+        schedule: Vec<ScheduleEntry>,
+    ) -> std::result::Result<(), DaemonError> {
+        debug!("Setting schedule with {} entries", schedule.len());
+        {
+            let mut config = self.config.lock().unwrap();
+            if let Some(entry) = schedule
+                .iter()
+                .find(|entry| !config.curves.contains(&entry.curve_name))
+            {
+                return Err(FanCurveError::FanCurveNotFound {
+                    name: entry.curve_name.clone(),
+                }
+                .into());
+            }
+            config.schedule = schedule;
+        }
+
+        if let Err(e) = self.save_config_internal() {
+            error!("Failed to save config: {}", e);
+            return Err(e.context("Failed to save config").into());
+        }
+        Ok(())
+    }
+
+    /// Set how the fan labelled `label` is driven: `curve`, `auto` or `manual:<percent>`
+    /// `curve` removes the fan's entry, so it follows the active curve again; that also works
+    /// for fans no longer detected, other modes need a detected fan
+    async fn set_fan_control(
+        &self,
+        label: &str,
+        mode: &str,
+    ) -> std::result::Result<(), DaemonError> {
+        debug!("Setting control mode of fan '{}' to '{}'", label, mode);
+        if label.trim().is_empty() {
+            return Err(DaemonError::InvalidArgument(
+                "Fan label cannot be empty".to_string(),
+            ));
+        }
+        let mode: FanControlMode = mode.parse()?;
+        if mode != FanControlMode::Curve {
+            self.check_fan_detected(label).await?;
+        }
+
+        {
+            let mut config = self.config.lock().unwrap();
+            if mode == FanControlMode::TargetRpm && !config.rpm_targets.contains_key(label) {
+                return Err(DaemonError::InvalidArgument(format!(
+                    "Fan '{}' has no target RPM curve in rpm_targets",
+                    label
+                )));
+            }
+            if mode == FanControlMode::Curve {
+                config.fan_control.remove(label);
+            } else {
+                config.fan_control.insert(label.to_string(), mode);
+            }
+        }
+
+        if let Err(e) = self.save_config_internal() {
+            error!("Failed to save config: {}", e);
+            return Err(e.context("Failed to save config").into());
+        }
+
+        self.reconfigure_monitor().await;
+        info!("Fan '{}' control mode set to {}", label, mode);
+        Ok(())
+    }
+
+    /// Run the fan labelled `follower` at the duty of `leader` plus `offset` (ten-thousandths)
+    /// Both fans must be detected. An empty `leader` removes the link, returning the follower
+    /// to its own control mode
+    async fn set_fan_link(
+        &self,
+        follower: &str,
+        leader: &str,
+        offset: i32,
+    ) -> std::result::Result<(), DaemonError> {
+        debug!(
+            "Linking fan '{}' to '{}' with offset {}",
+            follower, leader, offset
+        );
+        if follower.trim().is_empty() {
+            return Err(DaemonError::InvalidArgument(
+                "Fan label cannot be empty".to_string(),
+            ));
+        }
+        if !leader.is_empty() {
+            if leader == follower {
+                return Err(DaemonError::InvalidArgument(format!(
+                    "Fan '{}' can't follow itself",
+                    follower
+                )));
+            }
+            self.check_fan_detected(follower).await?;
+            self.check_fan_detected(leader).await?;
+        }
+
+        {
+            let mut config = self.config.lock().unwrap();
+            if leader.is_empty() {
+                config.remove_fan_link(follower);
+            } else {
+                config
+                    .set_fan_link(FanLink {
+                        follower: follower.to_string(),
+                        leader: leader.to_string(),
+                        offset,
+                    })
+                    .map_err(|e| DaemonError::InvalidArgument(e.to_string()))?;
+            }
+        }
+
+        if let Err(e) = self.save_config_internal() {
+            error!("Failed to save config: {}", e);
+            return Err(e.context("Failed to save config").into());
+        }
+
+        self.reconfigure_monitor().await;
+        if leader.is_empty() {
+            info!("Fan '{}' no longer follows another fan", follower);
+        } else {
+            info!(
+                "Fan '{}' now follows '{}' with offset {}",
+                follower, leader, offset
+            );
         }
+        Ok(())
+    }
+
+    /// The whole configuration as JSON, for backups
+    async fn export_config(&self) -> std::result::Result<String, DaemonError> {
+        debug!("Exporting configuration");
+        let config = self.config.lock().unwrap();
+        Ok(serde_json::to_string_pretty(&*config).map_err(FanCurveError::from)?)
+    }
+
+    /// Replace the whole configuration with `json` from `ExportConfig`
+    /// The current config is kept if `json` doesn't parse or holds an invalid curve
+    async fn import_config(&self, json: &str) -> std::result::Result<(), DaemonError> {
+        debug!("Importing configuration");
+
+        let imported = FanCurveConfig::from_json(json)
+            .and_then(|config| config.validate().map(|()| config))
+            .map_err(|e| {
+                warn!("Rejected imported config: {}", e);
+                DaemonError::InvalidArgument(format!("Invalid config: {}", e))
+            })?;
+
+        let curve_count = imported.curves.len();
+        *self.current_curve_index.lock().unwrap() = imported.initial_curve_index();
+        *self.inline_curve.lock().unwrap() = None;
+        *self.config.lock().unwrap() = imported;
+
+        if let Err(e) = self.save_config_internal() {
+            error!("Failed to save config: {}", e);
+            return Err(e.context("Failed to save config").into());
+        }
+
+        self.reconfigure_monitor().await;
+        info!("Imported configuration with {} fan curves", curve_count);
+
+        // Emit signal to notify fan monitor of the change
+        self.send_fan_curve_changed_signal().await;
+
+        Ok(())
     }
 
     /// Save configuration
-    async fn save_config(&self) -> zbus::fdo::Result<()> {
+    async fn save_config(&self) -> std::result::Result<(), DaemonError> {
         debug!("Saving configuration");
 
         if let Err(e) = self.save_config_internal() {
             error!("Failed to save config: {}", e);
-            return Err(zbus_error_from_display(format!(
-                "Failed to save config: {}",
-                e
-            )));
+            return Err(e.context("Failed to save config").into());
         }
 
         info!("Configuration saved");
         Ok(())
     }
+
+    /// Whether the fans are under "auto" (firmware), "manual" (PWM), "full-speed" or "mixed" control
+    async fn get_control_mode(&self) -> std::result::Result<String, DaemonError> {
+        debug!("Getting fan control mode");
+        tokio::task::spawn_blocking(|| {
+            let mut fans = FanDetector::new();
+            if let Err(e) = fans.initialize() {
+                warn!("Fan detection failed: {}", e);
+            }
+            fans.control_mode().to_string()
+        })
+        .await
+        .map_err(|e| {
+            error!("Fan detection failed: {}", e);
+            DaemonError::Failed(format!("Fan detection failed: {}", e))
+        })
+    }
+
+    /// Temperature (°C) and duty (ten-thousandths) the control loop last calculated, after
+    /// the idle floor, ramp-down hold and critical override
+    async fn get_target_duty(&self) -> std::result::Result<(f32, u16), DaemonError> {
+        debug!("Getting target duty");
+        self.monitor
+            .lock()
+            .await
+            .last_target()
+            .ok_or_else(|| DaemonError::Failed("No fan curve has been applied yet".to_string()))
+    }
+
+    /// Get the detected CPU sensor and fans
+    async fn get_hardware_info(&self) -> std::result::Result<HardwareInfo, DaemonError> {
+        debug!("Getting hardware info");
+        tokio::task::spawn_blocking(HardwareInfo::detect)
+            .await
+            .map_err(|e| {
+                error!("Hardware detection failed: {}", e);
+                DaemonError::Failed(format!("Hardware detection failed: {}", e))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temperature::MockTemperatureSource;
+    use crate::test_util::{ConfigPathOverride, EnvOverride, TempDir};
+    use std::collections::HashMap;
+
+    /// Daemon and client ends of a p2p D-Bus connection, with `daemon` served on the daemon end
+    async fn p2p_daemon(daemon: Option<impl zbus::Interface>) -> (Connection, Connection) {
+        let (daemon_end, client_end) = tokio::net::UnixStream::pair().unwrap();
+        let guid = zbus::Guid::generate();
+        let mut server = ConnectionBuilder::unix_stream(daemon_end);
+        if let Some(daemon) = daemon {
+            server = server.serve_at(DBUS_OBJECT_PATH, daemon).unwrap();
+        }
+        tokio::try_join!(
+            server.server(&guid).p2p().build(),
+            ConnectionBuilder::unix_stream(client_end).p2p().build(),
+        )
+        .unwrap()
+    }
+
+    /// Fake Thelio Io board under `dir/hwmon/hwmon2`, one fan per label under firmware control
+    fn fake_thelio_io(dir: &TempDir, labels: &[&str]) {
+        dir.write("hwmon/hwmon2/name", "system76_thelio_io\n");
+        for (i, label) in labels.iter().enumerate() {
+            let n = i + 1;
+            dir.write(
+                &format!("hwmon/hwmon2/fan{}_label", n),
+                &format!("{}\n", label),
+            );
+            dir.write(&format!("hwmon/hwmon2/fan{}_input", n), "1000\n");
+            dir.write(&format!("hwmon/hwmon2/pwm{}", n), "80\n");
+            dir.write(&format!("hwmon/hwmon2/pwm{}_enable", n), "2\n");
+        }
+    }
+
+    #[test]
+    fn test_save_exports_curves() {
+        let dir = TempDir::new("daemon-export");
+        let config_path = dir.path().join("config.json");
+        let config_override = ConfigPathOverride::new(&config_path);
+
+        let daemon = FanCurveDaemon::new().unwrap();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime
+            .block_on(daemon.set_default_fan_curve("HEDT"))
+            .unwrap();
+        drop(config_override);
+
+        let config = FanCurveConfig::load_from_file(&config_path).unwrap();
+        assert_eq!(config.default_curve_index, Some(2));
+        for name in ["Standard", "Threadripper 2", "HEDT", "Xeon"] {
+            assert!(dir
+                .path()
+                .join("fan_curves")
+                .join(format!("{}.json", name))
+                .exists());
+        }
+    }
+
+    #[test]
+    fn test_active_curve_survives_restart() {
+        let dir = TempDir::new("daemon-active");
+        let _config_override = ConfigPathOverride::new(&dir.path().join("config.json"));
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let daemon = FanCurveDaemon::new().unwrap();
+        runtime
+            .block_on(daemon.set_default_fan_curve("HEDT"))
+            .unwrap();
+        runtime
+            .block_on(daemon.set_fan_curve_by_name("Xeon"))
+            .unwrap();
+        drop(daemon);
+
+        let daemon = FanCurveDaemon::new().unwrap();
+        let current = runtime.block_on(daemon.get_current_fan_curve()).unwrap();
+        assert_eq!(current.name(), "Xeon");
+
+        runtime.block_on(daemon.set_fan_curve(1)).unwrap();
+        drop(daemon);
+
+        let daemon = FanCurveDaemon::new().unwrap();
+        let current = runtime.block_on(daemon.get_current_fan_curve()).unwrap();
+        assert_eq!(current.name(), "Threadripper 2");
+        assert!(runtime.block_on(daemon.set_fan_curve(99)).is_err());
+    }
+
+    #[test]
+    fn test_selecting_curve_applies_it() {
+        let dir = TempDir::new("daemon-apply");
+        let config_path = dir.path().join("config.json");
+        fake_thelio_io(&dir, &["CPU Fan"]);
+        let _env = EnvOverride::new(FanCurveConfig::CONFIG_PATH_ENV, &config_path)
+            .with(crate::HWMON_ROOT_ENV, dir.path().join("hwmon"));
+        let read = |file: &str| {
+            std::fs::read_to_string(dir.path().join("hwmon/hwmon2").join(file))
+                .unwrap()
+                .trim()
+                .to_string()
+        };
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let mut daemon = FanCurveDaemon::new().unwrap();
+        daemon.temperature_reader = Some(Arc::new(MockTemperatureSource::new([60.0])));
+
+        runtime
+            .block_on(daemon.set_fan_curve_by_name("Standard"))
+            .unwrap();
+        let expected = FanCurve::standard().calculate_duty_for_temperature(60_000);
+        assert_eq!(
+            read("pwm1"),
+            Duty::from_ten_thousandths(expected).as_pwm().to_string()
+        );
+        assert_eq!(read("pwm1_enable"), "1");
+
+        let auto_index = runtime
+            .block_on(daemon.get_fan_curves_v2())
+            .unwrap()
+            .iter()
+            .position(FanCurve::is_auto)
+            .unwrap();
+        runtime
+            .block_on(daemon.set_fan_curve(auto_index as u32))
+            .unwrap();
+        assert_eq!(read("pwm1_enable"), "2");
+
+        let config = FanCurveConfig::load_from_file(&config_path).unwrap();
+        assert_eq!(config.active_curve_index, Some(auto_index));
+    }
+
+    #[test]
+    fn test_simulate_duty() {
+        let dir = TempDir::new("daemon-simulate");
+        let _config_override = ConfigPathOverride::new(&dir.path().join("config.json"));
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let daemon = FanCurveDaemon::new().unwrap();
+        let simulate = |temp| runtime.block_on(daemon.simulate_duty("Standard", temp));
+
+        // Between the 70°C (60%) and 80°C (70%) points
+        assert!((simulate(73.0).unwrap() - 63.0).abs() < 0.05);
+        // Outside the curve the end duties hold
+        assert_eq!(simulate(-15.0).unwrap(), 0.0);
+        assert_eq!(simulate(120.0).unwrap(), 100.0);
+
+        assert!(simulate(f32::NAN).is_err());
+        assert!(runtime
+            .block_on(daemon.simulate_duty("Missing", 50.0))
+            .is_err());
+    }
+
+    #[test]
+    fn test_simulate_errors_are_daemon_replies() {
+        let dir = TempDir::new("daemon-simulate-errors");
+        let _config_override = ConfigPathOverride::new(&dir.path().join("config.json"));
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let daemon = FanCurveDaemon::new().unwrap();
+            let (_daemon_conn, client_conn) = p2p_daemon(Some(daemon)).await;
+            let simulate = |name: &'static str, temp: f32| {
+                let client_conn = client_conn.clone();
+                async move {
+                    client_conn
+                        .call_method(
+                            None::<&str>,
+                            DBUS_OBJECT_PATH,
+                            Some(crate::DBUS_INTERFACE_NAME),
+                            "SimulateDuty",
+                            &(name, temp),
+                        )
+                        .await
+                        .map_err(FanCurveError::from)
+                }
+            };
+
+            // Errors the daemon replies with must not send the client to its local config
+            let err = simulate("Missing", 50.0).await.unwrap_err();
+            assert_eq!(err.dbus_error_name(), Some(DaemonError::CURVE_NOT_FOUND));
+            assert!(err.is_daemon_reply());
+            assert!(simulate("Standard", 50.0).await.is_ok());
+
+            assert!(!FanCurveError::from(zbus::Error::InterfaceNotFound).is_daemon_reply());
+        });
+    }
+
+    #[test]
+    fn test_errors_have_dbus_names() {
+        use zbus::DBusError;
+
+        let dir = TempDir::new("daemon-error-names");
+        let _config_override = ConfigPathOverride::new(&dir.path().join("config.json"));
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let daemon = FanCurveDaemon::new().unwrap();
+
+        let err = runtime
+            .block_on(daemon.set_fan_curve_by_name("Missing"))
+            .unwrap_err();
+        assert_eq!(err.name().as_str(), DaemonError::CURVE_NOT_FOUND);
+        assert_eq!(err.description(), Some("Fan curve not found: Missing"));
+        let err = runtime.block_on(daemon.set_fan_curve(99)).unwrap_err();
+        assert_eq!(err.name().as_str(), DaemonError::CURVE_NOT_FOUND);
+        let err = runtime
+            .block_on(daemon.add_fan_curve_point(150, 50))
+            .unwrap_err();
+        assert_eq!(err.name().as_str(), DaemonError::INVALID_POINT);
+        let err = runtime
+            .block_on(daemon.set_fan_control("CPU Fan", "turbo"))
+            .unwrap_err();
+        assert_eq!(err.name().as_str(), DaemonError::INVALID_ARGUMENT);
+    }
+
+    #[test]
+    fn test_get_all_temperatures() {
+        let dir = TempDir::new("daemon-temps");
+        dir.write("hwmon/hwmon0/name", "coretemp\n");
+        dir.write("hwmon/hwmon0/temp1_label", "Package id 0\n");
+        dir.write("hwmon/hwmon0/temp1_input", "55000\n");
+        dir.write("hwmon/hwmon0/temp2_label", "Core 0\n");
+        dir.write("hwmon/hwmon0/temp2_input", "53000\n");
+        dir.write("hwmon/hwmon1/name", "amdgpu\n");
+        dir.write("hwmon/hwmon1/temp1_label", "edge\n");
+        dir.write("hwmon/hwmon1/temp1_input", "47000\n");
+        let _env = EnvOverride::new(
+            FanCurveConfig::CONFIG_PATH_ENV,
+            dir.path().join("config.json"),
+        )
+        .with(crate::HWMON_ROOT_ENV, dir.path().join("hwmon"));
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let daemon = FanCurveDaemon::new().unwrap();
+
+        assert_eq!(
+            runtime.block_on(daemon.get_all_temperatures()).unwrap(),
+            [
+                ("coretemp Package id 0".to_string(), 55.0),
+                ("coretemp Core 0".to_string(), 53.0),
+                ("amdgpu edge".to_string(), 47.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_schedule_switches_curve() {
+        let dir = TempDir::new("daemon-schedule");
+        let config_path = dir.path().join("config.json");
+        let _config_override = ConfigPathOverride::new(&config_path);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let at = |text| schedule::parse_time(text).unwrap();
+
+        let daemon = FanCurveDaemon::new().unwrap();
+        let schedule = vec![ScheduleEntry::new(at("22:00"), at("07:00"), "Xeon")];
+        assert!(runtime
+            .block_on(daemon.set_schedule(vec![ScheduleEntry::new(
+                at("09:00"),
+                at("17:00"),
+                "Missing"
+            )]))
+            .is_err());
+        runtime
+            .block_on(daemon.set_schedule(schedule.clone()))
+            .unwrap();
+        assert_eq!(
+            FanCurveConfig::load_from_file(&config_path)
+                .unwrap()
+                .schedule,
+            schedule
+        );
+        assert_eq!(runtime.block_on(daemon.get_schedule()).unwrap(), schedule);
+
+        let xeon = daemon
+            .config
+            .lock()
+            .unwrap()
+            .curves
+            .position("Xeon")
+            .unwrap();
+        let mut active = None;
+        let mut select = |time| {
+            FanCurveDaemon::select_curve_for_schedule(
+                &daemon.config,
+                &daemon.current_curve_index,
+                &daemon.inline_curve,
+                &mut active,
+                at(time),
+            );
+            *daemon.current_curve_index.lock().unwrap()
+        };
+        assert_eq!(select("12:00"), 0);
+        assert_eq!(select("23:00"), xeon);
+        assert_eq!(
+            FanCurveConfig::load_from_file(&config_path)
+                .unwrap()
+                .active_curve_index,
+            Some(xeon)
+        );
+
+        // Picking another curve inside the window sticks until the next window starts
+        *daemon.current_curve_index.lock().unwrap() = 1;
+        assert_eq!(select("01:00"), 1);
+        assert_eq!(select("08:00"), 1);
+        assert_eq!(select("22:00"), xeon);
+    }
+
+    #[test]
+    fn test_add_point_takes_percent() {
+        let dir = TempDir::new("daemon-add-point");
+        let _config_override = ConfigPathOverride::new(&dir.path().join("config.json"));
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let daemon = FanCurveDaemon::new().unwrap();
+        runtime
+            .block_on(daemon.add_fan_curve_point(42, 55))
+            .unwrap();
+        let current = runtime.block_on(daemon.get_current_fan_curve()).unwrap();
+        let point = current.points().iter().find(|p| p.temp == 42).unwrap();
+        assert_eq!(point.duty, 5500);
+
+        assert!(runtime
+            .block_on(daemon.add_fan_curve_point(42, 101))
+            .is_err());
+    }
+
+    #[test]
+    fn test_rename_fan_curve() {
+        let dir = TempDir::new("daemon-rename");
+        let config_path = dir.path().join("config.json");
+        let _config_override = ConfigPathOverride::new(&config_path);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let daemon = FanCurveDaemon::new().unwrap();
+        runtime
+            .block_on(daemon.set_profile_curve_mapping("Battery", "HEDT"))
+            .unwrap();
+        runtime
+            .block_on(daemon.rename_fan_curve("HEDT", "Workstation"))
+            .unwrap();
+        assert!(runtime
+            .block_on(daemon.rename_fan_curve("Xeon", "Workstation"))
+            .is_err());
+        assert!(runtime
+            .block_on(daemon.rename_fan_curve("HEDT", "Other"))
+            .is_err());
+
+        let config = FanCurveConfig::load_from_file(&config_path).unwrap();
+        assert_eq!(config.curves.position("Workstation"), Some(2));
+        assert_eq!(config.curve_index_for_profile("Battery"), Some(2));
+        assert!(!config.curves.contains("HEDT"));
+    }
+
+    #[test]
+    fn test_set_fan_control_persists() {
+        use zbus::DBusError;
+
+        let dir = TempDir::new("daemon-fan-control");
+        let config_path = dir.path().join("config.json");
+        fake_thelio_io(&dir, &["Intake Fan", "CPU Fan", "GPU Fan"]);
+        let _env = EnvOverride::new(FanCurveConfig::CONFIG_PATH_ENV, &config_path)
+            .with(crate::HWMON_ROOT_ENV, dir.path().join("hwmon"));
+        let read = |file: &str| {
+            std::fs::read_to_string(dir.path().join("hwmon/hwmon2").join(file))
+                .unwrap()
+                .trim()
+                .to_string()
+        };
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let mut daemon = FanCurveDaemon::new().unwrap();
+        daemon.temperature_reader = Some(Arc::new(MockTemperatureSource::new([60.0])));
+        runtime
+            .block_on(daemon.set_fan_control("GPU Fan", "auto"))
+            .unwrap();
+        runtime
+            .block_on(daemon.set_fan_control("Intake Fan", "manual:35"))
+            .unwrap();
+        assert!(runtime
+            .block_on(daemon.set_fan_control("CPU Fan", "manual:120"))
+            .is_err());
+        assert!(runtime
+            .block_on(daemon.set_fan_control(" ", "auto"))
+            .is_err());
+        let err = runtime
+            .block_on(daemon.set_fan_control("Exhaust Fan", "auto"))
+            .unwrap_err();
+        assert_eq!(err.name().as_str(), DaemonError::INVALID_ARGUMENT);
+
+        // Every detected fan is driven, not just the CPU fan
+        runtime
+            .block_on(daemon.set_fan_curve_by_name("Standard"))
+            .unwrap();
+        let expected = FanCurve::standard().calculate_duty_for_temperature(60_000);
+        assert_eq!(
+            read("pwm2"),
+            Duty::from_ten_thousandths(expected).as_pwm().to_string()
+        );
+        assert_eq!(read("pwm1"), Duty::from_percent(35.0).as_pwm().to_string());
+        assert_eq!(read("pwm3_enable"), "2");
+
+        let config = FanCurveConfig::load_from_file(&config_path).unwrap();
+        assert_eq!(
+            config.fan_control,
+            HashMap::from([
+                ("GPU Fan".to_string(), FanControlMode::Auto),
+                ("Intake Fan".to_string(), FanControlMode::Manual(35)),
+            ])
+        );
+
+        runtime
+            .block_on(daemon.set_fan_control("Intake Fan", "curve"))
+            .unwrap();
+        let config = FanCurveConfig::load_from_file(&config_path).unwrap();
+        assert!(!config.fan_control.contains_key("Intake Fan"));
+    }
+
+    #[test]
+    fn test_set_fan_link_persists() {
+        use zbus::DBusError;
+
+        let dir = TempDir::new("daemon-fan-link");
+        let config_path = dir.path().join("config.json");
+        fake_thelio_io(&dir, &["CPU Fan", "Intake Fan", "Exhaust Fan", "Top Fan"]);
+        let _env = EnvOverride::new(FanCurveConfig::CONFIG_PATH_ENV, &config_path)
+            .with(crate::HWMON_ROOT_ENV, dir.path().join("hwmon"));
+        let read = |file: &str| {
+            std::fs::read_to_string(dir.path().join("hwmon/hwmon2").join(file))
+                .unwrap()
+                .trim()
+                .to_string()
+        };
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let mut daemon = FanCurveDaemon::new().unwrap();
+        daemon.temperature_reader = Some(Arc::new(MockTemperatureSource::new([60.0])));
+        runtime
+            .block_on(daemon.set_fan_link("Exhaust Fan", "Intake Fan", 500))
+            .unwrap();
+        // A fan can't follow itself, a follower or a fan that isn't there
+        for (follower, leader) in [
+            ("Intake Fan", "Intake Fan"),
+            ("Top Fan", "Exhaust Fan"),
+            ("Rear Fan", "Intake Fan"),
+            ("Top Fan", "Rear Fan"),
+        ] {
+            let err = runtime
+                .block_on(daemon.set_fan_link(follower, leader, 0))
+                .unwrap_err();
+            assert_eq!(err.name().as_str(), DaemonError::INVALID_ARGUMENT);
+        }
+
+        // The follower runs 5% above its detected leader
+        runtime
+            .block_on(daemon.set_fan_curve_by_name("Standard"))
+            .unwrap();
+        let expected = FanCurve::standard().calculate_duty_for_temperature(60_000);
+        assert_eq!(
+            read("pwm2"),
+            Duty::from_ten_thousandths(expected).as_pwm().to_string()
+        );
+        assert_eq!(
+            read("pwm3"),
+            Duty::from_ten_thousandths(expected + 500)
+                .as_pwm()
+                .to_string()
+        );
+
+        let config = FanCurveConfig::load_from_file(&config_path).unwrap();
+        assert_eq!(
+            config.fan_links,
+            [FanLink {
+                follower: "Exhaust Fan".to_string(),
+                leader: "Intake Fan".to_string(),
+                offset: 500,
+            }]
+        );
+
+        runtime
+            .block_on(daemon.set_fan_link("Exhaust Fan", "", 0))
+            .unwrap();
+        let config = FanCurveConfig::load_from_file(&config_path).unwrap();
+        assert!(config.fan_links.is_empty());
+    }
+
+    #[test]
+    fn test_export_import_config_round_trip() {
+        let dir = TempDir::new("daemon-dump");
+        let config_path = dir.path().join("config.json");
+        let _config_override = ConfigPathOverride::new(&config_path);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let daemon = FanCurveDaemon::new().unwrap();
+        runtime
+            .block_on(daemon.set_default_fan_curve("Xeon"))
+            .unwrap();
+        let dump = runtime.block_on(daemon.export_config()).unwrap();
+
+        runtime
+            .block_on(daemon.set_default_fan_curve("Standard"))
+            .unwrap();
+        runtime
+            .block_on(daemon.rename_fan_curve("HEDT", "Workstation"))
+            .unwrap();
+
+        // Invalid configs leave the current one in place
+        let mut broken = FanCurveConfig::from_json(&dump).unwrap();
+        broken
+            .curves
+            .insert(FanCurve::new("Empty".to_string()))
+            .unwrap();
+        for json in ["{", serde_json::to_string(&broken).unwrap().as_str()] {
+            assert!(runtime.block_on(daemon.import_config(json)).is_err());
+        }
+        assert!(daemon.config.lock().unwrap().curves.contains("Workstation"));
+
+        runtime.block_on(daemon.import_config(&dump)).unwrap();
+        assert_eq!(runtime.block_on(daemon.export_config()).unwrap(), dump);
+        let saved = FanCurveConfig::load_from_file(&config_path).unwrap();
+        assert_eq!(saved, FanCurveConfig::from_json(&dump).unwrap());
+        assert_eq!(saved.default_curve_index, Some(3));
+        assert!(saved.curves.contains("HEDT"));
+    }
+
+    #[test]
+    fn test_locked_curve_rejects_edits() {
+        let dir = TempDir::new("daemon-locked");
+        let _config_override = ConfigPathOverride::new(&dir.path().join("config.json"));
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let daemon = FanCurveDaemon::new().unwrap();
+        let before = runtime.block_on(daemon.get_current_fan_curve()).unwrap();
+        {
+            let mut config = daemon.config.lock().unwrap();
+            let index = *daemon.current_curve_index.lock().unwrap();
+            config.curves.get_index_mut(index).unwrap().set_locked(true);
+        }
+
+        let err = runtime
+            .block_on(daemon.add_fan_curve_point(42, 55))
+            .unwrap_err();
+        assert!(err.to_string().contains("is locked"), "{}", err);
+        assert!(runtime.block_on(daemon.remove_fan_curve_point()).is_err());
+
+        let after = runtime.block_on(daemon.get_current_fan_curve()).unwrap();
+        assert_eq!(after.points(), before.points());
+    }
+
+    #[test]
+    fn test_fan_data_updates_reach_subscribers() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let (daemon_conn, client_conn) = p2p_daemon(None::<FanCurveDaemon>).await;
+
+            let updates = crate::client::fan_data_updates(&client_conn).await.unwrap();
+            tokio::pin!(updates);
+
+            let signal_ctx = SignalContext::new(&daemon_conn, DBUS_OBJECT_PATH).unwrap();
+            let mut temperature = 40.0;
+            tokio::spawn(FanCurveDaemon::publish_fan_data(
+                signal_ctx,
+                Duration::from_millis(10),
+                move || {
+                    temperature += 1.0;
+                    std::future::ready(Ok(FanDataPoint {
+                        timestamp: chrono::Local::now(),
+                        temperature,
+                        cpu_fan_speeds: vec![(1, 1200, "CPU Fan".to_string())],
+                        intake_fan_speeds: Vec::new(),
+                        gpu_fan_speeds: Vec::new(),
+                        fan_duty: 5000,
+                        cpu_usage: 12.5,
+                        cpu_model: "Test CPU".to_string(),
+                        cpu_core_count: 8,
+                    }))
+                },
+            ));
+
+            let mut received = Vec::new();
+            while received.len() < 2 {
+                let data = tokio::time::timeout(Duration::from_secs(5), updates.next())
+                    .await
+                    .expect("no fan data update received")
+                    .unwrap();
+                received.push(data);
+            }
+            let (first, second) = (&received[0], &received[1]);
+            assert_eq!(first.temperature, 41.0);
+            assert_eq!(second.temperature, 42.0);
+            assert_eq!(second.cpu_fan_speeds, [(1, 1200, "CPU Fan".to_string())]);
+            assert_eq!(second.fan_duty, 5000);
+            assert_eq!(second.cpu_model, "Test CPU");
+            assert!(second.timestamp >= first.timestamp);
+        });
+    }
+
+    #[test]
+    fn test_boost_reaches_the_control_loop() {
+        let dir = TempDir::new("daemon-boost");
+        let _config = ConfigPathOverride::new(&dir.path().join("config.json"));
+        let daemon = FanCurveDaemon::new().unwrap();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let (daemon_conn, _client_conn) = p2p_daemon(None::<FanCurveDaemon>).await;
+            let signal_ctx = SignalContext::new(&daemon_conn, DBUS_OBJECT_PATH).unwrap();
+
+            daemon.boost(signal_ctx.clone(), 100, 60).await.unwrap();
+            let monitor = daemon.monitor.lock().await;
+            assert!(monitor.boost_remaining().is_some());
+            assert_eq!(monitor.expected_duty(30.0), 10000);
+            drop(monitor);
+
+            assert!(daemon.boost(signal_ctx, 100, 0).await.is_err());
+        });
+    }
+
+    #[test]
+    fn test_control_loop_follows_temperature() {
+        let dir = TempDir::new("daemon-control-loop");
+        dir.write("hwmon/hwmon0/name", "coretemp\n");
+        dir.write("hwmon/hwmon0/temp1_label", "Package id 0\n");
+        dir.write("hwmon/hwmon0/temp1_input", "60000\n");
+        fake_thelio_io(&dir, &["CPU Fan"]);
+        let _env = EnvOverride::new(
+            FanCurveConfig::CONFIG_PATH_ENV,
+            dir.path().join("config.json"),
+        )
+        .with(crate::HWMON_ROOT_ENV, dir.path().join("hwmon"));
+        let pwm = || {
+            std::fs::read_to_string(dir.path().join("hwmon/hwmon2/pwm1"))
+                .unwrap()
+                .trim()
+                .parse::<u8>()
+                .unwrap()
+        };
+
+        let daemon = FanCurveDaemon::new().unwrap();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let curve = runtime.block_on(daemon.get_current_fan_curve()).unwrap();
+        let expected_pwm = |celsius: i32| {
+            Duty::from_ten_thousandths(curve.calculate_duty_for_temperature(celsius * 1000))
+                .as_pwm()
+        };
+
+        runtime.block_on(async {
+            let (daemon_conn, client_conn) = p2p_daemon(None::<FanCurveDaemon>).await;
+            let updates = crate::client::fan_data_updates(&client_conn).await.unwrap();
+            tokio::pin!(updates);
+
+            let signal_ctx = SignalContext::new(&daemon_conn, DBUS_OBJECT_PATH).unwrap();
+            tokio::spawn(FanCurveDaemon::control_fans(
+                signal_ctx,
+                Duration::from_millis(10),
+                daemon.monitor.clone(),
+                daemon.config.clone(),
+                daemon.current_curve_index.clone(),
+                daemon.inline_curve.clone(),
+            ));
+
+            // Every poll applies the curve to a fresh reading
+            for celsius in [60, 80] {
+                dir.write("hwmon/hwmon0/temp1_input", &format!("{}000\n", celsius));
+                loop {
+                    let data = tokio::time::timeout(Duration::from_secs(5), updates.next())
+                        .await
+                        .expect("no fan data update received")
+                        .unwrap();
+                    if data.temperature == celsius as f32 {
+                        break;
+                    }
+                }
+                assert_eq!(pwm(), expected_pwm(celsius));
+            }
+        });
+    }
+
+    #[test]
+    fn test_ramp_down_delay_holds_spike() {
+        let dir = TempDir::new("daemon-ramp-down");
+        let config_path = dir.path().join("config.json");
+        dir.write("hwmon/hwmon0/name", "coretemp\n");
+        dir.write("hwmon/hwmon0/temp1_label", "Package id 0\n");
+        dir.write("hwmon/hwmon0/temp1_input", "40000\n");
+        fake_thelio_io(&dir, &["CPU Fan"]);
+        let _env = EnvOverride::new(FanCurveConfig::CONFIG_PATH_ENV, &config_path)
+            .with(crate::HWMON_ROOT_ENV, dir.path().join("hwmon"));
+        let pwm = || {
+            std::fs::read_to_string(dir.path().join("hwmon/hwmon2/pwm1"))
+                .unwrap()
+                .trim()
+                .parse::<u8>()
+                .unwrap()
+        };
+        let config = FanCurveConfig {
+            ramp_down_delay_ms: 60_000,
+            ..FanCurveConfig::new()
+        };
+        config.save_to_file(&config_path).unwrap();
+
+        let daemon = FanCurveDaemon::new().unwrap();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let curve = runtime.block_on(daemon.get_current_fan_curve()).unwrap();
+        let expected_pwm = |celsius: i32| {
+            Duty::from_ten_thousandths(curve.calculate_duty_for_temperature(celsius * 1000))
+                .as_pwm()
+        };
+        assert_ne!(expected_pwm(40), expected_pwm(80));
+
+        runtime.block_on(async {
+            let (daemon_conn, client_conn) = p2p_daemon(None::<FanCurveDaemon>).await;
+            let updates = crate::client::fan_data_updates(&client_conn).await.unwrap();
+            tokio::pin!(updates);
+
+            let signal_ctx = SignalContext::new(&daemon_conn, DBUS_OBJECT_PATH).unwrap();
+            tokio::spawn(FanCurveDaemon::control_fans(
+                signal_ctx,
+                Duration::from_millis(10),
+                daemon.monitor.clone(),
+                daemon.config.clone(),
+                daemon.current_curve_index.clone(),
+                daemon.inline_curve.clone(),
+            ));
+
+            // The spike's duty is held well past its end
+            for (celsius, expected) in [(40, 40), (80, 80), (40, 80)] {
+                dir.write("hwmon/hwmon0/temp1_input", &format!("{}000\n", celsius));
+                for _ in 0..3 {
+                    loop {
+                        let data = tokio::time::timeout(Duration::from_secs(5), updates.next())
+                            .await
+                            .expect("no fan data update received")
+                            .unwrap();
+                        if data.temperature == celsius as f32 {
+                            break;
+                        }
+                    }
+                }
+                assert_eq!(pwm(), expected_pwm(expected));
+            }
+        });
+    }
+
+    #[test]
+    fn test_metrics_serve_control_loop_samples() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let monitor = Arc::new(tokio::sync::Mutex::new(FanMonitor::new()));
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(FanCurveDaemon::serve_metrics(listener, monitor.clone()));
+            let scrape = || async move {
+                let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+                stream
+                    .write_all(b"GET /metrics HTTP/1.1\r\n\r\n")
+                    .await
+                    .unwrap();
+                let mut response = String::new();
+                stream.read_to_string(&mut response).await.unwrap();
+                response
+            };
+
+            assert!(scrape().await.starts_with("HTTP/1.1 503"));
+
+            // No system76-power or second monitor needed, the control loop's sample is served
+            monitor.lock().await.record_sample(FanDataPoint {
+                timestamp: chrono::Local::now(),
+                temperature: 52.5,
+                cpu_fan_speeds: vec![(1, 1300, "CPU Fan".to_string())],
+                intake_fan_speeds: Vec::new(),
+                gpu_fan_speeds: Vec::new(),
+                fan_duty: 4500,
+                cpu_usage: 10.0,
+                cpu_model: "Test CPU".to_string(),
+                cpu_core_count: 8,
+            });
+            let response = scrape().await;
+            assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+            assert!(response.contains("fan_curve_temperature_celsius 52.5"));
+        });
+    }
+
+    #[test]
+    fn test_build_info_properties() {
+        let dir = TempDir::new("daemon-build-info");
+        let _config_override = ConfigPathOverride::new(&dir.path().join("config.json"));
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let daemon = FanCurveDaemon::new().unwrap();
+            let (_daemon_conn, client_conn) = p2p_daemon(Some(daemon)).await;
+
+            let info = crate::client::daemon_build_info(&client_conn)
+                .await
+                .unwrap();
+            assert_eq!(info, crate::BuildInfo::current());
+            assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        });
+    }
+
+    #[test]
+    fn test_apply_fan_curve_inline() {
+        let dir = TempDir::new("daemon-inline");
+        let config_path = dir.path().join("config.json");
+        fake_thelio_io(&dir, &["CPU Fan"]);
+        let _env = EnvOverride::new(FanCurveConfig::CONFIG_PATH_ENV, &config_path)
+            .with(crate::HWMON_ROOT_ENV, dir.path().join("hwmon"));
+        let pwm = || {
+            std::fs::read_to_string(dir.path().join("hwmon/hwmon2/pwm1"))
+                .unwrap()
+                .trim()
+                .parse::<u8>()
+                .unwrap()
+        };
+
+        let mut daemon = FanCurveDaemon::new().unwrap();
+        daemon.temperature_reader = Some(Arc::new(MockTemperatureSource::new([60.0])));
+        let config = daemon.config.clone();
+        let inline_curve = daemon.inline_curve.clone();
+        let config_json = || serde_json::to_string(&*config.lock().unwrap()).unwrap();
+        let config_before = config_json();
+        let xeon = config.lock().unwrap().curves.position("Xeon").unwrap();
+        let saved_before = std::fs::read_to_string(&config_path).unwrap();
+
+        let mut curve = FanCurve::new("Inline".to_string());
+        curve.add_point(40, 2000);
+        curve.add_point(80, 6000);
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let (_daemon_conn, client_conn) = p2p_daemon(Some(daemon)).await;
+            let rule = MatchRule::builder()
+                .msg_type(zbus::MessageType::Signal)
+                .member("FanCurveChanged")
+                .unwrap()
+                .build();
+            let mut changed = MessageStream::for_match_rule(rule, &client_conn, None)
+                .await
+                .unwrap();
+
+            let duty = crate::client::apply_fan_curve_inline(&client_conn, &curve, false)
+                .await
+                .unwrap();
+            assert_eq!(duty, 4000);
+            assert_eq!(pwm(), Duty::from_ten_thousandths(4000).as_pwm());
+            tokio::time::timeout(Duration::from_secs(5), changed.next())
+                .await
+                .expect("no fan curve changed signal")
+                .unwrap()
+                .unwrap();
+
+            // Driving the fans, but the config is untouched in memory and on disk
+            assert_eq!(inline_curve.lock().unwrap().as_ref(), Some(&curve));
+            assert_eq!(config_json(), config_before);
+            assert_eq!(std::fs::read_to_string(&config_path).unwrap(), saved_before);
+
+            // Invalid curves are rejected without touching the fans
+            let mut invalid = FanCurve::new("Broken".to_string());
+            invalid.add_point(50, 20000);
+            let err = crate::client::apply_fan_curve_inline(&client_conn, &invalid, true)
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("Invalid fan curve"), "{}", err);
+            assert!(!config.lock().unwrap().curves.contains("Broken"));
+
+            curve.add_point(60, 5000);
+            let duty = crate::client::apply_fan_curve_inline(&client_conn, &curve, true)
+                .await
+                .unwrap();
+            assert_eq!(duty, 5000);
+            assert!(inline_curve.lock().unwrap().is_none());
+
+            // Saving under an existing name replaces that curve in place, unless it is locked
+            let mut edited = curve.clone();
+            edited.set_name("Xeon".to_string());
+            crate::client::apply_fan_curve_inline(&client_conn, &edited, true)
+                .await
+                .unwrap();
+            assert_eq!(config.lock().unwrap().curves.position("Xeon"), Some(xeon));
+            config
+                .lock()
+                .unwrap()
+                .curves
+                .get_mut("Xeon")
+                .unwrap()
+                .set_locked(true);
+            edited.add_point(90, 9000);
+            let err = crate::client::apply_fan_curve_inline(&client_conn, &edited, true)
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("is locked"), "{}", err);
+        });
+
+        let saved = FanCurveConfig::load_from_file(&config_path).unwrap();
+        let index = saved.curves.position("Inline").unwrap();
+        assert_eq!(saved.curves[index], curve);
+        assert_eq!(saved.curves.position("Xeon"), Some(xeon));
+        assert_eq!(saved.active_curve_index, Some(xeon));
+        assert_eq!(saved.curves[xeon].points(), curve.points());
+    }
+
+    /// Daemon from before `GetFanCurvesV2`
+    struct OldDaemon(Vec<FanCurveDto>);
+
+    #[dbus_interface(name = "com.system76.FanCurveDaemon")]
+    impl OldDaemon {
+        async fn get_fan_curves(&self) -> Vec<FanCurveDto> {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn test_fan_curves_v1_and_v2_signatures() {
+        use zvariant::Type;
+        assert_eq!(FanCurve::signature(), crate::fan::FAN_CURVE_SIGNATURE);
+        assert_eq!(FanCurveDto::signature(), crate::fan::FAN_CURVE_V1_SIGNATURE);
+
+        let dir = TempDir::new("daemon-curve-versions");
+        let _config_override = ConfigPathOverride::new(&dir.path().join("config.json"));
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let daemon = FanCurveDaemon::new().unwrap();
+            let curves = daemon.get_fan_curves_v2().await.unwrap();
+            assert!(curves.iter().any(FanCurve::is_locked));
+            let (_daemon_conn, client_conn) = p2p_daemon(Some(daemon)).await;
+
+            // New clients get every setting
+            let received = crate::client::daemon_fan_curves(&client_conn)
+                .await
+                .unwrap();
+            assert_eq!(received, curves);
+
+            // Old clients still decode GetFanCurves as names and points
+            let old_reply = client_conn
+                .call_method(
+                    Some(DBUS_SERVICE_NAME),
+                    DBUS_OBJECT_PATH,
+                    Some(crate::DBUS_INTERFACE_NAME),
+                    "GetFanCurves",
+                    &(),
+                )
+                .await
+                .unwrap();
+            let old: Vec<FanCurveDto> = old_reply.body().unwrap();
+            assert_eq!(
+                old,
+                curves.iter().map(FanCurveDto::from).collect::<Vec<_>>()
+            );
+
+            // New clients fall back to GetFanCurves on old daemons
+            let (_old_conn, client_conn) = p2p_daemon(Some(OldDaemon(old.clone()))).await;
+            let received = crate::client::daemon_fan_curves(&client_conn)
+                .await
+                .unwrap();
+            assert_eq!(received.len(), curves.len());
+            assert_eq!(received[0].name(), curves[0].name());
+            assert_eq!(received[0].points(), curves[0].points());
+        });
+    }
+
+    #[test]
+    fn test_shutdown_restores_automatic_mode() {
+        let hwmon = TempDir::new("daemon-shutdown");
+        let read_enable = || {
+            std::fs::read_to_string(hwmon.path().join("pwm1_enable"))
+                .unwrap()
+                .trim()
+                .to_string()
+        };
+
+        let detector = FanDetector::fake(&hwmon);
+        detector.set_duty(Some(50)).unwrap();
+        assert_eq!(read_enable(), "1");
+
+        FanCurveDaemon::shutdown(false, || panic!("fans must not be touched")).unwrap();
+        assert_eq!(read_enable(), "1");
+
+        FanCurveDaemon::shutdown(true, || Ok(detector)).unwrap();
+        assert_eq!(read_enable(), "2");
+
+        // A fan taken over from another automatic mode goes back to it
+        let detector = FanDetector::fake(&hwmon);
+        hwmon.write("pwm1_enable", "5\n");
+        detector.set_duty(Some(50)).unwrap();
+        assert_eq!(read_enable(), "1");
+        FanCurveDaemon::shutdown(true, || Ok(detector)).unwrap();
+        assert_eq!(read_enable(), "5");
+    }
 }
@@ -0,0 +1,117 @@
+//! systemd readiness and watchdog notifications (the `sd_notify` protocol)
+
+use std::ffi::OsString;
+use std::io;
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::net::{SocketAddr, UnixDatagram};
+use std::path::PathBuf;
+use tokio::time::Duration;
+
+/// Environment variable systemd sets to the notification socket
+pub const NOTIFY_SOCKET_ENV: &str = "NOTIFY_SOCKET";
+
+/// Environment variable systemd sets to the watchdog timeout in microseconds
+pub const WATCHDOG_USEC_ENV: &str = "WATCHDOG_USEC";
+
+/// Ping interval when no watchdog timeout is given
+const DEFAULT_PING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Build a notification message from `KEY=VALUE` fields
+pub fn format_state(fields: &[(&str, &str)]) -> String {
+    fields
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// How often to ping the watchdog, half of `WATCHDOG_USEC` as systemd recommends
+pub fn ping_interval(watchdog_usec: Option<&str>) -> Duration {
+    watchdog_usec
+        .and_then(|usec| usec.trim().parse::<u64>().ok())
+        .filter(|&usec| usec > 0)
+        .map(|usec| Duration::from_micros(usec / 2))
+        .unwrap_or(DEFAULT_PING_INTERVAL)
+}
+
+/// Sends state updates to systemd's notification socket
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    socket: OsString,
+}
+
+impl Notifier {
+    /// Notifier for `socket`, where a leading `@` names an abstract socket
+    pub fn new(socket: impl Into<OsString>) -> Self {
+        Self {
+            socket: socket.into(),
+        }
+    }
+
+    /// Notifier for `$NOTIFY_SOCKET`, or `None` when not started by systemd
+    pub fn from_env() -> Option<Self> {
+        std::env::var_os(NOTIFY_SOCKET_ENV)
+            .filter(|socket| !socket.is_empty())
+            .map(Self::new)
+    }
+
+    /// Tell systemd the service is up
+    pub fn ready(&self) -> io::Result<()> {
+        self.notify(&[("READY", "1")])
+    }
+
+    /// Reset the systemd watchdog timer
+    pub fn watchdog(&self) -> io::Result<()> {
+        self.notify(&[("WATCHDOG", "1")])
+    }
+
+    /// Send `fields` as one datagram
+    pub fn notify(&self, fields: &[(&str, &str)]) -> io::Result<()> {
+        let message = format_state(fields);
+        let socket = UnixDatagram::unbound()?;
+        socket.send_to_addr(message.as_bytes(), &self.address()?)?;
+        Ok(())
+    }
+
+    fn address(&self) -> io::Result<SocketAddr> {
+        let bytes = self.socket.as_encoded_bytes();
+        match bytes.strip_prefix(b"@") {
+            Some(name) => SocketAddr::from_abstract_name(name),
+            None => SocketAddr::from_pathname(PathBuf::from(&self.socket)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_notify_messages() {
+        assert_eq!(format_state(&[("READY", "1")]), "READY=1");
+        assert_eq!(
+            format_state(&[("WATCHDOG", "1"), ("STATUS", "Running")]),
+            "WATCHDOG=1\nSTATUS=Running"
+        );
+
+        assert_eq!(ping_interval(Some("20000000")), Duration::from_secs(10));
+        assert_eq!(ping_interval(Some("3000000")), Duration::from_millis(1500));
+        assert_eq!(ping_interval(Some("0")), DEFAULT_PING_INTERVAL);
+        assert_eq!(ping_interval(None), DEFAULT_PING_INTERVAL);
+
+        let dir = TempDir::new("notify");
+        let path = dir.path().join("notify.sock");
+        let receiver = UnixDatagram::bind(&path).unwrap();
+        let notifier = Notifier::new(path.into_os_string());
+
+        let mut buf = [0u8; 64];
+        notifier.ready().unwrap();
+        let len = receiver.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"READY=1");
+
+        notifier.watchdog().unwrap();
+        let len = receiver.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"WATCHDOG=1");
+    }
+}
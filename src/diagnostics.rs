@@ -0,0 +1,154 @@
+//! Snapshot of everything the app can see at one moment, written out for bug reports
+
+use crate::errors::Result;
+use crate::fan::{FanCurve, FanCurveConfig};
+use crate::fan_detector::{FanDetector, FanSensor};
+use crate::fan_monitor::FanDataDto;
+use crate::hardware::HardwareInfo;
+use crate::BuildInfo;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Raw control files of one fan as they read when the snapshot was taken
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PwmState {
+    pub fan_number: u8,
+    pub label: String,
+    /// Contents of `pwmN`, None if it couldn't be read
+    pub pwm: Option<u8>,
+    /// Contents of `pwmN_enable`, None if it couldn't be read
+    pub enable: Option<String>,
+    pub pwm_max: u8,
+    pub inverted: bool,
+}
+
+impl PwmState {
+    pub fn read(fan: &FanSensor) -> Self {
+        let read = |path: &Path| {
+            fs::read_to_string(path)
+                .ok()
+                .map(|value| value.trim().to_string())
+        };
+        Self {
+            fan_number: fan.fan_number,
+            label: fan.fan_label.clone(),
+            pwm: read(&fan.pwm_path).and_then(|value| value.parse().ok()),
+            enable: read(&fan.pwm_enable_path),
+            pwm_max: fan.pwm_max,
+            inverted: fan.inverted,
+        }
+    }
+
+    /// States of every fan `detector` found
+    pub fn read_all(detector: &FanDetector) -> Vec<Self> {
+        detector.get_fans().iter().map(Self::read).collect()
+    }
+}
+
+/// Readings, curve, hardware and config captured together, see
+/// [`FanMonitor::capture_diagnostics`](crate::fan_monitor::FanMonitor::capture_diagnostics)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiagnosticSnapshot {
+    /// Local time the snapshot was taken, RFC 3339
+    pub captured_at: String,
+    /// Build of the binary that took the snapshot
+    pub app: BuildInfo,
+    /// Build of the running daemon, None if it couldn't be reached
+    pub daemon: Option<BuildInfo>,
+    /// Latest fan data, with its own timestamp
+    pub fan_data: Option<FanDataDto>,
+    pub active_curve: Option<FanCurve>,
+    pub hardware: HardwareInfo,
+    /// Every hwmon temperature input as (`<device> <label>`, °C)
+    pub temperatures: Vec<(String, f32)>,
+    pub pwm_states: Vec<PwmState>,
+    /// Whether the fans are under automatic, manual or mixed control
+    pub control_mode: String,
+    pub config: Option<FanCurveConfig>,
+    /// Readings that failed, so a missing section can be told apart from an empty one
+    pub errors: Vec<String>,
+}
+
+impl DiagnosticSnapshot {
+    /// Write the snapshot as pretty-printed JSON
+    pub fn save_to_file(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load_from_file(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::FanInfo;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let hwmon = TempDir::new("diagnostics");
+        let detector = FanDetector::fake(&hwmon);
+        let pwm_states = PwmState::read_all(&detector);
+        assert_eq!(pwm_states[0].pwm, Some(100));
+        assert_eq!(pwm_states[0].enable.as_deref(), Some("2"));
+
+        let config = FanCurveConfig::default();
+        let snapshot = DiagnosticSnapshot {
+            captured_at: "2024-05-01T12:00:00+02:00".to_string(),
+            app: BuildInfo::current(),
+            daemon: None,
+            fan_data: Some(FanDataDto {
+                timestamp_ms: 1_714_557_600_000,
+                temperature: 61.5,
+                cpu_fan_speeds: vec![(1, 1800, "CPU Fan".to_string())],
+                intake_fan_speeds: Vec::new(),
+                gpu_fan_speeds: Vec::new(),
+                fan_duty: 4500,
+                cpu_usage: 12.5,
+                cpu_model: "AMD Ryzen 9 7950X".to_string(),
+                cpu_core_count: 32,
+            }),
+            active_curve: config.curves.get_index(0).cloned(),
+            hardware: HardwareInfo {
+                cpu_manufacturer: "Amd".to_string(),
+                cpu_sensor_name: "k10temp".to_string(),
+                fan_hwmon_path: hwmon.path().display().to_string(),
+                fans: vec![FanInfo {
+                    fan_number: 1,
+                    label: "CPU Fan".to_string(),
+                    input_path: "fan1_input".to_string(),
+                    pwm_path: "pwm1".to_string(),
+                    pwm_enable_path: "pwm1_enable".to_string(),
+                    pwm_writable: true,
+                }],
+            },
+            temperatures: vec![("k10temp Tctl".to_string(), 61.5)],
+            pwm_states,
+            control_mode: "auto".to_string(),
+            config: Some(config),
+            errors: vec!["GPU temperature: no sensor".to_string()],
+        };
+
+        let path = hwmon.path().join("report.json");
+        snapshot.save_to_file(&path).unwrap();
+        let loaded = DiagnosticSnapshot::load_from_file(&path).unwrap();
+        assert_eq!(loaded.config, snapshot.config);
+        assert_eq!(loaded.fan_data, snapshot.fan_data);
+        assert_eq!(loaded.pwm_states, snapshot.pwm_states);
+        assert_eq!(loaded.hardware, snapshot.hardware);
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&snapshot).unwrap()
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["captured_at"], "2024-05-01T12:00:00+02:00");
+        assert_eq!(json["app"]["version"], crate::VERSION);
+        assert!(json["daemon"].is_null());
+    }
+}
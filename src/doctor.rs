@@ -0,0 +1,332 @@
+//! `fan-curve doctor`: check each stage from sensor to fan and report what is broken
+//!
+//! Every check runs even when an earlier one fails, and the report ends with a summary
+//! meant to be pasted into a bug report.
+
+use crate::client::daemon_build_info;
+use crate::cpu_temp::{CpuManufacturer, CpuTempDetector};
+use crate::errors::{FanCurveError, Result};
+use crate::fan::FanCurveConfig;
+use crate::fan_detector::FanDetector;
+use crate::fan_monitor::{FanMonitor, ReadingSource, SensorOptions};
+use crate::BuildInfo;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use zbus::Connection;
+
+/// Outcome of one check: what was found, or why it failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DoctorCheck {
+    pub name: &'static str,
+    pub outcome: std::result::Result<String, String>,
+}
+
+impl DoctorCheck {
+    pub fn passed(&self) -> bool {
+        self.outcome.is_ok()
+    }
+}
+
+/// Results of every check, in the order they ran
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DoctorReport {
+    build: BuildInfo,
+    kernel: String,
+    checks: Vec<DoctorCheck>,
+}
+
+impl DoctorReport {
+    pub fn new(build: BuildInfo, kernel: impl Into<String>) -> Self {
+        Self {
+            build,
+            kernel: kernel.into(),
+            checks: Vec::new(),
+        }
+    }
+
+    /// Record the result of a check, keeping the error message if it failed
+    pub fn record(&mut self, name: &'static str, result: Result<String>) {
+        self.checks.push(DoctorCheck {
+            name,
+            outcome: result.map_err(|e| e.to_string()),
+        });
+    }
+
+    pub fn checks(&self) -> &[DoctorCheck] {
+        &self.checks
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &DoctorCheck> {
+        self.checks.iter().filter(|check| !check.passed())
+    }
+
+    pub fn all_passed(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Error naming the failed checks, or Ok if there were none
+    pub fn into_result(self) -> Result<()> {
+        let failed: Vec<&str> = self.failures().map(|check| check.name).collect();
+        if failed.is_empty() {
+            return Ok(());
+        }
+        Err(FanCurveError::Config(format!(
+            "{} of {} checks failed: {}",
+            failed.len(),
+            self.checks.len(),
+            failed.join(", ")
+        )))
+    }
+}
+
+impl fmt::Display for DoctorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "fan-curve-app v{} (git {}) built {}",
+            self.build.version, self.build.git_hash, self.build.build_time
+        )?;
+        writeln!(f, "Kernel: {}", self.kernel)?;
+        for check in &self.checks {
+            match &check.outcome {
+                Ok(detail) => writeln!(f, "[PASS] {}: {}", check.name, detail)?,
+                Err(error) => writeln!(f, "[FAIL] {}: {}", check.name, error)?,
+            }
+        }
+        let passed = self.checks.iter().filter(|check| check.passed()).count();
+        write!(f, "{} of {} checks passed", passed, self.checks.len())
+    }
+}
+
+/// Run every check, print the report and fail if any check did
+pub async fn run(options: SensorOptions) -> Result<()> {
+    let kernel = fs::read_to_string("/proc/sys/kernel/osrelease")
+        .map(|release| release.trim().to_string())
+        .unwrap_or_else(|_| "unknown".to_string());
+    let mut report = DoctorReport::new(BuildInfo::current(), kernel);
+
+    report = tokio::task::spawn_blocking(move || {
+        run_local_checks(&mut report, options);
+        report
+    })
+    .await
+    .map_err(|e| FanCurveError::Task(e).context("Self-test failed"))?;
+    report.record("DBus daemon", check_daemon().await);
+    report.record(
+        "Config load/save",
+        check_config(&FanCurveConfig::get_config_path()),
+    );
+
+    println!("{}", report);
+    report.into_result()
+}
+
+/// Checks that only read sysfs and /proc
+fn run_local_checks(report: &mut DoctorReport, options: SensorOptions) {
+    report.record("CPU vendor", check_cpu_vendor(&CpuTempDetector::new()));
+
+    report.record("Temperature read", check_temperature(options));
+
+    let mut fans = FanDetector::new();
+    let detected = fans.initialize().and_then(|_| check_fans(&fans));
+    let detection_failed = detected.is_err();
+    report.record("Fan detection", detected);
+    report.record(
+        "PWM writability",
+        if detection_failed {
+            Err(FanCurveError::Config("No fans to check".to_string()))
+        } else {
+            check_pwm_writable(&fans)
+        },
+    );
+}
+
+fn check_cpu_vendor(cpu: &CpuTempDetector) -> Result<String> {
+    match cpu.detect_cpu_manufacturer()? {
+        CpuManufacturer::Unknown => Err(FanCurveError::Config(
+            "Unknown CPU vendor, only Intel and AMD sensors are recognized".to_string(),
+        )),
+        manufacturer => Ok(format!("{:?}", manufacturer)),
+    }
+}
+
+/// Read the temperature the way local monitors do, naming the sensor it most likely came from
+fn check_temperature(options: SensorOptions) -> Result<String> {
+    let mut monitor = FanMonitor::new();
+    monitor.set_sensor_options(options);
+    let celsius = monitor.read_temperature()?;
+
+    let sensor = match (options.thermal_zone, options.source) {
+        (Some(zone), _) => format!("thermal_zone{}", zone),
+        (None, ReadingSource::LmSensors) => "sensors -j".to_string(),
+        (None, ReadingSource::Sysfs) => {
+            let mut cpu = CpuTempDetector::new();
+            match cpu.initialize() {
+                Ok(()) => cpu
+                    .get_sensor_info()
+                    .map(|s| format!("{} {}", s.sensor_name, s.temp_label))
+                    .unwrap_or_default(),
+                Err(_) => "thermal zones".to_string(),
+            }
+        }
+    };
+    Ok(format!("{:.1}°C from {}", celsius, sensor))
+}
+
+fn check_fans(fans: &FanDetector) -> Result<String> {
+    if fans.fan_count() == 0 {
+        return Err(FanCurveError::Config("No fans found".to_string()));
+    }
+    let speeds = fans
+        .read_all_fan_speeds()?
+        .into_iter()
+        .map(|(number, rpm, label)| format!("fan{} '{}' {} RPM", number, label, rpm))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Ok(format!(
+        "{} at {}: {}",
+        fans.hwmon_name().unwrap_or("unknown"),
+        fans.hwmon_path().unwrap_or_default(),
+        speeds
+    ))
+}
+
+fn check_pwm_writable(fans: &FanDetector) -> Result<String> {
+    let read_only: Vec<String> = fans
+        .get_fans()
+        .iter()
+        .filter(|fan| !fan.pwm_writable)
+        .map(|fan| fan.pwm_path.display().to_string())
+        .collect();
+    if !fans.can_control() {
+        return Err(FanCurveError::Config(format!(
+            "No writable PWM files ({}); run as root or through the daemon",
+            read_only.join(", ")
+        )));
+    }
+    if read_only.is_empty() {
+        Ok(format!("{} of {} fans", fans.fan_count(), fans.fan_count()))
+    } else {
+        Ok(format!(
+            "{} of {} fans, read-only: {}",
+            fans.fan_count() - read_only.len(),
+            fans.fan_count(),
+            read_only.join(", ")
+        ))
+    }
+}
+
+async fn check_daemon() -> Result<String> {
+    let connection = Connection::system().await?;
+    let build = daemon_build_info(&connection).await?;
+    Ok(format!("v{} (git {})", build.version, build.git_hash))
+}
+
+/// Load the config at `path` if there is one, then save and reload a copy next to it
+fn check_config(path: &Path) -> Result<String> {
+    let (config, source) = if path.exists() {
+        let config = FanCurveConfig::load_from_file(path)
+            .map_err(|e| e.context(format!("Failed to load {}", path.display())))?;
+        (config, format!("loaded {}", path.display()))
+    } else {
+        (
+            FanCurveConfig::default(),
+            format!("{} missing, using defaults", path.display()),
+        )
+    };
+
+    let scratch = scratch_path(path);
+    let saved = config
+        .save_to_file(&scratch)
+        .and_then(|_| FanCurveConfig::load_from_file(&scratch));
+    let _ = fs::remove_file(&scratch);
+    let saved = saved.map_err(|e| e.context(format!("Failed to save {}", scratch.display())))?;
+    if saved.curves.len() != config.curves.len() {
+        return Err(FanCurveError::Config(format!(
+            "Saved config has {} curves instead of {}",
+            saved.curves.len(),
+            config.curves.len()
+        )));
+    }
+    Ok(format!("{}, {} curves", source, config.curves.len()))
+}
+
+/// File in the config directory (or the temp directory if it doesn't exist yet) to test saving
+fn scratch_path(config_path: &Path) -> PathBuf {
+    let dir = config_path
+        .parent()
+        .filter(|dir| dir.is_dir())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(std::env::temp_dir);
+    let file_name = config_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "config.json".to_string());
+    dir.join(format!(".doctor-{}-{}", std::process::id(), file_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn build() -> BuildInfo {
+        BuildInfo {
+            version: "1.2.3".to_string(),
+            git_hash: "abc1234".to_string(),
+            build_time: "2024-01-01".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_report_aggregates_mixed_results() {
+        let mut report = DoctorReport::new(build(), "6.8.0");
+        report.record("CPU vendor", Ok("Amd".to_string()));
+        report.record(
+            "Fan detection",
+            Err(FanCurveError::Config("No fans found".to_string())),
+        );
+        report.record("PWM writability", Ok("2 of 2 fans".to_string()));
+        report.record(
+            "DBus daemon",
+            Err(FanCurveError::Config("not running".to_string())),
+        );
+
+        assert!(!report.all_passed());
+        let failed: Vec<&str> = report.failures().map(|check| check.name).collect();
+        assert_eq!(failed, ["Fan detection", "DBus daemon"]);
+        assert_eq!(report.checks().len(), 4);
+
+        let text = report.to_string();
+        assert!(text.starts_with("fan-curve-app v1.2.3 (git abc1234) built 2024-01-01\n"));
+        assert!(text.contains("Kernel: 6.8.0\n"));
+        assert!(text.contains("[PASS] CPU vendor: Amd\n"));
+        assert!(text.contains("[FAIL] Fan detection: Configuration error: No fans found\n"));
+        assert!(text.ends_with("2 of 4 checks passed"));
+
+        let error = report.into_result().unwrap_err().to_string();
+        assert!(error.contains("2 of 4 checks failed: Fan detection, DBus daemon"));
+
+        let mut clean = DoctorReport::new(build(), "6.8.0");
+        clean.record("CPU vendor", Ok("Intel".to_string()));
+        assert!(clean.all_passed());
+        assert!(clean.into_result().is_ok());
+    }
+
+    #[test]
+    fn test_config_check() {
+        let dir = TempDir::new("doctor");
+        let path = dir.path().join("config.json");
+        let detail = check_config(&path).unwrap();
+        assert!(detail.contains("missing, using defaults"), "{}", detail);
+
+        FanCurveConfig::default().save_to_file(&path).unwrap();
+        assert!(check_config(&path).unwrap().starts_with("loaded"));
+        // The scratch copy is cleaned up
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let broken = dir.write("broken.json", "{ not json");
+        assert!(check_config(&broken).is_err());
+    }
+}
@@ -0,0 +1,109 @@
+//! Fan duty with explicit unit conversions
+//!
+//! Curves store duty in ten-thousandths of full speed (0-10000) like system76-power,
+//! people think in percent (0-100) and hwmon takes PWM values (0-255). Converting
+//! through [`Duty`] keeps the three from being mixed up.
+
+use std::fmt;
+
+/// Fan duty, stored in ten-thousandths of full speed (0-10000)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Duty(u16);
+
+impl Duty {
+    /// Largest duty in ten-thousandths
+    pub const MAX_TEN_THOUSANDTHS: u16 = 10000;
+    /// Fans stopped
+    pub const OFF: Duty = Duty(0);
+    /// Fans at full speed
+    pub const FULL: Duty = Duty(Self::MAX_TEN_THOUSANDTHS);
+
+    /// Duty from ten-thousandths, clamped to 10000
+    pub fn from_ten_thousandths(value: u16) -> Self {
+        Self(value.min(Self::MAX_TEN_THOUSANDTHS))
+    }
+
+    /// Duty from a percentage, rounded to the nearest ten-thousandth and clamped to 0-100%
+    pub fn from_percent(percent: f32) -> Self {
+        let value = (percent * 100.0).round();
+        Self(value.clamp(0.0, Self::MAX_TEN_THOUSANDTHS as f32) as u16)
+    }
+
+    /// Duty from a PWM value (0-255)
+    /// Rounds up so that [`Duty::as_pwm`] gives back the same PWM value
+    pub fn from_pwm(pwm: u8) -> Self {
+        Self((u32::from(pwm) * 10000).div_ceil(255) as u16)
+    }
+
+    /// Duty in ten-thousandths (0-10000)
+    pub fn as_ten_thousandths(self) -> u16 {
+        self.0
+    }
+
+    /// Duty as a percentage (0.0-100.0)
+    pub fn as_percent(self) -> f32 {
+        self.0 as f32 / 100.0
+    }
+
+    /// Duty as a whole percentage (0-100), rounded to nearest
+    pub fn as_whole_percent(self) -> u8 {
+        ((self.0 + 50) / 100) as u8
+    }
+
+    /// Duty as a fraction of full speed (0.0-1.0)
+    pub fn as_fraction(self) -> f64 {
+        f64::from(self.0) / 10000.0
+    }
+
+    /// Duty as a PWM value (0-255)
+    /// Truncates like system76-power: (duty * 255) / 10000
+    pub fn as_pwm(self) -> u8 {
+        ((u32::from(self.0) * 255) / 10000) as u8
+    }
+}
+
+impl fmt::Display for Duty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.1}%", self.as_percent())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duty_conversions() {
+        assert_eq!(Duty::from_ten_thousandths(12000), Duty::FULL);
+        assert_eq!(Duty::from_ten_thousandths(4567).as_ten_thousandths(), 4567);
+
+        assert_eq!(Duty::from_percent(45.5).as_ten_thousandths(), 4550);
+        assert_eq!(Duty::from_percent(33.333).as_ten_thousandths(), 3333);
+        assert_eq!(Duty::from_percent(-5.0), Duty::OFF);
+        assert_eq!(Duty::from_percent(150.0), Duty::FULL);
+
+        assert_eq!(Duty::from_pwm(0), Duty::OFF);
+        assert_eq!(Duty::from_pwm(255), Duty::FULL);
+        assert_eq!(Duty::from_pwm(1).as_ten_thousandths(), 40);
+        assert_eq!(Duty::from_pwm(127).as_ten_thousandths(), 4981);
+        assert_eq!(Duty::from_pwm(128).as_ten_thousandths(), 5020);
+
+        assert_eq!(Duty::from_ten_thousandths(4550).as_percent(), 45.5);
+        assert_eq!(Duty::from_ten_thousandths(4549).as_whole_percent(), 45);
+        assert_eq!(Duty::from_ten_thousandths(4550).as_whole_percent(), 46);
+        assert_eq!(Duty::FULL.as_whole_percent(), 100);
+        assert_eq!(Duty::from_ten_thousandths(4500).as_fraction(), 0.45);
+
+        assert_eq!(Duty::FULL.as_pwm(), 255);
+        assert_eq!(Duty::from_percent(50.0).as_pwm(), 127);
+        assert_eq!(Duty::from_ten_thousandths(39).as_pwm(), 0);
+        assert_eq!(Duty::from_ten_thousandths(40).as_pwm(), 1);
+
+        // PWM survives a round trip
+        for pwm in 0..=255u8 {
+            assert_eq!(Duty::from_pwm(pwm).as_pwm(), pwm);
+        }
+
+        assert_eq!(Duty::from_ten_thousandths(4550).to_string(), "45.5%");
+    }
+}
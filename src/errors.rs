@@ -14,15 +14,38 @@ pub enum FanCurveError {
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
 
+    #[error("TOML parse error: {0}")]
+    TomlDeserialization(#[from] toml::de::Error),
+
+    #[error("TOML serialization error: {0}")]
+    TomlSerialization(#[from] toml::ser::Error),
+
     #[error("DBus error: {0}")]
     DBus(#[from] zbus::Error),
 
+    #[error("Background task failed: {0}")]
+    Task(#[from] tokio::task::JoinError),
+
+    /// Another error with a description of what was being done, see [`FanCurveError::context`]
+    #[error("{context}: {source}")]
+    Context {
+        context: String,
+        #[source]
+        source: Box<FanCurveError>,
+    },
+
     #[error("Configuration error: {0}")]
     Config(String),
 
     #[error("Fan curve not found: {name}")]
     FanCurveNotFound { name: String },
 
+    #[error("Fan curve '{name}' is locked; unlock it before editing")]
+    CurveLocked { name: String },
+
+    #[error("A fan curve named '{name}' already exists")]
+    CurveNameTaken { name: String },
+
     #[error("Invalid fan curve point: temperature {temp}°C, duty {duty}%")]
     InvalidFanPoint { temp: i16, duty: u16 },
 
@@ -36,7 +59,218 @@ pub enum FanCurveError {
     Unknown(String),
 }
 
-/// Helper function to convert display errors to zbus errors
-pub fn zbus_error_from_display(err: impl std::fmt::Display) -> zbus::fdo::Error {
-    zbus::fdo::Error::Failed(format!("{}", err))
+impl FanCurveError {
+    /// Wrap this error with what was being done, keeping it as the `source()`
+    pub fn context(self, context: impl Into<String>) -> Self {
+        FanCurveError::Context {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// The error underneath any [`FanCurveError::Context`] layers
+    pub fn root(&self) -> &FanCurveError {
+        match self {
+            FanCurveError::Context { source, .. } => source.root(),
+            e => e,
+        }
+    }
+
+    /// The I/O error behind this one, if any
+    pub fn io_error(&self) -> Option<&std::io::Error> {
+        match self.root() {
+            FanCurveError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Whether this comes down to missing permissions, e.g. to write the fan PWM files
+    pub fn is_permission_denied(&self) -> bool {
+        matches!(self.root(), FanCurveError::PermissionDenied(_))
+            || self
+                .io_error()
+                .is_some_and(|e| e.kind() == std::io::ErrorKind::PermissionDenied)
+    }
+
+    /// Name of the DBus error a method call failed with, e.g. [`DaemonError::CURVE_NOT_FOUND`]
+    pub fn dbus_error_name(&self) -> Option<&str> {
+        match self.root() {
+            FanCurveError::DBus(zbus::Error::MethodError(name, _, _)) => Some(name.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Whether this is an error the fan curve daemon itself replied with, i.e. it is running
+    pub fn is_daemon_reply(&self) -> bool {
+        self.dbus_error_name()
+            .is_some_and(|name| name.starts_with(DaemonError::PREFIX))
+    }
+}
+
+/// Errors the daemon's DBus methods reply with, named `com.system76.FanCurveDaemon.<Variant>`
+/// so clients can tell them apart without matching on the message
+#[derive(zbus::DBusError, Debug)]
+#[dbus_error(prefix = "com.system76.FanCurveDaemon")]
+pub enum DaemonError {
+    #[dbus_error(zbus_error)]
+    ZBus(zbus::Error),
+    /// No fan curve with the given name or index
+    CurveNotFound(String),
+    /// A fan curve point outside the allowed temperature or duty range
+    InvalidPoint(String),
+    /// Any other argument that can't be used
+    InvalidArgument(String),
+    /// The curve is locked against edits
+    CurveLocked(String),
+    /// The daemon lacks the permissions the call needs, e.g. to write PWM files
+    NotPermitted(String),
+    /// Anything else, e.g. the config couldn't be saved
+    Failed(String),
+}
+
+impl DaemonError {
+    pub const PREFIX: &'static str = "com.system76.FanCurveDaemon.";
+    pub const CURVE_NOT_FOUND: &'static str = "com.system76.FanCurveDaemon.CurveNotFound";
+    pub const INVALID_POINT: &'static str = "com.system76.FanCurveDaemon.InvalidPoint";
+    pub const INVALID_ARGUMENT: &'static str = "com.system76.FanCurveDaemon.InvalidArgument";
+    pub const CURVE_LOCKED: &'static str = "com.system76.FanCurveDaemon.CurveLocked";
+    pub const NOT_PERMITTED: &'static str = "com.system76.FanCurveDaemon.NotPermitted";
+    pub const FAILED: &'static str = "com.system76.FanCurveDaemon.Failed";
+}
+
+impl From<FanCurveError> for DaemonError {
+    fn from(err: FanCurveError) -> Self {
+        let message = err.to_string();
+        if err.is_permission_denied() {
+            return DaemonError::NotPermitted(message);
+        }
+        match err.root() {
+            FanCurveError::FanCurveNotFound { .. } => DaemonError::CurveNotFound(message),
+            FanCurveError::InvalidFanPoint { .. } => DaemonError::InvalidPoint(message),
+            FanCurveError::CurveLocked { .. } => DaemonError::CurveLocked(message),
+            FanCurveError::CurveNameTaken { .. } => DaemonError::InvalidArgument(message),
+            // Reading, parsing or writing a file such as the config on disk failed, which is
+            // never the fault of the call's arguments
+            FanCurveError::Io(_)
+            | FanCurveError::Serialization(_)
+            | FanCurveError::TomlDeserialization(_)
+            | FanCurveError::TomlSerialization(_) => DaemonError::Failed(message),
+            // A bare config error is a validation error about the call's arguments; one with
+            // context came up while carrying the call out, e.g. applying a curve
+            FanCurveError::Config(_) if matches!(err, FanCurveError::Config(_)) => {
+                DaemonError::InvalidArgument(message)
+            }
+            _ => DaemonError::Failed(message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+    use std::io;
+
+    /// First error of type `E` in the `source()` chain
+    fn find_source<E: Error + 'static>(err: &FanCurveError) -> Option<&E> {
+        let mut source = err.source();
+        while let Some(e) = source {
+            if let Some(found) = e.downcast_ref::<E>() {
+                return Some(found);
+            }
+            source = e.source();
+        }
+        None
+    }
+
+    #[test]
+    fn test_source_keeps_inner_error() {
+        let err = FanCurveError::from(io::Error::new(io::ErrorKind::PermissionDenied, "pwm1"));
+        assert!(err.source().unwrap().is::<io::Error>());
+
+        let err = err.context("Failed to set PWM duty for fan 1");
+        assert_eq!(
+            err.to_string(),
+            "Failed to set PWM duty for fan 1: IO error: pwm1"
+        );
+        assert!(err.is_permission_denied());
+        assert!(matches!(err.root(), FanCurveError::Io(_)));
+        let io_err = find_source::<io::Error>(&err).unwrap();
+        assert_eq!(io_err.kind(), io::ErrorKind::PermissionDenied);
+
+        let err = FanCurveError::from(serde_json::from_str::<u32>("").unwrap_err());
+        assert!(err.source().unwrap().is::<serde_json::Error>());
+        let err = err.context("Failed to load config");
+        assert!(!err.is_permission_denied());
+        assert!(err.io_error().is_none());
+        assert!(find_source::<serde_json::Error>(&err).unwrap().is_eof());
+
+        let err = FanCurveError::from(toml::from_str::<toml::Table>("a =").unwrap_err());
+        assert!(err.source().unwrap().is::<toml::de::Error>());
+
+        let err = FanCurveError::from(zbus::Error::InterfaceNotFound);
+        assert!(err.source().unwrap().is::<zbus::Error>());
+
+        assert!(FanCurveError::Config("bad".to_string()).source().is_none());
+    }
+
+    #[test]
+    fn test_daemon_error_names() {
+        use zbus::DBusError;
+
+        let not_found = FanCurveError::FanCurveNotFound {
+            name: "Turbo".to_string(),
+        };
+        let err = DaemonError::from(not_found.context("Failed to select curve"));
+        assert_eq!(err.name().as_str(), DaemonError::CURVE_NOT_FOUND);
+        assert_eq!(
+            DBusError::description(&err),
+            Some("Failed to select curve: Fan curve not found: Turbo")
+        );
+
+        let denied = FanCurveError::from(io::Error::new(io::ErrorKind::PermissionDenied, "pwm1"));
+        let err = DaemonError::from(denied);
+        assert_eq!(err.name().as_str(), DaemonError::NOT_PERMITTED);
+        let err = DaemonError::from(FanCurveError::CurveLocked {
+            name: "Quiet".to_string(),
+        });
+        assert_eq!(err.name().as_str(), DaemonError::CURVE_LOCKED);
+        let err = DaemonError::from(FanCurveError::DaemonNotRunning);
+        assert_eq!(err.name().as_str(), DaemonError::FAILED);
+
+        let invalid = FanCurveError::Config("Unknown fan control mode 'turbo'".to_string());
+        assert_eq!(
+            DaemonError::from(invalid).name().as_str(),
+            DaemonError::INVALID_ARGUMENT
+        );
+        let failed = FanCurveError::Config("Fan detector not initialized".to_string())
+            .context("Failed to apply fan curve 'Standard'");
+        assert_eq!(
+            DaemonError::from(failed).name().as_str(),
+            DaemonError::FAILED
+        );
+    }
+
+    #[test]
+    fn test_config_file_failures_are_failed() {
+        use crate::fan::FanCurveConfig;
+        use crate::test_util::TempDir;
+        use zbus::DBusError;
+
+        let dir = TempDir::new("errors-config-file");
+        dir.write("broken.json", "{ not json");
+        dir.write("broken.toml", "curves = [");
+        dir.write("file", "");
+        let failures = [
+            FanCurveConfig::load_from_file(&dir.path().join("missing.json")).unwrap_err(),
+            FanCurveConfig::load_from_file(&dir.path().join("broken.json")).unwrap_err(),
+            FanCurveConfig::load_from_file(&dir.path().join("broken.toml")).unwrap_err(),
+            FanCurveConfig::new()
+                .save_to_file(&dir.path().join("file/config.json"))
+                .unwrap_err(),
+        ];
+        for err in failures {
+            assert_eq!(DaemonError::from(err).name().as_str(), DaemonError::FAILED);
+        }
+    }
 }
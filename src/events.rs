@@ -0,0 +1,97 @@
+//! Hooks for following the fans from outside this crate, e.g. to drive EC LEDs
+//!
+//! Register an [`EventObserver`] with
+//! [`FanMonitor::add_observer`](crate::fan_monitor::FanMonitor::add_observer); it is told about
+//! every sample recorded by the monitor that changes the duty, the temperature or a fan's stall
+//! state.
+
+use crate::duty::Duty;
+use log::{info, warn};
+use std::collections::HashSet;
+
+/// Receives fan state changes; every method does nothing unless overridden
+pub trait EventObserver: Send {
+    /// The fan duty (ten-thousandths) changed
+    fn on_duty_changed(&mut self, _duty: u16) {}
+
+    /// The temperature (°C) fed to the fan curve changed
+    fn on_temp_changed(&mut self, _temp: f32) {}
+
+    /// `fan` reads 0 RPM while driven above the curve's spin-up threshold
+    /// Called once per stall, and again only after the fan has spun
+    fn on_stall(&mut self, _fan: u8) {}
+}
+
+/// Observer that ignores every event
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopObserver;
+
+impl EventObserver for NoopObserver {}
+
+/// Observer that logs every event
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoggingObserver;
+
+impl EventObserver for LoggingObserver {
+    fn on_duty_changed(&mut self, duty: u16) {
+        info!("Fan duty changed to {}", Duty::from_ten_thousandths(duty));
+    }
+
+    fn on_temp_changed(&mut self, temp: f32) {
+        info!("Temperature changed to {:.1}°C", temp);
+    }
+
+    fn on_stall(&mut self, fan: u8) {
+        warn!("Fan {} has stalled", fan);
+    }
+}
+
+/// Registered observers and what they were last told, so each change is reported once
+#[derive(Default)]
+pub struct EventObservers {
+    observers: Vec<Box<dyn EventObserver>>,
+    last_duty: Option<u16>,
+    last_temp: Option<f32>,
+    stalled: HashSet<u8>,
+}
+
+impl EventObservers {
+    pub fn add(&mut self, observer: Box<dyn EventObserver>) {
+        self.observers.push(observer);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+
+    /// Report whatever changed since the last sample
+    /// `fan_speeds` are (fan number, RPM) pairs; a fan at 0 RPM counts as stalled when
+    /// `duty` is above `stall_threshold`
+    pub fn update(
+        &mut self,
+        duty: u16,
+        temp: f32,
+        fan_speeds: impl IntoIterator<Item = (u8, u16)>,
+        stall_threshold: u16,
+    ) {
+        if self.last_duty.replace(duty) != Some(duty) {
+            for observer in &mut self.observers {
+                observer.on_duty_changed(duty);
+            }
+        }
+        if self.last_temp.replace(temp) != Some(temp) {
+            for observer in &mut self.observers {
+                observer.on_temp_changed(temp);
+            }
+        }
+        for (fan, rpm) in fan_speeds {
+            if rpm > 0 {
+                self.stalled.remove(&fan);
+            } else if duty > stall_threshold && self.stalled.insert(fan) {
+                for observer in &mut self.observers {
+                    observer.on_stall(fan);
+                }
+            }
+        }
+    }
+}
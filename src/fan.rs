@@ -1,291 +1,2109 @@
-use crate::errors::Result;
-use serde::{Deserialize, Serialize};
+use crate::curve_registry::CurveRegistry;
+use crate::duty::Duty;
+use crate::errors::{FanCurveError, Result};
+use crate::closed_loop::RpmTarget;
+use crate::fan_detector::PwmEnableValues;
+use crate::idle::{IdleFloor, DEFAULT_IDLE_AFTER, DEFAULT_IDLE_CPU_PERCENT};
+use crate::schedule::ScheduleEntry;
+use log::{info, warn};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::ffi::OsString;
 use std::fs;
-use std::path::Path;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::fmt;
+use std::str::FromStr;
+use std::ops::Index;
+use std::time::Duration;
 use zvariant::Type;
 
-#[derive(Debug, Clone, Serialize, Deserialize, Type, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FanPoint {
     pub temp: i16,
     pub duty: u16,
+    /// Shape of the segment from the previous point up to this one
+    pub easing: Easing,
+}
+
+/// A point as saved in the config file; linear points leave out `easing`
+#[derive(Serialize, Deserialize)]
+struct SavedFanPoint {
+    temp: i16,
+    duty: u16,
+    #[serde(default, skip_serializing_if = "Easing::is_linear")]
+    easing: Easing,
+}
+
+// D-Bus peers know points as (°C, duty), so easing is only kept in the config file
+impl Type for FanPoint {
+    fn signature() -> zvariant::Signature<'static> {
+        <(i16, u16)>::signature()
+    }
+}
+
+impl Serialize for FanPoint {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            SavedFanPoint {
+                temp: self.temp,
+                duty: self.duty,
+                easing: self.easing,
+            }
+            .serialize(serializer)
+        } else {
+            (self.temp, self.duty).serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for FanPoint {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let saved = SavedFanPoint::deserialize(deserializer)?;
+            Ok(FanPoint::new(saved.temp, saved.duty).with_easing(saved.easing))
+        } else {
+            let (temp, duty) = <(i16, u16)>::deserialize(deserializer)?;
+            Ok(FanPoint::new(temp, duty))
+        }
+    }
 }
 
 impl FanPoint {
     pub fn new(temp: i16, duty: u16) -> Self {
-        Self { temp, duty }
+        Self {
+            temp,
+            duty,
+            easing: Easing::Linear,
+        }
+    }
+
+    /// The same point, approached with `easing`
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+}
+
+/// How the duty moves from one point to the next
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    /// Constant slope
+    #[default]
+    Linear,
+    /// Starts flat and steepens towards the point
+    EaseIn,
+    /// Starts steep and flattens towards the point
+    EaseOut,
+    /// Flat at both ends, steepest in the middle
+    SmoothStep,
+}
+
+impl Easing {
+    pub fn is_linear(&self) -> bool {
+        *self == Easing::Linear
+    }
+
+    /// Map the position within a segment (0.0-1.0) to the fraction of the duty change applied
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::SmoothStep => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// Coldest temperature (°C) accepted for a curve point, matching the sensor range
+pub const MIN_POINT_TEMP: i16 = -50;
+
+/// Hottest temperature (°C) kept for a curve point when loading a config
+pub const MAX_POINT_TEMP: i16 = 150;
+
+/// Highest temperature (°C) covered by the precomputed duty table
+pub const DUTY_TABLE_MAX_TEMP: usize = 150;
+
+/// Name of the built-in curve that leaves the fans to the firmware
+pub const AUTO_CURVE_NAME: &str = "Automatic (firmware)";
+
+/// Degrees between the points a formula curve is turned into
+pub const FORMULA_SAMPLE_STEP: i16 = 5;
+
+/// [`FanCurve::safety_lint`] warns when a curve's highest point is cooler than this (°C)
+pub const SAFETY_LINT_MIN_TEMP: i16 = 90;
+
+/// [`FanCurve::safety_lint`] warns when a curve never reaches this duty (ten-thousandths)
+pub const SAFETY_LINT_MIN_DUTY: u16 = 9500;
+
+/// How a curve turns a temperature into a duty
+///
+/// Saved under the curve's `points` key: a list of points, or the formula parameters.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CurveKind {
+    /// Straight lines between points, held flat past the ends
+    Points(Vec<FanPoint>),
+    /// `duty = clamp(a * (temp - t0)^p, min, max)` with duties in ten-thousandths and
+    /// temperatures in °C; below `t0` the duty is `min`
+    Formula {
+        t0: f32,
+        a: f32,
+        p: f32,
+        min: u16,
+        max: u16,
+    },
+}
+
+impl CurveKind {
+    /// Points of a point curve; formula curves have none
+    fn points(&self) -> &[FanPoint] {
+        match self {
+            CurveKind::Points(points) => points,
+            CurveKind::Formula { .. } => &[],
+        }
+    }
+
+    /// The points, or the formula sampled every `FORMULA_SAMPLE_STEP` degrees from 0 to 100°C
+    fn to_points(&self) -> Vec<FanPoint> {
+        match self {
+            CurveKind::Points(points) => points.clone(),
+            CurveKind::Formula { .. } => (0..=100)
+                .step_by(FORMULA_SAMPLE_STEP as usize)
+                .map(|temp| {
+                    let duty = self.formula_duty(f32::from(temp)).unwrap_or(0.0);
+                    FanPoint::new(temp, duty.round() as u16)
+                })
+                .collect(),
+        }
+    }
+
+    /// Check if a formula can be evaluated: finite parameters, `a` not negative and `p` positive
+    /// Always true for point curves
+    fn formula_is_usable(&self) -> bool {
+        match *self {
+            CurveKind::Points(_) => true,
+            CurveKind::Formula { t0, a, p, .. } => {
+                t0.is_finite() && a.is_finite() && p.is_finite() && a >= 0.0 && p > 0.0
+            }
+        }
+    }
+
+    /// Duty (ten-thousandths, unrounded) of a formula curve at `temp` °C
+    fn formula_duty(&self, temp: f32) -> Option<f32> {
+        let CurveKind::Formula { t0, a, p, min, max } = *self else {
+            return None;
+        };
+        let rise = (temp - t0).max(0.0);
+        Some((a * rise.powf(p)).clamp(f32::from(min), f32::from(max.max(min))))
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Type, PartialEq)]
+/// D-Bus signature of [`FanCurve`], returned by `GetFanCurvesV2`: name, points, spin-up
+/// duty, spin-up threshold, locked, firmware control and tags
+///
+/// Adding a field changes this signature and breaks clients decoding the old one, so new
+/// fields go with a new versioned method rather than changing an existing method's reply.
+pub const FAN_CURVE_SIGNATURE: &str = "(sa(nq)qqbbas)";
+
+/// Original D-Bus signature of a fan curve, name and points, kept by `GetFanCurves`
+/// See [`FanCurveDto`]
+pub const FAN_CURVE_V1_SIGNATURE: &str = "(sa(nq))";
+
+/// Fields of a [`FanCurve`] as sent over D-Bus, in [`FAN_CURVE_SIGNATURE`] order
+type FanCurveWire = (String, Vec<FanPoint>, u16, u16, bool, bool, Vec<String>);
+
+// Config files use the derived (de)serialization, D-Bus the fixed `FanCurveWire` tuple
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct FanCurve {
     name: String,
-    points: Vec<FanPoint>,
+    #[serde(rename = "points")]
+    kind: CurveKind,
+    /// Duty (ten-thousandths) sent for one cycle when a stopped fan has to start; 0 disables the kick
+    #[serde(default)]
+    spin_up_duty: u16,
+    /// A fan commanded at or below this duty (ten-thousandths) counts as stopped
+    #[serde(default)]
+    spin_up_threshold: u16,
+    /// Locked curves ignore edits to their name and points
+    #[serde(default)]
+    locked: bool,
+    /// Hand the fans back to the firmware instead of writing duties from the points
+    #[serde(default)]
+    firmware_control: bool,
+    /// Free-form labels like "summer" or "gaming" for grouping curves
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Measured (duty in ten-thousandths, dBA) pairs in duty order, for `max_noise`
+    /// Like `max_noise`, only kept in the config file and not sent over D-Bus
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    noise_table: Vec<(u16, f32)>,
+    /// Duty is capped where `noise_table` reaches this many dBA; no cap when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_noise: Option<f32>,
+    /// Duty for every whole degree up to `DUTY_TABLE_MAX_TEMP`, built by `precompute_table`
+    #[serde(skip)]
+    duty_table: Option<Box<[u16; DUTY_TABLE_MAX_TEMP + 1]>>,
+}
+
+// Implemented by hand so the duty table and noise cap stay out of the D-Bus signature,
+// which must match `FAN_CURVE_SIGNATURE`
+impl Type for FanCurve {
+    fn signature() -> zvariant::Signature<'static> {
+        FanCurveWire::signature()
+    }
+}
+
+// D-Bus peers only know point lists, so formula curves are sent sampled
+impl Serialize for FanCurve {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return FanCurve::serialize(self, serializer);
+        }
+        let wire: FanCurveWire = (
+            self.name.clone(),
+            self.to_points(),
+            self.spin_up_duty,
+            self.spin_up_threshold,
+            self.locked,
+            self.firmware_control,
+            self.tags.clone(),
+        );
+        wire.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for FanCurve {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            return FanCurve::deserialize(deserializer);
+        }
+        let (name, points, spin_up_duty, spin_up_threshold, locked, firmware_control, tags) =
+            FanCurveWire::deserialize(deserializer)?;
+        let mut curve = FanCurve::new(name);
+        curve.kind = CurveKind::Points(points);
+        curve.spin_up_duty = spin_up_duty;
+        curve.spin_up_threshold = spin_up_threshold;
+        curve.locked = locked;
+        curve.firmware_control = firmware_control;
+        curve.tags = tags;
+        Ok(curve)
+    }
+}
+
+// Point order is ignored, so a curve edited through `points_mut` still matches its saved copy
+impl PartialEq for FanCurve {
+    fn eq(&self, other: &Self) -> bool {
+        let sorted = |points: &[FanPoint]| {
+            let mut points: Vec<(i16, u16, Easing)> =
+                points.iter().map(|p| (p.temp, p.duty, p.easing)).collect();
+            points.sort_unstable();
+            points
+        };
+        let same_kind = match (&self.kind, &other.kind) {
+            (CurveKind::Points(a), CurveKind::Points(b)) => {
+                a.len() == b.len() && sorted(a) == sorted(b)
+            }
+            (a, b) => a == b,
+        };
+        self.name == other.name
+            && self.spin_up_duty == other.spin_up_duty
+            && self.spin_up_threshold == other.spin_up_threshold
+            && self.locked == other.locked
+            && self.firmware_control == other.firmware_control
+            && self.tags == other.tags
+            && self.noise_table == other.noise_table
+            && self.max_noise == other.max_noise
+            && same_kind
+    }
 }
 
+impl Eq for FanCurve {}
+
 impl fmt::Display for FanCurve {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.name)
     }
 }
 
+/// Points in temperature order
+///
+/// ```
+/// use fan_curve_app::fan::FanCurve;
+///
+/// let curve = FanCurve::standard();
+/// let mut previous = i16::MIN;
+/// for point in &curve {
+///     assert!(point.temp > previous);
+///     previous = point.temp;
+/// }
+/// ```
+impl<'a> IntoIterator for &'a FanCurve {
+    type Item = &'a FanPoint;
+    type IntoIter = std::slice::Iter<'a, FanPoint>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// The point at `index`; panics if it is out of range, see [`FanCurve::get_point`]
+///
+/// ```
+/// use fan_curve_app::fan::FanCurve;
+///
+/// let mut curve = FanCurve::new("Desk".to_string());
+/// curve.add_point(70, 8000);
+/// curve.add_point(30, 2000);
+/// assert_eq!(curve[0].temp, 30);
+/// assert_eq!(curve[curve.len() - 1].duty, 8000);
+/// ```
+impl Index<usize> for FanCurve {
+    type Output = FanPoint;
+
+    fn index(&self, index: usize) -> &FanPoint {
+        &self.points()[index]
+    }
+}
+
 impl FanCurve {
     pub fn new(name: String) -> Self {
         Self {
             name,
-            points: Vec::new(),
+            kind: CurveKind::Points(Vec::new()),
+            spin_up_duty: 0,
+            spin_up_threshold: 0,
+            locked: false,
+            firmware_control: false,
+            tags: Vec::new(),
+            noise_table: Vec::new(),
+            max_noise: None,
+            duty_table: None,
+        }
+    }
+
+    /// Curve following `duty = clamp(a * (temp - t0)^p, min, max)`, see [`CurveKind::Formula`]
+    pub fn from_formula(name: String, t0: f32, a: f32, p: f32, min: u16, max: u16) -> Self {
+        let mut curve = Self::new(name);
+        curve.kind = CurveKind::Formula { t0, a, p, min, max };
+        curve
+    }
+
+    pub fn kind(&self) -> &CurveKind {
+        &self.kind
+    }
+
+    /// Check if the duty comes from a formula rather than points
+    pub fn is_formula(&self) -> bool {
+        matches!(self.kind, CurveKind::Formula { .. })
+    }
+
+    /// Duty (ten-thousandths) a stopped fan gets for one cycle before the curve's duty
+    pub fn spin_up_duty(&self) -> u16 {
+        self.spin_up_duty
+    }
+
+    /// Duty (ten-thousandths) at or below which a fan counts as stopped
+    pub fn spin_up_threshold(&self) -> u16 {
+        self.spin_up_threshold
+    }
+
+    /// Kick fans rising above `threshold` with `duty` for one cycle; a `duty` of 0 turns this off
+    pub fn set_spin_up(&mut self, duty: u16, threshold: u16) {
+        self.spin_up_duty = duty;
+        self.spin_up_threshold = threshold;
+    }
+
+    /// Measured (duty, dBA) pairs used by [`FanCurve::max_noise`]
+    pub fn noise_table(&self) -> &[(u16, f32)] {
+        &self.noise_table
+    }
+
+    /// Loudness (dBA) the duty is capped at, if any
+    pub fn max_noise(&self) -> Option<f32> {
+        self.max_noise
+    }
+
+    /// Cap the duty at the highest duty `table` puts at or below `max_noise` dBA
+    /// `table` holds (duty in ten-thousandths, dBA) pairs in duty order; `max_noise` of
+    /// None removes the cap. Checked by [`FanCurve::validate`]
+    pub fn set_noise_cap(&mut self, table: Vec<(u16, f32)>, max_noise: Option<f32>) {
+        self.noise_table = table;
+        self.max_noise = max_noise;
+        self.points_changed();
+    }
+
+    /// Highest duty (ten-thousandths) whose interpolated noise stays at or below `max_noise`
+    /// Beyond the ends of the table the noise is held flat, so a table that never gets that
+    /// loud gives no cap, and one whose quietest entry is already too loud caps at that duty.
+    /// None without a cap or table
+    pub fn noise_cap_duty(&self) -> Option<u16> {
+        let max_noise = self.max_noise?;
+        let first = self.noise_table.first()?;
+        if first.1 > max_noise {
+            return Some(first.0);
         }
+        self.noise_table
+            .windows(2)
+            .find(|pair| pair[1].1 > max_noise)
+            .map(|pair| {
+                let ((duty1, noise1), (duty2, noise2)) = (pair[0], pair[1]);
+                let factor = ((max_noise - noise1) / (noise2 - noise1)).clamp(0.0, 1.0);
+                duty1 + (factor * f32::from(duty2.saturating_sub(duty1))) as u16
+            })
     }
 
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    /// Check if selecting this curve returns the fans to automatic (firmware) control
+    pub fn is_auto(&self) -> bool {
+        self.firmware_control
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Replace the tags; blanks and case-insensitive duplicates are dropped
+    pub fn set_tags<I, S>(&mut self, tags: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.tags.clear();
+        for tag in tags {
+            let tag = tag.as_ref().trim();
+            if !tag.is_empty() && !self.has_tag(tag) {
+                self.tags.push(tag.to_string());
+            }
+        }
+    }
+
+    /// Check if the curve has `tag`, ignoring case
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag.trim();
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Check if the curve is protected from edits
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    pub fn set_locked(&mut self, locked: bool) {
+        self.locked = locked;
+    }
+
+    /// Fail with `CurveLocked` if the curve is locked
+    pub fn ensure_unlocked(&self) -> Result<()> {
+        if self.locked {
+            return Err(FanCurveError::CurveLocked {
+                name: self.name.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Rename the curve; does nothing if it is locked
     pub fn set_name(&mut self, name: String) {
-        self.name = name;
+        if !self.locked {
+            self.name = name;
+        }
     }
 
+    /// The points; empty for formula curves
     pub fn points(&self) -> &[FanPoint] {
-        &self.points
+        self.kind.points()
     }
 
+    /// The points, or a formula curve's duty sampled every `FORMULA_SAMPLE_STEP` degrees
+    pub fn to_points(&self) -> Vec<FanPoint> {
+        self.kind.to_points()
+    }
+
+    /// Mutable access to the points; drops the precomputed duty table
+    /// A formula curve is turned into points first
     pub fn points_mut(&mut self) -> &mut Vec<FanPoint> {
-        &mut self.points
+        self.duty_table = None;
+        self.point_list()
+    }
+
+    /// The point list to edit, turning a formula curve into points first
+    fn point_list(&mut self) -> &mut Vec<FanPoint> {
+        if self.is_formula() {
+            self.kind = CurveKind::Points(self.kind.to_points());
+        }
+        match &mut self.kind {
+            CurveKind::Points(points) => points,
+            CurveKind::Formula { .. } => {
+                unreachable!("formula curves were just turned into points")
+            }
+        }
     }
 
+    /// Add a point, keeping the points sorted; does nothing if the curve is locked
     pub fn add_point(&mut self, temp: i16, duty: u16) {
-        self.points.push(FanPoint::new(temp, duty));
-        self.points.sort_by_key(|p| p.temp);
+        if self.locked {
+            return;
+        }
+        let points = self.point_list();
+        points.push(FanPoint::new(temp, duty));
+        points.sort_by_key(|p| (p.temp, p.duty));
+        self.points_changed();
+    }
+
+    /// Add two points at `temp` so the duty jumps from `low` to `high` there
+    /// Right at `temp` the higher duty applies; does nothing if the curve is locked
+    pub fn add_step(&mut self, temp: i16, low: u16, high: u16) {
+        self.add_point(temp, low.min(high));
+        self.add_point(temp, low.max(high));
     }
 
+    /// Remove the hottest point; returns `None` if the curve is locked
     pub fn remove_last_point(&mut self) -> Option<FanPoint> {
-        self.points.pop()
+        if self.locked {
+            return None;
+        }
+        let point = self.point_list().pop();
+        self.points_changed();
+        point
     }
 
+    /// Remove the point at `index`; returns `None` if the curve is locked
     pub fn remove_point(&mut self, index: usize) -> Option<FanPoint> {
-        if index < self.points.len() {
-            Some(self.points.remove(index))
+        if !self.locked && index < self.to_points().len() {
+            let point = self.point_list().remove(index);
+            self.points_changed();
+            Some(point)
         } else {
             None
         }
     }
 
     pub fn get_point(&self, index: usize) -> Option<&FanPoint> {
-        self.points.get(index)
+        self.points().get(index)
+    }
+
+    /// Points in temperature order, like iterating `&curve`
+    pub fn iter(&self) -> std::slice::Iter<'_, FanPoint> {
+        self.points().iter()
+    }
+
+    /// Number of points
+    pub fn len(&self) -> usize {
+        self.points().len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.points().is_empty()
+    }
+
+    /// The hottest point
+    pub fn last_point(&self) -> Option<&FanPoint> {
+        self.points().last()
+    }
+
+    /// Mutable access to a point; drops the precomputed duty table
+    /// Returns `None` if the curve is locked
     pub fn get_point_mut(&mut self, index: usize) -> Option<&mut FanPoint> {
-        self.points.get_mut(index)
+        if self.locked {
+            return None;
+        }
+        self.duty_table = None;
+        self.point_list().get_mut(index)
+    }
+
+    /// Build a duty lookup table for 0 to `DUTY_TABLE_MAX_TEMP` °C
+    /// The table is kept up to date by `add_point` and `remove_point`
+    pub fn precompute_table(&mut self) {
+        let mut table = Box::new([0; DUTY_TABLE_MAX_TEMP + 1]);
+        for (temp, duty) in table.iter_mut().enumerate() {
+            *duty = self.interpolate_duty(temp as i16);
+        }
+        self.duty_table = Some(table);
+    }
+
+    /// Check if a duty lookup table has been built
+    pub fn has_precomputed_table(&self) -> bool {
+        self.duty_table.is_some()
+    }
+
+    /// Rebuild the duty table, if one is in use, after the points changed
+    fn points_changed(&mut self) {
+        if self.duty_table.is_some() {
+            self.precompute_table();
+        }
     }
 
     /// Calculate fan duty for a given temperature using linear interpolation
     /// Returns duty in ten-thousandths (0-10000) to match system76-power standard
-    /// Temperature is in thousandths of Celsius (e.g., 35000 = 35.0°C)
-    pub fn calculate_duty_for_temperature(&self, temp_thousandths: u32) -> u16 {
-        if self.points.is_empty() {
-            return 0;
+    /// Temperature is in thousandths of Celsius (e.g., 35000 = 35.0°C, -10000 = -10.0°C)
+    pub fn calculate_duty_for_temperature(&self, temp_thousandths: i32) -> u16 {
+        // Round down to whole degrees, so -0.5°C counts as -1°C
+        let temp = temp_thousandths.div_euclid(1000);
+        match &self.duty_table {
+            Some(table) if (0..=DUTY_TABLE_MAX_TEMP as i32).contains(&temp) => {
+                table[temp as usize]
+            }
+            _ => self.interpolate_duty(temp.clamp(i16::MIN as i32, i16::MAX as i32) as i16),
         }
+    }
 
-        // Convert thousandths to tenths for comparison with curve points
-        // 30000 thousandths = 30.0°C = 30 tenths (if curve points are in tenths)
-        let temp_tenths = (temp_thousandths / 1000) as i16;
+    /// Interpolate the duty (ten-thousandths) at a whole-degree temperature
+    fn interpolate_duty(&self, temp: i16) -> u16 {
+        Duty::from_percent(self.calculate_duty_f32(f32::from(temp))).as_ten_thousandths()
+    }
 
-        // If temperature is below the lowest point, return the duty of the lowest point
-        if temp_tenths <= self.points[0].temp {
-            return self.points[0].duty;
+    /// Calculate fan duty as a percentage (0.0-100.0) using linear interpolation
+    /// Unlike `calculate_duty_for_temperature` the result isn't rounded, for smoother control.
+    /// Both are capped by [`FanCurve::noise_cap_duty`]
+    pub fn calculate_duty_f32(&self, temp_celsius: f32) -> f32 {
+        let duty = self.uncapped_duty_f32(temp_celsius);
+        match self.noise_cap_duty() {
+            Some(cap) => duty.min(Duty::from_ten_thousandths(cap).as_percent()),
+            None => duty,
         }
+    }
 
-        // If temperature is above the highest point, return the duty of the highest point
-        if temp_tenths >= self.points.last().unwrap().temp {
-            return self.points.last().unwrap().duty;
+    /// Duty percentage straight from the points or formula
+    fn uncapped_duty_f32(&self, temp_celsius: f32) -> f32 {
+        if let Some(duty) = self.kind.formula_duty(temp_celsius) {
+            return duty / 100.0;
         }
 
-        // Find the two points to interpolate between
-        for i in 0..self.points.len() - 1 {
-            let point1 = &self.points[i];
-            let point2 = &self.points[i + 1];
+        let points = self.points();
+        let (first, last) = match (points.first(), points.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return 0.0,
+        };
 
-            if temp_tenths >= point1.temp && temp_tenths <= point2.temp {
-                // Linear interpolation between the two points
-                let temp1 = point1.temp as f32;
-                let temp2 = point2.temp as f32;
-                let duty1 = point1.duty as f32;
-                let duty2 = point2.duty as f32;
-                let temp_current = temp_tenths as f32;
+        // A step (two points at one temperature) runs at its higher duty right at that temperature
+        if let Some(duty) = points
+            .iter()
+            .filter(|p| f32::from(p.temp) == temp_celsius)
+            .map(|p| p.duty)
+            .max()
+        {
+            return Duty::from_ten_thousandths(duty).as_percent();
+        }
 
-                // Calculate the interpolation factor
-                let factor = (temp_current - temp1) / (temp2 - temp1);
+        // Hold the end duties outside the curve
+        if temp_celsius <= f32::from(first.temp) {
+            return Duty::from_ten_thousandths(first.duty).as_percent();
+        }
+        if temp_celsius >= f32::from(last.temp) {
+            return Duty::from_ten_thousandths(last.duty).as_percent();
+        }
 
-                // Interpolate the duty
-                let interpolated_duty = duty1 + factor * (duty2 - duty1);
+        let Some(pair) = points.windows(2).find(|pair| {
+            temp_celsius >= f32::from(pair[0].temp) && temp_celsius <= f32::from(pair[1].temp)
+        }) else {
+            return 0.0;
+        };
 
-                return interpolated_duty.round() as u16;
-            }
+        let temp1 = f32::from(pair[0].temp);
+        let temp2 = f32::from(pair[1].temp);
+        let duty1 = Duty::from_ten_thousandths(pair[0].duty).as_percent();
+        let duty2 = Duty::from_ten_thousandths(pair[1].duty).as_percent();
+        if temp2 <= temp1 {
+            return duty1.max(duty2);
         }
 
-        // Fallback (should not reach here)
-        0
+        let factor = pair[1].easing.apply((temp_celsius - temp1) / (temp2 - temp1));
+        duty1 + factor * (duty2 - duty1)
+    }
+
+    /// Sample the curve every `step` degrees from `from` to `to` inclusive
+    /// Returns (°C, duty in ten-thousandths) pairs, ready to plot; empty if `step` isn't positive
+    pub fn sample(&self, from: i16, to: i16, step: i16) -> Vec<(i16, u16)> {
+        if step <= 0 {
+            return Vec::new();
+        }
+        (i32::from(from)..=i32::from(to))
+            .step_by(step as usize)
+            .map(|temp| (temp as i16, self.calculate_duty_for_temperature(temp * 1000)))
+            .collect()
     }
 
     /// Calculate fan duty percentage for a given temperature using linear interpolation
     /// This is a convenience method that maintains backward compatibility
     pub fn calculate_duty_for_temperature_celsius(&self, temperature: f32) -> u16 {
         // Convert Celsius to thousandths of Celsius
-        let temp_thousandths = (temperature * 1000.0) as u32;
+        let temp_thousandths = (temperature * 1000.0) as i32;
         self.calculate_duty_for_temperature(temp_thousandths)
     }
 
     pub fn standard() -> Self {
-        let mut curve = Self::new("Standard".to_string());
-        curve.add_point(0, 0);
-        curve.add_point(30, 2000); // 20% = 2000/10000
-        curve.add_point(40, 3000); // 30% = 3000/10000
-        curve.add_point(50, 4000); // 40% = 4000/10000
-        curve.add_point(60, 5000); // 50% = 5000/10000
-        curve.add_point(70, 6000); // 60% = 6000/10000
-        curve.add_point(80, 7000); // 70% = 7000/10000
-        curve.add_point(90, 8000); // 80% = 8000/10000
-        curve.add_point(100, 10000); // 100% = 10000/10000
-        curve
+        FanCurveBuilder::new()
+            .point_percent(0, 0)
+            .point_percent(30, 20)
+            .point_percent(40, 30)
+            .point_percent(50, 40)
+            .point_percent(60, 50)
+            .point_percent(70, 60)
+            .point_percent(80, 70)
+            .point_percent(90, 80)
+            .point_percent(100, 100)
+            .build("Standard")
     }
 
     pub fn threadripper2() -> Self {
-        let mut curve = Self::new("Threadripper 2".to_string());
-        curve.add_point(0, 0);
-        curve.add_point(25, 1000); // 10% = 1000/10000
-        curve.add_point(35, 2000); // 20% = 2000/10000
-        curve.add_point(45, 3000); // 30% = 3000/10000
-        curve.add_point(55, 4000); // 40% = 4000/10000
-        curve.add_point(65, 5000); // 50% = 5000/10000
-        curve.add_point(75, 6000); // 60% = 6000/10000
-        curve.add_point(85, 7000); // 70% = 7000/10000
-        curve.add_point(95, 8000); // 80% = 8000/10000
-        curve.add_point(100, 10000); // 100% = 10000/10000
-        curve
+        FanCurveBuilder::new()
+            .point_percent(0, 0)
+            .point_percent(25, 10)
+            .point_percent(35, 20)
+            .point_percent(45, 30)
+            .point_percent(55, 40)
+            .point_percent(65, 50)
+            .point_percent(75, 60)
+            .point_percent(85, 70)
+            .point_percent(95, 80)
+            .point_percent(100, 100)
+            .build("Threadripper 2")
     }
 
     pub fn hedt() -> Self {
-        let mut curve = Self::new("HEDT".to_string());
-        curve.add_point(0, 0);
-        curve.add_point(20, 1500); // 15% = 1500/10000
-        curve.add_point(30, 2500); // 25% = 2500/10000
-        curve.add_point(40, 3500); // 35% = 3500/10000
-        curve.add_point(50, 4500); // 45% = 4500/10000
-        curve.add_point(60, 5500); // 55% = 5500/10000
-        curve.add_point(70, 6500); // 65% = 6500/10000
-        curve.add_point(80, 7500); // 75% = 7500/10000
-        curve.add_point(90, 8500); // 85% = 8500/10000
-        curve.add_point(100, 10000); // 100% = 10000/10000
-        curve
+        FanCurveBuilder::new()
+            .point_percent(0, 0)
+            .point_percent(20, 15)
+            .point_percent(30, 25)
+            .point_percent(40, 35)
+            .point_percent(50, 45)
+            .point_percent(60, 55)
+            .point_percent(70, 65)
+            .point_percent(80, 75)
+            .point_percent(90, 85)
+            .point_percent(100, 100)
+            .build("HEDT")
     }
 
     pub fn xeon() -> Self {
-        let mut curve = Self::new("Xeon".to_string());
-        curve.add_point(0, 0);
-        curve.add_point(15, 500); // 5% = 500/10000
-        curve.add_point(25, 1500); // 15% = 1500/10000
-        curve.add_point(35, 2500); // 25% = 2500/10000
-        curve.add_point(45, 3500); // 35% = 3500/10000
-        curve.add_point(55, 4500); // 45% = 4500/10000
-        curve.add_point(65, 5500); // 55% = 5500/10000
-        curve.add_point(75, 6500); // 65% = 6500/10000
-        curve.add_point(85, 7500); // 75% = 7500/10000
-        curve.add_point(95, 8500); // 85% = 8500/10000
-        curve.add_point(100, 10000); // 100% = 10000/10000
+        FanCurveBuilder::new()
+            .point_percent(0, 0)
+            .point_percent(15, 5)
+            .point_percent(25, 15)
+            .point_percent(35, 25)
+            .point_percent(45, 35)
+            .point_percent(55, 45)
+            .point_percent(65, 55)
+            .point_percent(75, 65)
+            .point_percent(85, 75)
+            .point_percent(95, 85)
+            .point_percent(100, 100)
+            .build("Xeon")
+    }
+
+    /// Laptop-oriented preset: flat and quiet below 60°C, ramping steeply above 80°C
+    pub fn quiet() -> Self {
+        FanCurveBuilder::new()
+            .point_percent(0, 15)
+            .point_percent(60, 20)
+            .point_percent(70, 30)
+            .point_percent(80, 45)
+            .point_percent(85, 75)
+            .point_percent(90, 100)
+            .build("Quiet")
+    }
+
+    /// Leaves the fans entirely to the firmware; it has no points and can't be edited
+    pub fn auto() -> Self {
+        let mut curve = Self::new(AUTO_CURVE_NAME.to_string());
+        curve.firmware_control = true;
+        curve.locked = true;
         curve
     }
 
-    pub fn save_to_file(&self, path: &Path) -> Result<()> {
-        let json = serde_json::to_string_pretty(self)?;
-        fs::write(path, json)?;
-        Ok(())
+    /// Look up a built-in preset by name (case-insensitive)
+    pub fn preset_by_name(name: &str) -> Option<Self> {
+        ALL_PRESETS
+            .iter()
+            .find(|(preset_name, _)| preset_name.eq_ignore_ascii_case(name))
+            .map(|(_, build)| build())
     }
 
-    pub fn load_from_file(path: &Path) -> Result<Self> {
-        let json = fs::read_to_string(path)?;
-        let curve: FanCurve = serde_json::from_str(&json)?;
-        Ok(curve)
+    /// Shift every point's duty by `delta` ten-thousandths, clamped to 0-10000
+    /// This and the other adjustments turn a formula curve into points first
+    pub fn offset_duty(&mut self, delta: i32) {
+        for point in self.point_list() {
+            point.duty = (point.duty as i32 + delta).clamp(0, 10000) as u16;
+        }
+        self.points_changed();
     }
 
-    pub fn to_daemon_points(&self) -> Vec<(i16, u16)> {
-        self.points.iter().map(|p| (p.temp, p.duty)).collect()
+    /// Multiply every point's duty by `factor`, rounded and clamped to 0-10000
+    pub fn scale_duty(&mut self, factor: f32) {
+        for point in self.point_list() {
+            point.duty = (point.duty as f32 * factor).round().clamp(0.0, 10000.0) as u16;
+        }
+        self.points_changed();
     }
 
-    pub fn from_daemon_points(points: Vec<(i16, u16)>) -> Self {
-        let mut curve = Self::new("Custom".to_string());
-        for (temp, duty) in points {
-            curve.add_point(temp, duty);
+    /// Shift every point's temperature by `delta` degrees
+    pub fn offset_temp(&mut self, delta: i16) {
+        for point in self.point_list() {
+            point.temp = point.temp.saturating_add(delta);
         }
-        curve
+        self.points_changed();
     }
-}
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct FanCurveConfig {
-    pub curves: Vec<FanCurve>,
-    pub default_curve_index: Option<usize>,
-}
+    /// Combine with `other` into a curve running the louder of the two at every temperature
+    ///
+    /// Points are placed at every temperature either curve has a point at. Between those
+    /// both inputs are straight lines, so the merged line never drops below either.
+    /// Formula curves are merged through their sampled points.
+    pub fn max_merge(&self, other: &FanCurve, name: String) -> FanCurve {
+        let (ours, theirs) = (self.to_points(), other.to_points());
+        let mut temps: Vec<i16> = ours.iter().chain(&theirs).map(|p| p.temp).collect();
+        temps.sort_unstable();
+        temps.dedup();
 
-impl FanCurveConfig {
-    pub fn new() -> Self {
-        Self {
-            curves: vec![
-                FanCurve::standard(),
-                FanCurve::threadripper2(),
-                FanCurve::hedt(),
-                FanCurve::xeon(),
-            ],
-            default_curve_index: Some(0),
+        let mut merged = FanCurve::new(name);
+        for temp in temps {
+            merged.add_point(temp, duty_at_ceil(&ours, temp).max(duty_at_ceil(&theirs, temp)));
         }
+        merged
     }
 
-    pub fn save_to_file(&self, path: &Path) -> Result<()> {
-        let json = serde_json::to_string_pretty(self)?;
-        fs::write(path, json)?;
+    /// Whether a built-in preset has this curve's name, so it can be reset
+    pub fn is_preset(&self) -> bool {
+        !self.is_auto() && Self::preset_by_name(&self.name).is_some()
+    }
+
+    /// Replace the points with those of the built-in preset of the same name
+    /// Tags, spin-up and the lock flag are kept; a locked curve is left alone
+    pub fn reset_to_preset(&mut self) -> Result<()> {
+        let preset = Self::preset_by_name(&self.name)
+            .filter(|preset| !preset.is_auto())
+            .ok_or_else(|| {
+                FanCurveError::Config(format!("'{}' is not a built-in preset", self.name))
+            })?;
+        self.ensure_unlocked()?;
+        *self.points_mut() = preset.to_points();
         Ok(())
     }
 
-    pub fn load_from_file(path: &Path) -> Result<Self> {
-        let json = fs::read_to_string(path)?;
-        let config: FanCurveConfig = serde_json::from_str(&json)?;
-        Ok(config)
+    /// Warnings about a curve that may under-cool a hot CPU, see [`FanCurve::safety_lint_at`]
+    pub fn safety_lint(&self) -> Vec<String> {
+        self.safety_lint_at(SAFETY_LINT_MIN_TEMP)
     }
 
-    pub fn get_config_path() -> std::path::PathBuf {
-        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
-        std::path::PathBuf::from(home)
-            .join(".fan_curve_app")
-            .join("config.json")
+    /// Warn when the highest point is below `min_temp`, since anything hotter gets that
+    /// point's duty, or when the duty never gets near 100%. Firmware-controlled curves pass
+    pub fn safety_lint_at(&self, min_temp: i16) -> Vec<String> {
+        if self.firmware_control {
+            return Vec::new();
+        }
+        let points = self.to_points();
+        let (Some(top), Some(max_duty)) = (
+            points.iter().max_by_key(|p| (p.temp, p.duty)),
+            points.iter().map(|p| p.duty).max(),
+        ) else {
+            return Vec::new();
+        };
+
+        let mut lints = Vec::new();
+        if top.temp < min_temp {
+            lints.push(format!(
+                "Highest point is at {}°C, so hotter temperatures stay at {}",
+                top.temp,
+                Duty::from_ten_thousandths(top.duty)
+            ));
+        }
+        if max_duty < SAFETY_LINT_MIN_DUTY {
+            lints.push(format!(
+                "Duty never goes above {}, short of full speed",
+                Duty::from_ten_thousandths(max_duty)
+            ));
+        }
+        lints
     }
-}
 
-impl Default for FanCurveConfig {
-    fn default() -> Self {
-        Self::new()
+    /// Check that the curve is usable: at least two points, temperatures in order with
+    /// at most two points (a step) at each, and duties within 0-10000 that never decrease.
+    /// Formula curves need usable parameters and `min <= max <= 10000`.
+    /// Firmware-controlled curves have no points to check
+    pub fn validate(&self) -> Result<()> {
+        if self.firmware_control {
+            return Ok(());
+        }
+
+        if let CurveKind::Formula { min, max, .. } = self.kind {
+            if !self.kind.formula_is_usable() {
+                return Err(FanCurveError::Config(format!(
+                    "Fan curve '{}' formula needs finite values with a >= 0 and p > 0",
+                    self.name
+                )));
+            }
+            if min > max || max > 10000 {
+                return Err(FanCurveError::Config(format!(
+                    "Fan curve '{}' formula duty range {}-{} must be increasing and within 0-10000",
+                    self.name, min, max
+                )));
+            }
+        } else if self.points().len() < 2 {
+            return Err(FanCurveError::Config(format!(
+                "Fan curve '{}' needs at least two points",
+                self.name
+            )));
+        } else if self.points().first().map(|p| p.temp) == self.last_point().map(|p| p.temp) {
+            return Err(FanCurveError::Config(format!(
+                "Fan curve '{}' needs points at two different temperatures",
+                self.name
+            )));
+        }
+
+        if let Some(point) = self.points().iter().find(|p| p.duty > 10000) {
+            return Err(FanCurveError::Config(format!(
+                "Fan curve '{}' has duty {} above 10000 at {}°C",
+                self.name, point.duty, point.temp
+            )));
+        }
+
+        if self.spin_up_duty > 10000 || self.spin_up_threshold > 10000 {
+            return Err(FanCurveError::Config(format!(
+                "Fan curve '{}' spin-up duty {} and threshold {} must not exceed 10000",
+                self.name, self.spin_up_duty, self.spin_up_threshold
+            )));
+        }
+
+        if let Some(max_noise) = self.max_noise {
+            if !max_noise.is_finite() || self.noise_table.is_empty() {
+                return Err(FanCurveError::Config(format!(
+                    "Fan curve '{}' noise cap needs a finite dBA and a noise table",
+                    self.name
+                )));
+            }
+        }
+        if let Some(&(duty, noise)) = self
+            .noise_table
+            .iter()
+            .find(|(duty, noise)| *duty > 10000 || !noise.is_finite())
+        {
+            return Err(FanCurveError::Config(format!(
+                "Fan curve '{}' noise table entry {} -> {} dBA needs a duty within 0-10000 and a finite level",
+                self.name, duty, noise
+            )));
+        }
+        if let Some(pair) = self.noise_table.windows(2).find(|pair| pair[1].0 <= pair[0].0) {
+            return Err(FanCurveError::Config(format!(
+                "Fan curve '{}' noise table duties must be increasing ({} after {})",
+                self.name, pair[1].0, pair[0].0
+            )));
+        }
+
+        if let Some(run) = self.points().windows(3).find(|run| run[0].temp == run[2].temp) {
+            return Err(FanCurveError::Config(format!(
+                "Fan curve '{}' has more than two points at {}°C",
+                self.name, run[0].temp
+            )));
+        }
+
+        for pair in self.points().windows(2) {
+            if pair[1].temp < pair[0].temp {
+                return Err(FanCurveError::Config(format!(
+                    "Fan curve '{}' temperatures must be increasing ({}°C after {}°C)",
+                    self.name, pair[1].temp, pair[0].temp
+                )));
+            }
+            if pair[1].duty < pair[0].duty {
+                return Err(FanCurveError::Config(format!(
+                    "Fan curve '{}' duty decreases from {} to {} at {}°C",
+                    self.name, pair[0].duty, pair[1].duty, pair[1].temp
+                )));
+            }
+        }
+
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Indices into [`FanCurve::to_points`] of the points [`FanCurve::validate`] objects to
+    /// Every point is flagged when the whole curve is rejected: an unusable formula or duty
+    /// range, fewer than two points, or all points at one temperature. Otherwise the flagged
+    /// points are duties above 10000, a third point at one temperature, and points whose
+    /// temperature or duty is below the previous point's
+    pub fn invalid_points(&self) -> Vec<usize> {
+        if self.firmware_control {
+            return Vec::new();
+        }
+        let points = self.to_points();
+        let all = || (0..points.len()).collect();
+        if let CurveKind::Formula { min, max, .. } = self.kind {
+            if !self.kind.formula_is_usable() || min > max || max > 10000 {
+                return all();
+            }
+            return Vec::new();
+        }
+        if points.len() < 2 || points.first().map(|p| p.temp) == points.last().map(|p| p.temp) {
+            return all();
+        }
+        (0..points.len())
+            .filter(|&i| {
+                points[i].duty > 10000
+                    || (i > 1 && points[i].temp == points[i - 2].temp)
+                    || (i > 0
+                        && (points[i].temp < points[i - 1].temp
+                            || points[i].duty < points[i - 1].duty))
+            })
+            .collect()
+    }
 
-    #[test]
-    fn test_fan_curve_interpolation() {
-        let curve = FanCurve::standard();
+    /// Repair values a hand-edited file could contain: duties and spin-up settings are
+    /// clamped to 0-10000, temperatures to `MIN_POINT_TEMP`-`MAX_POINT_TEMP`, points are
+    /// sorted keeping only the lowest and highest duty at a repeated temperature (a step),
+    /// and a curve left with fewer than two points gets the points of the preset with its
+    /// name (or Standard).
+    /// A formula's duty range is clamped the same way; an unusable formula is replaced
+    /// like a curve without points.
+    /// Returns true if anything changed
+    pub fn sanitize(&mut self) -> bool {
+        let mut changed = false;
 
-        // Test exact points (using thousandths of Celsius)
-        assert_eq!(curve.calculate_duty_for_temperature(0), 0);
-        assert_eq!(curve.calculate_duty_for_temperature(30000), 2000); // 30°C = 20%
-        assert_eq!(curve.calculate_duty_for_temperature(70000), 6000); // 70°C = 60%
-        assert_eq!(curve.calculate_duty_for_temperature(100000), 10000); // 100°C = 100%
+        match &mut self.kind {
+            CurveKind::Points(points) => {
+                for point in points.iter_mut() {
+                    let sane = FanPoint::new(
+                        point.temp.clamp(MIN_POINT_TEMP, MAX_POINT_TEMP),
+                        point.duty.min(10000),
+                    );
+                    if *point != sane {
+                        warn!(
+                            "Fan curve '{}': clamped point {}°C -> {} to {}°C -> {}",
+                            self.name, point.temp, point.duty, sane.temp, sane.duty
+                        );
+                        *point = sane;
+                        changed = true;
+                    }
+                }
 
-        // Test interpolation between points
-        assert_eq!(curve.calculate_duty_for_temperature(35000), 2500); // Between 30°C(20%) and 40°C(30%)
-        assert_eq!(curve.calculate_duty_for_temperature(65000), 5500); // Between 60°C(50%) and 70°C(60%)
+                let count = points.len();
+                points.sort_by_key(|p| (p.temp, p.duty));
+                points.dedup();
+                // Points between the two ends of a step change nothing
+                let sorted = std::mem::take(points);
+                for (index, point) in sorted.iter().enumerate() {
+                    let same_temp =
+                        |other: Option<&FanPoint>| other.is_some_and(|o| o.temp == point.temp);
+                    let inside_step = index > 0
+                        && same_temp(sorted.get(index - 1))
+                        && same_temp(sorted.get(index + 1));
+                    if !inside_step {
+                        points.push(point.clone());
+                    }
+                }
+                if points.len() != count {
+                    warn!(
+                        "Fan curve '{}': dropped {} repeated points",
+                        self.name,
+                        count - points.len()
+                    );
+                    changed = true;
+                }
+            }
+            CurveKind::Formula { min, max, .. } => {
+                let sane_max = (*max).min(10000);
+                let sane_min = (*min).min(sane_max);
+                if (*min, *max) != (sane_min, sane_max) {
+                    warn!(
+                        "Fan curve '{}': clamped formula duty range {}-{} to {}-{}",
+                        self.name, min, max, sane_min, sane_max
+                    );
+                    (*min, *max) = (sane_min, sane_max);
+                    changed = true;
+                }
+            }
+        }
 
-        // Test edge cases
-        assert_eq!(curve.calculate_duty_for_temperature(0), 0); // Below minimum (0°C)
-        assert_eq!(curve.calculate_duty_for_temperature(150000), 10000); // Above maximum (150°C)
+        let (spin_up_duty, spin_up_threshold) = (self.spin_up_duty, self.spin_up_threshold);
+        self.spin_up_duty = spin_up_duty.min(10000);
+        self.spin_up_threshold = spin_up_threshold.min(10000);
+        changed |= (spin_up_duty, spin_up_threshold) != (self.spin_up_duty, self.spin_up_threshold);
+
+        let usable = match &self.kind {
+            CurveKind::Points(points) => points.len() >= 2,
+            formula => formula.formula_is_usable(),
+        };
+        if !self.firmware_control && !usable {
+            let fallback = Self::preset_by_name(&self.name)
+                .filter(|preset| !preset.is_auto())
+                .unwrap_or_else(Self::standard);
+            if self.is_formula() {
+                warn!(
+                    "Fan curve '{}' has an unusable formula, using the points of '{}'",
+                    self.name, fallback.name
+                );
+            } else {
+                warn!(
+                    "Fan curve '{}' has {} points, using the points of '{}'",
+                    self.name,
+                    self.points().len(),
+                    fallback.name
+                );
+            }
+            self.kind = fallback.kind;
+            changed = true;
+        }
+
+        if changed {
+            self.duty_table = None;
+        }
+        changed
+    }
+
+    /// Load a curve saved by system76-power, whose points are (°C, duty %) pairs
+    pub fn from_system76_json(path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path)?;
+        let saved: System76FanCurve = serde_json::from_str(&json)?;
+
+        let mut curve = FanCurve::new(saved.name);
+        for (temp, percent) in saved.points {
+            curve.add_point(
+                i16::from(temp),
+                Duty::from_percent(f32::from(percent)).as_ten_thousandths(),
+            );
+        }
+        Ok(curve)
+    }
+
+    pub fn save_to_file(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        write_atomic(path, json.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn load_from_file(path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path)?;
+        let curve: FanCurve = serde_json::from_str(&json)?;
+        Ok(curve)
+    }
+
+    #[deprecated(note = "use `FanCurveDto::from`, which keeps the curve name")]
+    pub fn to_daemon_points(&self) -> Vec<(i16, u16)> {
+        self.to_points().iter().map(|p| (p.temp, p.duty)).collect()
+    }
+
+    #[deprecated(note = "use `FanCurve::from(FanCurveDto)`, which keeps the curve name")]
+    pub fn from_daemon_points(points: Vec<(i16, u16)>) -> Self {
+        let mut curve = Self::new("Custom".to_string());
+        for (temp, duty) in points {
+            curve.add_point(temp, duty);
+        }
+        curve
+    }
+
+    /// Convert points to system76-power's (°C, duty %) pairs
+    pub fn to_system76_points(&self) -> Vec<(u8, u8)> {
+        self.to_points()
+            .iter()
+            .map(|p| {
+                let temp = p.temp.clamp(0, u8::MAX as i16) as u8;
+                let duty = Duty::from_ten_thousandths(p.duty).as_whole_percent();
+                (temp, duty)
+            })
+            .collect()
+    }
+}
+
+/// Exact interpolated duty (ten-thousandths) of `points` at a whole degree, rounded up
+fn duty_at_ceil(points: &[FanPoint], temp: i16) -> u16 {
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return 0;
+    };
+    if let Some(duty) = points.iter().filter(|p| p.temp == temp).map(|p| p.duty).max() {
+        return duty;
+    }
+    if temp <= first.temp {
+        return first.duty;
+    }
+    if temp >= last.temp {
+        return last.duty;
+    }
+
+    points
+        .windows(2)
+        .find(|pair| {
+            pair[0].temp <= temp && temp <= pair[1].temp && pair[0].temp < pair[1].temp
+        })
+        .map(|pair| {
+            let span = i32::from(pair[1].temp) - i32::from(pair[0].temp);
+            let rise = (i32::from(pair[1].duty) - i32::from(pair[0].duty))
+                * (i32::from(temp) - i32::from(pair[0].temp));
+            // Ceiling division that also works for falling segments
+            let step = -(-rise).div_euclid(span);
+            (i32::from(pair[0].duty) + step) as u16
+        })
+        .unwrap_or(last.duty)
+}
+
+/// Fan curve as exchanged with the power daemon over D-Bus
+/// Points are (°C, duty in ten-thousandths) pairs, so no precision is lost
+/// Its signature is [`FAN_CURVE_V1_SIGNATURE`], so it also serves clients of `GetFanCurves`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct FanCurveDto {
+    pub name: String,
+    pub points: Vec<(i16, u16)>,
+}
+
+impl From<&FanCurve> for FanCurveDto {
+    fn from(curve: &FanCurve) -> Self {
+        Self {
+            name: curve.name.clone(),
+            points: curve.to_points().iter().map(|p| (p.temp, p.duty)).collect(),
+        }
+    }
+}
+
+impl From<FanCurveDto> for FanCurve {
+    fn from(dto: FanCurveDto) -> Self {
+        let mut curve = FanCurve::new(dto.name);
+        for (temp, duty) in dto.points {
+            curve.add_point(temp, duty);
+        }
+        curve
+    }
+}
+
+/// Builds a curve from points given in whole percent
+#[derive(Debug, Clone, Default)]
+pub struct FanCurveBuilder {
+    points: Vec<FanPoint>,
+}
+
+impl FanCurveBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a point at `temp` °C running at `percent` duty, clamped to 100%
+    pub fn point_percent(mut self, temp: i16, percent: u8) -> Self {
+        let duty = Duty::from_percent(f32::from(percent)).as_ten_thousandths();
+        self.points.push(FanPoint::new(temp, duty));
+        self
+    }
+
+    /// Curve named `name` with the points sorted by temperature
+    pub fn build(self, name: impl Into<String>) -> FanCurve {
+        let mut points = self.points;
+        points.sort_by_key(|p| (p.temp, p.duty));
+        let mut curve = FanCurve::new(name.into());
+        curve.kind = CurveKind::Points(points);
+        curve
+    }
+}
+
+/// Constructor for a built-in preset curve
+pub type PresetFn = fn() -> FanCurve;
+
+/// Built-in presets as (name, constructor) pairs
+pub const ALL_PRESETS: &[(&str, PresetFn)] = &[
+    ("Standard", FanCurve::standard),
+    ("Threadripper 2", FanCurve::threadripper2),
+    ("HEDT", FanCurve::hedt),
+    ("Xeon", FanCurve::xeon),
+    ("Quiet", FanCurve::quiet),
+    (AUTO_CURVE_NAME, FanCurve::auto),
+];
+
+/// Fan curve in the on-disk format used by system76-power
+#[derive(Debug, Serialize, Deserialize)]
+struct System76FanCurve {
+    name: String,
+    /// (temperature °C, duty %) pairs
+    points: Vec<(u8, u8)>,
+}
+
+/// How one fan is driven, set per fan label in [`FanCurveConfig::fan_control`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FanControlMode {
+    /// Follow the active curve
+    #[default]
+    Curve,
+    /// Hold a fixed duty in whole percent
+    Manual(u8),
+    /// Leave the fan to the firmware (`pwmN_enable = 2`)
+    Auto,
+    /// Hold the speed given by the fan's entry in [`FanCurveConfig::rpm_targets`]
+    #[serde(rename = "rpm")]
+    TargetRpm,
+}
+
+impl fmt::Display for FanControlMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FanControlMode::Curve => write!(f, "curve"),
+            FanControlMode::Manual(percent) => write!(f, "manual:{}", percent),
+            FanControlMode::Auto => write!(f, "auto"),
+            FanControlMode::TargetRpm => write!(f, "rpm"),
+        }
+    }
+}
+
+/// Parses `curve`, `auto`, `rpm` or `manual:<percent>`, ignoring case
+impl FromStr for FanControlMode {
+    type Err = FanCurveError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim().to_ascii_lowercase();
+        let invalid = || {
+            FanCurveError::Config(format!(
+                "Invalid fan control mode '{}', expected curve, auto, rpm or manual:<percent>",
+                s
+            ))
+        };
+        match s.split_once(':') {
+            None if s == "curve" => Ok(FanControlMode::Curve),
+            None if s == "auto" => Ok(FanControlMode::Auto),
+            None if s == "rpm" => Ok(FanControlMode::TargetRpm),
+            Some(("manual", percent)) => percent
+                .trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|&percent| percent <= 100)
+                .map(FanControlMode::Manual)
+                .ok_or_else(invalid),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Fan that runs at another fan's duty plus an offset, e.g. an exhaust fan kept a little faster
+/// than the intake for positive pressure; set in [`FanCurveConfig::fan_links`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FanLink {
+    /// Label of the fan that follows
+    pub follower: String,
+    /// Label of the fan whose duty it follows
+    pub leader: String,
+    /// Added to the leader's duty, in ten-thousandths (e.g. `500` for 5%, negative for slower)
+    #[serde(default)]
+    pub offset: i32,
+}
+
+impl FanLink {
+    /// Duty (ten-thousandths) of the follower when the leader runs at `leader_duty`, clamped to 0-100%
+    pub fn follower_duty(&self, leader_duty: u16) -> u16 {
+        (i32::from(leader_duty) + self.offset).clamp(0, i32::from(Duty::MAX_TEN_THOUSANDTHS)) as u16
+    }
+}
+
+/// Check that every fan follows at most one other fan, never itself, and not through a chain
+fn validate_fan_links(links: &[FanLink]) -> Result<()> {
+    for (index, link) in links.iter().enumerate() {
+        if link.follower.trim().is_empty() || link.leader.trim().is_empty() {
+            return Err(FanCurveError::Config("Fan link labels cannot be empty".to_string()));
+        }
+        if link.follower == link.leader {
+            return Err(FanCurveError::Config(format!(
+                "Fan '{}' can't follow itself",
+                link.follower
+            )));
+        }
+        if link.offset.unsigned_abs() > u32::from(Duty::MAX_TEN_THOUSANDTHS) {
+            return Err(FanCurveError::Config(format!(
+                "Offset {} of fan '{}' is outside -10000 to 10000",
+                link.offset, link.follower
+            )));
+        }
+        if links[..index].iter().any(|other| other.follower == link.follower) {
+            return Err(FanCurveError::Config(format!(
+                "Fan '{}' follows more than one fan",
+                link.follower
+            )));
+        }
+        if links.iter().any(|other| other.follower == link.leader) {
+            return Err(FanCurveError::Config(format!(
+                "Fan '{}' follows '{}', which follows another fan itself",
+                link.follower, link.leader
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Current config file schema version
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Default time between fan monitoring samples, in milliseconds
+pub const DEFAULT_POLL_INTERVAL_MS: u64 = 1000;
+
+fn default_poll_interval_ms() -> u64 {
+    DEFAULT_POLL_INTERVAL_MS
+}
+
+/// CPU temperature (°C) above which the fans run at least at the critical duty
+pub const DEFAULT_CRITICAL_TEMP: f32 = 90.0;
+
+/// Minimum duty (ten-thousandths) forced above the critical temperature
+pub const DEFAULT_CRITICAL_DUTY: u16 = 10000;
+
+/// CPU temperature (°C) above which `--notifications` shows a desktop notification
+pub const DEFAULT_WARN_TEMP: f32 = 85.0;
+
+fn default_idle_cpu_percent() -> f32 {
+    DEFAULT_IDLE_CPU_PERCENT
+}
+
+fn default_idle_after_secs() -> u64 {
+    DEFAULT_IDLE_AFTER.as_secs()
+}
+
+fn default_warn_temp() -> f32 {
+    DEFAULT_WARN_TEMP
+}
+
+fn default_critical_temp() -> f32 {
+    DEFAULT_CRITICAL_TEMP
+}
+
+fn default_critical_duty() -> u16 {
+    DEFAULT_CRITICAL_DUTY
+}
+
+/// On-disk format of a config file, picked from its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfigFormat {
+    Json,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// `.toml` files are TOML, everything else is JSON
+    fn from_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => ConfigFormat::Toml,
+            _ => ConfigFormat::Json,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct FanCurveConfig {
+    /// Schema version; files written before versioning was added load as 0
+    #[serde(default)]
+    pub version: u32,
+    pub curves: CurveRegistry,
+    #[serde(default)]
+    pub default_curve_index: Option<usize>,
+    /// Curve the daemon was last switched to, restored on startup
+    #[serde(default)]
+    pub active_curve_index: Option<usize>,
+    /// Maps System76 power profile names (e.g. "Performance") to curve names
+    #[serde(default)]
+    pub profile_curve_map: HashMap<String, String>,
+    /// Time between fan monitoring samples, in milliseconds
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    /// Add curves found in system76-power's curve directories when the daemon starts
+    #[serde(default)]
+    pub import_system76_curves: bool,
+    /// CPU temperature (°C) above which `critical_duty` overrides any curve
+    #[serde(default = "default_critical_temp")]
+    pub critical_temp: f32,
+    /// Minimum duty (ten-thousandths) above `critical_temp`
+    #[serde(default = "default_critical_duty")]
+    pub critical_duty: u16,
+    /// Smooth temperatures with this moving-average weight (0-1] before the curve; off when unset
+    #[serde(default)]
+    pub temp_filter_alpha: Option<f32>,
+    /// Keep a higher duty this long (milliseconds) after the curve calls for less; 0 ramps down at once
+    #[serde(default)]
+    pub ramp_down_delay_ms: u64,
+    /// Highest duty (ten-thousandths) the curve may ask for while on battery; no cap when unset
+    #[serde(default)]
+    pub battery_max_duty: Option<u16>,
+    /// Duty (ten-thousandths) to drop to once the CPU has been idle for a while and the curve is
+    /// at its lowest; off when unset
+    #[serde(default)]
+    pub idle_floor_duty: Option<u16>,
+    /// CPU usage (%) below which the system counts as idle
+    #[serde(default = "default_idle_cpu_percent")]
+    pub idle_cpu_percent: f32,
+    /// Seconds usage has to stay below `idle_cpu_percent` before the idle floor applies
+    #[serde(default = "default_idle_after_secs")]
+    pub idle_after_secs: u64,
+    /// `pwmN_enable` values for manual and automatic control, for controllers that don't
+    /// use 1 and 2
+    #[serde(default)]
+    pub pwm_enable: Option<PwmEnableValues>,
+    /// Fan numbers whose controller slows the fan down as PWM rises
+    #[serde(default)]
+    pub inverted_fans: Vec<u8>,
+    /// Control mode per fan label (e.g. "GPU Fan"); fans not listed follow the curve
+    #[serde(default)]
+    pub fan_control: HashMap<String, FanControlMode>,
+    /// Target RPM curve per fan label, followed by fans in the `rpm` control mode
+    #[serde(default)]
+    pub rpm_targets: HashMap<String, RpmTarget>,
+    /// Fans that follow another fan's duty instead of their own control mode
+    #[serde(default)]
+    pub fan_links: Vec<FanLink>,
+    /// Have the GUI read live data over DBus instead of sysfs, using sysfs only when the daemon is unreachable
+    #[serde(default)]
+    pub gui_use_dbus: bool,
+    /// CPU temperature (°C) that triggers a desktop notification when run with `--notifications`
+    #[serde(default = "default_warn_temp")]
+    pub warn_temp: f32,
+    /// Time-of-day windows that switch the active curve when they start; the first match wins
+    #[serde(default)]
+    pub schedule: Vec<ScheduleEntry>,
+}
+
+impl FanCurveConfig {
+    pub fn new() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            curves: ALL_PRESETS.iter().map(|(_, build)| build()).collect(),
+            default_curve_index: Some(0),
+            active_curve_index: None,
+            profile_curve_map: HashMap::new(),
+            poll_interval_ms: DEFAULT_POLL_INTERVAL_MS,
+            import_system76_curves: false,
+            critical_temp: DEFAULT_CRITICAL_TEMP,
+            critical_duty: DEFAULT_CRITICAL_DUTY,
+            temp_filter_alpha: None,
+            ramp_down_delay_ms: 0,
+            battery_max_duty: None,
+            idle_floor_duty: None,
+            idle_cpu_percent: DEFAULT_IDLE_CPU_PERCENT,
+            idle_after_secs: DEFAULT_IDLE_AFTER.as_secs(),
+            pwm_enable: None,
+            inverted_fans: Vec::new(),
+            fan_control: HashMap::new(),
+            rpm_targets: HashMap::new(),
+            fan_links: Vec::new(),
+            gui_use_dbus: false,
+            warn_temp: DEFAULT_WARN_TEMP,
+            schedule: Vec::new(),
+        }
+    }
+
+    /// Time between fan monitoring samples
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_interval_ms)
+    }
+
+    /// Time a higher duty is kept after the curve drops
+    pub fn ramp_down_delay(&self) -> Duration {
+        Duration::from_millis(self.ramp_down_delay_ms)
+    }
+
+    /// Idle floor settings, if `idle_floor_duty` is set
+    pub fn idle_floor(&self) -> Option<IdleFloor> {
+        self.idle_floor_duty.map(|duty| IdleFloor {
+            duty: duty.min(10000),
+            cpu_percent: self.idle_cpu_percent,
+            after: Duration::from_secs(self.idle_after_secs),
+        })
+    }
+
+    /// Index of the curve to start with: the last active one, then the default, then the first
+    pub fn initial_curve_index(&self) -> usize {
+        [self.active_curve_index, self.default_curve_index]
+            .into_iter()
+            .flatten()
+            .find(|&index| index < self.curves.len())
+            .unwrap_or(0)
+    }
+
+    /// Look up the index of the curve mapped to a power profile
+    /// Returns None if the profile has no mapping or the mapped curve no longer exists
+    pub fn curve_index_for_profile(&self, profile: &str) -> Option<usize> {
+        let curve_name = self.profile_curve_map.get(profile)?;
+        self.curves.position(curve_name)
+    }
+
+    /// Find a curve by its exact name
+    pub fn curve(&self, name: &str) -> Result<&FanCurve> {
+        self.curves
+            .get(name)
+            .ok_or_else(|| FanCurveError::FanCurveNotFound {
+                name: name.to_string(),
+            })
+    }
+
+    /// Restore the points of the built-in preset `name`, leaving other curves untouched
+    pub fn reset_to_preset(&mut self, name: &str) -> Result<()> {
+        self.curves
+            .get_mut(name)
+            .ok_or_else(|| FanCurveError::FanCurveNotFound {
+                name: name.to_string(),
+            })?
+            .reset_to_preset()
+    }
+
+    /// Run `f` on every unlocked curve and validate the results
+    /// Nothing changes if any curve ends up invalid. Returns the names of the curves changed
+    pub fn map_all(&mut self, f: impl Fn(&mut FanCurve)) -> Result<Vec<String>> {
+        let mut curves = self.curves.clone();
+        let mut changed = Vec::new();
+        for index in 0..curves.len() {
+            let mut curve = curves.get_index_mut(index).unwrap();
+            if curve.is_locked() {
+                continue;
+            }
+            f(&mut curve);
+            curve.validate()?;
+            changed.push(curve.name().to_string());
+        }
+        self.curves = curves;
+        Ok(changed)
+    }
+
+    /// Rename a curve and repoint any power profiles mapped to it
+    pub fn rename_curve(&mut self, old: &str, new: &str) -> Result<()> {
+        self.curves.rename(old, new)?;
+        for curve_name in self
+            .profile_curve_map
+            .values_mut()
+            .chain(self.schedule.iter_mut().map(|entry| &mut entry.curve_name))
+        {
+            if curve_name == old {
+                *curve_name = new.to_string();
+            }
+        }
+        Ok(())
+    }
+
+    /// Save the config as TOML if `path` ends in `.toml`, otherwise as JSON
+    pub fn save_to_file(&self, path: &Path) -> Result<()> {
+        let contents = match ConfigFormat::from_path(path) {
+            ConfigFormat::Json => serde_json::to_string_pretty(self)?,
+            ConfigFormat::Toml => toml::to_string_pretty(self)?,
+        };
+        write_atomic(path, contents.as_bytes())?;
+        Ok(())
+    }
+
+    /// Load a config file, upgrading older schema versions
+    /// `.toml` files are parsed as TOML, everything else as JSON.
+    /// Unknown fields written by newer versions are ignored
+    pub fn load_from_file(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let mut config: FanCurveConfig = match ConfigFormat::from_path(path) {
+            ConfigFormat::Json => serde_json::from_str(&contents)?,
+            ConfigFormat::Toml => toml::from_str(&contents)?,
+        };
+        config.migrate();
+        config.sanitize();
+        Ok(config)
+    }
+
+    /// Like [`FanCurveConfig::load_from_file`], but a missing file gives the default config
+    /// Other errors are returned, so a config that doesn't parse isn't saved over with defaults
+    pub fn load_or_default(path: &Path) -> Result<Self> {
+        match Self::load_from_file(path) {
+            Err(e) if e.io_error().is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound) => {
+                Ok(Self::default())
+            }
+            result => result,
+        }
+    }
+
+    /// Parse a config from JSON, upgrading older schema versions
+    pub fn from_json(json: &str) -> Result<Self> {
+        let mut config: FanCurveConfig = serde_json::from_str(json)?;
+        config.migrate();
+        Ok(config)
+    }
+
+    /// Check every curve and that the indices and profile mappings point at existing curves
+    pub fn validate(&self) -> Result<()> {
+        if self.curves.is_empty() {
+            return Err(FanCurveError::Config("Config has no fan curves".to_string()));
+        }
+        for curve in &self.curves {
+            curve.validate()?;
+        }
+
+        for (field, index) in [
+            ("default_curve_index", self.default_curve_index),
+            ("active_curve_index", self.active_curve_index),
+        ] {
+            if let Some(index) = index.filter(|&i| i >= self.curves.len()) {
+                return Err(FanCurveError::Config(format!(
+                    "{} {} is out of range for {} curves",
+                    field,
+                    index,
+                    self.curves.len()
+                )));
+            }
+        }
+
+        if let Some((profile, name)) = self
+            .profile_curve_map
+            .iter()
+            .find(|(_, name)| !self.curves.contains(name))
+        {
+            return Err(FanCurveError::Config(format!(
+                "Power profile '{}' is mapped to missing fan curve '{}'",
+                profile, name
+            )));
+        }
+        if let Some(entry) = self
+            .schedule
+            .iter()
+            .find(|entry| !self.curves.contains(&entry.curve_name))
+        {
+            return Err(FanCurveError::Config(format!(
+                "Schedule {} uses missing fan curve '{}'",
+                entry.window(),
+                entry.curve_name
+            )));
+        }
+        for (label, target) in &self.rpm_targets {
+            target.validate().map_err(|e| e.context(format!("Fan '{}'", label)))?;
+        }
+        if let Some((label, _)) = self.fan_control.iter().find(|(label, &mode)| {
+            mode == FanControlMode::TargetRpm && !self.rpm_targets.contains_key(*label)
+        }) {
+            return Err(FanCurveError::Config(format!(
+                "Fan '{}' is in rpm mode but has no entry in rpm_targets",
+                label
+            )));
+        }
+        validate_fan_links(&self.fan_links)
+    }
+
+    /// Make `link.follower` follow `link.leader`, replacing the link it had
+    /// Fails without changing anything if the links would no longer be valid
+    pub fn set_fan_link(&mut self, link: FanLink) -> Result<()> {
+        let mut links: Vec<FanLink> = self
+            .fan_links
+            .iter()
+            .filter(|other| other.follower != link.follower)
+            .cloned()
+            .collect();
+        links.push(link);
+        validate_fan_links(&links)?;
+        self.fan_links = links;
+        Ok(())
+    }
+
+    /// Stop `follower` following another fan; returns false if it didn't
+    pub fn remove_fan_link(&mut self, follower: &str) -> bool {
+        let before = self.fan_links.len();
+        self.fan_links.retain(|link| link.follower != follower);
+        self.fan_links.len() != before
+    }
+
+    /// Repair out-of-range values in a loaded config, see [`FanCurve::sanitize`]
+    /// Non-finite settings fall back to their defaults and out-of-range indices are cleared.
+    /// Returns true if anything changed
+    pub fn sanitize(&mut self) -> bool {
+        let mut changed = false;
+        for index in 0..self.curves.len() {
+            if let Some(mut curve) = self.curves.get_index_mut(index) {
+                changed |= curve.sanitize();
+            }
+        }
+
+        if !self.critical_temp.is_finite() {
+            warn!(
+                "critical_temp {} is not a number, using {}",
+                self.critical_temp, DEFAULT_CRITICAL_TEMP
+            );
+            self.critical_temp = DEFAULT_CRITICAL_TEMP;
+            changed = true;
+        }
+        if !self.warn_temp.is_finite() {
+            warn!(
+                "warn_temp {} is not a number, using {}",
+                self.warn_temp, DEFAULT_WARN_TEMP
+            );
+            self.warn_temp = DEFAULT_WARN_TEMP;
+            changed = true;
+        }
+        if self.critical_duty > 10000 {
+            warn!("critical_duty {} is above 10000, clamping", self.critical_duty);
+            self.critical_duty = 10000;
+            changed = true;
+        }
+        if !self.idle_cpu_percent.is_finite() {
+            warn!(
+                "idle_cpu_percent {} is not a number, using {}",
+                self.idle_cpu_percent, DEFAULT_IDLE_CPU_PERCENT
+            );
+            self.idle_cpu_percent = DEFAULT_IDLE_CPU_PERCENT;
+            changed = true;
+        }
+        if let Some(duty) = self.battery_max_duty.filter(|&duty| duty > 10000) {
+            warn!("battery_max_duty {} is above 10000, clamping", duty);
+            self.battery_max_duty = Some(10000);
+            changed = true;
+        }
+        for (label, mode) in self.fan_control.iter_mut() {
+            if let FanControlMode::Manual(percent @ 101..) = *mode {
+                warn!("Manual duty {}% for fan '{}' is above 100%, clamping", percent, label);
+                *mode = FanControlMode::Manual(100);
+                changed = true;
+            }
+        }
+        if self.temp_filter_alpha.is_some_and(|alpha| !alpha.is_finite()) {
+            warn!("temp_filter_alpha is not a number, filtering disabled");
+            self.temp_filter_alpha = None;
+            changed = true;
+        }
+
+        let len = self.curves.len();
+        for (field, index) in [
+            ("default_curve_index", &mut self.default_curve_index),
+            ("active_curve_index", &mut self.active_curve_index),
+        ] {
+            if let Some(i) = index.filter(|&i| i >= len) {
+                warn!("{} {} is out of range for {} curves, ignoring it", field, i, len);
+                *index = None;
+                changed = true;
+            }
+        }
+
+        changed
+    }
+
+    /// Upgrade a config loaded from an older schema version in place
+    fn migrate(&mut self) {
+        if self.version > CONFIG_VERSION {
+            warn!(
+                "Config version {} is newer than supported version {}, unknown settings are ignored",
+                self.version, CONFIG_VERSION
+            );
+            return;
+        }
+
+        if self.version == 0 {
+            // v0 files may predate default_curve_index
+            if self.default_curve_index.is_none() && !self.curves.is_empty() {
+                self.default_curve_index = Some(0);
+            }
+        }
+
+        if self.version < CONFIG_VERSION {
+            info!(
+                "Migrated config from version {} to {}",
+                self.version, CONFIG_VERSION
+            );
+            self.version = CONFIG_VERSION;
+        }
+    }
+
+    /// Write every curve as `<name>.json` in system76-power's format into `dir`
+    /// The firmware curve has no system76-power equivalent and is skipped
+    pub fn export_system76_curves(&self, dir: &Path) -> Result<()> {
+        for curve in self.curves.iter().filter(|c| !c.is_auto()) {
+            let exported = System76FanCurve {
+                name: curve.name().to_string(),
+                points: curve.to_system76_points(),
+            };
+            let json = serde_json::to_string_pretty(&exported)?;
+            write_atomic(&dir.join(curve_file_name(curve.name())), json.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Add every `*.json` curve in `dir` saved in system76-power's format
+    /// Curves named like an existing one are skipped, so our own exports aren't duplicated.
+    /// Returns how many curves were added; a missing directory adds none
+    pub fn import_system76_curves(&mut self, dir: &Path) -> Result<usize> {
+        if !dir.is_dir() {
+            return Ok(0);
+        }
+
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+
+        let mut added = 0;
+        for path in paths {
+            match FanCurve::from_system76_json(&path) {
+                Ok(curve) if self.curves.contains(curve.name()) => {}
+                Ok(curve) => {
+                    info!("Imported fan curve '{}' from {}", curve.name(), path.display());
+                    self.curves.insert(curve)?;
+                    added += 1;
+                }
+                Err(e) => warn!("Skipping fan curve {}: {}", path.display(), e),
+            }
+        }
+        Ok(added)
+    }
+
+    /// Get the directories curves are exported to in system76-power's format
+    /// With `$FAN_APP_CONFIG` set, curves go to `fan_curves` next to that file instead
+    pub fn get_curve_export_dirs() -> Vec<PathBuf> {
+        match std::env::var_os(Self::CONFIG_PATH_ENV).filter(|p| !p.is_empty()) {
+            Some(path) => {
+                let parent = Path::new(&path).parent().unwrap_or(Path::new("."));
+                vec![parent.join("fan_curves")]
+            }
+            None => Self::SYSTEM76_CURVE_DIRS
+                .iter()
+                .map(PathBuf::from)
+                .collect(),
+        }
+    }
+
+    /// Directories system76-power reads fan curves from
+    pub const SYSTEM76_CURVE_DIRS: &'static [&'static str] = &[
+        "/etc/system76-power/fan_curves",
+        "/var/lib/system76-power/fan_curves",
+    ];
+
+    /// Environment variable overriding the config file location
+    pub const CONFIG_PATH_ENV: &'static str = "FAN_APP_CONFIG";
+
+    /// System-wide config file used by the daemon when running as root
+    pub const SYSTEM_CONFIG_PATH: &'static str = "/etc/system76-power/fan-curve-app.json";
+
+    /// Get the per-user config file path
+    /// Honors `$FAN_APP_CONFIG`, then `$XDG_CONFIG_HOME/fan-curve-app/config.json`,
+    /// then falls back to `$HOME/.fan_curve_app/config.json`
+    pub fn get_config_path() -> PathBuf {
+        Self::resolve_config_path(
+            std::env::var_os(Self::CONFIG_PATH_ENV),
+            std::env::var_os("XDG_CONFIG_HOME"),
+            std::env::var_os("HOME"),
+        )
+    }
+
+    /// Get the config file path used by the daemon
+    /// When running as root (and not overridden) this is the system-wide path,
+    /// so a root daemon doesn't write its config into /root
+    pub fn get_daemon_config_path() -> PathBuf {
+        let overridden = std::env::var_os(Self::CONFIG_PATH_ENV).is_some_and(|p| !p.is_empty());
+        if !overridden && running_as_root() {
+            PathBuf::from(Self::SYSTEM_CONFIG_PATH)
+        } else {
+            Self::get_config_path()
+        }
+    }
+
+    fn resolve_config_path(
+        override_path: Option<OsString>,
+        xdg_config_home: Option<OsString>,
+        home: Option<OsString>,
+    ) -> PathBuf {
+        if let Some(path) = override_path.filter(|p| !p.is_empty()) {
+            return PathBuf::from(path);
+        }
+
+        if let Some(xdg) = xdg_config_home.filter(|p| !p.is_empty()) {
+            return PathBuf::from(xdg).join("fan-curve-app").join("config.json");
+        }
+
+        let home = home
+            .filter(|p| !p.is_empty())
+            .unwrap_or_else(|| OsString::from("/tmp"));
+        PathBuf::from(home)
+            .join(".fan_curve_app")
+            .join("config.json")
+    }
+}
+
+/// File name for an exported curve, replacing characters that aren't safe in paths
+fn curve_file_name(name: &str) -> String {
+    let stem: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}.json", stem)
+}
+
+/// Atomically replace the file at `path` with `contents`
+/// Creates the parent directory if needed
+fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    write_atomic_with(path, |file| file.write_all(contents))
+}
+
+/// Write through a temporary file in the same directory, fsync it and rename it
+/// over `path`, so a crash mid-write never leaves a truncated file behind
+fn write_atomic_with<F>(path: &Path, write: F) -> io::Result<()>
+where
+    F: FnOnce(&mut fs::File) -> io::Result<()>,
+{
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "config path has no file name")
+    })?;
+    fs::create_dir_all(dir)?;
+
+    let mut tmp_name = OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(format!(".tmp.{}", std::process::id()));
+    let tmp_path = dir.join(tmp_name);
+
+    let result = fs::File::create(&tmp_path)
+        .and_then(|mut file| {
+            write(&mut file)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&tmp_path, path));
+
+    match result {
+        Ok(()) => {
+            // Persist the rename itself; not all filesystems support syncing directories
+            if let Ok(dir) = fs::File::open(dir) {
+                let _ = dir.sync_all();
+            }
+            Ok(())
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&tmp_path);
+            Err(e)
+        }
+    }
+}
+
+/// Check whether the process runs as root, using the owner of /proc/self
+fn running_as_root() -> bool {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata("/proc/self")
+        .map(|m| m.uid() == 0)
+        .unwrap_or(false)
+}
+
+impl Default for FanCurveConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_fan_curve_interpolation() {
+        let curve = FanCurve::standard();
+
+        // Test exact points (using thousandths of Celsius)
+        assert_eq!(curve.calculate_duty_for_temperature(0), 0);
+        assert_eq!(curve.calculate_duty_for_temperature(30000), 2000); // 30°C = 20%
+        assert_eq!(curve.calculate_duty_for_temperature(70000), 6000); // 70°C = 60%
+        assert_eq!(curve.calculate_duty_for_temperature(100000), 10000); // 100°C = 100%
+
+        // Test interpolation between points
+        assert_eq!(curve.calculate_duty_for_temperature(35000), 2500); // Between 30°C(20%) and 40°C(30%)
+        assert_eq!(curve.calculate_duty_for_temperature(65000), 5500); // Between 60°C(50%) and 70°C(60%)
+
+        // Test edge cases
+        assert_eq!(curve.calculate_duty_for_temperature(0), 0); // Below minimum (0°C)
+        assert_eq!(curve.calculate_duty_for_temperature(150000), 10000); // Above maximum (150°C)
 
         // Test backward compatibility with Celsius
         assert_eq!(curve.calculate_duty_for_temperature_celsius(0.0), 0);
@@ -293,4 +2111,989 @@ mod tests {
         assert_eq!(curve.calculate_duty_for_temperature_celsius(70.0), 6000);
         assert_eq!(curve.calculate_duty_for_temperature_celsius(100.0), 10000);
     }
+
+    #[test]
+    fn test_step_at_repeated_temperature() {
+        let mut curve = FanCurve::new("Silent until 70".to_string());
+        curve.add_point(30, 1500);
+        curve.add_step(70, 8000, 2000);
+        curve.add_point(90, 10000);
+        curve.validate().unwrap();
+        assert_eq!(curve[1], FanPoint::new(70, 2000));
+        assert_eq!(curve[2], FanPoint::new(70, 8000));
+
+        for temp in [69.0, 69.99, 70.0, 70.01, 71.0] {
+            assert!(!curve.calculate_duty_f32(temp).is_nan(), "NaN at {}°C", temp);
+        }
+        assert!((curve.calculate_duty_f32(69.99) - 20.0).abs() < 0.01);
+        assert_eq!(curve.calculate_duty_f32(70.0), 80.0);
+        assert!((curve.calculate_duty_f32(70.01) - 80.0).abs() < 0.05);
+        assert_eq!(curve.calculate_duty_f32(80.0), 90.0);
+
+        assert_eq!(curve.calculate_duty_for_temperature(69_999), 1988);
+        assert_eq!(curve.calculate_duty_for_temperature(70_000), 8000);
+        assert_eq!(curve.calculate_duty_for_temperature(70_500), 8000);
+        curve.precompute_table();
+        assert_eq!(curve.calculate_duty_for_temperature(70_000), 8000);
+
+        // A step at either end
+        let mut edges = FanCurve::new("Edges".to_string());
+        edges.add_step(40, 0, 3000);
+        edges.add_step(80, 6000, 10000);
+        assert_eq!(edges.calculate_duty_f32(39.0), 0.0);
+        assert_eq!(edges.calculate_duty_f32(40.0), 30.0);
+        assert_eq!(edges.calculate_duty_f32(79.0), 59.25);
+        assert_eq!(edges.calculate_duty_f32(80.0), 100.0);
+
+        // Merging keeps the louder side of the step
+        let merged = curve.max_merge(&FanCurve::standard(), "Merged".to_string());
+        assert_eq!(merged.calculate_duty_for_temperature(70_000), 8000);
+
+        curve.add_point(70, 5000);
+        assert!(curve.validate().is_err());
+        assert!(curve.sanitize());
+        assert_eq!(curve.len(), 4);
+        curve.validate().unwrap();
+    }
+
+    #[test]
+    fn test_float_duty() {
+        let curve = FanCurve::standard();
+        assert_eq!(curve.calculate_duty_f32(35.0), 25.0);
+        assert_eq!(curve.calculate_duty_f32(-5.0), 0.0);
+        assert_eq!(curve.calculate_duty_f32(120.0), 100.0);
+
+        // Fractions of a degree and of a percent are kept
+        assert!((curve.calculate_duty_f32(35.5) - 25.5).abs() < 1e-4);
+        assert!((curve.calculate_duty_f32(25.0) - 16.666_666).abs() < 1e-4);
+        assert_eq!(curve.calculate_duty_for_temperature(25000), 1667);
+
+        assert_eq!(FanCurve::new("Empty".to_string()).calculate_duty_f32(50.0), 0.0);
+    }
+
+    #[test]
+    fn test_sample_curve() {
+        let curve = FanCurve::standard();
+        let samples = curve.sample(0, 100, 10);
+        assert_eq!(samples.len(), 11);
+        for point in curve.points() {
+            assert!(samples.contains(&(point.temp, point.duty)));
+        }
+
+        // Between control points the samples follow the interpolation
+        assert_eq!(
+            curve.sample(20, 45, 5),
+            vec![(20, 1333), (25, 1667), (30, 2000), (35, 2500), (40, 3000), (45, 3500)]
+        );
+        assert!(curve.sample(30, 100, 0).is_empty());
+        assert!(curve.sample(100, 30, 10).is_empty());
+    }
+
+    #[test]
+    fn test_locked_curve_ignores_edits() {
+        let mut curve = FanCurve::standard();
+        let original = curve.clone();
+        curve.set_locked(true);
+        assert!(curve.is_locked());
+        assert!(curve.ensure_unlocked().is_err());
+
+        curve.add_point(45, 3500);
+        curve.set_name("Renamed".to_string());
+        assert!(curve.remove_point(0).is_none());
+        assert!(curve.remove_last_point().is_none());
+        assert!(curve.get_point_mut(0).is_none());
+        assert_eq!(curve.name(), original.name());
+        assert_eq!(curve.points(), original.points());
+
+        curve.set_locked(false);
+        assert!(curve.ensure_unlocked().is_ok());
+        curve.add_point(45, 3500);
+        assert_eq!(curve.points().len(), original.points().len() + 1);
+        assert!(curve.remove_last_point().is_some());
+
+        // Older configs without the field load unlocked
+        let loaded: FanCurve =
+            serde_json::from_str(r#"{"name":"Old","points":[{"temp":50,"duty":5000}]}"#).unwrap();
+        assert!(!loaded.is_locked());
+    }
+
+    #[test]
+    fn test_curve_equality_ignores_point_order() {
+        let mut ascending = FanCurve::new("Custom".to_string());
+        ascending.add_point(30, 2000);
+        ascending.add_point(60, 5000);
+        ascending.add_point(90, 10000);
+
+        let mut shuffled = FanCurve::new("Custom".to_string());
+        shuffled.add_point(90, 10000);
+        shuffled.add_point(30, 2000);
+        shuffled.add_point(60, 5000);
+        assert_eq!(ascending, shuffled);
+
+        // Unsorted edits still compare equal
+        shuffled.points_mut().reverse();
+        assert_eq!(ascending, shuffled);
+
+        shuffled.get_point_mut(0).unwrap().duty = 9000;
+        assert_ne!(ascending, shuffled);
+
+        let mut renamed = ascending.clone();
+        renamed.set_name("Other".to_string());
+        assert_ne!(ascending, renamed);
+    }
+
+    #[test]
+    fn test_max_merge_is_louder_than_both() {
+        let threadripper = FanCurve::threadripper2();
+        let hedt = FanCurve::hedt();
+        let merged = threadripper.max_merge(&hedt, "Loudest".to_string());
+        assert_eq!(merged.name(), "Loudest");
+        assert!(merged.validate().is_ok());
+
+        for temp in -10..=110 {
+            let merged_duty = merged.calculate_duty_for_temperature(temp * 1000);
+            assert!(merged_duty >= threadripper.calculate_duty_for_temperature(temp * 1000));
+            assert!(merged_duty >= hedt.calculate_duty_for_temperature(temp * 1000));
+        }
+
+        // Crossing curves: each input is louder over part of the range
+        let mut early = FanCurve::new("Early".to_string());
+        early.add_point(30, 5000);
+        early.add_point(80, 6000);
+        let mut late = FanCurve::new("Late".to_string());
+        late.add_point(40, 1000);
+        late.add_point(70, 10000);
+        let merged = early.max_merge(&late, "Both".to_string());
+        for temp in 0..=100 {
+            let merged_duty = merged.calculate_duty_for_temperature(temp * 1000);
+            assert!(merged_duty >= early.calculate_duty_for_temperature(temp * 1000));
+            assert!(merged_duty >= late.calculate_duty_for_temperature(temp * 1000));
+        }
+        assert_eq!(merged.points().len(), 4);
+    }
+
+    #[test]
+    fn test_relative_adjustments_clamp() {
+        let mut curve = FanCurve::standard();
+        curve.precompute_table();
+
+        curve.offset_duty(-2500);
+        let duties: Vec<u16> = curve.iter().map(|p| p.duty).collect();
+        assert_eq!(duties, vec![0, 0, 500, 1500, 2500, 3500, 4500, 5500, 7500]);
+        assert_eq!(curve.calculate_duty_for_temperature(50000), 1500);
+
+        curve.offset_duty(5000);
+        assert_eq!(curve[0].duty, 5000);
+        assert_eq!(curve.last_point().unwrap().duty, 10000);
+
+        curve.scale_duty(0.5);
+        assert_eq!(curve.points()[0].duty, 2500);
+        assert_eq!(curve.points().last().unwrap().duty, 5000);
+        curve.scale_duty(3.0);
+        assert_eq!(curve.points()[0].duty, 7500);
+        assert_eq!(curve.points().last().unwrap().duty, 10000);
+        curve.scale_duty(-1.0);
+        assert!(curve.points().iter().all(|p| p.duty == 0));
+
+        let mut curve = FanCurve::standard();
+        curve.offset_temp(2);
+        assert_eq!(curve.points()[0].temp, 2);
+        assert_eq!(curve.calculate_duty_for_temperature(32000), 2000);
+        curve.offset_temp(i16::MIN);
+        assert_eq!(curve.points()[0].temp, i16::MIN + 2);
+    }
+
+    #[test]
+    fn test_negative_temperatures() {
+        let mut curve = FanCurve::new("Liquid".to_string());
+        curve.add_point(-20, 1000);
+        curve.add_point(0, 2000);
+        curve.add_point(40, 6000);
+        curve.validate().unwrap();
+
+        assert_eq!(curve.calculate_duty_for_temperature(-10000), 1500);
+        assert_eq!(curve.calculate_duty_for_temperature_celsius(-10.0), 1500);
+        // Below the lowest point clamps to its duty
+        assert_eq!(curve.calculate_duty_for_temperature(-40000), 1000);
+        // Fractions round down to the colder degree
+        assert_eq!(curve.calculate_duty_for_temperature(-500), 1950);
+
+        curve.precompute_table();
+        assert_eq!(curve.calculate_duty_for_temperature(-10000), 1500);
+        assert_eq!(curve.calculate_duty_for_temperature(-40000), 1000);
+        assert_eq!(curve.calculate_duty_for_temperature(20000), 4000);
+    }
+
+    #[test]
+    fn test_precomputed_table_matches_interpolation() {
+        let reference = FanCurve::hedt();
+        let mut curve = FanCurve::hedt();
+        curve.precompute_table();
+        assert!(curve.has_precomputed_table());
+        assert_eq!(curve, reference);
+
+        for temp in 0..=DUTY_TABLE_MAX_TEMP as i32 + 10 {
+            assert_eq!(
+                curve.calculate_duty_for_temperature(temp * 1000 + 500),
+                reference.interpolate_duty(temp as i16),
+                "mismatch at {}°C",
+                temp
+            );
+        }
+
+        // Editing points keeps the table in sync
+        curve.add_point(45, 9000);
+        assert!(curve.has_precomputed_table());
+        assert_eq!(curve.calculate_duty_for_temperature(45000), 9000);
+        curve.remove_point(curve.points().iter().position(|p| p.temp == 45).unwrap());
+        assert_eq!(
+            curve.calculate_duty_for_temperature(45000),
+            reference.calculate_duty_for_temperature(45000)
+        );
+
+        curve.points_mut().clear();
+        assert!(!curve.has_precomputed_table());
+        assert_eq!(curve.calculate_duty_for_temperature(45000), 0);
+
+        // The table is not part of the D-Bus representation
+        assert_eq!(FanCurve::signature().as_str(), "(sa(nq)qqbbas)");
+    }
+
+    #[test]
+    fn test_curve_tags() {
+        let mut curve = FanCurve::quiet();
+        curve.set_tags([" summer", "Silent", "", "silent ", "night"]);
+        assert_eq!(curve.tags(), ["summer", "Silent", "night"]);
+        assert!(curve.has_tag("SUMMER"));
+        assert!(!curve.has_tag("winter"));
+
+        let json = serde_json::to_string(&curve).unwrap();
+        assert_eq!(serde_json::from_str::<FanCurve>(&json).unwrap(), curve);
+
+        // Curves saved before tags existed load untagged
+        let old: FanCurve =
+            serde_json::from_str(r#"{"name": "Old", "points": [{"temp": 30, "duty": 2000}]}"#)
+                .unwrap();
+        assert!(old.tags().is_empty());
+        assert_ne!(old, {
+            let mut tagged = old.clone();
+            tagged.set_tags(["summer"]);
+            tagged
+        });
+    }
+
+    #[test]
+    fn test_all_presets_are_valid() {
+        for (name, build) in ALL_PRESETS {
+            let curve = build();
+            assert_eq!(curve.name(), *name);
+            curve.validate().unwrap();
+            assert_eq!(FanCurve::preset_by_name(name).as_ref(), Some(&curve));
+        }
+
+        assert_eq!(FanCurve::preset_by_name("quiet"), Some(FanCurve::quiet()));
+        assert!(FanCurve::preset_by_name("Turbo").is_none());
+        assert_eq!(FanCurveConfig::new().curves.len(), ALL_PRESETS.len());
+    }
+
+    #[test]
+    fn test_builder_presets_match_tables() {
+        let curve = |name: &str, points: &[(i16, u16)]| {
+            let mut curve = FanCurve::new(name.to_string());
+            for &(temp, duty) in points {
+                curve.add_point(temp, duty);
+            }
+            curve
+        };
+        assert_eq!(
+            FanCurve::standard(),
+            curve(
+                "Standard",
+                &[(0, 0), (30, 2000), (40, 3000), (50, 4000), (60, 5000), (70, 6000), (80, 7000), (90, 8000), (100, 10000)]
+            )
+        );
+        assert_eq!(
+            FanCurve::threadripper2(),
+            curve(
+                "Threadripper 2",
+                &[(0, 0), (25, 1000), (35, 2000), (45, 3000), (55, 4000), (65, 5000), (75, 6000), (85, 7000), (95, 8000), (100, 10000)]
+            )
+        );
+        assert_eq!(
+            FanCurve::hedt(),
+            curve(
+                "HEDT",
+                &[(0, 0), (20, 1500), (30, 2500), (40, 3500), (50, 4500), (60, 5500), (70, 6500), (80, 7500), (90, 8500), (100, 10000)]
+            )
+        );
+        assert_eq!(
+            FanCurve::xeon(),
+            curve(
+                "Xeon",
+                &[(0, 0), (15, 500), (25, 1500), (35, 2500), (45, 3500), (55, 4500), (65, 5500), (75, 6500), (85, 7500), (95, 8500), (100, 10000)]
+            )
+        );
+        assert_eq!(
+            FanCurve::quiet(),
+            curve("Quiet", &[(0, 1500), (60, 2000), (70, 3000), (80, 4500), (85, 7500), (90, 10000)])
+        );
+
+        // Points may be given in any order and percentages are capped
+        let built = FanCurveBuilder::new()
+            .point_percent(80, 150)
+            .point_percent(20, 10)
+            .build("Custom");
+        assert_eq!(built.points(), [FanPoint::new(20, 1000), FanPoint::new(80, 10000)]);
+    }
+
+    #[test]
+    fn test_point_easing() {
+        let duty_at_midpoint = |easing| {
+            let mut curve = FanCurve::new("Eased".to_string());
+            curve.add_point(40, 2000);
+            curve.add_point(60, 6000);
+            curve.points_mut()[1].easing = easing;
+            curve.calculate_duty_f32(50.0)
+        };
+        assert!((duty_at_midpoint(Easing::Linear) - 40.0).abs() < 1e-4);
+        assert!((duty_at_midpoint(Easing::EaseIn) - 30.0).abs() < 1e-4);
+        assert!((duty_at_midpoint(Easing::EaseOut) - 50.0).abs() < 1e-4);
+        assert!((duty_at_midpoint(Easing::SmoothStep) - 40.0).abs() < 1e-4);
+
+        // Every mode still meets both points
+        for easing in [Easing::EaseIn, Easing::EaseOut, Easing::SmoothStep] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+        }
+        assert!(Easing::SmoothStep.apply(0.25) < 0.25);
+
+        // Only the segment ending at the eased point changes
+        let mut curve = FanCurve::standard();
+        curve.points_mut()[4].easing = Easing::EaseIn;
+        let standard = FanCurve::standard();
+        assert!(curve.calculate_duty_f32(55.0) < standard.calculate_duty_f32(55.0));
+        assert_eq!(curve.calculate_duty_f32(65.0), standard.calculate_duty_f32(65.0));
+
+        // Linear points are saved as before; eased ones name their easing
+        let json = serde_json::to_string(&curve.points()[3..5]).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"temp":50,"duty":4000},{"temp":60,"duty":5000,"easing":"ease_in"}]"#
+        );
+        let points: Vec<FanPoint> = serde_json::from_str(&json).unwrap();
+        assert_eq!(points, curve.points()[3..5]);
+    }
+
+    #[test]
+    fn test_reset_to_preset() {
+        let mut config = FanCurveConfig::new();
+        let mut custom = FanCurve::new("Desk".to_string());
+        custom.add_point(30, 2000);
+        custom.add_point(80, 9000);
+        config.curves.insert(custom.clone()).unwrap();
+
+        {
+            let mut standard = config.curves.get_mut("Standard").unwrap();
+            standard.offset_duty(1500);
+            standard.remove_last_point();
+            standard.set_tags(["loud"]);
+            assert_ne!(standard.points(), FanCurve::standard().points());
+        }
+
+        config.reset_to_preset("Standard").unwrap();
+        let standard = config.curve("Standard").unwrap();
+        assert_eq!(standard.len(), 9);
+        assert_eq!(standard.points(), FanCurve::standard().points());
+        assert_eq!(standard.tags(), ["loud"]);
+        assert_eq!(config.curve("Desk").unwrap(), &custom);
+
+        assert!(config.reset_to_preset("Desk").is_err());
+        assert!(config.reset_to_preset("Turbo").is_err());
+
+        config.curves.get_mut("Quiet").unwrap().set_locked(true);
+        assert!(config.reset_to_preset("Quiet").is_err());
+        assert!(!FanCurve::auto().is_preset());
+        assert!(FanCurve::xeon().is_preset());
+    }
+
+    #[test]
+    fn test_map_all_skips_locked() {
+        let mut config = FanCurveConfig::new();
+        config.curves.get_mut("Quiet").unwrap().set_locked(true);
+        let before = config.curves.clone();
+
+        let changed = config.map_all(|curve| curve.offset_duty(1000)).unwrap();
+        assert!(!changed.contains(&"Quiet".to_string()));
+        assert!(changed.contains(&"Standard".to_string()));
+        for (curve, old) in config.curves.iter().zip(before.iter()) {
+            if curve.is_locked() {
+                assert_eq!(curve, old);
+            } else {
+                for (point, old_point) in curve.iter().zip(old.iter()) {
+                    assert_eq!(point.duty, (old_point.duty + 1000).min(10000));
+                }
+            }
+        }
+
+        // A result that fails validation leaves every curve as it was
+        let adjusted = config.curves.clone();
+        assert!(config.map_all(|curve| curve.points_mut().reverse()).is_err());
+        assert_eq!(config.curves, adjusted);
+    }
+
+    #[test]
+    fn test_safety_lint() {
+        assert!(FanCurve::standard().safety_lint().is_empty());
+        assert!(FanCurve::auto().safety_lint().is_empty());
+        assert!(FanCurve::from_formula("Formula".to_string(), 40.0, 5.0, 2.0, 2000, 10000)
+            .safety_lint()
+            .is_empty());
+
+        let mut truncated = FanCurve::xeon();
+        truncated.points_mut().retain(|p| p.temp <= 80);
+        let lints = truncated.safety_lint();
+        assert_eq!(lints.len(), 2);
+        assert!(lints[0].contains("75°C"));
+        assert!(lints[0].contains("65.0%"));
+        assert!(lints[1].contains("65.0%"));
+
+        // Reaching full speed early is fine as long as the curve covers hot temperatures
+        let mut curve = FanCurve::standard();
+        curve.points_mut().retain(|p| p.temp <= 80);
+        curve.add_point(85, 10000);
+        assert_eq!(curve.safety_lint().len(), 1);
+        assert!(curve.safety_lint_at(85).is_empty());
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_curves() {
+        let mut curve = FanCurve::new("Bad".to_string());
+        curve.add_point(40, 3000);
+        assert!(curve.validate().is_err());
+
+        curve.add_point(40, 4000);
+        assert!(curve.validate().is_err());
+
+        curve.points_mut()[1] = FanPoint::new(60, 2000);
+        assert!(curve.validate().is_err());
+
+        curve.points_mut()[1] = FanPoint::new(60, 12000);
+        assert!(curve.validate().is_err());
+
+        curve.points_mut()[1] = FanPoint::new(60, 6000);
+        assert!(curve.validate().is_ok());
+    }
+
+    #[test]
+    fn test_noise_cap() {
+        // A fan reaching 32 dBA at 55% duty
+        let table = vec![(2000, 22.0), (4000, 28.0), (6000, 34.0), (10000, 45.0)];
+        let mut curve = FanCurve::standard();
+        let uncapped = curve.calculate_duty_for_temperature(90_000);
+        assert_eq!(curve.noise_cap_duty(), None);
+
+        curve.set_noise_cap(table.clone(), Some(32.0));
+        curve.validate().unwrap();
+        assert_eq!(curve.noise_cap_duty(), Some(5333));
+        assert_eq!(uncapped, 8000);
+        assert_eq!(curve.calculate_duty_for_temperature(90_000), 5333);
+        assert!((curve.calculate_duty_f32(100.0) - 53.33).abs() < 0.01);
+        // Quieter parts of the curve are untouched
+        assert_eq!(curve.calculate_duty_for_temperature(50_000), 4000);
+
+        // The precomputed table follows the cap
+        curve.precompute_table();
+        assert_eq!(curve.calculate_duty_for_temperature(90_000), 5333);
+        curve.set_noise_cap(table.clone(), None);
+        assert_eq!(curve.calculate_duty_for_temperature(90_000), 8000);
+
+        // Beyond the table the noise is held flat
+        curve.set_noise_cap(table.clone(), Some(50.0));
+        assert_eq!(curve.noise_cap_duty(), None);
+        curve.set_noise_cap(table.clone(), Some(20.0));
+        assert_eq!(curve.noise_cap_duty(), Some(2000));
+
+        // Saved with the curve, and left out of the file when unset
+        curve.set_noise_cap(table.clone(), Some(32.0));
+        let json = serde_json::to_string(&curve).unwrap();
+        assert_eq!(serde_json::from_str::<FanCurve>(&json).unwrap(), curve);
+        assert!(!serde_json::to_string(&FanCurve::standard())
+            .unwrap()
+            .contains("noise"));
+
+        // D-Bus keeps its signature and drops the cap
+        let message =
+            zbus::Message::method(None::<&str>, None::<&str>, "/", None::<&str>, "Test", &curve)
+                .unwrap();
+        let received: FanCurve = message.body().unwrap();
+        assert_eq!(received.points(), curve.points());
+        assert_eq!(received.max_noise(), None);
+
+        for (table, max_noise) in [
+            (vec![(4000, 28.0), (2000, 22.0)], Some(32.0)),
+            (vec![(2000, f32::NAN)], Some(32.0)),
+            (vec![(12000, 40.0)], Some(32.0)),
+            (Vec::new(), Some(32.0)),
+            (table.clone(), Some(f32::INFINITY)),
+        ] {
+            curve.set_noise_cap(table.clone(), max_noise);
+            assert!(curve.validate().is_err(), "{:?} {:?}", table, max_noise);
+        }
+    }
+
+    #[test]
+    fn test_invalid_points_after_edit() {
+        let mut curve = FanCurve::standard();
+        assert!(curve.invalid_points().is_empty());
+
+        // Editing a point the way the GUI does, to a duty below its neighbour's
+        let point = curve.remove_point(4).unwrap();
+        curve.add_point(point.temp, 1000);
+        assert!(curve.validate().is_err());
+        assert_eq!(curve.invalid_points(), [4]);
+
+        curve.points_mut()[8].duty = 12000;
+        assert_eq!(curve.invalid_points(), [4, 8]);
+
+        curve.reset_to_preset().unwrap();
+        assert!(curve.validate().is_ok());
+        assert!(curve.invalid_points().is_empty());
+
+        // A step is two points at one temperature, a third one is rejected
+        curve.add_step(45, 3500, 3600);
+        assert!(curve.validate().is_ok());
+        assert!(curve.invalid_points().is_empty());
+        curve.add_point(45, 3550);
+        assert!(curve.validate().is_err());
+        assert_eq!(curve.invalid_points(), [5]);
+
+        // A rejected formula flags every sampled point
+        let formula = FanCurve::from_formula("Broken".to_string(), 30.0, 20.0, 2.0, 9000, 1000);
+        assert!(formula.validate().is_err());
+        assert_eq!(formula.invalid_points().len(), formula.to_points().len());
+        let formula = FanCurve::from_formula("Smooth".to_string(), 30.0, 20.0, 2.0, 1000, 9000);
+        assert!(formula.validate().is_ok());
+        assert!(formula.invalid_points().is_empty());
+
+        let mut flat = FanCurve::new("Flat".to_string());
+        flat.add_point(50, 3000);
+        flat.add_point(50, 4000);
+        assert!(flat.validate().is_err());
+        assert_eq!(flat.invalid_points(), [0, 1]);
+    }
+
+    #[test]
+    fn test_formula_curve() {
+        // 20 ten-thousandths per degree squared above 30°C, between 10% and 90%
+        let curve = FanCurve::from_formula("Smooth".to_string(), 30.0, 20.0, 2.0, 1000, 9000);
+        curve.validate().unwrap();
+        assert!(curve.is_formula());
+        assert!(curve.points().is_empty());
+
+        let duty = |celsius: i32| curve.calculate_duty_for_temperature(celsius * 1000);
+        assert_eq!(duty(-10), 1000);
+        assert_eq!(duty(30), 1000);
+        assert_eq!(duty(40), 2000);
+        assert_eq!(duty(45), 4500);
+        assert_eq!(duty(50), 8000);
+        assert_eq!(duty(60), 9000);
+        assert_eq!(duty(150), 9000);
+        assert_eq!(curve.calculate_duty_f32(42.5), 31.25);
+
+        // Saved next to point curves in both formats
+        let dir = TempDir::new("formula-curve");
+        let mut config = FanCurveConfig::new();
+        config.curves.insert(curve.clone()).unwrap();
+        for file in ["config.json", "config.toml"] {
+            let path = dir.path().join(file);
+            config.save_to_file(&path).unwrap();
+            let loaded = FanCurveConfig::load_from_file(&path).unwrap();
+            assert_eq!(loaded.curve("Smooth").unwrap(), &curve);
+            assert_eq!(loaded.curve("Standard").unwrap(), &FanCurve::standard());
+        }
+
+        // D-Bus peers get the sampled points in the usual signature
+        let message = zbus::Message::method(
+            None::<&str>,
+            None::<&str>,
+            "/",
+            None::<&str>,
+            "GetFanCurves",
+            &(vec![curve.clone()],),
+        )
+        .unwrap();
+        type SentCurve = (String, Vec<FanPoint>, u16, u16, bool, bool, Vec<String>);
+        let (sent,): (Vec<SentCurve>,) = message.body().unwrap();
+        assert_eq!(sent[0].1, curve.to_points());
+        assert_eq!(sent[0].1[10], FanPoint::new(50, 8000));
+
+        // Editing points turns the formula into samples
+        let mut edited = curve.clone();
+        edited.add_point(33, 1200);
+        assert!(!edited.is_formula());
+        assert_eq!(edited.len(), 22);
+        assert_eq!(edited.calculate_duty_for_temperature(50_000), 8000);
+
+        let bad = FanCurve::from_formula("Bad".to_string(), 30.0, 20.0, 0.0, 1000, 9000);
+        assert!(bad.validate().is_err());
+        let mut backwards =
+            FanCurve::from_formula("Backwards".to_string(), 30.0, 20.0, 2.0, 9000, 1000);
+        assert!(backwards.validate().is_err());
+        assert!(backwards.sanitize());
+        backwards.validate().unwrap();
+    }
+
+    #[test]
+    fn test_profile_curve_mapping() {
+        let mut config = FanCurveConfig::new();
+        config
+            .profile_curve_map
+            .insert("Performance".to_string(), "HEDT".to_string());
+        config
+            .profile_curve_map
+            .insert("Battery".to_string(), "Missing".to_string());
+
+        assert_eq!(config.curve_index_for_profile("Performance"), Some(2));
+        // Mapped to a curve that doesn't exist
+        assert_eq!(config.curve_index_for_profile("Battery"), None);
+        // No mapping at all
+        assert_eq!(config.curve_index_for_profile("Balanced"), None);
+    }
+
+    #[test]
+    fn test_load_v0_config() {
+        let dir = TempDir::new("config-v0");
+        let path = dir.write(
+            "config.json",
+            r#"{
+                "curves": [
+                    {"name": "Old", "points": [{"temp": 30, "duty": 2000}, {"temp": 80, "duty": 10000}]}
+                ]
+            }"#,
+        );
+
+        let config = FanCurveConfig::load_from_file(&path).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.default_curve_index, Some(0));
+        assert_eq!(config.curves.len(), 1);
+        assert_eq!(config.curves[0].name(), "Old");
+        assert!(config.curves[0].tags().is_empty());
+        assert!(config.profile_curve_map.is_empty());
+        assert_eq!(config.poll_interval(), Duration::from_secs(1));
+        assert_eq!(config.active_curve_index, None);
+        assert_eq!(config.initial_curve_index(), 0);
+    }
+
+    #[test]
+    fn test_fan_control_mode_parsing() {
+        assert_eq!("curve".parse::<FanControlMode>().unwrap(), FanControlMode::Curve);
+        assert_eq!(" Auto ".parse::<FanControlMode>().unwrap(), FanControlMode::Auto);
+        assert_eq!(
+            "manual:45".parse::<FanControlMode>().unwrap(),
+            FanControlMode::Manual(45)
+        );
+        for invalid in ["manual", "manual:101", "manual:-1", "off", ""] {
+            assert!(invalid.parse::<FanControlMode>().is_err(), "{}", invalid);
+        }
+        assert_eq!("RPM".parse::<FanControlMode>().unwrap(), FanControlMode::TargetRpm);
+        for mode in [
+            FanControlMode::Curve,
+            FanControlMode::Manual(30),
+            FanControlMode::Auto,
+            FanControlMode::TargetRpm,
+        ] {
+            assert_eq!(mode.to_string().parse::<FanControlMode>().unwrap(), mode);
+        }
+    }
+
+    #[test]
+    fn test_fan_links() {
+        let link = |follower: &str, leader: &str, offset: i32| FanLink {
+            follower: follower.to_string(),
+            leader: leader.to_string(),
+            offset,
+        };
+        let exhaust = link("Exhaust Fan", "Intake Fan", 500);
+        assert_eq!(exhaust.follower_duty(4000), 4500);
+        assert_eq!(exhaust.follower_duty(9800), 10000);
+        assert_eq!(link("Exhaust Fan", "Intake Fan", -3000).follower_duty(2000), 0);
+
+        let mut config = FanCurveConfig::new();
+        config.set_fan_link(exhaust.clone()).unwrap();
+        for invalid in [
+            link("Intake Fan", "Intake Fan", 0),
+            link("Top Fan", "Exhaust Fan", 0),
+            link("Intake Fan", "CPU Fan", 0),
+            link("Top Fan", "Intake Fan", 20000),
+            link("", "Intake Fan", 0),
+        ] {
+            assert!(config.set_fan_link(invalid.clone()).is_err(), "{:?}", invalid);
+        }
+        assert_eq!(config.fan_links.len(), 1);
+        assert_eq!(config.fan_links[0], exhaust);
+        config.validate().unwrap();
+
+        // Linking the follower again replaces its link
+        config.set_fan_link(link("Exhaust Fan", "CPU Fan", 0)).unwrap();
+        assert_eq!(config.fan_links.len(), 1);
+        assert!(config.remove_fan_link("Exhaust Fan"));
+        assert!(!config.remove_fan_link("Exhaust Fan"));
+
+        config.fan_links = vec![exhaust.clone(), link("Exhaust Fan", "CPU Fan", 0)];
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_load_sanitizes_out_of_range_values() {
+        let dir = TempDir::new("config-insane");
+        let path = dir.write(
+            "config.json",
+            r#"{
+                "version": 1,
+                "curves": [
+                    {"name": "Wild", "spin_up_duty": 65535, "points": [
+                        {"temp": 80, "duty": 65535},
+                        {"temp": -32768, "duty": 1000},
+                        {"temp": 32767, "duty": 12000},
+                        {"temp": 80, "duty": 5000}
+                    ]},
+                    {"name": "Quiet", "points": [{"temp": 40, "duty": 20000}]},
+                    {"name": "Empty", "points": []}
+                ],
+                "default_curve_index": 7,
+                "critical_duty": 50000,
+                "fan_control": {"GPU Fan": "auto", "CPU Fan": {"manual": 250}}
+            }"#,
+        );
+
+        let config = FanCurveConfig::load_from_file(&path).unwrap();
+        let wild = &config.curves[0];
+        // The two points at 80°C make a step
+        assert_eq!(
+            wild.points(),
+            [
+                FanPoint::new(MIN_POINT_TEMP, 1000),
+                FanPoint::new(80, 5000),
+                FanPoint::new(80, 10000),
+                FanPoint::new(MAX_POINT_TEMP, 10000),
+            ]
+        );
+        assert_eq!(wild.spin_up_duty(), 10000);
+        wild.validate().unwrap();
+        assert_eq!(wild.calculate_duty_for_temperature(i32::MAX), 10000);
+
+        // Too few points fall back to the preset with the same name, or Standard
+        assert_eq!(config.curves[1].points(), FanCurve::quiet().points());
+        assert_eq!(config.curves[2].name(), "Empty");
+        assert_eq!(config.curves[2].points(), FanCurve::standard().points());
+
+        assert_eq!(config.critical_duty, 10000);
+        assert_eq!(config.default_curve_index, None);
+        assert_eq!(config.fan_control["GPU Fan"], FanControlMode::Auto);
+        assert_eq!(config.fan_control["CPU Fan"], FanControlMode::Manual(100));
+
+        // TOML can spell out NaN
+        let path = dir.write(
+            "config.toml",
+            "critical_temp = nan\ntemp_filter_alpha = nan\n[[curves]]\nname = \"Empty\"\npoints = []\n",
+        );
+        let mut config = FanCurveConfig::load_from_file(&path).unwrap();
+        assert_eq!(config.critical_temp, DEFAULT_CRITICAL_TEMP);
+        assert_eq!(config.temp_filter_alpha, None);
+        assert!(!config.sanitize());
+    }
+
+    #[test]
+    fn test_load_ignores_unknown_fields() {
+        let dir = TempDir::new("config-future");
+        let path = dir.write(
+            "config.json",
+            r#"{
+                "version": 99,
+                "curves": [{"name": "New", "points": []}],
+                "default_curve_index": 0,
+                "some_future_setting": {"enabled": true}
+            }"#,
+        );
+
+        let config = FanCurveConfig::load_from_file(&path).unwrap();
+        assert_eq!(config.version, 99);
+        assert_eq!(config.curves[0].name(), "New");
+    }
+
+    #[test]
+    fn test_load_or_default_only_on_missing_file() {
+        let dir = TempDir::new("config-load-or-default");
+        let missing = dir.path().join("config.json");
+        assert_eq!(FanCurveConfig::load_or_default(&missing).unwrap(), FanCurveConfig::new());
+
+        let broken = dir.write("broken.json", "{\"curves\": [");
+        assert!(FanCurveConfig::load_or_default(&broken).is_err());
+    }
+
+    #[test]
+    fn test_json_and_toml_round_trip() {
+        let dir = TempDir::new("config-formats");
+        let mut config = FanCurveConfig::new();
+        config.default_curve_index = Some(1);
+        config.active_curve_index = Some(3);
+        config.poll_interval_ms = 500;
+        config
+            .profile_curve_map
+            .insert("Battery".to_string(), "Quiet".to_string());
+
+        let json_path = dir.path().join("config.json");
+        let toml_path = dir.path().join("config.toml");
+        config.save_to_file(&json_path).unwrap();
+        config.save_to_file(&toml_path).unwrap();
+
+        assert!(fs::read_to_string(&json_path).unwrap().starts_with('{'));
+        assert!(fs::read_to_string(&toml_path)
+            .unwrap()
+            .contains("[[curves]]"));
+
+        let from_json = FanCurveConfig::load_from_file(&json_path).unwrap();
+        let from_toml = FanCurveConfig::load_from_file(&toml_path).unwrap();
+        assert_eq!(from_json, config);
+        assert_eq!(from_toml, config);
+    }
+
+    #[test]
+    fn test_load_commented_toml() {
+        let dir = TempDir::new("config-toml");
+        let path = dir.write(
+            "config.toml",
+            r#"
+            # Hand-written config
+            version = 1
+            default_curve_index = 0
+            poll_interval_ms = 2000 # sample every two seconds
+
+            [[curves]]
+            name = "Desk"
+            points = [
+                { temp = 40, duty = 2000 },
+                { temp = 85, duty = 10000 },
+            ]
+            "#,
+        );
+
+        let config = FanCurveConfig::load_from_file(&path).unwrap();
+        assert_eq!(config.poll_interval(), Duration::from_secs(2));
+        assert_eq!(config.curve("Desk").unwrap().points().len(), 2);
+    }
+
+    #[test]
+    fn test_export_system76_curves() {
+        let dir = TempDir::new("export-s76");
+        let mut config = FanCurveConfig::new();
+        let mut custom = FanCurve::new("My/Curve".to_string());
+        custom.add_point(40, 2549);
+        custom.add_point(90, 10000);
+        config.curves.insert(custom).unwrap();
+
+        config.export_system76_curves(dir.path()).unwrap();
+
+        assert!(dir.path().join("Standard.json").exists());
+        let json = fs::read_to_string(dir.path().join("My_Curve.json")).unwrap();
+        let exported: System76FanCurve = serde_json::from_str(&json).unwrap();
+        assert_eq!(exported.name, "My/Curve");
+        assert_eq!(exported.points, vec![(40, 25), (90, 100)]);
+    }
+
+    #[test]
+    fn test_import_system76_curves() {
+        let dir = TempDir::new("import-s76");
+        let path = dir.write(
+            "Loud.json",
+            r#"{"name": "Loud", "points": [[60, 55], [30, 20], [90, 100]]}"#,
+        );
+        let curve = FanCurve::from_system76_json(&path).unwrap();
+        assert_eq!(curve.name(), "Loud");
+        let points: Vec<(i16, u16)> = curve.points().iter().map(|p| (p.temp, p.duty)).collect();
+        assert_eq!(points, vec![(30, 2000), (60, 5500), (90, 10000)]);
+
+        // Our own exports are already in the config and are not added twice
+        let mut config = FanCurveConfig::new();
+        config.export_system76_curves(dir.path()).unwrap();
+        dir.write("broken.json", "{");
+        let before = config.curves.len();
+        assert_eq!(config.import_system76_curves(dir.path()).unwrap(), 1);
+        assert_eq!(config.curves.len(), before + 1);
+        assert_eq!(config.curve("Loud").unwrap(), &curve);
+
+        assert_eq!(
+            config
+                .import_system76_curves(&dir.path().join("missing"))
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_save_is_atomic() {
+        let dir = TempDir::new("atomic-save");
+        let path = dir.path().join("nested").join("config.json");
+
+        let mut config = FanCurveConfig::new();
+        config.default_curve_index = Some(2);
+        config.save_to_file(&path).unwrap();
+        let original = fs::read_to_string(&path).unwrap();
+
+        // Simulate a crash halfway through writing the temp file
+        let result = write_atomic_with(&path, |file| {
+            file.write_all(b"{\"curves\": [")?;
+            Err(io::Error::other("interrupted"))
+        });
+        assert!(result.is_err());
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+        let loaded = FanCurveConfig::load_from_file(&path).unwrap();
+        assert_eq!(loaded.default_curve_index, Some(2));
+
+        // The partial temp file is cleaned up
+        let leftovers = fs::read_dir(path.parent().unwrap()).unwrap().count();
+        assert_eq!(leftovers, 1);
+    }
+
+    #[test]
+    fn test_config_path_resolution() {
+        let _guard = crate::test_util::env_lock();
+        let saved: Vec<_> = ["FAN_APP_CONFIG", "XDG_CONFIG_HOME", "HOME"]
+            .iter()
+            .map(|key| (*key, std::env::var_os(key)))
+            .collect();
+
+        std::env::set_var("HOME", "/home/tester");
+        std::env::set_var("XDG_CONFIG_HOME", "/home/tester/.config");
+        std::env::set_var("FAN_APP_CONFIG", "/opt/fans/custom.json");
+        assert_eq!(
+            FanCurveConfig::get_config_path(),
+            PathBuf::from("/opt/fans/custom.json")
+        );
+        assert_eq!(
+            FanCurveConfig::get_daemon_config_path(),
+            PathBuf::from("/opt/fans/custom.json")
+        );
+
+        std::env::remove_var("FAN_APP_CONFIG");
+        assert_eq!(
+            FanCurveConfig::get_config_path(),
+            PathBuf::from("/home/tester/.config/fan-curve-app/config.json")
+        );
+
+        // An empty XDG_CONFIG_HOME counts as unset
+        std::env::set_var("XDG_CONFIG_HOME", "");
+        assert_eq!(
+            FanCurveConfig::get_config_path(),
+            PathBuf::from("/home/tester/.fan_curve_app/config.json")
+        );
+
+        for (key, value) in saved {
+            match value {
+                Some(value) => std::env::set_var(key, value),
+                None => std::env::remove_var(key),
+            }
+        }
+    }
 }
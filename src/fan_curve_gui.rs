@@ -1,19 +1,24 @@
+use crate::curve_registry::CurveRegistry;
+use crate::duty::Duty;
 use crate::errors::Result;
-use crate::fan::{FanCurve, FanCurveConfig};
-use crate::fan_monitor::FanMonitor;
+use crate::fan::{FanCurve, FanCurveConfig, MIN_POINT_TEMP};
+use crate::fan_monitor::{FanDataFeed, FanMonitor};
 use eframe::egui;
 
 pub struct FanCurveApp {
     fan_curves: Vec<FanCurve>,
     current_curve_index: usize,
     default_curve_index: Option<usize>,
+    /// Curves as last loaded from or written to the config file
+    saved_curves: Vec<FanCurve>,
     status_message: Option<String>,
     new_curve_name: String,
     show_save_dialog: bool,
     fan_monitor: FanMonitor,
     last_applied_curve_index: Option<usize>,
     current_fan_data: Option<crate::fan_monitor::FanDataPoint>,
-    last_fan_data_update: std::time::Instant,
+    /// Live data read off the UI thread, polled in `update`
+    data_feed: FanDataFeed,
     show_add_point_dialog: bool,
     new_point_temp: String,
     new_point_duty: String,
@@ -21,6 +26,10 @@ pub struct FanCurveApp {
     edit_point_index: Option<usize>,
     edit_point_temp: String,
     edit_point_duty: String,
+    /// Why the selected curve can't be applied, checked after each edit
+    validation_error: Option<String>,
+    /// Points of the selected curve shown in red
+    invalid_points: Vec<usize>,
 }
 
 impl FanCurveApp {
@@ -29,15 +38,15 @@ impl FanCurveApp {
         let config_path = FanCurveConfig::get_config_path();
         let (fan_curves, default_curve_index) = if config_path.exists() {
             match FanCurveConfig::load_from_file(&config_path) {
-                Ok(config) => (config.curves, config.default_curve_index),
+                Ok(config) => (config.curves.into_vec(), config.default_curve_index),
                 Err(_) => {
                     let default_config = FanCurveConfig::new();
-                    (default_config.curves, default_config.default_curve_index)
+                    (default_config.curves.into_vec(), default_config.default_curve_index)
                 }
             }
         } else {
             let default_config = FanCurveConfig::new();
-            (default_config.curves, default_config.default_curve_index)
+            (default_config.curves.into_vec(), default_config.default_curve_index)
         };
 
         let mut fan_monitor = FanMonitor::new();
@@ -46,8 +55,10 @@ impl FanCurveApp {
             eprintln!("Warning: Failed to initialize CPU temperature detection: {}", e);
             eprintln!("Falling back to simulation mode");
         }
+        let data_feed = fan_monitor.spawn_data_feed(std::time::Duration::from_secs(1));
 
-        Self {
+        let mut app = Self {
+            saved_curves: fan_curves.clone(),
             fan_curves,
             current_curve_index: default_curve_index.unwrap_or(0),
             default_curve_index,
@@ -56,7 +67,7 @@ impl FanCurveApp {
             show_save_dialog: false,
             fan_monitor,
             current_fan_data: None,
-            last_fan_data_update: std::time::Instant::now(),
+            data_feed,
             show_add_point_dialog: false,
             new_point_temp: String::new(),
             new_point_duty: String::new(),
@@ -65,24 +76,41 @@ impl FanCurveApp {
             edit_point_temp: String::new(),
             edit_point_duty: String::new(),
             last_applied_curve_index: None,
-        }
+            validation_error: None,
+            invalid_points: Vec::new(),
+        };
+        app.revalidate();
+        app
+    }
+
+    /// Check the selected curve again, after it changed or another one was selected
+    fn revalidate(&mut self) {
+        let curve = &self.fan_curves[self.current_curve_index];
+        self.validation_error = curve.validate().err().map(|e| e.to_string());
+        self.invalid_points = curve.invalid_points();
     }
 
-    fn save_config(&self) -> Result<()> {
+    fn save_config(&mut self) -> Result<()> {
         let config_path = FanCurveConfig::get_config_path();
         if let Some(parent) = config_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
 
-        let config = FanCurveConfig {
-            curves: self.fan_curves.clone(),
-            default_curve_index: self.default_curve_index,
-        };
+        // Start from the stored config so settings owned by the daemon are preserved
+        let mut config = FanCurveConfig::load_from_file(&config_path).unwrap_or_default();
+        config.curves = CurveRegistry::from(self.fan_curves.clone());
+        config.default_curve_index = self.default_curve_index;
 
         config.save_to_file(&config_path)?;
+        self.saved_curves = self.fan_curves.clone();
         Ok(())
     }
 
+    /// Check if the curves have changes that aren't saved yet
+    fn is_dirty(&self) -> bool {
+        self.fan_curves != self.saved_curves
+    }
+
     fn set_status(&mut self, message: String) {
         self.status_message = Some(message);
     }
@@ -90,18 +118,19 @@ impl FanCurveApp {
 
 impl eframe::App for FanCurveApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Always update live fan data every 1s
-        if self.last_fan_data_update.elapsed() >= std::time::Duration::from_secs(1) {
-            // Ensure the monitor is using the currently selected curve
-            if self.last_applied_curve_index != Some(self.current_curve_index) {
-                self.fan_monitor
-                    .set_fan_curve(self.fan_curves[self.current_curve_index].clone());
-                self.last_applied_curve_index = Some(self.current_curve_index);
-            }
+        // Ensure the monitor is using the currently selected curve
+        if self.last_applied_curve_index != Some(self.current_curve_index) {
+            self.fan_monitor
+                .set_fan_curve(self.fan_curves[self.current_curve_index].clone());
+            self.last_applied_curve_index = Some(self.current_curve_index);
+            self.revalidate();
+        }
 
-            if let Ok(data) = self.fan_monitor.get_current_fan_data_sync() {
+        // Live fan data arrives about once a second from the feed's thread
+        match self.data_feed.try_latest() {
+            Some(Ok(data)) => {
                 println!(
-                    "🔄 GUI: Updated fan data - Temp: {:.1}°C, Fans: {}, Duty: {}%",
+                    "🔄 GUI: Updated fan data - Temp: {:.1}°C, Fans: {}, Duty: {}",
                     data.temperature, 
                     if data.cpu_fan_speeds.is_empty() {
                         "No fans".to_string()
@@ -111,11 +140,13 @@ impl eframe::App for FanCurveApp {
                             .collect::<Vec<_>>()
                             .join(" | ")
                     },
-                    data.fan_duty
+                    Duty::from_ten_thousandths(data.fan_duty)
                 );
+                self.fan_monitor.record_sample(data.clone());
                 self.current_fan_data = Some(data);
-                self.last_fan_data_update = std::time::Instant::now();
             }
+            Some(Err(e)) => log::debug!("Failed to read fan data: {}", e),
+            None => {}
         }
 
         // No test mode state to manage
@@ -126,7 +157,11 @@ impl eframe::App for FanCurveApp {
         // Main content area
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.horizontal(|ui| {
-                ui.heading("Fan Curve Control");
+                ui.heading(if self.is_dirty() {
+                    "Fan Curve Control *"
+                } else {
+                    "Fan Curve Control"
+                });
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                     ui.colored_label(egui::Color32::GRAY, format!("v{}", env!("CARGO_PKG_VERSION")));
                 });
@@ -175,6 +210,21 @@ impl eframe::App for FanCurveApp {
                             }
                         });
 
+            // Lock toggle, protecting the curve from accidental edits
+            let mut locked = self.fan_curves[self.current_curve_index].is_locked();
+            if ui.checkbox(&mut locked, "🔒 Locked").changed() {
+                self.fan_curves[self.current_curve_index].set_locked(locked);
+                if locked {
+                    self.show_add_point_dialog = false;
+                    self.show_edit_point_dialog = false;
+                }
+                match self.save_config() {
+                    Ok(_) if locked => self.set_status("Curve locked".to_string()),
+                    Ok(_) => self.set_status("Curve unlocked".to_string()),
+                    Err(e) => self.set_status(format!("Failed to save: {}", e)),
+                }
+            }
+
             // Display fan curve points
             ui.separator();
             ui.label("Fan Curve Points:");
@@ -182,23 +232,29 @@ impl eframe::App for FanCurveApp {
             let mut points_to_remove = Vec::new();
 
             // First pass: display points and collect indices to remove
-            for (i, point) in self.fan_curves[self.current_curve_index].points().iter().enumerate() {
+            // A formula curve shows its sampled points, which editing turns into real ones
+            for (i, point) in self.fan_curves[self.current_curve_index].to_points().iter().enumerate() {
                 ui.horizontal(|ui| {
                     ui.label(format!("Point {}: ", i + 1));
-                    ui.label(format!("{}°C -> {:.1}%", point.temp, point.duty as f32 / 100.0));
+                    let text = format!("{}°C -> {}", point.temp, Duty::from_ten_thousandths(point.duty));
+                    if self.invalid_points.contains(&i) {
+                        ui.colored_label(egui::Color32::RED, text);
+                    } else {
+                        ui.label(text);
+                    }
 
                     ui.add_space(10.0);
 
-                    if ui.button("Edit").clicked() {
+                    if ui.add_enabled(!locked, egui::Button::new("Edit")).clicked() {
                         self.show_edit_point_dialog = true;
                         self.edit_point_index = Some(i);
                         self.edit_point_temp = point.temp.to_string();
-                        self.edit_point_duty = point.duty.to_string();
+                        self.edit_point_duty = Duty::from_ten_thousandths(point.duty).as_whole_percent().to_string();
                     }
 
                     ui.add_space(5.0);
 
-                    if ui.button("Remove").clicked() {
+                    if ui.add_enabled(!locked, egui::Button::new("Remove")).clicked() {
                         points_to_remove.push(i);
                     }
                 });
@@ -207,40 +263,61 @@ impl eframe::App for FanCurveApp {
             // Second pass: remove points in reverse order to maintain indices
             for &index in points_to_remove.iter().rev() {
                 if let Some(removed_point) = self.fan_curves[self.current_curve_index].remove_point(index) {
-                    self.set_status(format!("Removed point {}: {}°C -> {}%",
+                    self.set_status(format!("Removed point {}: {}°C -> {}",
                         index + 1,
                         removed_point.temp,
-                        removed_point.duty
+                        Duty::from_ten_thousandths(removed_point.duty)
                     ));
                 }
             }
+            if !points_to_remove.is_empty() {
+                self.revalidate();
+            }
 
             ui.separator();
 
             // Add point button
-            if ui.button("Add Point").clicked() {
+            if ui.add_enabled(!locked, egui::Button::new("Add Point")).clicked() {
                 self.show_add_point_dialog = true;
                 self.new_point_temp = "50".to_string();
                 self.new_point_duty = "50".to_string();
             }
 
+            // Save button, only enabled with unsaved changes
+            if ui
+                .add_enabled(self.is_dirty(), egui::Button::new("Save Changes"))
+                .clicked()
+            {
+                match self.save_config() {
+                    Ok(_) => self.set_status("Changes saved".to_string()),
+                    Err(e) => self.set_status(format!("Failed to save: {}", e)),
+                }
+            }
+
             // Save as new profile button
             if ui.button("Save as New Profile").clicked() {
                 self.show_save_dialog = true;
             }
 
-            // Apply button
-            if ui.button("Apply Fan Curve").clicked() {
+            // Apply button, disabled while the curve would be rejected
+            if let Some(error) = &self.validation_error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+            if ui
+                .add_enabled(self.validation_error.is_none(), egui::Button::new("Apply Fan Curve"))
+                .clicked()
+            {
                 // First save the config locally
                 if let Err(e) = self.save_config() {
                     self.set_status(format!("Failed to save: {}", e));
                     return;
                 }
                 
-                /// Then apply the curve to the daemon using current temperature
+                // Then apply the curve to the daemon using current temperature
                 if let Some(ref data) = self.current_fan_data {
                     match self.fan_monitor.apply_fan_curve_sync(data.temperature) {
                         Ok(_) => self.set_status("Fan curve applied to daemon and saved!".to_string()),
+                        Err(e) if e.is_permission_denied() => self.set_status(e.to_string()),
                         Err(e) => self.set_status(format!("Failed to apply to daemon: {}", e)),
                     }
                 } else {
@@ -283,6 +360,8 @@ impl eframe::App for FanCurveApp {
                     self.show_save_dialog = false;
                     if should_save && !self.new_curve_name.is_empty() {
                         let mut new_curve = self.fan_curves[self.current_curve_index].clone();
+                        // The copy starts out editable even if the original is locked
+                        new_curve.set_locked(false);
                         new_curve.set_name(self.new_curve_name.clone());
                         self.fan_curves.push(new_curve);
                         self.new_curve_name.clear();
@@ -327,11 +406,11 @@ impl eframe::App for FanCurveApp {
                                     self.new_point_temp.parse::<i16>(),
                                     self.new_point_duty.parse::<u16>()
                                 ) {
-                                    if (0..=100).contains(&temp) && duty <= 100 {
+                                    if (MIN_POINT_TEMP..=100).contains(&temp) && duty <= 100 {
                                         should_add = true;
                                         should_close = true;
                                     } else {
-                                        error_message = Some("Invalid values: Temperature must be 0-100°C, Duty must be 0-100%".to_string());
+                                        error_message = Some(format!("Invalid values: Temperature must be {}-100°C, Duty must be 0-100%", MIN_POINT_TEMP));
                                     }
                                 } else {
                                     error_message = Some("Invalid input: Please enter valid numbers".to_string());
@@ -350,8 +429,10 @@ impl eframe::App for FanCurveApp {
                             self.new_point_temp.parse::<i16>(),
                             self.new_point_duty.parse::<u16>()
                         ) {
-                            self.fan_curves[self.current_curve_index].add_point(temp, duty);
+                            self.fan_curves[self.current_curve_index]
+                                .add_point(temp, Duty::from_percent(duty as f32).as_ten_thousandths());
                             self.set_status(format!("Added point: {}°C -> {}%", temp, duty));
+                            self.revalidate();
                         }
                         self.new_point_temp.clear();
                         self.new_point_duty.clear();
@@ -398,11 +479,11 @@ impl eframe::App for FanCurveApp {
                                     self.edit_point_temp.parse::<i16>(),
                                     self.edit_point_duty.parse::<u16>()
                                 ) {
-                                    if (0..=100).contains(&temp) && duty <= 100 {
+                                    if (MIN_POINT_TEMP..=100).contains(&temp) && duty <= 100 {
                                         should_edit = true;
                                         should_close = true;
                                     } else {
-                                        error_message = Some("Invalid values: Temperature must be 0-100°C, Duty must be 0-100%".to_string());
+                                        error_message = Some(format!("Invalid values: Temperature must be {}-100°C, Duty must be 0-100%", MIN_POINT_TEMP));
                                     }
                                 } else {
                                     error_message = Some("Invalid input: Please enter valid numbers".to_string());
@@ -422,11 +503,14 @@ impl eframe::App for FanCurveApp {
                             self.edit_point_duty.parse::<u16>()
                         ) {
                             if let Some(index) = self.edit_point_index {
-                                if index < self.fan_curves[self.current_curve_index].points().len() {
+                                if index < self.fan_curves[self.current_curve_index].len() {
                                     // Remove the old point and add the new one
                                     if let Some(_old_point) = self.fan_curves[self.current_curve_index].remove_point(index) {
-                                        self.fan_curves[self.current_curve_index].add_point(temp, duty);
+                                        self.fan_curves[self.current_curve_index]
+                                            .add_point(temp, Duty::from_percent(duty as f32).as_ten_thousandths());
                                         self.set_status(format!("Updated point {}: {}°C -> {}%", index + 1, temp, duty));
+                                        // Checked once the edit is committed, not on every keystroke
+                                        self.revalidate();
                                     }
                                 }
                             }
@@ -515,15 +599,20 @@ impl eframe::App for FanCurveApp {
                                 ui.vertical(|ui| {
                                     ui.horizontal(|ui| {
                                         ui.label("⚡ Fan Duty:");
+                                        if self.fan_curves[self.current_curve_index].is_auto() {
+                                            ui.label("Automatic (firmware)");
+                                            return;
+                                        }
+                                        let duty = Duty::from_ten_thousandths(data.fan_duty);
                                         ui.colored_label(
-                                            if data.fan_duty > 80 {
+                                            if duty.as_percent() > 80.0 {
                                                 egui::Color32::RED
-                                            } else if data.fan_duty > 50 {
+                                            } else if duty.as_percent() > 50.0 {
                                                 egui::Color32::YELLOW
                                             } else {
                                                 egui::Color32::GREEN
                                             },
-                                            format!("{}%", data.fan_duty),
+                                            duty.to_string(),
                                         );
                                     });
                                     ui.horizontal(|ui| {
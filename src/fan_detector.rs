@@ -1,7 +1,13 @@
-use crate::errors::Result;
+use crate::errors::{FanCurveError, Result};
 use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 /// Fan sensor information
 #[derive(Debug, Clone)]
@@ -11,27 +17,344 @@ pub struct FanSensor {
     pub fan_input_path: String,
     pub fan_label_path: String,
     pub fan_label: String,
+    /// `pwmN` control file, resolved once during detection
+    pub pwm_path: PathBuf,
+    /// `pwmN_enable` mode file, resolved once during detection
+    pub pwm_enable_path: PathBuf,
+    /// Whether `pwm_path` could be opened for writing during detection
+    pub pwm_writable: bool,
+    /// Value of `pwm_path` at full speed, from `pwmN_max` or [`PWM_MAX_ENV`]; usually 255
+    pub pwm_max: u8,
+    /// Whether the controller slows the fan down as `pwm_path` rises
+    pub inverted: bool,
+    /// Duty to RPM samples seen while monitoring, shared with clones
+    rpm_model: Arc<Mutex<RpmModel>>,
+}
+
+impl FanSensor {
+    /// Scale a PWM value on the usual 0-255 range into this fan's 0-`pwm_max` range,
+    /// flipping it for inverted controllers so full speed is written as 0
+    pub fn scale_pwm(&self, pwm: u8) -> u8 {
+        let scaled = ((u32::from(pwm) * u32::from(self.pwm_max) + 127) / 255) as u8;
+        if self.inverted {
+            self.pwm_max - scaled
+        } else {
+            scaled
+        }
+    }
+
+    /// Scale a value read from `pwm_path` back onto the usual 0-255 range,
+    /// undoing [`FanSensor::scale_pwm`] including the inversion
+    pub fn unscale_pwm(&self, raw: u8) -> u8 {
+        let max = u32::from(self.pwm_max.max(1));
+        let raw = u32::from(raw).min(max);
+        let raw = if self.inverted { max - raw } else { raw };
+        ((raw * 255 + max / 2) / max) as u8
+    }
+
+    /// Remember the RPM observed while the fan was commanded to `duty` percent
+    pub fn record_rpm_sample(&self, duty: u8, rpm: u16) {
+        self.rpm_model.lock().unwrap().record(duty, rpm);
+    }
+
+    /// Predict the RPM at `duty` percent from the samples seen so far
+    /// Returns None until enough samples at different duties have been recorded
+    pub fn estimate_rpm(&self, duty: u8) -> Option<u16> {
+        self.rpm_model.lock().unwrap().estimate(duty)
+    }
+}
+
+/// Samples needed before an RPM estimate is made
+pub const MIN_RPM_SAMPLES: usize = 8;
+
+/// Older samples are dropped beyond this many, so the model follows wear and dust
+const MAX_RPM_SAMPLES: usize = 256;
+
+/// Least-squares line through observed (duty percent, RPM) samples
+#[derive(Debug, Clone, Default)]
+pub struct RpmModel {
+    samples: VecDeque<(u8, u16)>,
+}
+
+impl RpmModel {
+    pub fn record(&mut self, duty: u8, rpm: u16) {
+        if self.samples.len() == MAX_RPM_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((duty, rpm));
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// (RPM per duty percent, RPM at 0%), or None with too few samples or a single duty
+    pub fn fit(&self) -> Option<(f64, f64)> {
+        if self.samples.len() < MIN_RPM_SAMPLES {
+            return None;
+        }
+
+        let n = self.samples.len() as f64;
+        let mean_duty = self.samples.iter().map(|&(d, _)| f64::from(d)).sum::<f64>() / n;
+        let mean_rpm = self.samples.iter().map(|&(_, r)| f64::from(r)).sum::<f64>() / n;
+
+        let (covariance, variance) =
+            self.samples
+                .iter()
+                .fold((0.0, 0.0), |(cov, var), &(duty, rpm)| {
+                    let dx = f64::from(duty) - mean_duty;
+                    (cov + dx * (f64::from(rpm) - mean_rpm), var + dx * dx)
+                });
+        if variance == 0.0 {
+            return None;
+        }
+
+        let slope = covariance / variance;
+        Some((slope, mean_rpm - slope * mean_duty))
+    }
+
+    /// RPM predicted at `duty` percent, clamped to the u16 range
+    pub fn estimate(&self, duty: u8) -> Option<u16> {
+        let (slope, intercept) = self.fit()?;
+        let rpm = slope * f64::from(duty) + intercept;
+        Some(rpm.round().clamp(0.0, f64::from(u16::MAX)) as u16)
+    }
+}
+
+/// Largest difference between a written and read-back PWM value that counts as applied
+pub const PWM_VERIFY_TOLERANCE: u8 = 2;
+
+/// Full-speed PWM value assumed when a fan has no `pwmN_max`
+pub const DEFAULT_PWM_MAX: u8 = u8::MAX;
+
+/// Environment variable forcing the full-speed PWM value of every fan, for drivers that
+/// take 0-100 but don't expose `pwmN_max`
+pub const PWM_MAX_ENV: &str = "FAN_APP_PWM_MAX";
+
+/// Environment variable listing fans with inverted PWM, e.g. `1,3`
+pub const PWM_INVERTED_ENV: &str = "FAN_APP_PWM_INVERTED";
+
+/// Environment variable forcing the `pwmN_enable` values as `<manual>,<auto>`, e.g. `1,5`
+pub const PWM_ENABLE_ENV: &str = "FAN_APP_PWM_ENABLE";
+
+/// `pwmN_enable` values a driver uses for manual PWM control and for firmware control
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PwmEnableValues {
+    pub manual: u8,
+    pub auto: u8,
+}
+
+impl Default for PwmEnableValues {
+    /// The hwmon convention: 1 is manual, 2 is automatic
+    fn default() -> Self {
+        Self { manual: 1, auto: 2 }
+    }
+}
+
+impl PwmEnableValues {
+    /// Parse `<manual>,<auto>`, as in [`PWM_ENABLE_ENV`]
+    pub fn parse(text: &str) -> Result<Self> {
+        let values = text.split_once(',').and_then(|(manual, auto)| {
+            Some((manual.trim().parse().ok()?, auto.trim().parse().ok()?))
+        });
+        match values {
+            Some((manual, auto)) if manual != auto => Ok(Self { manual, auto }),
+            _ => Err(FanCurveError::Config(format!(
+                "Invalid pwm_enable values '{}', expected <manual>,<auto> such as 1,2",
+                text
+            ))),
+        }
+    }
+}
+
+/// PWM decrease between steps of [`FanDetector::calibrate_min_duty`]
+pub const CALIBRATE_PWM_STEP: u8 = 5;
+
+/// Time a fan gets to settle at each calibration step before its RPM is read
+pub const CALIBRATE_SETTLE: Duration = Duration::from_secs(3);
+
+/// hwmon `name`s of the System76 fan controllers
+const THELIO_IO_NAMES: [&str; 2] = ["system76_thelio_io", "system76"];
+
+/// Outcome of a write to each fan, by fan number
+pub type FanResults = Vec<(u8, Result<()>)>;
+
+/// Whether the fans are driven by PWM writes or by the firmware, from `pwmN_enable`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlMode {
+    /// Every fan is under automatic (firmware) control
+    Auto,
+    /// Every fan is in manual PWM mode
+    Manual,
+    /// Every fan has `pwmN_enable` set to 0, running at full speed with no control
+    FullSpeed,
+    /// Some fans are manual and some automatic
+    Mixed,
+    /// No fan's enable mode could be read
+    Unknown,
+}
+
+impl ControlMode {
+    /// Mode reported for a set of `pwmN_enable` values, `manual` being the manual mode
+    pub fn from_enable_modes<'a>(modes: impl IntoIterator<Item = &'a str>, manual: u8) -> Self {
+        modes
+            .into_iter()
+            .map(|mode| {
+                let mode = mode.trim().parse();
+                if mode == Ok(manual) {
+                    ControlMode::Manual
+                } else if mode == Ok(0) {
+                    ControlMode::FullSpeed
+                } else {
+                    ControlMode::Auto
+                }
+            })
+            .fold(ControlMode::Unknown, |acc, mode| match acc {
+                ControlMode::Unknown => mode,
+                acc if acc == mode => acc,
+                _ => ControlMode::Mixed,
+            })
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ControlMode::Auto => "auto",
+            ControlMode::Manual => "manual",
+            ControlMode::FullSpeed => "full-speed",
+            ControlMode::Mixed => "mixed",
+            ControlMode::Unknown => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for ControlMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 /// Fan detector for System76 Thelio IO
 #[derive(Clone)]
 pub struct FanDetector {
     fans: Vec<FanSensor>,
+    /// Resolved `hwmonN` directory; N can change between boots
     hwmon_path: Option<String>,
+    /// Stable hwmon `name` of the device, used to find it again if it moves
+    hwmon_name: Option<String>,
+    /// Directory scanned for hwmon devices
+    hwmon_root: PathBuf,
+    dry_run: bool,
+    scan_count: usize,
+    /// `pwmN_enable` modes found before switching fans to manual, shared with clones
+    original_enable_modes: Arc<Mutex<HashMap<u8, String>>>,
+    /// Set when a write finds a fan file missing, shared with clones
+    files_missing: Arc<AtomicBool>,
+    /// Full-speed PWM value used instead of each fan's `pwmN_max`
+    pwm_max_override: Option<u8>,
+    /// `pwmN_enable` values written for manual and automatic control
+    enable_values: PwmEnableValues,
+    /// Fans treated as inverted whether or not the driver hints at it
+    inverted_fans: Vec<u8>,
 }
 
 impl FanDetector {
-    /// Create a new fan detector
+    /// Create a new fan detector for the hwmon tree from [`crate::hwmon_root`]
+    /// A valid `$FAN_APP_PWM_MAX` overrides the PWM range of every fan, a valid
+    /// `$FAN_APP_PWM_ENABLE` the `pwmN_enable` values, and `$FAN_APP_PWM_INVERTED`
+    /// marks fans as inverted
     pub fn new() -> Self {
+        let mut detector = Self::with_hwmon_root(crate::hwmon_root());
+        if let Ok(value) = std::env::var(PWM_MAX_ENV) {
+            match value.trim().parse() {
+                Ok(max) if max > 0 => detector.pwm_max_override = Some(max),
+                _ => warn!("Ignoring {}={}, expected 1-255", PWM_MAX_ENV, value),
+            }
+        }
+        if let Ok(value) = std::env::var(PWM_ENABLE_ENV) {
+            match PwmEnableValues::parse(&value) {
+                Ok(values) => detector.set_pwm_enable_override(Some(values)),
+                Err(e) => warn!("Ignoring {}: {}", PWM_ENABLE_ENV, e),
+            }
+        }
+        if let Ok(value) = std::env::var(PWM_INVERTED_ENV) {
+            match parse_fan_numbers(&value) {
+                Ok(fans) => detector.inverted_fans = fans,
+                Err(e) => warn!("Ignoring {}: {}", PWM_INVERTED_ENV, e),
+            }
+        }
+        detector
+    }
+
+    /// Detector that looks for hwmon devices in `root` instead of /sys/class/hwmon
+    pub fn with_hwmon_root(root: impl Into<PathBuf>) -> Self {
         Self {
             fans: Vec::new(),
             hwmon_path: None,
+            hwmon_name: None,
+            hwmon_root: root.into(),
+            dry_run: false,
+            scan_count: 0,
+            original_enable_modes: Arc::new(Mutex::new(HashMap::new())),
+            files_missing: Arc::new(AtomicBool::new(false)),
+            pwm_max_override: None,
+            enable_values: PwmEnableValues::default(),
+            inverted_fans: Vec::new(),
         }
     }
 
+    /// Use `values` for `pwmN_enable` instead of the hwmon convention; None goes back to it
+    /// The System76 controllers this drives use the convention
+    pub fn set_pwm_enable_override(&mut self, values: Option<PwmEnableValues>) {
+        self.enable_values = values.unwrap_or_default();
+    }
+
+    /// `pwmN_enable` values written for manual and automatic control
+    pub fn pwm_enable_values(&self) -> PwmEnableValues {
+        self.enable_values
+    }
+
+    /// Treat `fans` as inverted in addition to those whose driver has `pwmN_invert` set
+    /// Applies to the detected fans at once
+    pub fn set_inverted_fans(&mut self, fans: Vec<u8>) {
+        self.inverted_fans = fans;
+        for fan in &mut self.fans {
+            fan.inverted =
+                self.inverted_fans.contains(&fan.fan_number) || pwm_invert_hint(&fan.pwm_path);
+        }
+    }
+
+    /// Use `pwm_max` as every fan's full-speed PWM value instead of reading `pwmN_max`
+    /// Takes effect on the next `initialize`
+    pub fn set_pwm_max_override(&mut self, pwm_max: Option<u8>) {
+        self.pwm_max_override = pwm_max;
+    }
+
+    /// Log PWM writes instead of performing them
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Check if PWM writes are only logged
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Number of times hwmon has been scanned by `initialize`
+    pub fn scan_count(&self) -> usize {
+        self.scan_count
+    }
+
     /// Initialize the detector by finding System76 Thelio IO sensors
     pub fn initialize(&mut self) -> Result<()> {
         info!("Initializing fan detector...");
+        self.scan_count += 1;
+        self.fans.clear();
+        self.hwmon_path = None;
+        self.hwmon_name = None;
 
         // Find the System76 Thelio IO hwmon directory
         self.find_thelio_io_sensor()?;
@@ -57,43 +380,131 @@ impl FanDetector {
 
     /// Find the System76 Thelio IO sensor directory
     fn find_thelio_io_sensor(&mut self) -> Result<()> {
-        let hwmon_dir = Path::new("/sys/class/hwmon");
+        let (path, name) = self
+            .find_hwmon_device(|name| THELIO_IO_NAMES.contains(&name))?
+            .ok_or_else(|| {
+                crate::errors::FanCurveError::Config(
+                    "System76 Thelio IO sensor not found".to_string(),
+                )
+            })?;
+
+        info!("Found System76 sensor '{}' at: {}", name, path.display());
+        self.hwmon_path = Some(path.to_string_lossy().to_string());
+        self.hwmon_name = Some(name);
+        Ok(())
+    }
 
-        if !hwmon_dir.exists() {
+    /// First hwmon directory whose `name` is accepted by `matches`, with that name
+    fn find_hwmon_device<F>(&self, matches: F) -> Result<Option<(PathBuf, String)>>
+    where
+        F: Fn(&str) -> bool,
+    {
+        if !self.hwmon_root.exists() {
             return Err(crate::errors::FanCurveError::Config(
                 "Hardware monitoring directory not found".to_string(),
             ));
         }
 
-        let entries = fs::read_dir(hwmon_dir)?;
+        let mut paths: Vec<PathBuf> = fs::read_dir(&self.hwmon_root)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_dir())
+            .collect();
+        paths.sort();
 
-        for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
+        for path in paths {
+            if let Ok(name_content) = fs::read_to_string(path.join("name")) {
+                let name = name_content.trim();
+                info!("Checking hwmon device: {} -> '{}'", path.display(), name);
+                if matches(name) {
+                    return Ok(Some((path, name.to_string())));
+                }
+            }
+        }
+        Ok(None)
+    }
 
-            if path.is_dir() {
-                let name_file = path.join("name");
-                if name_file.exists() {
-                    if let Ok(name_content) = fs::read_to_string(&name_file) {
-                        let name = name_content.trim();
-                        info!("Checking hwmon device: {} -> '{}'", path.display(), name);
+    /// Stable hwmon `name` of the fan controller, e.g. `system76_thelio_io`
+    pub fn hwmon_name(&self) -> Option<&str> {
+        self.hwmon_name.as_deref()
+    }
 
-                        if name == "system76_thelio_io" || name == "system76" {
-                            self.hwmon_path = Some(path.to_string_lossy().to_string());
-                            info!("Found System76 sensor '{}' at: {}", name, path.display());
-                            return Ok(());
-                        }
-                    }
-                }
+    /// Find the fan controller again by name if its `hwmonN` directory changed
+    /// Returns true if the paths moved. Learned RPM models are kept
+    pub fn refresh_paths(&mut self) -> Result<bool> {
+        let Some(name) = self.hwmon_name.clone() else {
+            return Ok(false);
+        };
+
+        // Still in place if the directory we use reports the same name
+        if let Some(path) = &self.hwmon_path {
+            let current = fs::read_to_string(Path::new(path).join("name")).ok();
+            if current.as_deref().map(str::trim) == Some(name.as_str()) {
+                return Ok(false);
+            }
+        }
+
+        let (path, _) = self.find_hwmon_device(|n| n == name)?.ok_or_else(|| {
+            crate::errors::FanCurveError::Config(format!("hwmon device '{}' disappeared", name))
+        })?;
+        warn!(
+            "hwmon device '{}' moved from {} to {}, updating fan paths",
+            name,
+            self.hwmon_path.as_deref().unwrap_or("nowhere"),
+            path.display()
+        );
+
+        let models: HashMap<u8, Arc<Mutex<RpmModel>>> = self
+            .fans
+            .drain(..)
+            .map(|fan| (fan.fan_number, fan.rpm_model))
+            .collect();
+        self.hwmon_path = Some(path.to_string_lossy().to_string());
+        self.find_fan_sensors()?;
+        for fan in &mut self.fans {
+            if let Some(model) = models.get(&fan.fan_number) {
+                fan.rpm_model = model.clone();
             }
         }
+        Ok(true)
+    }
+
+    /// Re-resolve the device with [`FanDetector::refresh_paths`] if a write since the last call
+    /// found a fan file missing, as when a driver reload renumbers the hwmon devices
+    pub fn refresh_if_files_missing(&mut self) -> Result<bool> {
+        if !self.files_missing.swap(false, Ordering::Relaxed) {
+            return Ok(false);
+        }
+        self.refresh_paths()
+    }
+
+    /// Write a fan file, noting a missing one for [`FanDetector::refresh_if_files_missing`]
+    fn write_fan_file(&self, path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+        let result = fs::write(path, contents);
+        if matches!(&result, Err(e) if e.kind() == std::io::ErrorKind::NotFound) {
+            self.files_missing.store(true, Ordering::Relaxed);
+        }
+        result
+    }
 
-        Err(crate::errors::FanCurveError::Config(
-            "System76 Thelio IO sensor not found".to_string(),
-        ))
+    /// Like [`FanDetector::read_fan_speed`], re-resolving the device once if its files vanished
+    pub fn read_fan_speed_refreshing(&mut self, fan_number: u8) -> Result<u16> {
+        match self.read_fan_speed(fan_number) {
+            Err(crate::errors::FanCurveError::Io(e))
+                if e.kind() == std::io::ErrorKind::NotFound =>
+            {
+                if self.refresh_paths()? {
+                    self.read_fan_speed(fan_number)
+                } else {
+                    Err(crate::errors::FanCurveError::Io(e))
+                }
+            }
+            result => result,
+        }
     }
 
-    /// Find the CPU Fan sensor in the System76 Thelio IO directory
+    /// Find every fan sensor in the System76 Thelio IO directory, CPU fans first
+    /// Multi-socket boards expose one CPU fan per socket, and case fans can be given their
+    /// own control mode, so every labelled `fanN_input` is registered
     fn find_fan_sensors(&mut self) -> Result<()> {
         let hwmon_path = self.hwmon_path.as_ref().ok_or_else(|| {
             crate::errors::FanCurveError::Config(
@@ -102,12 +513,9 @@ impl FanDetector {
         })?;
 
         let hwmon_dir = Path::new(hwmon_path);
-        info!(
-            "Searching for CPU Fan in directory: {}",
-            hwmon_dir.display()
-        );
+        info!("Searching for fans in directory: {}", hwmon_dir.display());
 
-        // Search through fan1_label, fan2_label, fan3_label, etc. until we find "CPU Fan"
+        // Search through fan1_label, fan2_label, fan3_label, etc.
         let mut fan_number = 1;
         loop {
             let label_path = hwmon_dir.join(format!("fan{}_label", fan_number));
@@ -124,32 +532,47 @@ impl FanDetector {
                     let fan_label = label_content.trim().to_string();
                     info!("Found fan{}_label: '{}'", fan_number, fan_label);
 
-                    if fan_label == "CPU Fan"
-                        || fan_label == "CPU fan"
-                        || fan_label.to_lowercase().contains("cpu")
-                    {
-                        info!(
-                            "Found CPU Fan at fan{}! Using fan{}_input for data",
-                            fan_number, fan_number
+                    let pwm_path = hwmon_dir.join(format!("pwm{}", fan_number));
+                    let pwm_writable = is_writable(&pwm_path);
+                    if !pwm_writable {
+                        warn!(
+                            "PWM control {} is not writable, fan speed cannot be set directly",
+                            pwm_path.display()
                         );
+                    }
 
-                        let fan_sensor = FanSensor {
-                            fan_number,
-                            hwmon_path: hwmon_path.clone(),
-                            fan_input_path: input_path.to_string_lossy().to_string(),
-                            fan_label_path: label_path.to_string_lossy().to_string(),
-                            fan_label: fan_label.clone(),
-                        };
-
-                        self.fans.push(fan_sensor);
-                        info!(
-                            "CPU Fan sensor added: Fan {} - {} -> {}",
-                            fan_number,
-                            fan_label,
-                            input_path.display()
-                        );
-                        return Ok(());
+                    let pwm_max = self.pwm_max(hwmon_dir, fan_number);
+                    if pwm_max != DEFAULT_PWM_MAX {
+                        info!("Fan {} takes PWM values 0-{}", fan_number, pwm_max);
+                    }
+
+                    let inverted =
+                        self.inverted_fans.contains(&fan_number) || pwm_invert_hint(&pwm_path);
+                    if inverted {
+                        info!("Fan {} has inverted PWM, 0 is full speed", fan_number);
                     }
+
+                    let fan_sensor = FanSensor {
+                        fan_number,
+                        hwmon_path: hwmon_path.clone(),
+                        fan_input_path: input_path.to_string_lossy().to_string(),
+                        fan_label_path: label_path.to_string_lossy().to_string(),
+                        fan_label: fan_label.clone(),
+                        pwm_path,
+                        pwm_enable_path: hwmon_dir.join(format!("pwm{}_enable", fan_number)),
+                        pwm_writable,
+                        pwm_max,
+                        inverted,
+                        rpm_model: Arc::default(),
+                    };
+
+                    self.fans.push(fan_sensor);
+                    info!(
+                        "Fan sensor added: Fan {} - {} -> {}",
+                        fan_number,
+                        fan_label,
+                        input_path.display()
+                    );
                 }
             } else {
                 // No more fan files found, stop searching
@@ -164,9 +587,15 @@ impl FanDetector {
             }
         }
 
-        Err(crate::errors::FanCurveError::Config(
-            "CPU Fan not found in System76 Thelio IO".to_string(),
-        ))
+        if self.fans.is_empty() {
+            return Err(crate::errors::FanCurveError::Config(
+                "No fans found in System76 Thelio IO".to_string(),
+            ));
+        }
+        // The first fan stands for all of them when only one reading is shown
+        self.fans
+            .sort_by_key(|fan| !is_cpu_fan_label(&fan.fan_label));
+        Ok(())
     }
 
     /// Read fan speed for a specific fan
@@ -226,11 +655,7 @@ impl FanDetector {
 
     /// Get the CPU fan specifically
     pub fn get_cpu_fan(&self) -> Option<&FanSensor> {
-        let cpu_fan = self.fans.iter().find(|f| {
-            f.fan_label == "CPU Fan"
-                || f.fan_label == "CPU fan"
-                || f.fan_label.to_lowercase().contains("cpu")
-        });
+        let cpu_fan = self.fans.iter().find(|f| is_cpu_fan_label(&f.fan_label));
         if cpu_fan.is_none() {
             warn!(
                 "CPU Fan not found. Available fans: {:?}",
@@ -253,6 +678,11 @@ impl FanDetector {
         }
     }
 
+    /// Get the hwmon directory of the detected System76 sensor
+    pub fn hwmon_path(&self) -> Option<&str> {
+        self.hwmon_path.as_deref()
+    }
+
     /// Check if the detector is initialized
     pub fn is_initialized(&self) -> bool {
         !self.fans.is_empty()
@@ -263,17 +693,44 @@ impl FanDetector {
         self.fans.len()
     }
 
+    /// Full-speed PWM value of a fan: the override, else `pwmN_max`, else [`DEFAULT_PWM_MAX`]
+    fn pwm_max(&self, hwmon_dir: &Path, fan_number: u8) -> u8 {
+        if let Some(max) = self.pwm_max_override {
+            return max;
+        }
+        let max_path = hwmon_dir.join(format!("pwm{}_max", fan_number));
+        let Ok(content) = fs::read_to_string(&max_path) else {
+            return DEFAULT_PWM_MAX;
+        };
+        match content.trim().parse::<u16>() {
+            Ok(max) if max > 0 => u8::try_from(max).unwrap_or(DEFAULT_PWM_MAX),
+            _ => {
+                warn!(
+                    "Ignoring unreadable {}: {:?}",
+                    max_path.display(),
+                    content.trim()
+                );
+                DEFAULT_PWM_MAX
+            }
+        }
+    }
+
+    /// Check whether any detected fan's PWM file was writable during `initialize`
+    pub fn can_control(&self) -> bool {
+        self.fans.iter().any(|fan| fan.pwm_writable)
+    }
+
     /// Set fan PWM duty (0-255, where 255 = 100%)
     /// This method sets a specific fan's PWM value
     pub fn set_fan_pwm(&self, fan_number: u8, duty: u8) -> Result<()> {
         if let Some(fan) = self.fans.iter().find(|f| f.fan_number == fan_number) {
-            let pwm_path = Path::new(&fan.hwmon_path).join(format!("pwm{}", fan_number));
-            let pwm_enable_path =
-                Path::new(&fan.hwmon_path).join(format!("pwm{}_enable", fan_number));
+            let pwm_path = &fan.pwm_path;
+            let pwm_enable_path = &fan.pwm_enable_path;
 
+            let pwm = fan.scale_pwm(duty);
             info!(
-                "Setting fan {} PWM to {} (duty: {})",
-                fan_number, duty, duty
+                "Setting fan {} PWM to {} (duty: {}/255)",
+                fan_number, pwm, duty
             );
             info!(
                 "PWM paths: enable={}, pwm={}",
@@ -283,15 +740,26 @@ impl FanDetector {
 
             // Check if PWM file exists and is writable
             if !pwm_path.exists() {
+                self.files_missing.store(true, Ordering::Relaxed);
                 return Err(crate::errors::FanCurveError::Config(format!(
                     "PWM file not found: {}",
                     pwm_path.display()
                 )));
             }
 
+            if self.dry_run {
+                info!(
+                    "[dry-run] Would write {} to {} and 1 to {}",
+                    pwm,
+                    pwm_path.display(),
+                    pwm_enable_path.display()
+                );
+                return Ok(());
+            }
+
             // Try to enable PWM control if enable file exists (optional)
             if pwm_enable_path.exists() {
-                if let Err(e) = fs::write(&pwm_enable_path, "1") {
+                if let Err(e) = self.enable_manual(fan) {
                     warn!(
                         "Failed to enable PWM control for fan {} at {}: {}",
                         fan_number,
@@ -310,23 +778,19 @@ impl FanDetector {
             }
 
             // Set PWM duty (0-255)
-            fs::write(&pwm_path, duty.to_string()).map_err(|e| {
-                if e.kind() == std::io::ErrorKind::PermissionDenied {
-                    crate::errors::FanCurveError::PermissionDenied(format!(
-                        "Failed to set PWM duty for fan {} at {}: {}",
+            self.write_fan_file(pwm_path, pwm.to_string())
+                .map_err(|e| {
+                    crate::errors::FanCurveError::Io(e).context(format!(
+                        "Failed to set PWM duty for fan {} at {}",
                         fan_number,
-                        pwm_path.display(),
-                        e
+                        pwm_path.display()
                     ))
-                } else {
-                    crate::errors::FanCurveError::Io(e)
-                }
-            })?;
+                })?;
 
             info!(
                 "Fan {} PWM set to {} at {}",
                 fan_number,
-                duty,
+                pwm,
                 pwm_path.display()
             );
             Ok(())
@@ -339,51 +803,419 @@ impl FanDetector {
     }
 
     /// Set duty cycle for all fans (0-255) - matches system76-power approach
-    /// If duty_opt is None, enables automatic mode (pwm1_enable = "2")
-    /// If duty_opt is Some(duty), sets all fans to the same duty value
+    /// If duty_opt is None, enables automatic mode (the chip's automatic `pwmN_enable` value)
+    /// If duty_opt is Some(duty), sets all fans to the same duty value.
+    /// Every fan is attempted; the error lists the fans that failed
     pub fn set_duty(&self, duty_opt: Option<u8>) -> Result<()> {
+        combine_fan_results(self.set_duty_each(duty_opt))
+    }
+
+    /// Like [`FanDetector::set_duty`], writing all fans concurrently and reporting each one
+    pub fn set_duty_each(&self, duty_opt: Option<u8>) -> FanResults {
         if let Some(duty) = duty_opt {
-            let duty_str = format!("{}", duty);
             info!("Setting all fans to PWM duty: {}", duty);
+        } else {
+            info!("Enabling automatic fan control mode");
+        }
+
+        self.for_each_fan(|fan| {
+            let Some(duty) = duty_opt else {
+                if self.dry_run {
+                    info!(
+                        "[dry-run] Would set fan {} to automatic mode at {}",
+                        fan.fan_number,
+                        fan.pwm_enable_path.display()
+                    );
+                    return Ok(());
+                }
+                // As in set_fan_pwm, fans without an enable file have no mode to switch
+                if !fan.pwm_enable_path.exists() {
+                    info!(
+                        "PWM enable file not found for fan {} - leaving its mode alone",
+                        fan.fan_number
+                    );
+                    return Ok(());
+                }
+                self.write_fan_file(&fan.pwm_enable_path, self.enable_values.auto.to_string())?;
+                info!("Fan {} set to automatic mode", fan.fan_number);
+                return Ok(());
+            };
+
+            let pwm = fan.scale_pwm(duty);
+            if self.dry_run {
+                info!(
+                    "[dry-run] Would set fan {} PWM to {} at {}",
+                    fan.fan_number,
+                    pwm,
+                    fan.pwm_path.display()
+                );
+                return Ok(());
+            }
+
+            // Some drivers accept PWM writes without a writable enable file
+            let _ = self.enable_manual(fan);
+            self.write_fan_file(&fan.pwm_path, pwm.to_string())?;
+            info!("Fan {} PWM set to {}", fan.fan_number, pwm);
+            Ok(())
+        })
+    }
+
+    /// Run `write` for every fan at once, so slow sysfs writes don't add up
+    fn for_each_fan<F>(&self, write: F) -> FanResults
+    where
+        F: Fn(&FanSensor) -> Result<()> + Sync,
+    {
+        if self.fans.len() <= 1 {
+            return self
+                .fans
+                .iter()
+                .map(|fan| (fan.fan_number, write(fan)))
+                .collect();
+        }
+
+        let write = &write;
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .fans
+                .iter()
+                .map(|fan| (fan.fan_number, scope.spawn(move || write(fan))))
+                .collect();
+            handles
+                .into_iter()
+                .map(|(fan_number, handle)| {
+                    let result = handle.join().unwrap_or_else(|_| {
+                        Err(crate::errors::FanCurveError::Unknown(format!(
+                            "PWM write for fan {} panicked",
+                            fan_number
+                        )))
+                    });
+                    (fan_number, result)
+                })
+                .collect()
+        })
+    }
+
+    /// Current control mode of the detected fans; unreadable enable files are skipped
+    pub fn control_mode(&self) -> ControlMode {
+        let modes: Vec<String> = self
+            .fans
+            .iter()
+            .filter_map(|fan| fs::read_to_string(&fan.pwm_enable_path).ok())
+            .collect();
+        ControlMode::from_enable_modes(modes.iter().map(String::as_str), self.enable_values.manual)
+    }
 
-            // Set all available fans to the same duty
-            for fan in &self.fans {
-                let pwm_path = Path::new(&fan.hwmon_path).join(format!("pwm{}", fan.fan_number));
-                let pwm_enable_path =
-                    Path::new(&fan.hwmon_path).join(format!("pwm{}_enable", fan.fan_number));
+    /// Switch a fan to manual control, remembering its previous `pwmN_enable` mode
+    /// Nothing is written if the fan is already in manual mode.
+    fn enable_manual(&self, fan: &FanSensor) -> std::io::Result<()> {
+        let manual = self.enable_values.manual.to_string();
+        let current = fs::read_to_string(&fan.pwm_enable_path)
+            .ok()
+            .map(|mode| mode.trim().to_string());
+        if current.as_deref() == Some(manual.as_str()) {
+            return Ok(());
+        }
 
-                // Enable manual PWM control
-                let _ = fs::write(&pwm_enable_path, "1");
-                // Set PWM duty
-                let _ = fs::write(&pwm_path, &duty_str);
+        if let Some(mode) = current {
+            self.original_enable_modes
+                .lock()
+                .unwrap()
+                .entry(fan.fan_number)
+                .or_insert(mode);
+        }
+        self.write_fan_file(&fan.pwm_enable_path, manual)
+    }
 
-                info!("Fan {} PWM set to {}", fan.fan_number, duty);
+    /// Put every fan switched to manual back into the `pwmN_enable` mode it had before
+    pub fn restore_enable_mode(&self) -> Result<()> {
+        let modes = std::mem::take(&mut *self.original_enable_modes.lock().unwrap());
+        for (fan_number, mode) in modes {
+            let Some(fan) = self.get_fan(fan_number) else {
+                continue;
+            };
+            if self.dry_run {
+                info!(
+                    "[dry-run] Would restore fan {} enable mode {} at {}",
+                    fan_number,
+                    mode,
+                    fan.pwm_enable_path.display()
+                );
+                continue;
             }
-        } else {
-            info!("Enabling automatic fan control mode");
+            self.write_fan_file(&fan.pwm_enable_path, &mode)?;
+            info!("Fan {} enable mode restored to {}", fan_number, mode);
+        }
+        Ok(())
+    }
 
-            // Enable automatic mode for all fans
-            for fan in &self.fans {
-                let pwm_enable_path =
-                    Path::new(&fan.hwmon_path).join(format!("pwm{}_enable", fan.fan_number));
-                let _ = fs::write(&pwm_enable_path, "2");
-                info!("Fan {} set to automatic mode", fan.fan_number);
+    /// Hand one fan back to the firmware (`pwmN_enable` set to the chip's automatic value);
+    /// nothing is written if it already is
+    pub fn set_fan_auto(&self, fan_number: u8) -> Result<()> {
+        let fan = self.get_fan(fan_number).ok_or_else(|| {
+            crate::errors::FanCurveError::Config(format!("Fan {} not found", fan_number))
+        })?;
+        self.original_enable_modes
+            .lock()
+            .unwrap()
+            .remove(&fan_number);
+        let auto = self.enable_values.auto.to_string();
+        let current = fs::read_to_string(&fan.pwm_enable_path).unwrap_or_default();
+        if current.trim() == auto {
+            return Ok(());
+        }
+        if self.dry_run {
+            info!(
+                "[dry-run] Would set fan {} to automatic mode at {}",
+                fan_number,
+                fan.pwm_enable_path.display()
+            );
+            return Ok(());
+        }
+        self.write_fan_file(&fan.pwm_enable_path, auto)?;
+        info!("Fan {} set to automatic mode", fan_number);
+        Ok(())
+    }
+
+    /// Find the lowest PWM (0-255) at which a fan still reports a non-zero RPM
+    /// Ramps down from full speed in [`CALIBRATE_PWM_STEP`] steps, waiting
+    /// [`CALIBRATE_SETTLE`] at each. The fan is returned to automatic mode afterwards, even on error
+    pub fn calibrate_min_duty(&self, fan_number: u8) -> Result<u8> {
+        self.calibrate_min_duty_with(fan_number, || std::thread::sleep(CALIBRATE_SETTLE))
+    }
+
+    /// Calibrate a fan, calling `settle` after each PWM write
+    fn calibrate_min_duty_with<S>(&self, fan_number: u8, mut settle: S) -> Result<u8>
+    where
+        S: FnMut(),
+    {
+        let fan = self.get_fan(fan_number).ok_or_else(|| {
+            crate::errors::FanCurveError::Config(format!("Fan {} not found", fan_number))
+        })?;
+        if self.dry_run {
+            return Err(crate::errors::FanCurveError::Config(
+                "Calibration has to write PWM values and can't run in dry-run mode".to_string(),
+            ));
+        }
+
+        info!("Calibrating fan {} ({})", fan_number, fan.fan_label);
+        let mut ramp = || -> Result<u8> {
+            let mut lowest = None;
+            for pwm in (0..=u8::MAX).rev().step_by(usize::from(CALIBRATE_PWM_STEP)) {
+                self.set_fan_pwm(fan_number, pwm)?;
+                settle();
+                let rpm = self.read_fan_speed(fan_number)?;
+                info!("Fan {} at PWM {}: {} RPM", fan_number, pwm, rpm);
+                if rpm == 0 {
+                    break;
+                }
+                lowest = Some(pwm);
             }
+            lowest.ok_or_else(|| {
+                crate::errors::FanCurveError::Config(format!(
+                    "Fan {} reports 0 RPM even at full speed",
+                    fan_number
+                ))
+            })
+        };
+        let result = ramp();
+
+        self.original_enable_modes
+            .lock()
+            .unwrap()
+            .remove(&fan_number);
+        if let Err(e) =
+            self.write_fan_file(&fan.pwm_enable_path, self.enable_values.auto.to_string())
+        {
+            warn!(
+                "Failed to return fan {} to automatic mode at {}: {}",
+                fan_number,
+                fan.pwm_enable_path.display(),
+                e
+            );
+            return result.and(Err(e.into()));
+        }
+        info!("Fan {} set to automatic mode", fan_number);
+        result
+    }
+
+    /// Set all fans to the same PWM duty (0-255) and confirm each write took effect
+    /// See [`FanDetector::set_fan_pwm_verified`] for how failed writes are handled.
+    /// Fans are written concurrently and a failing fan doesn't stop the others
+    pub fn set_duty_verified(&self, duty: u8) -> Result<()> {
+        combine_fan_results(
+            self.for_each_fan(|fan| self.write_pwm_verified(fan, duty, Self::read_pwm)),
+        )
+    }
+
+    /// Set a fan's PWM duty (0-255) and read it back to confirm it took effect
+    /// If the value didn't stick, manual mode is re-enabled and the duty rewritten once;
+    /// an error is returned if it still differs by more than [`PWM_VERIFY_TOLERANCE`]
+    pub fn set_fan_pwm_verified(&self, fan_number: u8, duty: u8) -> Result<()> {
+        let fan = self
+            .fans
+            .iter()
+            .find(|f| f.fan_number == fan_number)
+            .ok_or_else(|| {
+                crate::errors::FanCurveError::Config(format!(
+                    "Fan {} not found for PWM control",
+                    fan_number
+                ))
+            })?;
+        self.write_pwm_verified(fan, duty, Self::read_pwm)
+    }
+
+    /// Write a fan's PWM duty, verifying it with `read_back`
+    fn write_pwm_verified<R>(&self, fan: &FanSensor, duty: u8, mut read_back: R) -> Result<()>
+    where
+        R: FnMut(&Path) -> Result<u8>,
+    {
+        self.set_fan_pwm(fan.fan_number, duty)?;
+        if self.dry_run {
+            return Ok(());
+        }
+
+        // Read back in the fan's own range
+        let expected = fan.scale_pwm(duty);
+        let actual = read_back(&fan.pwm_path)?;
+        if actual.abs_diff(expected) <= PWM_VERIFY_TOLERANCE {
+            return Ok(());
+        }
+
+        warn!(
+            "Fan {} PWM reads back {} after writing {}, re-enabling manual mode and retrying",
+            fan.fan_number, actual, expected
+        );
+        self.set_fan_pwm(fan.fan_number, duty)?;
+
+        let actual = read_back(&fan.pwm_path)?;
+        if actual.abs_diff(expected) <= PWM_VERIFY_TOLERANCE {
+            info!(
+                "Fan {} PWM {} applied after retry",
+                fan.fan_number, expected
+            );
+            Ok(())
+        } else {
+            Err(crate::errors::FanCurveError::Config(format!(
+                "PWM for fan {} did not stick: wrote {}, read back {}",
+                fan.fan_number, expected, actual
+            )))
         }
+    }
+
+    /// Read a raw PWM value (0-`pwm_max`) from sysfs
+    fn read_pwm(path: &Path) -> Result<u8> {
+        let content = fs::read_to_string(path)?;
+        content.trim().parse().map_err(|_| {
+            crate::errors::FanCurveError::Config(format!(
+                "Failed to parse PWM value from {}",
+                path.display()
+            ))
+        })
+    }
+}
+
+/// Ok if every fan succeeded, otherwise one error naming each failed fan
+fn combine_fan_results(results: FanResults) -> Result<()> {
+    let failures: Vec<String> = results
+        .into_iter()
+        .filter_map(|(fan_number, result)| {
+            result.err().map(|e| {
+                warn!("Fan {}: {}", fan_number, e);
+                format!("fan {}: {}", fan_number, e)
+            })
+        })
+        .collect();
 
+    if failures.is_empty() {
         Ok(())
+    } else {
+        Err(crate::errors::FanCurveError::Config(format!(
+            "Failed to control {}",
+            failures.join("; ")
+        )))
     }
 }
 
+/// Whether a fan label names a CPU fan, e.g. "CPU Fan" or "CPU 2 Fan"
+fn is_cpu_fan_label(label: &str) -> bool {
+    label.to_lowercase().contains("cpu")
+}
+
+/// Check whether a sysfs attribute can be opened for writing without modifying it
+fn is_writable(path: &Path) -> bool {
+    fs::OpenOptions::new().write(true).open(path).is_ok()
+}
+
+/// Whether the driver marks a PWM control as inverted with `pwmN_invert` reading 1
+fn pwm_invert_hint(pwm_path: &Path) -> bool {
+    let mut invert_path = pwm_path.as_os_str().to_owned();
+    invert_path.push("_invert");
+    fs::read_to_string(invert_path).is_ok_and(|value| value.trim() == "1")
+}
+
+/// Parse a comma separated list of fan numbers, e.g. `1,3`
+pub fn parse_fan_numbers(text: &str) -> Result<Vec<u8>> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|number| !number.is_empty())
+        .map(|number| {
+            number.parse().map_err(|_| {
+                FanCurveError::Config(format!("Invalid fan number '{}' in '{}'", number, text))
+            })
+        })
+        .collect()
+}
+
 impl Default for FanDetector {
     fn default() -> Self {
         Self::new()
     }
 }
 
+#[cfg(test)]
+impl FanDetector {
+    /// Detector with one CPU fan whose PWM files live in `hwmon` (PWM 100, automatic mode)
+    pub(crate) fn fake(hwmon: &crate::test_util::TempDir) -> Self {
+        Self::fake_with_labels(hwmon, &["CPU Fan"])
+    }
+
+    /// Like [`FanDetector::fake`] with one fan per label, numbered from 1
+    pub(crate) fn fake_with_labels(hwmon: &crate::test_util::TempDir, labels: &[&str]) -> Self {
+        let hwmon_path = hwmon.path().to_string_lossy().to_string();
+        let mut detector = FanDetector::new();
+        for (fan_number, label) in (1..).zip(labels) {
+            hwmon.write(&format!("pwm{}", fan_number), "100\n");
+            hwmon.write(&format!("pwm{}_enable", fan_number), "2\n");
+            detector.fans.push(FanSensor {
+                fan_number,
+                hwmon_path: hwmon_path.clone(),
+                fan_input_path: format!("{}/fan{}_input", hwmon_path, fan_number),
+                fan_label_path: format!("{}/fan{}_label", hwmon_path, fan_number),
+                fan_label: label.to_string(),
+                pwm_path: hwmon.path().join(format!("pwm{}", fan_number)),
+                pwm_enable_path: hwmon.path().join(format!("pwm{}_enable", fan_number)),
+                pwm_writable: true,
+                pwm_max: DEFAULT_PWM_MAX,
+                inverted: false,
+                rpm_model: Arc::default(),
+            });
+        }
+        detector.hwmon_path = Some(hwmon_path);
+        detector
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::{EnvOverride, TempDir};
+
+    fn read(hwmon: &TempDir, file: &str) -> String {
+        fs::read_to_string(hwmon.path().join(file))
+            .unwrap()
+            .trim()
+            .to_string()
+    }
 
     #[test]
     fn test_fan_detector_creation() {
@@ -391,4 +1223,533 @@ mod tests {
         assert!(!detector.is_initialized());
         assert_eq!(detector.fan_count(), 0);
     }
+
+    #[test]
+    fn test_dry_run_skips_writes() {
+        let hwmon = TempDir::new("fans-dry-run");
+        let mut detector = FanDetector::fake(&hwmon);
+        detector.set_dry_run(true);
+
+        detector.set_duty(Some(200)).unwrap();
+        detector.set_fan_pwm(1, 200).unwrap();
+        detector.set_duty(None).unwrap();
+        assert_eq!(read(&hwmon, "pwm1"), "100");
+        assert_eq!(read(&hwmon, "pwm1_enable"), "2");
+
+        // Unknown fans are still reported in dry-run mode
+        assert!(detector.set_fan_pwm(2, 200).is_err());
+    }
+
+    #[test]
+    fn test_set_duty_writes_pwm() {
+        let hwmon = TempDir::new("fans-write");
+        let detector = FanDetector::fake(&hwmon);
+
+        detector.set_duty(Some(200)).unwrap();
+        assert_eq!(read(&hwmon, "pwm1"), "200");
+        assert_eq!(read(&hwmon, "pwm1_enable"), "1");
+
+        detector.set_duty(None).unwrap();
+        assert_eq!(read(&hwmon, "pwm1_enable"), "2");
+    }
+
+    #[test]
+    fn test_restore_enable_mode() {
+        let hwmon = TempDir::new("fans-restore-mode");
+        let detector = FanDetector::fake(&hwmon);
+
+        detector.set_duty(Some(200)).unwrap();
+        detector.clone().set_fan_pwm(1, 180).unwrap();
+        assert_eq!(read(&hwmon, "pwm1_enable"), "1");
+
+        detector.restore_enable_mode().unwrap();
+        assert_eq!(read(&hwmon, "pwm1_enable"), "2");
+        assert_eq!(read(&hwmon, "pwm1"), "180");
+
+        // Fans that were already manual stay manual
+        hwmon.write("pwm1_enable", "1\n");
+        detector.set_duty(Some(120)).unwrap();
+        detector.restore_enable_mode().unwrap();
+        assert_eq!(read(&hwmon, "pwm1_enable"), "1");
+    }
+
+    #[test]
+    fn test_set_duty_verified_retries_lagging_write() {
+        let hwmon = TempDir::new("fans-verify-lag");
+        let detector = FanDetector::fake(&hwmon);
+        let fan = detector.fans[0].clone();
+
+        // The first read still reports the old value, as if the write was reset
+        let mut reads = 0;
+        let result = detector.write_pwm_verified(&fan, 200, |path| {
+            reads += 1;
+            if reads == 1 {
+                Ok(100)
+            } else {
+                FanDetector::read_pwm(path)
+            }
+        });
+
+        assert!(result.is_ok());
+        assert_eq!(reads, 2);
+        assert_eq!(read(&hwmon, "pwm1"), "200");
+        assert_eq!(read(&hwmon, "pwm1_enable"), "1");
+    }
+
+    #[test]
+    fn test_calibrate_finds_stall_point() {
+        let hwmon = TempDir::new("fans-calibrate");
+        let detector = FanDetector::fake(&hwmon);
+
+        // The fake fan stalls below PWM 62
+        let spin = || {
+            let pwm: u16 = read(&hwmon, "pwm1").parse().unwrap();
+            let rpm = if pwm >= 62 { pwm * 8 } else { 0 };
+            hwmon.write("fan1_input", &format!("{}\n", rpm));
+        };
+        assert_eq!(detector.calibrate_min_duty_with(1, spin).unwrap(), 65);
+        assert_eq!(read(&hwmon, "pwm1_enable"), "2");
+
+        // A fan that never spins is an error, and still goes back to automatic mode
+        let stalled = || {
+            hwmon.write("fan1_input", "0\n");
+        };
+        assert!(detector.calibrate_min_duty_with(1, stalled).is_err());
+        assert_eq!(read(&hwmon, "pwm1_enable"), "2");
+
+        // So is an unreadable RPM
+        fs::remove_file(hwmon.path().join("fan1_input")).unwrap();
+        assert!(detector.calibrate_min_duty_with(1, || {}).is_err());
+        assert_eq!(read(&hwmon, "pwm1_enable"), "2");
+
+        assert!(detector.calibrate_min_duty_with(2, || {}).is_err());
+    }
+
+    #[test]
+    fn test_set_duty_verified_fails_when_write_never_sticks() {
+        let hwmon = TempDir::new("fans-verify-reset");
+        let detector = FanDetector::fake(&hwmon);
+        let fan = detector.fans[0].clone();
+
+        let result = detector.write_pwm_verified(&fan, 200, |_| Ok(100));
+        assert!(result.is_err());
+
+        // Values within tolerance count as applied
+        assert!(detector.write_pwm_verified(&fan, 200, |_| Ok(199)).is_ok());
+        assert!(detector.set_duty_verified(150).is_ok());
+        assert_eq!(read(&hwmon, "pwm1"), "150");
+    }
+
+    #[test]
+    fn test_pwm_writability() {
+        let hwmon = TempDir::new("fans-writable");
+        let pwm = hwmon.write("pwm1", "100\n");
+        assert!(is_writable(&pwm));
+        assert!(!is_writable(&hwmon.path().join("pwm2")));
+
+        // Probing must not truncate or change the current value
+        assert_eq!(read(&hwmon, "pwm1"), "100");
+    }
+
+    #[test]
+    fn test_read_only_pwm_cannot_be_controlled() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        let hwmon = TempDir::new("fans-read-only");
+        hwmon.write("fan1_label", "CPU Fan\n");
+        hwmon.write("fan1_input", "1200\n");
+        let pwm = hwmon.write("pwm1", "100\n");
+        fs::set_permissions(&pwm, fs::Permissions::from_mode(0o444)).unwrap();
+
+        let mut detector = FanDetector::new();
+        assert!(!detector.can_control());
+        detector.hwmon_path = Some(hwmon.path().to_string_lossy().to_string());
+        detector.find_fan_sensors().unwrap();
+
+        // Root ignores file permissions, so the file stays writable there
+        let root = fs::metadata("/proc/self").is_ok_and(|m| m.uid() == 0);
+        assert_eq!(detector.can_control(), root);
+
+        fs::set_permissions(&pwm, fs::Permissions::from_mode(0o644)).unwrap();
+        let mut detector = FanDetector::new();
+        detector.hwmon_path = Some(hwmon.path().to_string_lossy().to_string());
+        detector.find_fan_sensors().unwrap();
+        assert!(detector.can_control());
+    }
+
+    #[test]
+    fn test_finds_every_fan_cpu_first() {
+        let hwmon = TempDir::new("fans-dual-socket");
+        for (n, label) in [(1, "CPU Fan"), (2, "Intake Fan"), (3, "CPU 2 Fan")] {
+            hwmon.write(&format!("fan{}_label", n), &format!("{}\n", label));
+            hwmon.write(&format!("fan{}_input", n), "1200\n");
+            hwmon.write(&format!("pwm{}", n), "100\n");
+        }
+
+        let mut detector = FanDetector::new();
+        detector.hwmon_path = Some(hwmon.path().to_string_lossy().to_string());
+        detector.find_fan_sensors().unwrap();
+
+        let numbers: Vec<u8> = detector.get_fans().iter().map(|f| f.fan_number).collect();
+        assert_eq!(numbers, vec![1, 3, 2]);
+        assert_eq!(detector.get_cpu_fan().unwrap().fan_number, 1);
+
+        detector.set_duty(Some(200)).unwrap();
+        for pwm in ["pwm1", "pwm2", "pwm3"] {
+            assert_eq!(read(&hwmon, pwm), "200");
+        }
+    }
+
+    #[test]
+    fn test_rpm_estimate_fits_line() {
+        let mut model = RpmModel::default();
+        for duty in [20u8, 35, 50, 65, 80, 95, 30] {
+            model.record(duty, 300 + 20 * u16::from(duty));
+        }
+        assert_eq!(model.fit(), None);
+
+        // A single duty can't give a slope
+        let mut flat = RpmModel::default();
+        for _ in 0..MIN_RPM_SAMPLES {
+            flat.record(50, 1200);
+        }
+        assert_eq!(flat.fit(), None);
+
+        model.record(100, 2300);
+        let (slope, intercept) = model.fit().unwrap();
+        assert!((slope - 20.0).abs() < 1e-9, "{}", slope);
+        assert!((intercept - 300.0).abs() < 1e-9, "{}", intercept);
+        assert_eq!(model.estimate(40), Some(1100));
+
+        let hwmon = TempDir::new("fan-rpm-model");
+        let detector = FanDetector::fake(&hwmon);
+        let fan = detector.clone().get_fans()[0].clone();
+        assert_eq!(fan.estimate_rpm(40), None);
+        for duty in (30..=100).step_by(10) {
+            fan.record_rpm_sample(duty, 300 + 20 * u16::from(duty));
+        }
+        // Clones of the detector share the samples
+        assert_eq!(detector.get_fans()[0].estimate_rpm(45), Some(1200));
+    }
+
+    #[test]
+    fn test_auto_mode_skips_missing_enable_file() {
+        let hwmon = TempDir::new("fans-no-enable");
+        let detector = FanDetector::fake(&hwmon);
+        fs::remove_file(hwmon.path().join("pwm1_enable")).unwrap();
+
+        assert!(detector.set_duty(None).is_ok());
+        assert!(!hwmon.path().join("pwm1_enable").exists());
+    }
+
+    #[test]
+    fn test_set_duty_reports_each_fan() {
+        let hwmon = TempDir::new("fans-partial");
+        let mut detector = FanDetector::fake(&hwmon);
+        for n in [2, 3] {
+            let mut fan = detector.fans[0].clone();
+            fan.fan_number = n;
+            fan.pwm_path = hwmon.path().join(format!("pwm{}", n));
+            fan.pwm_enable_path = hwmon.path().join(format!("pwm{}_enable", n));
+            detector.fans.push(fan);
+        }
+        hwmon.write("pwm3", "100\n");
+        hwmon.write("pwm3_enable", "2\n");
+        // A directory can't be written even as root, unlike a read-only file
+        fs::create_dir(hwmon.path().join("pwm2")).unwrap();
+
+        let results = detector.set_duty_each(Some(200));
+        let outcome: Vec<(u8, bool)> = results.iter().map(|(n, r)| (*n, r.is_ok())).collect();
+        assert_eq!(outcome, vec![(1, true), (2, false), (3, true)]);
+        assert_eq!(read(&hwmon, "pwm1"), "200");
+        assert_eq!(read(&hwmon, "pwm3"), "200");
+
+        let err = detector.set_duty_verified(150).unwrap_err().to_string();
+        assert!(err.contains("fan 2") && !err.contains("fan 1"), "{}", err);
+        assert_eq!(read(&hwmon, "pwm1"), "150");
+        assert_eq!(read(&hwmon, "pwm3"), "150");
+
+        assert!(detector.set_duty(None).is_ok());
+        assert_eq!(read(&hwmon, "pwm3_enable"), "2");
+    }
+
+    #[test]
+    fn test_control_mode() {
+        let hwmon = TempDir::new("fans-control-mode");
+        let mut detector = FanDetector::fake(&hwmon);
+        assert_eq!(detector.control_mode(), ControlMode::Auto);
+
+        let mut second = detector.fans[0].clone();
+        second.fan_number = 2;
+        second.pwm_enable_path = hwmon.path().join("pwm2_enable");
+        detector.fans.push(second);
+        hwmon.write("pwm2_enable", "1\n");
+        assert_eq!(detector.control_mode(), ControlMode::Mixed);
+        assert_eq!(detector.control_mode().to_string(), "mixed");
+
+        hwmon.write("pwm1_enable", "1\n");
+        assert_eq!(detector.control_mode(), ControlMode::Manual);
+
+        hwmon.write("pwm1_enable", "0\n");
+        hwmon.write("pwm2_enable", "0\n");
+        assert_eq!(detector.control_mode(), ControlMode::FullSpeed);
+        assert_eq!(detector.control_mode().to_string(), "full-speed");
+        hwmon.write("pwm2_enable", "2\n");
+        assert_eq!(detector.control_mode(), ControlMode::Mixed);
+
+        assert_eq!(FanDetector::new().control_mode(), ControlMode::Unknown);
+    }
+
+    #[test]
+    fn test_fake_thelio_io_tree() {
+        let root = TempDir::new("fans-thelio-io");
+        root.write("hwmon0/name", "k10temp\n");
+        root.write("hwmon2/name", "system76_thelio_io\n");
+        for (n, label, rpm) in [
+            (1, "CPU Fan", 1100),
+            (2, "Intake Fan", 800),
+            (3, "CPU Fan 2", 1150),
+        ] {
+            root.write(&format!("hwmon2/fan{}_label", n), &format!("{}\n", label));
+            root.write(&format!("hwmon2/fan{}_input", n), &format!("{}\n", rpm));
+            root.write(&format!("hwmon2/pwm{}", n), "80\n");
+            root.write(&format!("hwmon2/pwm{}_enable", n), "2\n");
+        }
+
+        // Picked up through $FAN_APP_HWMON_ROOT like the daemon and CLI would
+        let mut detector = {
+            let _root = EnvOverride::new(crate::HWMON_ROOT_ENV, root.path());
+            FanDetector::new()
+        };
+        detector.initialize().unwrap();
+        assert_eq!(detector.hwmon_name(), Some("system76_thelio_io"));
+        assert_eq!(
+            detector.read_all_fan_speeds().unwrap(),
+            [
+                (1, 1100, "CPU Fan".to_string()),
+                (3, 1150, "CPU Fan 2".to_string()),
+                (2, 800, "Intake Fan".to_string())
+            ]
+        );
+        assert!(detector.can_control());
+
+        detector.set_duty(Some(160)).unwrap();
+        for n in [1, 2, 3] {
+            assert_eq!(read(&root, &format!("hwmon2/pwm{}", n)), "160");
+            assert_eq!(read(&root, &format!("hwmon2/pwm{}_enable", n)), "1");
+        }
+        assert_eq!(detector.control_mode(), ControlMode::Manual);
+
+        detector.set_duty(None).unwrap();
+        assert_eq!(detector.control_mode(), ControlMode::Auto);
+    }
+
+    #[test]
+    fn test_percent_pwm_range() {
+        let root = TempDir::new("fans-percent-pwm");
+        root.write("hwmon1/name", "system76\n");
+        root.write("hwmon1/fan1_label", "CPU Fan\n");
+        root.write("hwmon1/fan1_input", "1500\n");
+        root.write("hwmon1/pwm1", "30\n");
+        root.write("hwmon1/pwm1_max", "100\n");
+        root.write("hwmon1/pwm1_enable", "2\n");
+
+        let mut detector = FanDetector::with_hwmon_root(root.path());
+        detector.initialize().unwrap();
+        assert_eq!(detector.get_fans()[0].pwm_max, 100);
+
+        let half = crate::duty::Duty::from_ten_thousandths(5000).as_pwm();
+        detector.set_duty_verified(half).unwrap();
+        assert_eq!(read(&root, "hwmon1/pwm1"), "50");
+        detector.set_fan_pwm_verified(1, u8::MAX).unwrap();
+        assert_eq!(read(&root, "hwmon1/pwm1"), "100");
+        // Readings are scaled back onto 0-255
+        let fan = &detector.get_fans()[0];
+        assert_eq!(fan.unscale_pwm(100), u8::MAX);
+        assert_eq!(fan.unscale_pwm(50), 128);
+        assert_eq!(fan.unscale_pwm(0), 0);
+
+        // The override wins over pwmN_max
+        let mut detector = {
+            let _root =
+                EnvOverride::new(crate::HWMON_ROOT_ENV, root.path()).with(PWM_MAX_ENV, "200");
+            FanDetector::new()
+        };
+        detector.initialize().unwrap();
+        assert_eq!(detector.get_fans()[0].pwm_max, 200);
+        detector.set_duty(Some(half)).unwrap();
+        assert_eq!(read(&root, "hwmon1/pwm1"), "100");
+
+        // Without pwmN_max the usual 0-255 range is assumed
+        fs::remove_file(root.path().join("hwmon1/pwm1_max")).unwrap();
+        let mut detector = FanDetector::with_hwmon_root(root.path());
+        detector.initialize().unwrap();
+        assert_eq!(detector.get_fans()[0].pwm_max, DEFAULT_PWM_MAX);
+        detector.set_duty(Some(half)).unwrap();
+        assert_eq!(read(&root, "hwmon1/pwm1"), half.to_string());
+    }
+
+    #[test]
+    fn test_inverted_pwm() {
+        let root = TempDir::new("fans-inverted-pwm");
+        root.write("hwmon1/name", "system76\n");
+        for n in [1, 2] {
+            root.write(
+                &format!("hwmon1/fan{}_label", n),
+                &format!("CPU Fan {}\n", n),
+            );
+            root.write(&format!("hwmon1/fan{}_input", n), "1500\n");
+            root.write(&format!("hwmon1/pwm{}", n), "30\n");
+            root.write(&format!("hwmon1/pwm{}_enable", n), "2\n");
+        }
+        root.write("hwmon1/pwm2_invert", "1\n");
+
+        let mut detector = FanDetector::with_hwmon_root(root.path());
+        detector.initialize().unwrap();
+        assert!(!detector.get_fans()[0].inverted);
+        assert!(detector.get_fans()[1].inverted);
+
+        // Full duty is PWM 0 on the inverted fan, and the verified write reads that back
+        detector.set_duty_verified(u8::MAX).unwrap();
+        assert_eq!(read(&root, "hwmon1/pwm1"), "255");
+        assert_eq!(read(&root, "hwmon1/pwm2"), "0");
+        detector.set_fan_pwm(2, 0).unwrap();
+        assert_eq!(read(&root, "hwmon1/pwm2"), "255");
+        detector.set_duty(Some(64)).unwrap();
+        assert_eq!(read(&root, "hwmon1/pwm2"), "191");
+
+        // Overrides apply to detected fans right away, on top of the driver hint
+        detector.set_inverted_fans(vec![1]);
+        assert!(detector.get_fans().iter().all(|fan| fan.inverted));
+        detector.set_duty(Some(u8::MAX)).unwrap();
+        assert_eq!(read(&root, "hwmon1/pwm1"), "0");
+
+        // With a 0-100 range, full speed is still 0
+        fs::remove_file(root.path().join("hwmon1/pwm2_invert")).unwrap();
+        let mut detector = {
+            let _env = EnvOverride::new(crate::HWMON_ROOT_ENV, root.path())
+                .with(PWM_INVERTED_ENV, "2")
+                .with(PWM_MAX_ENV, "100");
+            FanDetector::new()
+        };
+        detector.initialize().unwrap();
+        assert!(detector.get_fans()[1].inverted);
+        detector.set_fan_pwm(2, u8::MAX).unwrap();
+        assert_eq!(read(&root, "hwmon1/pwm2"), "0");
+        detector.set_fan_pwm(2, 0).unwrap();
+        assert_eq!(read(&root, "hwmon1/pwm2"), "100");
+        // Readings are flipped back, so full speed reads as 255
+        let fan = &detector.get_fans()[1];
+        assert_eq!(fan.unscale_pwm(0), u8::MAX);
+        assert_eq!(fan.unscale_pwm(100), 0);
+        assert_eq!(fan.unscale_pwm(fan.scale_pwm(u8::MAX)), u8::MAX);
+
+        assert_eq!(parse_fan_numbers(" 1, 3,").unwrap(), [1, 3]);
+        assert!(parse_fan_numbers("").unwrap().is_empty());
+        assert!(parse_fan_numbers("cpu").is_err());
+    }
+
+    #[test]
+    fn test_pwm_enable_override() {
+        let hwmon_default = PwmEnableValues { manual: 1, auto: 2 };
+        assert_eq!(
+            PwmEnableValues::parse(" 0, 3").unwrap(),
+            PwmEnableValues { manual: 0, auto: 3 }
+        );
+        for invalid in ["1", "1,1", "manual,auto", "1,256"] {
+            assert!(PwmEnableValues::parse(invalid).is_err(), "{}", invalid);
+        }
+
+        let root = TempDir::new("fans-pwm-enable");
+        root.write("hwmon1/name", "system76\n");
+        root.write("hwmon1/fan1_label", "CPU Fan\n");
+        root.write("hwmon1/fan1_input", "1500\n");
+        root.write("hwmon1/pwm1", "30\n");
+        root.write("hwmon1/pwm1_enable", "5\n");
+
+        let mut detector = FanDetector::with_hwmon_root(root.path());
+        detector.initialize().unwrap();
+        assert_eq!(detector.pwm_enable_values(), hwmon_default);
+        assert_eq!(detector.control_mode(), ControlMode::Auto);
+
+        let mut detector = {
+            let _env =
+                EnvOverride::new(crate::HWMON_ROOT_ENV, root.path()).with(PWM_ENABLE_ENV, "1,5");
+            FanDetector::new()
+        };
+        detector.initialize().unwrap();
+        assert_eq!(detector.pwm_enable_values().auto, 5);
+        detector.set_duty(Some(128)).unwrap();
+        assert_eq!(read(&root, "hwmon1/pwm1_enable"), "1");
+        assert_eq!(detector.control_mode(), ControlMode::Manual);
+        detector.set_duty(None).unwrap();
+        assert_eq!(read(&root, "hwmon1/pwm1_enable"), "5");
+        detector.set_fan_auto(1).unwrap();
+        assert_eq!(read(&root, "hwmon1/pwm1_enable"), "5");
+
+        // Dropping the override goes back to the hwmon convention
+        detector.set_pwm_enable_override(None);
+        assert_eq!(detector.pwm_enable_values(), hwmon_default);
+    }
+
+    #[test]
+    fn test_refresh_paths_follows_renumbered_hwmon() {
+        let root = TempDir::new("fans-renumbered");
+        root.write("hwmon0/name", "acpitz\n");
+        root.write("hwmon3/name", "system76_thelio_io\n");
+        root.write("hwmon3/fan1_label", "CPU Fan\n");
+        root.write("hwmon3/fan1_input", "1200\n");
+        root.write("hwmon3/pwm1", "100\n");
+
+        let mut detector = FanDetector::with_hwmon_root(root.path());
+        detector.initialize().unwrap();
+        assert_eq!(detector.hwmon_name(), Some("system76_thelio_io"));
+        assert!(!detector.refresh_paths().unwrap());
+        detector.get_fans()[0].record_rpm_sample(50, 1200);
+
+        // After a reboot the device comes back as hwmon5
+        fs::rename(root.path().join("hwmon3"), root.path().join("hwmon5")).unwrap();
+        assert!(detector.read_fan_speed(1).is_err());
+        assert_eq!(detector.read_fan_speed_refreshing(1).unwrap(), 1200);
+        assert!(detector.hwmon_path().unwrap().ends_with("hwmon5"));
+        assert_eq!(
+            detector.get_fans()[0].pwm_path,
+            root.path().join("hwmon5/pwm1")
+        );
+        assert_eq!(detector.get_fans()[0].rpm_model.lock().unwrap().len(), 1);
+
+        // A different chip taking over the old number is not mistaken for ours
+        root.write("hwmon5/name", "nct6775\n");
+        root.write("hwmon7/name", "system76_thelio_io\n");
+        root.write("hwmon7/fan1_label", "CPU Fan\n");
+        root.write("hwmon7/fan1_input", "900\n");
+        assert!(detector.refresh_paths().unwrap());
+        assert_eq!(detector.read_fan_speed(1).unwrap(), 900);
+    }
+
+    #[test]
+    fn test_failed_write_refreshes_paths() {
+        let root = TempDir::new("fans-renumbered-write");
+        root.write("hwmon3/name", "system76_thelio_io\n");
+        root.write("hwmon3/fan1_label", "CPU Fan\n");
+        root.write("hwmon3/fan1_input", "1200\n");
+        root.write("hwmon3/pwm1", "100\n");
+        root.write("hwmon3/pwm1_enable", "2\n");
+
+        let mut detector = FanDetector::with_hwmon_root(root.path());
+        detector.initialize().unwrap();
+        assert!(!detector.refresh_if_files_missing().unwrap());
+
+        // A module reload brings the device back as hwmon4
+        fs::rename(root.path().join("hwmon3"), root.path().join("hwmon4")).unwrap();
+        assert!(detector.set_duty(Some(200)).is_err());
+        assert!(detector.refresh_if_files_missing().unwrap());
+        assert!(!detector.refresh_if_files_missing().unwrap());
+
+        detector.set_duty(Some(200)).unwrap();
+        assert_eq!(
+            fs::read_to_string(root.path().join("hwmon4/pwm1")).unwrap(),
+            "200"
+        );
+    }
 }
@@ -1,15 +1,31 @@
+use crate::closed_loop::{ClosedLoopController, RpmTarget};
 use crate::cpu_temp::CpuTempDetector;
+use crate::diagnostics::{DiagnosticSnapshot, PwmState};
+use crate::duty::Duty;
 use crate::errors::Result;
-use crate::fan_detector::FanDetector;
+use crate::events::{EventObserver, EventObservers};
+use crate::fan::{FanControlMode, FanLink};
+use crate::fan_detector::{ControlMode, FanDetector, FanSensor, PwmEnableValues};
+use crate::idle::{CpuTimes, IdleDetector, IdleFloor};
+use crate::gpu_temp::GpuTempDetector;
+use crate::lm_sensors::LmSensorsSource;
+use crate::overheat::OverheatAlert;
 use crate::system76_power_client::System76PowerClient;
-use chrono::{DateTime, Local};
+use crate::temperature::TemperatureSource;
+use chrono::{DateTime, Local, TimeZone};
 use futures_util::stream::StreamExt;
 use log::{info, warn};
 use rand;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
 use std::time::Instant;
 use tokio::time::{sleep, Duration};
 use zbus::{Connection, MatchRule, MessageStream};
+use zvariant::Type;
 
 /// Fan data point for monitoring
 #[derive(Debug, Clone)]
@@ -22,6 +38,275 @@ pub struct FanDataPoint {
     pub fan_duty: u16,
     pub cpu_usage: f32,
     pub cpu_model: String,
+    /// Number of physical cores
+    pub cpu_core_count: u32,
+}
+
+/// Fan data point as sent in the daemon's `FanDataUpdated` signal
+/// The timestamp is in milliseconds since the Unix epoch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct FanDataDto {
+    pub timestamp_ms: i64,
+    pub temperature: f64,
+    pub cpu_fan_speeds: Vec<(u8, u16, String)>,
+    pub intake_fan_speeds: Vec<(u8, u16, String)>,
+    pub gpu_fan_speeds: Vec<(u8, u16, String)>,
+    pub fan_duty: u16,
+    pub cpu_usage: f64,
+    pub cpu_model: String,
+    pub cpu_core_count: u32,
+}
+
+impl From<&FanDataPoint> for FanDataDto {
+    fn from(data: &FanDataPoint) -> Self {
+        Self {
+            timestamp_ms: data.timestamp.timestamp_millis(),
+            temperature: data.temperature.into(),
+            cpu_fan_speeds: data.cpu_fan_speeds.clone(),
+            intake_fan_speeds: data.intake_fan_speeds.clone(),
+            gpu_fan_speeds: data.gpu_fan_speeds.clone(),
+            fan_duty: data.fan_duty,
+            cpu_usage: data.cpu_usage.into(),
+            cpu_model: data.cpu_model.clone(),
+            cpu_core_count: data.cpu_core_count,
+        }
+    }
+}
+
+impl From<FanDataDto> for FanDataPoint {
+    fn from(dto: FanDataDto) -> Self {
+        Self {
+            timestamp: Local
+                .timestamp_millis_opt(dto.timestamp_ms)
+                .single()
+                .unwrap_or_else(Local::now),
+            temperature: dto.temperature as f32,
+            cpu_fan_speeds: dto.cpu_fan_speeds,
+            intake_fan_speeds: dto.intake_fan_speeds,
+            gpu_fan_speeds: dto.gpu_fan_speeds,
+            fan_duty: dto.fan_duty,
+            cpu_usage: dto.cpu_usage as f32,
+            cpu_model: dto.cpu_model,
+            cpu_core_count: dto.cpu_core_count,
+        }
+    }
+}
+
+/// Fan data read on a background thread, so a UI never waits on DBus or sysfs
+/// The thread stops once the feed is dropped.
+pub struct FanDataFeed {
+    receiver: mpsc::Receiver<Result<FanDataPoint>>,
+}
+
+impl FanDataFeed {
+    /// Call `fetch` every `interval` on a new thread, sending each result to the feed
+    pub fn spawn<F>(interval: Duration, mut fetch: F) -> Self
+    where
+        F: FnMut() -> Result<FanDataPoint> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || loop {
+            if sender.send(fetch()).is_err() {
+                break;
+            }
+            std::thread::sleep(interval);
+        });
+        Self { receiver }
+    }
+
+    /// Newest result sent since the last call, without blocking; older ones are dropped
+    pub fn try_latest(&self) -> Option<Result<FanDataPoint>> {
+        self.receiver.try_iter().last()
+    }
+}
+
+/// Which temperature drives the fan curve
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TempSource {
+    /// CPU package temperature
+    #[default]
+    Cpu,
+    /// GPU temperature
+    Gpu,
+    /// The hotter of CPU and GPU
+    Max,
+}
+
+impl TempSource {
+    /// Pick the curve temperature from the CPU and (optional) GPU readings
+    /// Falls back to the CPU temperature when no GPU reading is available
+    pub fn select(self, cpu_temperature: f32, gpu_temperature: Option<f32>) -> f32 {
+        match (self, gpu_temperature) {
+            (TempSource::Gpu, Some(gpu)) => gpu,
+            (TempSource::Max, Some(gpu)) => cpu_temperature.max(gpu),
+            _ => cpu_temperature,
+        }
+    }
+}
+
+/// Fan key used for the single duty sent through system76-power, which drives all fans
+const DAEMON_FANS: u8 = 0;
+
+/// Per-fan spin-up state: a fan starting from standstill gets the curve's
+/// spin-up duty for one cycle before it settles to the curve's duty
+#[derive(Debug, Default)]
+struct SpinUpTracker {
+    /// Whether each fan was last commanded above the spin-up threshold
+    running: HashMap<u8, bool>,
+}
+
+impl SpinUpTracker {
+    /// Duty (ten-thousandths) to send to `fan` this cycle for a curve target of `target`
+    /// Fans with no history count as stopped
+    fn next_duty(&mut self, fan: u8, target: u16, curve: &crate::fan::FanCurve) -> u16 {
+        let running = target > curve.spin_up_threshold();
+        let was_running = self.running.insert(fan, running).unwrap_or(false);
+        if running && !was_running && curve.spin_up_duty() > target {
+            curve.spin_up_duty()
+        } else {
+            target
+        }
+    }
+}
+
+/// Temporary duty override, see [`FanMonitor::set_boost`]
+#[derive(Debug, Clone, Copy)]
+struct Boost {
+    /// Duty in ten-thousandths
+    duty: u16,
+    until: Instant,
+}
+
+impl Boost {
+    fn new(duty: u16, duration: Duration) -> Self {
+        Self {
+            duty: Duty::from_ten_thousandths(duty).as_ten_thousandths(),
+            until: Instant::now() + duration,
+        }
+    }
+}
+
+/// Duty being held while the target is lower, for the ramp-down delay
+#[derive(Debug, Clone, Copy)]
+struct RampDownHold {
+    /// Duty in ten-thousandths
+    duty: u16,
+    /// When the target first dropped below `duty`
+    lowered_at: Option<Instant>,
+}
+
+/// What the last update computed and wrote, so polls at the same temperature skip both
+/// Cleared by [`FanMonitor::force_update`]
+#[derive(Debug, Clone, Default)]
+struct DutyCache {
+    /// Curve input rounded down to whole °C like the curve lookup, and the duty it gave
+    curve: Option<(i32, u16)>,
+    /// Duties (ten-thousandths) last written, per fan
+    written: Option<Vec<(u8, u16)>>,
+}
+
+/// Last good sample and the reads that have failed since, see [`FanMonitor::control_from_sample`]
+#[derive(Debug, Clone, Default)]
+struct ReadFailures {
+    last_good: Option<(FanDataPoint, Instant)>,
+    consecutive: u32,
+}
+
+/// Convert a fan duty percentage (0-100) to the PWM scale (0-255)
+/// A PWM value of 0 tells the daemon to return the fans to automatic control
+pub fn percent_to_pwm(percent: u8) -> Result<u8> {
+    if percent > 100 {
+        return Err(crate::errors::FanCurveError::Config(format!(
+            "Fan duty must be between 0 and 100%, got {}%",
+            percent
+        )));
+    }
+    Ok(Duty::from_percent(percent as f32).as_pwm())
+}
+
+/// Shortest allowed time between monitoring samples
+pub const MIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Failed reads in a row after which the fans run at the critical duty
+pub const MAX_READ_FAILURES: u32 = 5;
+
+/// How long the last good sample keeps driving the curve while reads fail
+pub const MAX_SAMPLE_AGE: Duration = Duration::from_secs(10);
+
+/// Number of samples kept in [`FanMonitor::history`]
+pub const HISTORY_CAPACITY: usize = 300;
+
+/// Directory holding the kernel's thermal zones
+const THERMAL_ROOT: &str = "/sys/class/thermal";
+
+/// Directory listing the kernel's power supplies
+const POWER_SUPPLY_ROOT: &str = "/sys/class/power_supply";
+
+/// Check if the machine runs on battery: some power supply has an `online` file and none reads 1
+/// Desktops without power supply entries count as on AC
+fn on_battery(root: &Path) -> bool {
+    let Ok(entries) = fs::read_dir(root) else {
+        return false;
+    };
+    let online: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| fs::read_to_string(entry.path().join("online")).ok())
+        .map(|value| value.trim().to_string())
+        .collect();
+    !online.is_empty() && online.iter().all(|value| value != "1")
+}
+
+/// Thermal zone types that report the CPU temperature, most specific first
+const CPU_THERMAL_ZONE_TYPES: [&str; 3] = ["x86_pkg_temp", "cpu-thermal", "acpitz"];
+
+/// Find the `temp` file of the thermal zone to read the CPU temperature from
+///
+/// An explicit `zone` always wins. Otherwise the zones under `root` are matched
+/// against [`CPU_THERMAL_ZONE_TYPES`]; `None` means no zone has a known CPU type.
+fn find_cpu_thermal_zone(root: &Path, zone: Option<u32>) -> Option<PathBuf> {
+    if let Some(zone) = zone {
+        return Some(root.join(format!("thermal_zone{}", zone)).join("temp"));
+    }
+
+    let mut zones: Vec<(u32, String)> = fs::read_dir(root)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let number = name.strip_prefix("thermal_zone")?.parse().ok()?;
+            let zone_type = fs::read_to_string(entry.path().join("type")).ok()?;
+            Some((number, zone_type.trim().to_string()))
+        })
+        .collect();
+    zones.sort();
+
+    CPU_THERMAL_ZONE_TYPES.iter().find_map(|wanted| {
+        zones
+            .iter()
+            .find(|(_, zone_type)| zone_type == wanted)
+            .map(|(number, _)| root.join(format!("thermal_zone{}", number)).join("temp"))
+    })
+}
+
+/// Where local temperatures and fan speeds are read from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReadingSource {
+    /// hwmon and thermal zone files
+    #[default]
+    Sysfs,
+    /// lm-sensors' `sensors -j`, falling back to sysfs when it isn't installed
+    LmSensors,
+}
+
+/// Command line overrides for how the CPU temperature is read
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SensorOptions {
+    /// Thermal zone forced with `--thermal-zone`
+    pub thermal_zone: Option<u32>,
+    /// Degrees subtracted from AMD `Tctl` readings, set with `--tctl-offset`
+    pub tctl_offset: f32,
+    /// Where readings come from, set with `--source`
+    pub source: ReadingSource,
 }
 
 /// Fan monitoring system
@@ -29,11 +314,74 @@ pub struct FanDataPoint {
 pub struct FanMonitor {
     is_monitoring: bool,
     last_log_time: Instant,
+    /// Time between samples in the monitoring loop
+    poll_interval: Duration,
     current_fan_curve: Option<crate::fan::FanCurve>,
     cpu_temp_detector: CpuTempDetector,
-    fan_detector: FanDetector,
+    gpu_temp_detector: GpuTempDetector,
+    temperature_source: TempSource,
+    /// GPU reading taken with the latest sample when the source uses it, shared with clones
+    gpu_temp: Arc<Mutex<Option<f32>>>,
+    /// Temperature and duty of the latest curve calculation, see [`FanMonitor::last_target`]
+    last_target: Arc<Mutex<Option<(f32, u16)>>>,
+    /// Thermal zone forced with `--thermal-zone`, bypassing sensor detection
+    thermal_zone: Option<u32>,
+    /// Injected temperature reader, used instead of the daemon when set
+    temperature_reader: Option<Arc<dyn TemperatureSource>>,
+    /// Read local temperatures and fan speeds through `sensors -j` instead of sysfs
+    lm_sensors: Option<LmSensorsSource>,
+    /// Most recent samples, oldest first, capped at [`HISTORY_CAPACITY`]
+    history: VecDeque<FanDataPoint>,
+    duty_cache: Arc<Mutex<DutyCache>>,
+    /// Keeps the fans under control through transient read errors
+    read_failures: ReadFailures,
+    /// Spin-up state per fan, shared with clones since they drive the same fans
+    spin_up: Arc<Mutex<SpinUpTracker>>,
+    /// Active boost, shared with clones and the DBus listener
+    boost: Arc<Mutex<Option<Boost>>>,
+    /// CPU temperature (°C) above which the duty never drops below `critical_duty`
+    critical_temp: f32,
+    /// Minimum duty (ten-thousandths) while above `critical_temp`
+    critical_duty: u16,
+    /// Whether the critical override applied last cycle, so entering and leaving it is logged once
+    critical_active: Arc<AtomicBool>,
+    /// Weight of each new reading in the temperature moving average; None reads raw values
+    temp_filter_alpha: Option<f32>,
+    /// Moving average kept across samples, shared with clones
+    smoothed_temp: Arc<Mutex<Option<f32>>>,
+    /// How long a higher duty is kept after the target drops
+    ramp_down_delay: Duration,
+    /// Duty last applied and when the target fell below it, shared with clones
+    ramp_down: Arc<Mutex<Option<RampDownHold>>>,
+    /// Highest duty (ten-thousandths) the curve may ask for while on battery
+    battery_max_duty: Option<u16>,
+    /// Where AC adapters and batteries report whether they are `online`
+    power_supply_root: PathBuf,
+    /// Control mode per fan label; fans not listed follow the curve
+    fan_control: HashMap<String, FanControlMode>,
+    /// Target RPM curve per fan label, for fans in [`FanControlMode::TargetRpm`]
+    rpm_targets: HashMap<String, RpmTarget>,
+    /// Fans driven from another fan's duty, see [`FanMonitor::set_fan_links`]
+    fan_links: Vec<FanLink>,
+    /// Controller and time of its last update per fan number, shared with clones
+    closed_loop: Arc<Mutex<HashMap<u8, (ClosedLoopController, Instant)>>>,
+    /// CPU usage tracking when an idle floor is set, shared with clones
+    idle: Option<Arc<Mutex<IdleDetector>>>,
+    /// CPU times at the previous sample, for the usage between samples; shared with clones
+    cpu_times: Arc<Mutex<Option<CpuTimes>>>,
+    /// Desktop notification state when overheat warnings are on, shared with clones
+    overheat: Option<Arc<Mutex<OverheatAlert>>>,
+    /// Told about duty, temperature and stall changes in recorded samples, shared with clones
+    observers: Arc<Mutex<EventObservers>>,
+    /// Scanned on first use and reused afterwards, even if the scan failed
+    fan_detector: OnceLock<FanDetector>,
     system76_power_client: Option<System76PowerClient>,
     dbus_connection: Option<Connection>,
+    dry_run: bool,
+    /// `pwmN_enable` values used instead of the hwmon convention
+    pwm_enable_override: Option<PwmEnableValues>,
+    /// Fans with inverted PWM, on top of those the driver marks
+    inverted_fans: Vec<u8>,
 }
 
 impl FanMonitor {
@@ -42,11 +390,44 @@ impl FanMonitor {
         Self {
             is_monitoring: false,
             last_log_time: Instant::now(),
+            poll_interval: Duration::from_millis(crate::fan::DEFAULT_POLL_INTERVAL_MS),
             current_fan_curve: None,
             cpu_temp_detector: CpuTempDetector::new(),
-            fan_detector: FanDetector::new(),
+            gpu_temp_detector: GpuTempDetector::new(),
+            temperature_source: TempSource::default(),
+            gpu_temp: Arc::new(Mutex::new(None)),
+            last_target: Arc::new(Mutex::new(None)),
+            thermal_zone: None,
+            temperature_reader: None,
+            lm_sensors: None,
+            history: VecDeque::with_capacity(HISTORY_CAPACITY),
+            duty_cache: Arc::default(),
+            read_failures: ReadFailures::default(),
+            spin_up: Arc::new(Mutex::new(SpinUpTracker::default())),
+            boost: Arc::new(Mutex::new(None)),
+            critical_temp: crate::fan::DEFAULT_CRITICAL_TEMP,
+            critical_duty: crate::fan::DEFAULT_CRITICAL_DUTY,
+            critical_active: Arc::new(AtomicBool::new(false)),
+            temp_filter_alpha: None,
+            smoothed_temp: Arc::new(Mutex::new(None)),
+            ramp_down_delay: Duration::ZERO,
+            ramp_down: Arc::new(Mutex::new(None)),
+            battery_max_duty: None,
+            power_supply_root: PathBuf::from(POWER_SUPPLY_ROOT),
+            fan_control: HashMap::new(),
+            rpm_targets: HashMap::new(),
+            fan_links: Vec::new(),
+            closed_loop: Arc::new(Mutex::new(HashMap::new())),
+            idle: None,
+            cpu_times: Arc::default(),
+            overheat: None,
+            observers: Arc::default(),
+            fan_detector: OnceLock::new(),
             system76_power_client: None,
             dbus_connection: None,
+            dry_run: false,
+            pwm_enable_override: None,
+            inverted_fans: Vec::new(),
         }
     }
 
@@ -57,14 +438,17 @@ impl FanMonitor {
             warn!("Failed to initialize CPU temperature detection: {}", e);
         }
 
-        // Initialize fan detection
-        if let Err(e) = self.fan_detector.initialize() {
-            warn!("Failed to initialize fan detection: {}", e);
+        // GPU temperature is only needed when it feeds the curve
+        if self.temperature_source != TempSource::Cpu {
+            if let Err(e) = self.gpu_temp_detector.initialize() {
+                warn!("Failed to initialize GPU temperature detection: {}", e);
+            }
         }
 
+        // Initialize fan detection
         info!(
             "Fan monitor initialized with {} fans detected",
-            self.fan_detector.fan_count()
+            self.fan_detector().fan_count()
         );
         Ok(())
     }
@@ -104,13 +488,44 @@ impl FanMonitor {
     }
 
     /// Set the current fan curve for duty calculation
-    pub fn set_fan_curve(&mut self, curve: crate::fan::FanCurve) {
+    pub fn set_fan_curve(&mut self, mut curve: crate::fan::FanCurve) {
+        curve.precompute_table();
         self.current_fan_curve = Some(curve);
+        self.force_update();
     }
 
     /// Update the current fan curve for duty calculation
-    pub fn update_fan_curve(&mut self, curve: crate::fan::FanCurve) {
+    pub fn update_fan_curve(&mut self, mut curve: crate::fan::FanCurve) {
+        curve.precompute_table();
         self.current_fan_curve = Some(curve);
+        self.force_update();
+    }
+
+    /// Curve the duty is calculated from, if one is set
+    pub fn fan_curve(&self) -> Option<&crate::fan::FanCurve> {
+        self.current_fan_curve.as_ref()
+    }
+
+    /// Recompute the curve duty and write the fans on the next update, even if the rounded
+    /// temperature and the duty are unchanged
+    pub fn force_update(&self) {
+        *self.duty_cache.lock().unwrap() = DutyCache::default();
+    }
+
+    /// Remember `duties` as written, returning false if they are what was written last
+    fn duties_changed(&self, duties: &[(u8, u16)]) -> bool {
+        let mut cache = self.duty_cache.lock().unwrap();
+        if cache.written.as_deref() == Some(duties) {
+            log::debug!("Fan duties unchanged, skipping the write");
+            return false;
+        }
+        cache.written = Some(duties.to_vec());
+        true
+    }
+
+    /// Forget what was written after a failed write, so the next update tries again
+    fn forget_written_duties(&self) {
+        self.duty_cache.lock().unwrap().written = None;
     }
 
     /// Start listening for fan curve change signals from the daemon
@@ -129,6 +544,31 @@ impl FanMonitor {
 
             info!("Started listening for fan curve change signals");
 
+            let boost_rule = MatchRule::builder()
+                .msg_type(zbus::MessageType::Signal)
+                .sender("com.system76.FanCurveDaemon")?
+                .path("/com/system76/FanCurveDaemon")?
+                .member("BoostStarted")?
+                .build();
+            let mut boosts = MessageStream::for_match_rule(boost_rule, connection, None).await?;
+            let boost = self.boost.clone();
+            tokio::spawn(async move {
+                while let Some(Ok(msg)) = boosts.next().await {
+                    match msg.body::<(u16, u32)>() {
+                        Ok((duty, seconds)) => {
+                            info!(
+                                "Boost requested: {} for {}s",
+                                Duty::from_ten_thousandths(duty),
+                                seconds
+                            );
+                            let duration = Duration::from_secs(seconds.into());
+                            *boost.lock().unwrap() = Some(Boost::new(duty, duration));
+                        }
+                        Err(e) => warn!("Ignoring malformed boost signal: {}", e),
+                    }
+                }
+            });
+
             // Spawn a task to handle incoming signals
             tokio::spawn(async move {
                 while let Some(msg) = stream.next().await {
@@ -150,6 +590,44 @@ impl FanMonitor {
         }
     }
 
+    /// Run the fans at `duty` (ten-thousandths) for `duration` instead of the curve
+    /// Curve control resumes once the boost expires; a new boost replaces the current one
+    pub fn set_boost(&self, duty: u16, duration: Duration) {
+        let boost = Boost::new(duty, duration);
+        info!(
+            "Boosting fans to {} for {:?}",
+            Duty::from_ten_thousandths(boost.duty),
+            duration
+        );
+        *self.boost.lock().unwrap() = Some(boost);
+    }
+
+    /// End a boost early
+    pub fn clear_boost(&self) {
+        *self.boost.lock().unwrap() = None;
+    }
+
+    /// Time left on the current boost, if one is active
+    pub fn boost_remaining(&self) -> Option<Duration> {
+        self.active_boost()?;
+        let until = self.boost.lock().unwrap().as_ref()?.until;
+        Some(until.saturating_duration_since(Instant::now()))
+    }
+
+    /// Duty (ten-thousandths) of the active boost, dropping it once expired
+    fn active_boost(&self) -> Option<u16> {
+        let mut boost = self.boost.lock().unwrap();
+        match *boost {
+            Some(active) if Instant::now() < active.until => Some(active.duty),
+            Some(_) => {
+                *boost = None;
+                info!("Boost expired, resuming fan curve");
+                None
+            }
+            None => None,
+        }
+    }
+
     /// Start monitoring
     pub fn start_monitoring(&mut self) -> Result<()> {
         self.is_monitoring = true;
@@ -174,9 +652,526 @@ impl FanMonitor {
         &self.cpu_temp_detector
     }
 
-    /// Get the fan detector
+    /// Get the GPU temperature detector
+    pub fn gpu_temp_detector(&self) -> &GpuTempDetector {
+        &self.gpu_temp_detector
+    }
+
+    /// Get the temperature source driving the fan curve
+    pub fn temperature_source(&self) -> TempSource {
+        self.temperature_source
+    }
+
+    /// Set the temperature source driving the fan curve
+    pub fn set_temperature_source(&mut self, source: TempSource) {
+        self.temperature_source = source;
+        *self.gpu_temp.lock().unwrap() = None;
+        if source != TempSource::Cpu && !self.gpu_temp_detector.is_initialized() {
+            if let Err(e) = self.gpu_temp_detector.initialize() {
+                warn!("Failed to initialize GPU temperature detection: {}", e);
+            }
+        }
+    }
+
+    /// Notify `observer` of changes in every sample recorded from now on
+    pub fn add_observer(&mut self, observer: Box<dyn EventObserver>) {
+        self.observers.lock().unwrap().add(observer);
+    }
+
+    /// Tell the observers what changed in `data`; [`FanMonitor::record_sample`] does this
+    pub fn notify_observers(&self, data: &FanDataPoint) {
+        let mut observers = self.observers.lock().unwrap();
+        if observers.is_empty() {
+            return;
+        }
+        let stall_threshold = self
+            .current_fan_curve
+            .as_ref()
+            .map_or(0, |curve| curve.spin_up_threshold());
+        observers.update(
+            data.fan_duty,
+            data.temperature,
+            data.cpu_fan_speeds.iter().map(|&(fan, rpm, _)| (fan, rpm)),
+            stall_threshold,
+        );
+    }
+
+    /// Add a sample to the history, dropping the oldest once it is full
+    pub fn record_sample(&mut self, data: FanDataPoint) {
+        self.notify_observers(&data);
+
+        // Teach the detected fans how fast they spin at the commanded duty
+        if let Some(detector) = self.fan_detector.get() {
+            let duty = Duty::from_ten_thousandths(data.fan_duty).as_whole_percent();
+            for &(fan_number, rpm, _) in &data.cpu_fan_speeds {
+                if let Some(fan) = detector.get_fan(fan_number) {
+                    fan.record_rpm_sample(duty, rpm);
+                }
+            }
+        }
+
+        if self.history.len() == HISTORY_CAPACITY {
+            self.history.pop_front();
+        }
+        self.history.push_back(data);
+    }
+
+    /// RPM the first detected fan is expected to reach at `duty` (ten-thousandths)
+    /// None until monitoring has recorded enough samples, and never triggers fan detection
+    pub fn estimate_rpm(&self, duty: u16) -> Option<u16> {
+        let fan = self.fan_detector.get()?.get_fans().first()?;
+        fan.estimate_rpm(Duty::from_ten_thousandths(duty).as_whole_percent())
+    }
+
+    /// Recent samples for graphing, oldest first
+    pub fn history(&self) -> &VecDeque<FanDataPoint> {
+        &self.history
+    }
+
+    /// Forget all recorded samples
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    /// Capture the current readings, curve, detected hardware and PWM files for a bug report
+    /// Fresh fan data is read from sysfs, falling back to the latest sample; anything that
+    /// can't be read is listed in the snapshot's `errors`. The config and daemon build are
+    /// left for the caller to fill in
+    pub fn capture_diagnostics(&self) -> DiagnosticSnapshot {
+        let mut errors = Vec::new();
+
+        // A plain read, so taking a snapshot can't move the filter or trip the overheat alert
+        let fan_data = match self.read_temperature().and_then(|t| self.read_fan_data(t)) {
+            Ok(data) => Some(FanDataDto::from(&data)),
+            Err(e) => {
+                errors.push(format!("Fan data: {}", e));
+                self.history.back().map(FanDataDto::from)
+            }
+        };
+
+        let mut cpu = self.cpu_temp_detector.clone();
+        if !cpu.is_initialized() {
+            if let Err(e) = cpu.initialize() {
+                errors.push(format!("CPU temperature sensor: {}", e));
+            }
+        }
+        let temperatures = cpu.read_all_temperatures().unwrap_or_else(|e| {
+            errors.push(format!("Temperatures: {}", e));
+            Vec::new()
+        });
+
+        let detector = self.fan_detector();
+        if !detector.is_initialized() {
+            errors.push("Fan detection: no fans found".to_string());
+        }
+
+        DiagnosticSnapshot {
+            captured_at: Local::now().to_rfc3339(),
+            app: crate::BuildInfo::current(),
+            daemon: None,
+            fan_data,
+            active_curve: self.current_fan_curve.clone(),
+            hardware: crate::hardware::HardwareInfo::from_detectors(&cpu, detector),
+            temperatures,
+            pwm_states: PwmState::read_all(detector),
+            control_mode: detector.control_mode().to_string(),
+            config: None,
+            errors,
+        }
+    }
+
+    /// Read the CPU temperature from this thermal zone instead of the detected sensor
+    pub fn set_thermal_zone(&mut self, zone: Option<u32>) {
+        self.thermal_zone = zone;
+    }
+
+    /// Apply the command line sensor overrides
+    pub fn set_sensor_options(&mut self, options: SensorOptions) {
+        self.thermal_zone = options.thermal_zone;
+        self.cpu_temp_detector.set_tctl_offset(options.tctl_offset);
+        self.lm_sensors = match options.source {
+            ReadingSource::Sysfs => None,
+            ReadingSource::LmSensors => {
+                let source = LmSensorsSource::detect()
+                    .map(|source| source.with_tctl_offset(options.tctl_offset));
+                if source.is_none() {
+                    warn!("lm-sensors `sensors` not found, reading sysfs instead");
+                }
+                source
+            }
+        };
+    }
+
+    /// Get the thermal zone forced with [`FanMonitor::set_thermal_zone`]
+    pub fn thermal_zone(&self) -> Option<u32> {
+        self.thermal_zone
+    }
+
+    /// Read temperatures from `reader` instead of the system76-power daemon
+    pub fn set_temperature_reader(&mut self, reader: Arc<dyn TemperatureSource>) {
+        self.temperature_reader = Some(reader);
+    }
+
+    /// Read the CPU temperature from local sensors
+    ///
+    /// Uses the forced thermal zone if one is set, then `sensors -j` when selected,
+    /// then the detected hwmon sensor, then the thermal zones.
+    pub fn read_temperature(&self) -> Result<f32> {
+        if self.thermal_zone.is_some() {
+            return self.read_cpu_temperature_direct();
+        }
+
+        if let Some(ref sensors) = self.lm_sensors {
+            match sensors.read_cpu_temperature() {
+                Ok(temperature) => return Ok(temperature),
+                Err(e) => warn!("lm-sensors reading failed, using sysfs: {}", e),
+            }
+        }
+
+        let sensor_reading = if self.cpu_temp_detector.is_initialized() {
+            self.cpu_temp_detector.read_temperature()
+        } else {
+            // Initialize CPU temp detector if not already initialized
+            let mut temp_detector = self.cpu_temp_detector.clone();
+            temp_detector
+                .initialize()
+                .and_then(|_| temp_detector.read_temperature())
+        };
+
+        sensor_reading.or_else(|e| {
+            warn!("CPU temperature sensor unavailable, trying thermal zones: {}", e);
+            self.read_cpu_temperature_direct()
+        })
+    }
+
+    /// Resolve the temperature fed to the fan curve from the configured source
+    /// The GPU reading is the one taken with the latest sample, see [`FanMonitor::sample_gpu_temperature`]
+    fn source_temperature(&self, cpu_temperature: f32) -> f32 {
+        if self.temperature_source == TempSource::Cpu {
+            return cpu_temperature;
+        }
+        let gpu_temperature = *self.gpu_temp.lock().unwrap();
+        self.temperature_source.select(cpu_temperature, gpu_temperature)
+    }
+
+    /// Read the GPU temperature once per sample when the source uses it
+    /// `nvidia-smi` is too slow to run on every duty calculation
+    fn sample_gpu_temperature(&self) {
+        if self.temperature_source == TempSource::Cpu {
+            return;
+        }
+        let gpu_temperature = match self.gpu_temp_detector.read_temperature() {
+            Ok(temp) => Some(temp),
+            Err(e) => {
+                warn!("Failed to read GPU temperature, using CPU temperature: {}", e);
+                None
+            }
+        };
+        *self.gpu_temp.lock().unwrap() = gpu_temperature;
+    }
+
+    /// Run the full apply logic but only log fan changes instead of making them
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+        if let Some(detector) = self.fan_detector.get_mut() {
+            detector.set_dry_run(dry_run);
+        }
+    }
+
+    /// Write `values` to `pwmN_enable` for manual and automatic control instead of the
+    /// hwmon convention of 1 and 2; None uses that
+    pub fn set_pwm_enable_override(&mut self, values: Option<PwmEnableValues>) {
+        self.pwm_enable_override = values;
+        if let Some(detector) = self.fan_detector.get_mut() {
+            detector.set_pwm_enable_override(values);
+        }
+    }
+
+    /// Treat these fan numbers as inverted, where higher PWM means slower
+    pub fn set_inverted_fans(&mut self, fans: Vec<u8>) {
+        self.inverted_fans = fans.clone();
+        if let Some(detector) = self.fan_detector.get_mut() {
+            detector.set_inverted_fans(fans);
+        }
+        self.force_update();
+    }
+
+    /// Check if fan changes are only logged
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Run the fans at least at `duty` (ten-thousandths) whenever the CPU exceeds `temp` °C
+    /// This protects against curves that stay too quiet when the CPU overheats
+    pub fn set_critical_override(&mut self, temp: f32, duty: u16) {
+        self.critical_temp = temp;
+        self.critical_duty = duty.min(Duty::MAX_TEN_THOUSANDTHS);
+    }
+
+    /// Critical temperature (°C) and the minimum duty forced above it
+    pub fn critical_override(&self) -> (f32, u16) {
+        (self.critical_temp, self.critical_duty)
+    }
+
+    /// Cap the curve's duty at `duty` (ten-thousandths) while running on battery; None removes the cap
+    /// Boosts and the critical override are not capped
+    pub fn set_battery_max_duty(&mut self, duty: Option<u16>) {
+        self.battery_max_duty = duty.map(|duty| duty.min(Duty::MAX_TEN_THOUSANDTHS));
+    }
+
+    /// Get the cap set with [`FanMonitor::set_battery_max_duty`]
+    pub fn battery_max_duty(&self) -> Option<u16> {
+        self.battery_max_duty
+    }
+
+    /// Apply the config's overrides, smoothing, ramp-down delay and per-fan settings, as the
+    /// daemon does
+    pub fn configure(&mut self, config: &crate::fan::FanCurveConfig) {
+        self.set_critical_override(config.critical_temp, config.critical_duty);
+        self.set_battery_max_duty(config.battery_max_duty);
+        self.set_idle_floor(config.idle_floor());
+        // Only on a change, so reconfiguring doesn't drop the moving average or end a hold
+        if self.temp_filter_alpha != config.temp_filter_alpha {
+            self.set_temp_filter_alpha(config.temp_filter_alpha);
+        }
+        if self.ramp_down_delay != config.ramp_down_delay() {
+            self.set_ramp_down_delay(config.ramp_down_delay());
+        }
+        self.set_pwm_enable_override(config.pwm_enable);
+        self.set_inverted_fans(config.inverted_fans.clone());
+        self.set_fan_control(config.fan_control.clone());
+        self.set_rpm_targets(config.rpm_targets.clone());
+        self.set_fan_links(config.fan_links.clone());
+    }
+
+    /// Set how each fan is driven, keyed by fan label
+    /// Any mode other than curve bypasses system76-power, which sets one duty for every fan
+    pub fn set_fan_control(&mut self, fan_control: HashMap<String, FanControlMode>) {
+        self.fan_control = fan_control;
+        self.force_update();
+    }
+
+    /// Target RPM curves by fan label, used by fans in [`FanControlMode::TargetRpm`]
+    /// Controllers of fans whose target or gains changed start over; the rest keep their state
+    pub fn set_rpm_targets(&mut self, rpm_targets: HashMap<String, RpmTarget>) {
+        let changed = |label: &String| self.rpm_targets.get(label) != rpm_targets.get(label);
+        // Controllers only exist once the fans have been scanned
+        if let Some(detector) = self.fan_detector.get() {
+            let mut controllers = self.closed_loop.lock().unwrap();
+            for fan in detector.get_fans().iter().filter(|fan| changed(&fan.fan_label)) {
+                controllers.remove(&fan.fan_number);
+            }
+        }
+        self.rpm_targets = rpm_targets;
+    }
+
+    /// Run each link's follower at its leader's duty plus the link's offset
+    /// A follower whose leader isn't detected or is left to the firmware keeps its own control
+    /// mode. Like [`FanMonitor::set_fan_control`], links bypass system76-power
+    pub fn set_fan_links(&mut self, links: Vec<FanLink>) {
+        self.fan_links = links;
+        self.force_update();
+    }
+
+    /// Whether any fan has its own control mode or follows another fan
+    /// These need direct PWM writes, system76-power only takes one duty for every fan
+    fn has_per_fan_control(&self) -> bool {
+        !self.fan_links.is_empty()
+            || self.fan_control.values().any(|&mode| mode != FanControlMode::Curve)
+    }
+
+    /// Control mode for the fan labelled `label`
+    pub fn fan_control_mode(&self, label: &str) -> FanControlMode {
+        self.fan_control.get(label).copied().unwrap_or_default()
+    }
+
+    /// Show a desktop notification when the CPU goes above `warn_temp` °C and when it recovers
+    /// None turns the notifications off; see [`OverheatAlert`] for the rate limit
+    pub fn set_overheat_warning(&mut self, warn_temp: Option<f32>) {
+        self.overheat = warn_temp
+            .filter(|temp| temp.is_finite())
+            .map(|temp| Arc::new(Mutex::new(OverheatAlert::new(temp))));
+    }
+
+    /// Notify about the CPU crossing the warning temperature, sending in the background
+    fn check_overheat(&self, temperature: f32) {
+        let Some(ref alert) = self.overheat else {
+            return;
+        };
+        let mut alert = alert.lock().unwrap();
+        let Some(event) = alert.update(temperature, Instant::now()) else {
+            return;
+        };
+        let body = event.body(alert.warn_temp());
+        warn!("{}: {}", event.summary(), body);
+        let runtime = match crate::runtime::shared_handle() {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                warn!("Failed to show desktop notification: {}", e);
+                return;
+            }
+        };
+        runtime.spawn(async move {
+            if let Err(e) = crate::overheat::send_desktop_notification(event.summary(), &body).await {
+                warn!("Failed to show desktop notification: {}", e);
+            }
+        });
+    }
+
+    /// Drop the fans to `floor.duty` once the CPU has stayed idle for `floor.after`, while the
+    /// curve is at its lowest duty; None turns it off
+    pub fn set_idle_floor(&mut self, floor: Option<IdleFloor>) {
+        self.idle = floor.map(|floor| Arc::new(Mutex::new(IdleDetector::new(floor))));
+    }
+
+    /// Lower a duty at the curve's lowest to the idle floor if the CPU has been idle long enough
+    /// Idle time is measured from the CPU times read with each sample, see [`FanMonitor::read_cpu_usage`]
+    fn apply_idle_floor(&self, duty: u16, now: Instant) -> u16 {
+        let Some(ref idle) = self.idle else {
+            return duty;
+        };
+        let idle = idle.lock().unwrap();
+        if !idle.is_idle(now) {
+            return duty;
+        }
+        let lowest = self
+            .current_fan_curve
+            .as_ref()
+            .and_then(|curve| curve.to_points().iter().map(|point| point.duty).min());
+        if lowest.map_or(true, |lowest| duty > lowest) {
+            return duty;
+        }
+        let floor = idle.floor().duty.min(duty);
+        log::debug!(
+            "CPU idle, using {} instead of {}",
+            Duty::from_ten_thousandths(floor),
+            Duty::from_ten_thousandths(duty)
+        );
+        floor
+    }
+
+    /// Apply the battery cap to a curve duty if on battery
+    fn limit_on_battery(&self, duty: u16) -> u16 {
+        match self.battery_max_duty {
+            Some(max) if duty > max && on_battery(&self.power_supply_root) => {
+                log::debug!(
+                    "On battery, capping {} at {}",
+                    Duty::from_ten_thousandths(duty),
+                    Duty::from_ten_thousandths(max)
+                );
+                max
+            }
+            _ => duty,
+        }
+    }
+
+    /// Smooth temperature readings with an exponential moving average before the curve lookup
+    /// The critical override and the reported temperature keep the raw reading
+    /// `alpha` in (0, 1] is the weight of each new reading; None or an invalid value turns it off
+    pub fn set_temp_filter_alpha(&mut self, alpha: Option<f32>) {
+        let valid = alpha.filter(|a| *a > 0.0 && *a <= 1.0);
+        if valid != alpha {
+            warn!("Temperature filter alpha {:?} is not in (0, 1], filtering disabled", alpha);
+        }
+        self.temp_filter_alpha = valid;
+        *self.smoothed_temp.lock().unwrap() = None;
+    }
+
+    /// Apply the moving average to a new reading; unchanged when filtering is off
+    fn filter_temperature(&self, raw: f32) -> f32 {
+        let Some(alpha) = self.temp_filter_alpha else {
+            return raw;
+        };
+        let mut smoothed = self.smoothed_temp.lock().unwrap();
+        let value = match *smoothed {
+            Some(previous) => previous + alpha * (raw - previous),
+            None => raw,
+        };
+        *smoothed = Some(value);
+        value
+    }
+
+    /// Temperature to look up on the curve: the moving average of the sampled readings while
+    /// filtering is on, otherwise `raw`
+    fn curve_temperature(&self, raw: f32) -> f32 {
+        if self.temp_filter_alpha.is_none() {
+            return raw;
+        }
+        self.smoothed_temp.lock().unwrap().unwrap_or(raw)
+    }
+
+    /// Keep the fans at a higher duty for `delay` after the target drops, before ramping down
+    /// Unlike the temperature filter this doesn't slow ramping up; zero turns it off
+    pub fn set_ramp_down_delay(&mut self, delay: Duration) {
+        self.ramp_down_delay = delay;
+        *self.ramp_down.lock().unwrap() = None;
+    }
+
+    pub fn ramp_down_delay(&self) -> Duration {
+        self.ramp_down_delay
+    }
+
+    /// Hold the previous duty while `target` is lower and the delay since it dropped hasn't passed
+    fn hold_ramp_down(&self, target: u16, now: Instant) -> u16 {
+        let mut hold = self.ramp_down.lock().unwrap();
+        match *hold {
+            Some(RampDownHold { duty, lowered_at }) if target < duty => {
+                let lowered_at = lowered_at.unwrap_or(now);
+                if now.duration_since(lowered_at) < self.ramp_down_delay {
+                    log::debug!(
+                        "Holding {} for the ramp-down delay instead of {}",
+                        Duty::from_ten_thousandths(duty),
+                        Duty::from_ten_thousandths(target)
+                    );
+                    *hold = Some(RampDownHold {
+                        duty,
+                        lowered_at: Some(lowered_at),
+                    });
+                    return duty;
+                }
+            }
+            _ => {}
+        }
+        *hold = Some(RampDownHold {
+            duty: target,
+            lowered_at: None,
+        });
+        target
+    }
+
+    /// Set the time between monitoring samples, clamped to [`MIN_POLL_INTERVAL`]
+    pub fn set_poll_interval(&mut self, interval: Duration) {
+        if interval < MIN_POLL_INTERVAL {
+            warn!(
+                "Poll interval {:?} is too short, using {:?}",
+                interval, MIN_POLL_INTERVAL
+            );
+        }
+        self.poll_interval = interval.max(MIN_POLL_INTERVAL);
+    }
+
+    /// Get the time between monitoring samples
+    pub fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    /// Get the fan detector, scanning hwmon the first time it is needed
+    /// A failed scan is not retried, so per-tick reads never walk /sys/class/hwmon again
     pub fn fan_detector(&self) -> &FanDetector {
-        &self.fan_detector
+        self.fan_detector.get_or_init(|| {
+            let mut detector = FanDetector::new();
+            detector.set_dry_run(self.dry_run);
+            if self.pwm_enable_override.is_some() {
+                detector.set_pwm_enable_override(self.pwm_enable_override);
+            }
+            if !self.inverted_fans.is_empty() {
+                detector.set_inverted_fans(self.inverted_fans.clone());
+            }
+            if let Err(e) = detector.initialize() {
+                warn!("Failed to initialize fan detection: {}", e);
+            }
+            detector
+        })
     }
 
     /// Initialize the CPU temperature detector
@@ -205,32 +1200,27 @@ impl FanMonitor {
     }
 
     /// Get current fan data using direct file reading (for display)
+    /// The reading also feeds the overheat warning, the temperature filter and the GPU sample
     pub fn get_current_fan_data_direct(&self) -> Result<FanDataPoint> {
         log::debug!("FanMonitor::get_current_fan_data_direct called");
         
         // Use existing detectors for direct file reading (no D-Bus needed)
-        let temperature = if self.cpu_temp_detector.is_initialized() {
-            self.cpu_temp_detector.read_temperature()?
-        } else {
-            // Initialize CPU temp detector if not already initialized
-            let mut temp_detector = self.cpu_temp_detector.clone();
-            temp_detector.initialize()?;
-            temp_detector.read_temperature()?
-        };
-        
-        let cpu_fan_speeds = if self.fan_detector.is_initialized() {
-            self.fan_detector.read_all_fan_speeds()?
-        } else {
-            // Initialize fan detector if not already initialized
-            let mut fan_detector = self.fan_detector.clone();
-            fan_detector.initialize()?;
-            fan_detector.read_all_fan_speeds()?
-        };
+        let temperature = self.read_temperature()?;
+        self.check_overheat(temperature);
+        self.filter_temperature(temperature);
+        self.sample_gpu_temperature();
+        self.read_fan_data(temperature)
+    }
+
+    /// Fan speeds, duty and CPU usage read directly from sysfs, reported with `temperature`
+    /// Unlike [`FanMonitor::get_current_fan_data_direct`] this leaves the monitor's state alone
+    fn read_fan_data(&self, temperature: f32) -> Result<FanDataPoint> {
+        let cpu_fan_speeds = self.read_local_fan_speeds()?;
         
         // Read current fan duty from PWM files
         let fan_duty = self.read_current_fan_duty_from_pwm()?;
-        let cpu_usage = self.read_cpu_usage_direct().unwrap_or(0.0);
-        let cpu_model = self.get_cpu_model();
+        let cpu_usage = self.read_cpu_usage().unwrap_or(0.0);
+        let cpu_info = crate::cpu_info::cpu_info();
         
         // Create empty vectors for other fan types (we can add these later if needed)
         let intake_fan_speeds = Vec::new();
@@ -243,47 +1233,38 @@ impl FanMonitor {
             intake_fan_speeds,
             gpu_fan_speeds,
             cpu_usage,
-            cpu_model,
+            cpu_model: cpu_info.model.clone(),
+            cpu_core_count: cpu_info.core_count,
             timestamp: chrono::Local::now(),
         };
         
         log::debug!("Direct file reading - Temperature: {:.1}°C, Fan Duty: {:.1}%, Fan RPMs: {:?}", 
-            temperature, fan_duty as f32 / 100.0, cpu_fan_speeds);
+            temperature, Duty::from_ten_thousandths(fan_duty).as_percent(), cpu_fan_speeds);
         
         Ok(data_point)
     }
 
-    /// Read current fan duty from PWM files using existing fan detector
-    fn read_current_fan_duty_from_pwm(&self) -> Result<u16> {
-        if self.fan_detector.is_initialized() {
-            // Use existing fan detector to find PWM files
-            if let Some(cpu_fan) = self.fan_detector.get_cpu_fan() {
-                let pwm_path = std::path::Path::new(&cpu_fan.hwmon_path).join(format!("pwm{}", cpu_fan.fan_number));
-                if let Ok(content) = std::fs::read_to_string(&pwm_path) {
-                    if let Ok(pwm_value) = content.trim().parse::<u16>() {
-                        // Convert PWM (0-255) to duty percentage (0-10000)
-                        let duty_percentage = (pwm_value as f32 / 255.0 * 10000.0) as u16;
-                        log::debug!("Read fan duty from {:?}: PWM={}, Duty={:.1}%", 
-                            pwm_path, pwm_value, duty_percentage as f32 / 100.0);
-                        return Ok(duty_percentage);
-                    }
-                }
+    /// Read fan speeds from `sensors -j` when selected, otherwise from the detected hwmon fans
+    fn read_local_fan_speeds(&self) -> Result<Vec<(u8, u16, String)>> {
+        if let Some(ref sensors) = self.lm_sensors {
+            match sensors.read() {
+                Ok(readings) => return Ok(readings.fan_speeds()),
+                Err(e) => warn!("lm-sensors reading failed, using sysfs: {}", e),
             }
         }
-        
-        // Fallback: try to initialize fan detector and read PWM
-        let mut fan_detector = self.fan_detector.clone();
-        if fan_detector.initialize().is_ok() {
-            if let Some(cpu_fan) = fan_detector.get_cpu_fan() {
-                let pwm_path = std::path::Path::new(&cpu_fan.hwmon_path).join(format!("pwm{}", cpu_fan.fan_number));
-                if let Ok(content) = std::fs::read_to_string(&pwm_path) {
-                    if let Ok(pwm_value) = content.trim().parse::<u16>() {
-                        // Convert PWM (0-255) to duty percentage (0-10000)
-                        let duty_percentage = (pwm_value as f32 / 255.0 * 10000.0) as u16;
-                        log::debug!("Read fan duty from {:?}: PWM={}, Duty={:.1}%", 
-                            pwm_path, pwm_value, duty_percentage as f32 / 100.0);
-                        return Ok(duty_percentage);
-                    }
+        self.fan_detector().read_all_fan_speeds()
+    }
+
+    /// Read current fan duty from PWM files using existing fan detector
+    /// The raw value is scaled back from the fan's own PWM range first
+    fn read_current_fan_duty_from_pwm(&self) -> Result<u16> {
+        if let Some(cpu_fan) = self.fan_detector().get_cpu_fan() {
+            if let Ok(content) = std::fs::read_to_string(&cpu_fan.pwm_path) {
+                if let Ok(pwm_value) = content.trim().parse::<u8>() {
+                    let duty = Duty::from_pwm(cpu_fan.unscale_pwm(pwm_value));
+                    log::debug!("Read fan duty from {:?}: PWM={}, Duty={}", 
+                        cpu_fan.pwm_path, pwm_value, duty);
+                    return Ok(duty.as_ten_thousandths());
                 }
             }
         }
@@ -308,46 +1289,28 @@ impl FanMonitor {
             ));
         }
         
-        // Create a new Tokio runtime for this operation
-        let rt = match tokio::runtime::Runtime::new() {
-            Ok(rt) => {
-                log::debug!("Successfully created Tokio runtime");
-                rt
-            }
-            Err(e) => {
-                log::error!("Failed to create Tokio runtime: {}", e);
-                return Err(crate::errors::FanCurveError::Unknown(format!("Failed to create Tokio runtime: {}", e)));
-            }
-        };
-        
         log::debug!("About to call get_current_fan_data()");
-        let result = rt.block_on(self.get_current_fan_data());
+        let result = self.block_on(self.get_current_fan_data())?;
         log::debug!("get_current_fan_data() completed with result: {:?}", result.is_ok());
         result
     }
+
+    /// Read fan data every `interval` on a background thread, over DBus when the client
+    /// is initialized and from sysfs otherwise
+    pub fn spawn_data_feed(&self, interval: Duration) -> FanDataFeed {
+        let monitor = self.clone();
+        FanDataFeed::spawn(interval, move || {
+            monitor
+                .get_current_fan_data_sync()
+                .or_else(|_| monitor.get_current_fan_data_direct())
+        })
+    }
+
     /// Synchronous wrapper for apply_fan_curve
+    /// Runs on the shared runtime, see [`FanMonitor::block_on`]
     pub fn apply_fan_curve_sync(&self, temperature: f32) -> Result<()> {
         log::debug!("FanMonitor::apply_fan_curve_sync called with temperature={}", temperature);
-        
-        // Use the same separate thread approach to avoid GUI conflicts
-        let (tx, rx) = std::sync::mpsc::channel();
-        let self_clone = self.clone();
-        
-        std::thread::spawn(move || {
-            let rt = tokio::runtime::Runtime::new();
-            match rt {
-                Ok(runtime) => {
-                    let result = runtime.block_on(self_clone.apply_fan_curve(temperature));
-                    let _ = tx.send(result);
-                }
-                Err(e) => {
-                    let _ = tx.send(Err(crate::errors::FanCurveError::Unknown(format!("Failed to create Tokio runtime: {}", e))));
-                }
-            }
-        });
-        
-        // Wait for the result
-        rx.recv().map_err(|e| crate::errors::FanCurveError::Unknown(format!("Failed to receive result: {}", e)))?
+        self.block_on(self.apply_fan_curve(temperature))?
     }
 
     /// Apply fan curve using daemon D-Bus interface (for GUI integration)
@@ -357,7 +1320,8 @@ impl FanMonitor {
         
         // Set the fan curve in the monitor
         self.current_fan_curve = Some(curve.clone());
-        log::info!("Fan curve set in monitor: {} points", curve.points().len());
+        self.force_update();
+        log::info!("Fan curve set in monitor: {} points", curve.len());
         
         // Use daemon D-Bus interface instead of direct PWM control
         log::info!("Attempting to use daemon D-Bus interface...");
@@ -368,6 +1332,7 @@ impl FanMonitor {
             if let Err(e) = self.initialize_system76_power_sync() {
                 log::warn!("Failed to initialize D-Bus client: {}", e);
                 log::info!("Falling back to direct PWM control...");
+                self.ensure_can_control()?;
                 return self.apply_fan_curve_direct_pwm(curve, temperature);
             }
         }
@@ -383,48 +1348,60 @@ impl FanMonitor {
             Err(e) => {
                 log::warn!("Failed to apply fan curve via daemon: {}", e);
                 log::info!("Falling back to direct PWM control...");
+                self.ensure_can_control()?;
                 self.apply_fan_curve_direct_pwm(curve, temperature)
             }
         }
     }
     
+    /// Fail early with a clear message when the PWM files can't be written
+    /// Dry runs and machines without detected fans are left to the normal fallbacks
+    fn ensure_can_control(&self) -> Result<()> {
+        let detector = self.fan_detector();
+        if self.dry_run || !detector.is_initialized() || detector.can_control() {
+            return Ok(());
+        }
+        Err(crate::errors::FanCurveError::PermissionDenied(
+            "this user cannot write the fan PWM files; apply curves through the fan-curve-app daemon or run the GUI with pkexec".to_string(),
+        ))
+    }
+
     /// Fallback method for direct PWM control when daemon is unavailable
     fn apply_fan_curve_direct_pwm(&mut self, curve: &crate::fan::FanCurve, temperature: f32) -> Result<()> {
         log::info!("=== FALLBACK: DIRECT PWM CONTROL ===");
+
+        if curve.is_auto() {
+            return self.release_fans_to_firmware();
+        }
         
         // Use direct PWM control for GUI (avoids D-Bus runtime conflicts)
-        log::info!("Checking fan detector initialization status...");
-        if !self.fan_detector.is_initialized() {
-            log::warn!("Fan detector not initialized, attempting to initialize");
-            log::info!("Calling fan_detector.initialize()...");
-            match self.fan_detector.initialize() {
-                Ok(_) => {
-                    log::info!("✅ Fan detector initialized successfully");
-                    log::info!("Fan detector now has {} fans", self.fan_detector.get_fans().len());
-                }
-                Err(e) => {
-                    log::error!("❌ Failed to initialize fan detector: {}", e);
-                    log::error!("Fan detector initialization error details: {:?}", e);
-                    return Err(e);
-                }
-            }
-        } else {
-            log::info!("Fan detector already initialized with {} fans", self.fan_detector.get_fans().len());
+        if !self.fan_detector().is_initialized() {
+            log::error!("❌ Fan detector not initialized, cannot apply PWM control");
+            return Err(crate::errors::FanCurveError::Config(
+                "Fan detector not initialized".to_string(),
+            ));
         }
+        log::info!("Fan detector initialized with {} fans", self.fan_detector().get_fans().len());
 
         log::info!("Calculating fan duty from curve...");
         let duty = self.calculate_fan_duty_from_curve(temperature);
-        let duty_percentage = duty / 100; // Convert ten-thousandths to percentage for display
+        let duty_percentage = Duty::from_ten_thousandths(duty);
         let pwm_value = self.duty_to_pwm(duty);
 
         log::info!(
-            "Fan curve calculation: {:.1}°C -> {}% duty ({} ten-thousandths) -> PWM {}",
+            "Fan curve calculation: {:.1}°C -> {} duty ({} ten-thousandths) -> PWM {}",
             temperature, duty_percentage, duty, pwm_value
         );
 
+        if self.has_per_fan_control() {
+            log::info!("Some fans have their own control mode, applying per fan");
+            self.write_fan_duties(self.fan_duties(temperature, duty));
+            return Ok(());
+        }
+
         log::info!("Attempting to apply PWM control to fans...");
         // Apply to all fans using the set_duty method
-        match self.fan_detector.set_duty(Some(pwm_value)) {
+        match self.fan_detector().set_duty_verified(pwm_value) {
             Ok(_) => {
                 log::info!(
                     "✅ Successfully applied PWM control to all fans: {} (duty: {})",
@@ -441,12 +1418,12 @@ impl FanMonitor {
 
         // Fallback to individual CPU fan control
         log::info!("Getting CPU fan information...");
-        if let Some(cpu_fan) = self.fan_detector.get_cpu_fan() {
+        if let Some(cpu_fan) = self.fan_detector().get_cpu_fan() {
             log::info!(
                 "Found CPU fan: number={}, applying PWM control -> PWM {}",
                 cpu_fan.fan_number, pwm_value
             );
-            match self.fan_detector.set_fan_pwm(cpu_fan.fan_number, pwm_value) {
+            match self.fan_detector().set_fan_pwm_verified(cpu_fan.fan_number, pwm_value) {
                 Ok(_) => {
                     log::info!("✅ Successfully applied PWM control to CPU fan {}", cpu_fan.fan_number);
                     log::info!("=== FAN CURVE APPLICATION SUCCESS (FALLBACK) ===");
@@ -467,12 +1444,13 @@ impl FanMonitor {
     /// Set fan duty directly from GUI (0-255 PWM value)
     pub fn set_fan_duty_from_gui(&mut self, duty: u8) -> Result<()> {
         log::debug!("FanMonitor::set_fan_duty_from_gui called with duty={}", duty);
+
+        if self.dry_run {
+            log::info!("[dry-run] Would set fan duty to PWM {}", duty);
+            return Ok(());
+        }
         
-        // Create a new Tokio runtime for this synchronous call
-        let rt = tokio::runtime::Runtime::new()
-            .map_err(|e| crate::errors::FanCurveError::Unknown(format!("Failed to create Tokio runtime: {}", e)))?;
-        
-        rt.block_on(async {
+        self.block_on(async {
             if let Some(ref client) = self.system76_power_client {
                 log::debug!("Using existing D-Bus client to set fan duty");
                 client.set_fan_duty(duty).await
@@ -481,7 +1459,7 @@ impl FanMonitor {
                 let client = System76PowerClient::new().await?;
                 client.set_fan_duty(duty).await
             }
-        })
+        })?
     }
 
     /// Get current fan data - async version
@@ -500,8 +1478,12 @@ impl FanMonitor {
         
         // Read real CPU temperature using async method
         let temperature = self.read_cpu_temperature_async().await?;
+        self.check_overheat(temperature);
+        self.filter_temperature(temperature);
+        self.sample_gpu_temperature();
         let cpu_fan_speeds = self.read_fan_speeds_async().await?;
-        let fan_duty = self.calculate_fan_duty_from_curve(temperature);
+        // Applying the curve to this sample runs the stateful pipeline; this only reports
+        let fan_duty = self.expected_duty(temperature);
         let cpu_usage = self.read_cpu_usage()?;
 
         Ok(FanDataPoint {
@@ -512,29 +1494,35 @@ impl FanMonitor {
             gpu_fan_speeds: Vec::new(),
             fan_duty,
             cpu_usage,
-            cpu_model: self.get_cpu_model(),
+            cpu_model: crate::cpu_info::cpu_info().model.clone(),
+            cpu_core_count: crate::cpu_info::cpu_info().core_count,
         })
     }
 
+    /// Read a sample through system76-power when its client is set up, otherwise from local sensors
+    /// A missing client doesn't go away between polls, so it never counts as a failed read
+    pub async fn read_sample(&self) -> Result<FanDataPoint> {
+        if self.system76_power_client.is_some() {
+            self.get_current_fan_data().await
+        } else {
+            self.get_current_fan_data_direct()
+        }
+    }
+
     /// Log fan data if monitoring is enabled
     pub async fn log_fan_data(&mut self) -> Result<()> {
         if !self.is_monitoring {
             return Ok(());
         }
 
-        // Log every 1 second for real-time updates
-        if self.last_log_time.elapsed() < Duration::from_secs(1) {
+        // Log once per poll interval for real-time updates
+        if self.last_log_time.elapsed() < self.poll_interval {
             return Ok(());
         }
 
-        let data = self.get_current_fan_data().await?;
-
-        // Apply fan curve to hardware
-        if let Err(e) = self.apply_fan_curve(data.temperature).await {
-            warn!("Failed to apply fan curve: {}", e);
-        }
-
+        let sample = self.read_sample().await;
         self.last_log_time = Instant::now();
+        let data = self.control_from_sample(sample, Instant::now()).await?;
 
         // Real-time console output with formatting
         let fan_info = if data.cpu_fan_speeds.is_empty() {
@@ -547,8 +1535,7 @@ impl FanMonitor {
                 .join(" | ")
         };
 
-        // Convert duty from ten-thousandths to percentage for display
-        let duty_percentage = data.fan_duty / 100;
+        let duty_percentage = Duty::from_ten_thousandths(data.fan_duty).as_whole_percent();
 
         println!(
             "🌡️  Temperature: {:.1}°C | 🌀 Fans: {} | ⚡ Fan Duty: {}% | 💻 CPU: {:.1}% | ⏰ {}",
@@ -562,16 +1549,116 @@ impl FanMonitor {
         Ok(())
     }
 
+    /// Record a sample and apply the curve to it
+    /// A failed read applies the curve to the last good sample while it is younger than
+    /// [`MAX_SAMPLE_AGE`], and runs the fans at the critical duty once that is too old or
+    /// [`MAX_READ_FAILURES`] reads in a row have failed; the read error is returned either way
+    /// If a previous write found the fan files missing, the device is looked up again first
+    pub async fn control_from_sample(&mut self, sample: Result<FanDataPoint>, now: Instant) -> Result<FanDataPoint> {
+        self.refresh_missing_fan_paths();
+        let error = match sample {
+            Ok(data) => {
+                if self.read_failures.consecutive > 0 {
+                    info!("Sensor reads recovered after {} failures", self.read_failures.consecutive);
+                }
+                self.read_failures = ReadFailures {
+                    last_good: Some((data.clone(), now)),
+                    consecutive: 0,
+                };
+                self.record_sample(data.clone());
+                if let Err(e) = self.apply_fan_curve(data.temperature).await {
+                    warn!("Failed to apply fan curve: {}", e);
+                }
+                return Ok(data);
+            }
+            Err(e) => e,
+        };
+
+        self.read_failures.consecutive += 1;
+        let failures = self.read_failures.consecutive;
+        let recent = self
+            .read_failures
+            .last_good
+            .as_ref()
+            .filter(|(_, read_at)| now.duration_since(*read_at) <= MAX_SAMPLE_AGE)
+            .map(|(data, _)| data.temperature);
+
+        let applied = match recent {
+            Some(temperature) if failures < MAX_READ_FAILURES => {
+                warn!(
+                    "Sensor read failed ({} in a row), applying the curve to the last reading of {:.1}°C",
+                    failures, temperature
+                );
+                self.apply_fan_curve(temperature).await
+            }
+            _ => {
+                warn!(
+                    "Sensor read failed ({} in a row) with no recent reading, running fans at {}",
+                    failures,
+                    Duty::from_ten_thousandths(self.critical_duty)
+                );
+                self.apply_fail_safe_duty().await
+            }
+        };
+        if let Err(e) = applied {
+            warn!("Failed to control fans without a reading: {}", e);
+        }
+        Err(error)
+    }
+
+    /// Run the fans at the critical duty, for when the temperature is unknown
+    async fn apply_fail_safe_duty(&self) -> Result<()> {
+        let duty = self.critical_duty;
+        if self.dry_run {
+            info!("[dry-run] Would run fans at {}", Duty::from_ten_thousandths(duty));
+            return Ok(());
+        }
+        if let Some(ref client) = self.system76_power_client {
+            let duty_percentage = u16::from(Duty::from_ten_thousandths(duty).as_whole_percent());
+            return client.apply_fan_curve(self.critical_temp, duty_percentage).await;
+        }
+        if !self.fan_detector().is_initialized() {
+            warn!("Fan detector not initialized, cannot apply fail-safe duty");
+            return Ok(());
+        }
+        self.ensure_can_control()?;
+        self.forget_written_duties();
+        self.fan_detector().set_duty_verified(self.duty_to_pwm(duty))
+    }
+
+    /// Find the fan controller again if a write found its files missing, e.g. after a driver
+    /// reload renumbered the hwmon devices
+    fn refresh_missing_fan_paths(&mut self) {
+        let Some(detector) = self.fan_detector.get_mut() else {
+            return;
+        };
+        match detector.refresh_if_files_missing() {
+            Ok(true) => self.force_update(),
+            Ok(false) => {}
+            Err(e) => warn!("Failed to re-resolve fan paths: {}", e),
+        }
+    }
+
     /// Run monitoring loop
     pub async fn run_monitoring_loop(&mut self) -> Result<()> {
         info!("Starting fan monitoring loop");
 
         while self.is_monitoring {
+            // Expire a finished boost even when no sample is due this iteration
+            self.active_boost();
+
             if let Err(e) = self.log_fan_data().await {
                 warn!("Failed to log fan data: {}", e);
+
+                // The fan controller may have moved to another hwmonN, e.g. after a driver reload
+                if let Some(detector) = self.fan_detector.get_mut() {
+                    if let Err(e) = detector.refresh_paths() {
+                        warn!("Failed to re-resolve fan paths: {}", e);
+                    }
+                }
             }
 
-            sleep(Duration::from_secs(1)).await;
+            sleep(self.poll_interval).await;
         }
 
         Ok(())
@@ -581,9 +1668,7 @@ impl FanMonitor {
     fn read_cpu_temperature(&self) -> Result<f32> {
         // Use System76 Power daemon if available
         if let Some(ref client) = self.system76_power_client {
-            // Use tokio::runtime::Handle to run async code in sync context
-            let handle = tokio::runtime::Handle::current();
-            let temp_thousandths = handle.block_on(client.get_current_temperature_from_daemon())?;
+            let temp_thousandths = self.block_on(client.get_current_temperature_from_daemon())??;
             
             // Convert to Celsius
             let temp_celsius = temp_thousandths as f32 / 1000.0;
@@ -601,32 +1686,29 @@ impl FanMonitor {
         self.cpu_temp_detector.read_temperature()
     }
 
-    /// Read CPU temperature from System76 Power daemon (asynchronous)
+    /// Read CPU temperature from the injected reader or the System76 Power daemon (asynchronous)
     async fn read_cpu_temperature_async(&self) -> Result<f32> {
-        // Use System76 Power daemon if available
-        if let Some(ref client) = self.system76_power_client {
-            let temp_thousandths = client.get_current_temperature_from_daemon().await?;
-            
-            // Convert to Celsius
-            let temp_celsius = temp_thousandths as f32 / 1000.0;
-            
-            info!("Temperature from daemon: {:.1}°C ({} thousandths)", temp_celsius, temp_thousandths);
-            return Ok(temp_celsius);
-        }
-        
-        // Force D-Bus usage - no simulation fallback
-        Err(crate::errors::FanCurveError::Config(
-            "System76 Power D-Bus client not initialized. Please ensure the daemon is running.".to_string()
-        ))
+        let source: &dyn TemperatureSource = match (&self.temperature_reader, &self.system76_power_client) {
+            (Some(reader), _) => reader.as_ref(),
+            (None, Some(client)) => client,
+            // Force D-Bus usage - no simulation fallback
+            (None, None) => {
+                return Err(crate::errors::FanCurveError::Config(
+                    "System76 Power D-Bus client not initialized. Please ensure the daemon is running.".to_string()
+                ));
+            }
+        };
+
+        let temp_celsius = source.read_celsius().await?;
+        info!("Temperature from source: {:.1}°C", temp_celsius);
+        Ok(temp_celsius)
     }
 
     /// Read fan speeds from System76 Power daemon (synchronous)
     fn read_fan_speeds(&self) -> Result<Vec<(u8, u16, String)>> {
         // Use System76 Power daemon if available
         if let Some(ref client) = self.system76_power_client {
-            // Use tokio::runtime::Handle to run async code in sync context
-            let handle = tokio::runtime::Handle::current();
-            let speeds_rpm = handle.block_on(client.get_fan_speeds_from_daemon())?;
+            let speeds_rpm = self.block_on(client.get_fan_speeds_from_daemon())??;
             
             // Convert Vec<u32> (RPM) to Vec<(u8, u16, String)> (fan_number, speed, label)
             let mut fan_speeds = Vec::new();
@@ -641,8 +1723,15 @@ impl FanMonitor {
             return Ok(fan_speeds);
         }
         
+        if let Some(ref sensors) = self.lm_sensors {
+            match sensors.read() {
+                Ok(readings) => return Ok(readings.fan_speeds()),
+                Err(e) => warn!("lm-sensors reading failed, using sysfs: {}", e),
+            }
+        }
+
         // Fallback to direct sysfs if daemon not available
-        if !self.fan_detector.is_initialized() {
+        if !self.fan_detector().is_initialized() {
             warn!("Fan detector not initialized, using simulation");
             return Ok(self.simulate_fan_speeds_fallback());
         }
@@ -650,7 +1739,7 @@ impl FanMonitor {
         info!("Fan detector initialized, reading from hardware sensors");
 
         // Prioritize CPU fan if available
-        if let Ok(Some(cpu_fan_data)) = self.fan_detector.read_cpu_fan_speed() {
+        if let Ok(Some(cpu_fan_data)) = self.fan_detector().read_cpu_fan_speed() {
             info!(
                 "Found CPU fan: Fan {} at {} RPM",
                 cpu_fan_data.0, cpu_fan_data.1
@@ -660,7 +1749,7 @@ impl FanMonitor {
 
         // Fallback to all fans if no CPU fan found
         info!("No CPU fan found, reading all fans");
-        self.fan_detector.read_all_fan_speeds()
+        self.fan_detector().read_all_fan_speeds()
     }
 
     /// Read fan speeds from System76 Power daemon (asynchronous)
@@ -708,7 +1797,10 @@ impl FanMonitor {
     fn simulate_temperature_fallback(&self) -> f32 {
         let base_temp = 35.0;
         let time_factor = (chrono::Local::now().timestamp() % 60) as f32 / 60.0;
-        let cpu_factor = self.read_cpu_usage().unwrap_or(20.0) * 0.5;
+        let cpu_factor = CpuTimes::read()
+            .ok()
+            .and_then(|times| times.usage_since(&CpuTimes::default()))
+            .unwrap_or(20.0) * 0.5;
         let random_factor = (rand::random::<f32>() - 0.5) * 5.0;
 
         base_temp + time_factor * 10.0 + cpu_factor + random_factor
@@ -716,19 +1808,93 @@ impl FanMonitor {
 
     /// Calculate fan duty based on the current fan curve
     /// Returns duty in ten-thousandths (0-10000) to match system76-power standard
+    /// Duty for `temperature`, capped on battery and raised to the critical duty when the CPU is too hot
+    /// Overheat warnings are checked once per sample when it is read, not here
     fn calculate_fan_duty_from_curve(&self, temperature: f32) -> u16 {
+        let mut duty = self.boost_or_curve_duty(temperature);
+        if self.active_boost().is_none() {
+            duty = self.apply_idle_floor(duty, Instant::now());
+        }
+
+        let critical = temperature > self.critical_temp;
+        let was_critical = self.critical_active.swap(critical, Ordering::Relaxed);
+        if !critical {
+            if was_critical {
+                info!("CPU back below {:.1}°C, fan curve resumed", self.critical_temp);
+            }
+            return self.record_target(temperature, self.hold_ramp_down(duty, Instant::now()));
+        }
+
+        if !was_critical {
+            warn!(
+                "CPU at {:.1}°C is above the critical {:.1}°C, running fans at least at {}",
+                temperature,
+                self.critical_temp,
+                Duty::from_ten_thousandths(self.critical_duty)
+            );
+        }
+        let duty = self.hold_ramp_down(duty.max(self.critical_duty), Instant::now());
+        self.record_target(temperature, duty)
+    }
+
+    /// Remember `duty` as the one calculated for `temperature` and return it
+    fn record_target(&self, temperature: f32, duty: u16) -> u16 {
+        *self.last_target.lock().unwrap() = Some((temperature, duty));
+        duty
+    }
+
+    /// Temperature and duty (ten-thousandths) of the latest curve calculation, after the idle
+    /// floor, ramp-down hold and critical override; None until the curve has been applied
+    pub fn last_target(&self) -> Option<(f32, u16)> {
+        *self.last_target.lock().unwrap()
+    }
+
+    /// Duty the curve and overrides call for at `temperature`, without logging or notifying
+    /// Used to check what the fans should be doing against what they report
+    pub fn expected_duty(&self, temperature: f32) -> u16 {
+        let duty = self.boost_or_curve_duty(temperature);
+        if temperature > self.critical_temp {
+            duty.max(self.critical_duty)
+        } else {
+            duty
+        }
+    }
+
+    fn boost_or_curve_duty(&self, temperature: f32) -> u16 {
+        if let Some(duty) = self.active_boost() {
+            log::debug!("Boost active, using {} instead of the curve", Duty::from_ten_thousandths(duty));
+            return duty;
+        }
+
+        self.limit_on_battery(self.curve_duty(temperature))
+    }
+
+    fn curve_duty(&self, temperature: f32) -> u16 {
+        let temperature = self.source_temperature(self.curve_temperature(temperature));
+        let degree = ((temperature * 1000.0) as i32).div_euclid(1000);
+        if let Some((cached, duty)) = self.duty_cache.lock().unwrap().curve {
+            if cached == degree {
+                return duty;
+            }
+        }
+        let duty = self.calculate_curve_duty(temperature);
+        self.duty_cache.lock().unwrap().curve = Some((degree, duty));
+        duty
+    }
+
+    fn calculate_curve_duty(&self, temperature: f32) -> u16 {
         log::debug!("Calculating fan duty for temperature: {:.1}°C", temperature);
         
         if let Some(ref curve) = self.current_fan_curve {
-            log::debug!("Using fan curve '{}' with {} points", curve.name(), curve.points().len());
+            log::debug!("Using fan curve '{}' with {} points", curve.name(), curve.len());
             
             // Log all curve points
-            for (i, point) in curve.points().iter().enumerate() {
-                log::debug!("  Point {}: {}°C -> {:.1}%", i + 1, point.temp, point.duty as f32 / 100.0);
+            for (i, point) in curve.iter().enumerate() {
+                log::debug!("  Point {}: {}°C -> {}", i + 1, point.temp, Duty::from_ten_thousandths(point.duty));
             }
             
             // Convert Celsius to thousandths of Celsius
-            let temp_thousandths = (temperature * 1000.0) as u32;
+            let temp_thousandths = (temperature * 1000.0) as i32;
             log::debug!("Temperature in thousandths: {}", temp_thousandths);
             
             let duty = curve.calculate_duty_for_temperature(temp_thousandths);
@@ -739,34 +1905,208 @@ impl FanMonitor {
             // Fallback to simple simulation if no curve is set
             let duty_percent = ((temperature - 30.0).max(0.0) * 2.0) as u16;
             let duty_percent = duty_percent.min(100);
-            // Convert percentage to ten-thousandths
-            let duty = duty_percent * 100;
+            let duty = Duty::from_percent(duty_percent as f32).as_ten_thousandths();
             log::debug!("Fallback calculation: {}°C -> {}% -> {} ten-thousandths", temperature, duty_percent, duty);
             duty
         }
     }
 
+    /// Duty to command for `fan` this cycle, kicking fans that start from standstill
+    fn spin_up_duty(&self, fan: u8, target: u16) -> u16 {
+        let Some(ref curve) = self.current_fan_curve else {
+            return target;
+        };
+        let duty = self.spin_up.lock().unwrap().next_duty(fan, target, curve);
+        if duty != target {
+            info!(
+                "Spinning up fan {} at {} before settling to {}",
+                fan,
+                Duty::from_ten_thousandths(duty),
+                Duty::from_ten_thousandths(target)
+            );
+        }
+        duty
+    }
+
+    /// Duty (ten-thousandths) for each fan that follows the curve, holds a manual duty or
+    /// chases a target RPM at `temperature`
+    /// Fans in [`FanControlMode::Auto`] are handed to the firmware here and left out.
+    /// A linked follower whose leader gets a duty takes its duty from the link instead of its
+    /// own mode. Manual, RPM and linked duties are still raised by the critical override
+    fn fan_duties(&self, temperature: f32, target: u16) -> Vec<(u8, u16)> {
+        let detector = self.fan_detector();
+        let number = |label: &str| {
+            detector
+                .get_fans()
+                .iter()
+                .find(|fan| fan.fan_label == label)
+                .map(|fan| fan.fan_number)
+        };
+        let links: Vec<(&FanLink, u8, u8)> = self
+            .fan_links
+            .iter()
+            .filter_map(|link| Some((link, number(&link.leader)?, number(&link.follower)?)))
+            .collect();
+        let led = |fan: u8| {
+            links.iter().any(|&(link, _, follower)| {
+                follower == fan && self.fan_control_mode(&link.leader) != FanControlMode::Auto
+            })
+        };
+
+        let mut duties = Vec::new();
+        for fan in detector.get_fans() {
+            if led(fan.fan_number) {
+                continue;
+            }
+            match self.fan_control_mode(&fan.fan_label) {
+                FanControlMode::Curve => {
+                    duties.push((fan.fan_number, self.spin_up_duty(fan.fan_number, target)));
+                }
+                FanControlMode::Manual(percent) => {
+                    let mut duty = Duty::from_percent(f32::from(percent)).as_ten_thousandths();
+                    if self.critical_active.load(Ordering::Relaxed) {
+                        duty = duty.max(self.critical_duty);
+                    }
+                    duties.push((fan.fan_number, duty));
+                }
+                FanControlMode::Auto => {
+                    if let Err(e) = detector.set_fan_auto(fan.fan_number) {
+                        warn!("Failed to return fan {} to automatic mode: {}", fan.fan_number, e);
+                    }
+                }
+                FanControlMode::TargetRpm => {
+                    let duty = match self.closed_loop_duty(fan, temperature, Instant::now()) {
+                        Ok(mut duty) => {
+                            if self.critical_active.load(Ordering::Relaxed) {
+                                duty = duty.max(self.critical_duty);
+                            }
+                            duty
+                        }
+                        Err(e) => {
+                            warn!("Fan {} can't follow its target RPM, using the curve: {}", fan.fan_number, e);
+                            self.spin_up_duty(fan.fan_number, target)
+                        }
+                    };
+                    duties.push((fan.fan_number, duty));
+                }
+            }
+        }
+
+        for (link, leader, follower) in links {
+            let Some(&(_, leader_duty)) = duties.iter().find(|&&(fan, _)| fan == leader) else {
+                continue;
+            };
+            let mut duty = link.follower_duty(leader_duty);
+            if self.critical_active.load(Ordering::Relaxed) {
+                duty = duty.max(self.critical_duty);
+            }
+            match duties.iter_mut().find(|(fan, _)| *fan == follower) {
+                Some(entry) => entry.1 = duty,
+                None => duties.push((follower, duty)),
+            }
+        }
+        duties
+    }
+
+    /// Duty (ten-thousandths) moving `fan` towards its target RPM at `temperature`, from its
+    /// `fanN_input` reading and the time since its controller last ran
+    fn closed_loop_duty(&self, fan: &FanSensor, temperature: f32, now: Instant) -> Result<u16> {
+        let target = self.rpm_targets.get(&fan.fan_label).ok_or_else(|| {
+            crate::errors::FanCurveError::Config(format!("No target RPM curve for '{}'", fan.fan_label))
+        })?;
+        let measured = self.fan_detector().read_fan_speed(fan.fan_number)?;
+        let target_rpm = target.rpm_at(temperature);
+
+        let mut controllers = self.closed_loop.lock().unwrap();
+        let (controller, last_update) = controllers
+            .entry(fan.fan_number)
+            .or_insert_with(|| (ClosedLoopController::new(target.gains), now));
+        // The first update covers one poll interval
+        let dt = match now.duration_since(*last_update) {
+            Duration::ZERO => self.poll_interval,
+            dt => dt,
+        };
+        *last_update = now;
+        let duty = controller.update(target_rpm, measured, dt);
+        log::debug!(
+            "Fan {}: {} RPM, target {} RPM at {:.1}°C -> {}",
+            fan.fan_number, measured, target_rpm, temperature, Duty::from_ten_thousandths(duty)
+        );
+        Ok(duty)
+    }
+
+    /// Write per-fan duties from [`FanMonitor::fan_duties`], logging fans that fail
+    fn write_fan_duties(&self, duties: Vec<(u8, u16)>) {
+        for (fan_number, duty) in duties {
+            if let Err(e) = self
+                .fan_detector()
+                .set_fan_pwm_verified(fan_number, self.duty_to_pwm(duty))
+            {
+                warn!("Failed to set fan {} PWM: {}", fan_number, e);
+                self.forget_written_duties();
+            }
+        }
+    }
+
     /// Calculate PWM value from duty (0-10000) to PWM (0-255)
     /// Matches system76-power conversion: (duty * 255) / 10000
     fn duty_to_pwm(&self, duty: u16) -> u8 {
-        ((u32::from(duty) * 255) / 10000) as u8
+        Duty::from_ten_thousandths(duty).as_pwm()
+    }
+
+    /// Return every fan to automatic (firmware) control, skipping the write if they already are
+    fn release_fans_to_firmware(&self) -> Result<()> {
+        let detector = self.fan_detector();
+        if !detector.is_initialized() || detector.control_mode() == ControlMode::Auto {
+            return Ok(());
+        }
+        info!("Returning fans to automatic (firmware) control");
+        self.forget_written_duties();
+        detector.set_duty(None)
     }
 
     /// Apply fan curve to hardware via System76 Power daemon
+    /// A firmware-controlled curve returns the fans to automatic mode instead of writing a duty
     pub async fn apply_fan_curve(&self, temperature: f32) -> Result<()> {
-        // Use System76 Power daemon if available
-        if let Some(ref client) = self.system76_power_client {
+        if self.current_fan_curve.as_ref().is_some_and(|curve| curve.is_auto()) {
+            if let Some(ref client) = self.system76_power_client {
+                if self.dry_run {
+                    info!("[dry-run] Would return fans to automatic control via daemon");
+                    return Ok(());
+                }
+                // A duty of 0 hands the fans back to the firmware
+                return client.set_fan_duty(0).await;
+            }
+            return self.release_fans_to_firmware();
+        }
+
+        // Use System76 Power daemon if available and no fan needs its own duty
+        let client = self.system76_power_client.as_ref().filter(|_| !self.has_per_fan_control());
+        if let Some(client) = client {
             // Get current fan curve from daemon
-            let current_curve = client.get_fan_curve_from_daemon().await?;
+            let current_curve = client.get_fan_curve().await?;
             
             // Convert app's FanCurve to daemon format
             if let Some(ref curve) = self.current_fan_curve {
-                let daemon_points = curve.to_daemon_points();
+                let daemon_curve = crate::fan::FanCurveDto::from(curve);
+                let changed = current_curve.points != daemon_curve.points;
                 
+                if self.dry_run {
+                    let duty = self.calculate_fan_duty_from_curve(temperature);
+                    info!(
+                        "[dry-run] Would send {} curve points to daemon ({}) and apply {:.1}°C -> {} duty",
+                        daemon_curve.points.len(),
+                        if changed { "changed" } else { "unchanged" },
+                        temperature,
+                        Duty::from_ten_thousandths(duty)
+                    );
+                    return Ok(());
+                }
+
                 // Check if curve has changed
-                if current_curve != daemon_points {
+                if changed {
                     info!("Fan curve changed, updating daemon");
-                    client.set_fan_curve_to_daemon(daemon_points).await?;
+                    client.set_fan_curve(&daemon_curve).await?;
                     info!("Fan curve updated in daemon successfully");
                 } else {
                     info!("Fan curve unchanged, daemon already has current curve");
@@ -775,8 +2115,15 @@ impl FanMonitor {
                 // Apply the fan curve to hardware
                 info!("Applying fan curve to hardware via daemon");
                 let duty = self.calculate_fan_duty_from_curve(temperature);
-                let duty_percentage = duty / 100; // Convert ten-thousandths to percentage for display
-                client.apply_fan_curve(temperature, duty_percentage).await?;
+                let duty = self.spin_up_duty(DAEMON_FANS, duty);
+                if !self.duties_changed(&[(DAEMON_FANS, duty)]) {
+                    return Ok(());
+                }
+                let duty_percentage = u16::from(Duty::from_ten_thousandths(duty).as_whole_percent());
+                if let Err(e) = client.apply_fan_curve(temperature, duty_percentage).await {
+                    self.forget_written_duties();
+                    return Err(e);
+                }
                 info!("Fan curve applied to hardware successfully");
             } else {
                 warn!("No fan curve set in app, cannot apply to daemon");
@@ -786,31 +2133,54 @@ impl FanMonitor {
         }
 
         // Fallback to direct PWM control - requires fan detector to be initialized
-        if !self.fan_detector.is_initialized() {
+        if !self.fan_detector().is_initialized() {
+            if self.system76_power_client.is_some() {
+                return Err(crate::errors::FanCurveError::Config(
+                    "per-fan control modes and links need direct PWM control, but no fans were detected".to_string(),
+                ));
+            }
             warn!("Fan detector not initialized, cannot apply fan curve");
             return Ok(());
         }
+        self.ensure_can_control()?;
 
-        let duty = self.calculate_fan_duty_from_curve(temperature);
-        let duty_percentage = duty / 100; // Convert ten-thousandths to percentage for display
+        let target = self.calculate_fan_duty_from_curve(temperature);
+        let duties = self.fan_duties(temperature, target);
+        if !self.duties_changed(&duties) {
+            return Ok(());
+        }
+
+        // Fans only disagree while some of them are being spun up or have their own control mode
+        if duties.len() != self.fan_detector().fan_count()
+            || duties.iter().any(|&(_, duty)| duty != duties[0].1)
+        {
+            self.write_fan_duties(duties);
+            return Ok(());
+        }
+
+        let Some(&(_, duty)) = duties.first() else {
+            return Ok(());
+        };
+        let duty_percentage = Duty::from_ten_thousandths(duty);
         let pwm_value = self.duty_to_pwm(duty);
 
         info!(
-            "Applying fan curve: {:.1}°C -> {}% duty ({} ten-thousandths)",
+            "Applying fan curve: {:.1}°C -> {} duty ({} ten-thousandths)",
             temperature, duty_percentage, duty
         );
 
         // Apply to all fans using the new set_duty method (matches system76-power approach)
-        if let Err(e) = self.fan_detector.set_duty(Some(pwm_value)) {
+        if let Err(e) = self.fan_detector().set_duty_verified(pwm_value) {
             warn!("Failed to set fan PWM via set_duty: {}", e);
+            self.forget_written_duties();
 
             // Fallback to individual CPU fan control
-            if let Some(cpu_fan) = self.fan_detector.get_cpu_fan() {
+            if let Some(cpu_fan) = self.fan_detector().get_cpu_fan() {
                 info!(
                     "Fallback: Applying direct PWM control to CPU fan {} -> PWM {}",
                     cpu_fan.fan_number, pwm_value
                 );
-                if let Err(e) = self.fan_detector.set_fan_pwm(cpu_fan.fan_number, pwm_value) {
+                if let Err(e) = self.fan_detector().set_fan_pwm_verified(cpu_fan.fan_number, pwm_value) {
                     warn!("Failed to set CPU fan PWM directly: {}", e);
                 }
             } else {
@@ -826,84 +2196,45 @@ impl FanMonitor {
         Ok(())
     }
 
-    /// Read CPU usage from /proc/stat
+    /// CPU usage (%) since the previous sample, from one read of /proc/stat
+    /// Called once per sample; the same reading feeds idle detection
     fn read_cpu_usage(&self) -> Result<f32> {
-        let stat_content =
-            fs::read_to_string("/proc/stat").map_err(crate::errors::FanCurveError::Io)?;
-
-        let first_line = stat_content
-            .lines()
-            .next()
-            .ok_or_else(|| crate::errors::FanCurveError::Config("Empty /proc/stat".to_string()))?;
-
-        let fields: Vec<&str> = first_line.split_whitespace().collect();
-        if fields.len() < 8 {
-            return Err(crate::errors::FanCurveError::Config(
-                "Invalid /proc/stat format".to_string(),
-            ));
-        }
-
-        // Parse CPU times: user, nice, system, idle, iowait, irq, softirq, steal
-        let user: u64 = fields[1].parse().map_err(|_| {
-            crate::errors::FanCurveError::Config("Failed to parse user time".to_string())
-        })?;
-        let nice: u64 = fields[2].parse().map_err(|_| {
-            crate::errors::FanCurveError::Config("Failed to parse nice time".to_string())
-        })?;
-        let system: u64 = fields[3].parse().map_err(|_| {
-            crate::errors::FanCurveError::Config("Failed to parse system time".to_string())
-        })?;
-        let idle: u64 = fields[4].parse().map_err(|_| {
-            crate::errors::FanCurveError::Config("Failed to parse idle time".to_string())
-        })?;
-        let iowait: u64 = fields[5].parse().map_err(|_| {
-            crate::errors::FanCurveError::Config("Failed to parse iowait time".to_string())
-        })?;
-        let irq: u64 = fields[6].parse().map_err(|_| {
-            crate::errors::FanCurveError::Config("Failed to parse irq time".to_string())
-        })?;
-        let softirq: u64 = fields[7].parse().map_err(|_| {
-            crate::errors::FanCurveError::Config("Failed to parse softirq time".to_string())
-        })?;
-        let steal: u64 = if fields.len() > 8 {
-            fields[8].parse().unwrap_or(0)
-        } else {
-            0
-        };
-
-        let total_idle = idle + iowait;
-        let total_non_idle = user + nice + system + irq + softirq + steal;
-        let total = total_idle + total_non_idle;
-
-        // For a single reading, we can't calculate percentage accurately
-        // This is a simplified approach - in practice, you'd want to store previous values
-        // and calculate the difference over time
-        if total == 0 {
-            return Ok(0.0);
-        }
-
-        let cpu_usage = (total_non_idle as f32 / total as f32) * 100.0;
-        Ok(cpu_usage.clamp(0.0, 100.0))
+        Ok(self.record_cpu_times(CpuTimes::read()?, Instant::now()))
     }
 
-    /// Get CPU model information
-    fn get_cpu_model(&self) -> String {
-        // Try to read CPU model from /proc/cpuinfo
-        if let Ok(content) = std::fs::read_to_string("/proc/cpuinfo") {
-            for line in content.lines() {
-                if line.starts_with("model name") {
-                    if let Some(model) = line.split(':').nth(1) {
-                        return model.trim().to_string();
-                    }
-                }
-            }
+    /// Feed CPU times read at `now` to idle detection and return the usage (%) since the
+    /// previous times; the first sample measures from boot
+    /// Times with no ticks since the previous sample report 0% and are not kept
+    fn record_cpu_times(&self, times: CpuTimes, now: Instant) -> f32 {
+        if let Some(ref idle) = self.idle {
+            idle.lock().unwrap().update(times, now);
         }
-        "Unknown CPU".to_string()
+        let mut previous = self.cpu_times.lock().unwrap();
+        let Some(usage) = times.usage_since(&previous.unwrap_or_default()) else {
+            return 0.0;
+        };
+        *previous = Some(times);
+        log::debug!("Read CPU usage: {:.1}%", usage);
+        usage
     }
 
     /// Read CPU temperature directly from thermal zone files
     fn read_cpu_temperature_direct(&self) -> Result<f32> {
-        // Try different thermal zone paths
+        if let Some(path) = find_cpu_thermal_zone(Path::new(THERMAL_ROOT), self.thermal_zone) {
+            let content = std::fs::read_to_string(&path)?;
+            let temp_millicelsius = content.trim().parse::<f32>().map_err(|e| {
+                crate::errors::FanCurveError::Config(format!(
+                    "Invalid temperature in {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+            let temp_celsius = temp_millicelsius / 1000.0;
+            log::debug!("Read temperature from {}: {:.1}°C", path.display(), temp_celsius);
+            return Ok(temp_celsius);
+        }
+
+        // No zone with a known CPU type, try the first zones
         let thermal_paths = [
             "/sys/class/thermal/thermal_zone0/temp",
             "/sys/class/thermal/thermal_zone1/temp",
@@ -931,7 +2262,7 @@ impl FanMonitor {
         let mut fan_speeds = Vec::new();
         
         // Look for hwmon directories
-        if let Ok(entries) = std::fs::read_dir("/sys/class/hwmon") {
+        if let Ok(entries) = std::fs::read_dir(crate::hwmon_root()) {
             for entry in entries.flatten() {
                 let hwmon_path = entry.path();
                 if let Some(_hwmon_name) = hwmon_path.file_name() {
@@ -972,7 +2303,7 @@ impl FanMonitor {
     /// Read current fan duty directly from hwmon PWM files
     fn read_current_fan_duty_direct(&self) -> Result<u16> {
         // Look for hwmon directories
-        if let Ok(entries) = std::fs::read_dir("/sys/class/hwmon") {
+        if let Ok(entries) = std::fs::read_dir(crate::hwmon_root()) {
             for entry in entries.flatten() {
                 let hwmon_path = entry.path();
                 
@@ -983,12 +2314,11 @@ impl FanMonitor {
                         if pwm_name.starts_with("pwm") && !pwm_name.contains("_") {
                             let pwm_path = pwm_entry.path();
                             if let Ok(content) = std::fs::read_to_string(&pwm_path) {
-                                if let Ok(pwm_value) = content.trim().parse::<u16>() {
-                                    // Convert PWM (0-255) to duty percentage (0-10000)
-                                    let duty_percentage = (pwm_value as f32 / 255.0 * 10000.0) as u16;
-                                    log::debug!("Read fan duty from {:?}: PWM={}, Duty={:.1}%", 
-                                        pwm_path, pwm_value, duty_percentage as f32 / 100.0);
-                                    return Ok(duty_percentage);
+                                if let Ok(pwm_value) = content.trim().parse::<u8>() {
+                                    let duty = Duty::from_pwm(pwm_value);
+                                    log::debug!("Read fan duty from {:?}: PWM={}, Duty={}", 
+                                        pwm_path, pwm_value, duty);
+                                    return Ok(duty.as_ten_thousandths());
                                 }
                             }
                         }
@@ -1002,101 +2332,48 @@ impl FanMonitor {
         ))
     }
 
-    /// Read CPU usage from /proc/stat
-    fn read_cpu_usage_direct(&self) -> Result<f32> {
-        if let Ok(content) = std::fs::read_to_string("/proc/stat") {
-            if let Some(first_line) = content.lines().next() {
-                let parts: Vec<&str> = first_line.split_whitespace().collect();
-                if parts.len() >= 8 {
-                    // Parse CPU times: user, nice, system, idle, iowait, irq, softirq, steal
-                    let user: u64 = parts[1].parse().unwrap_or(0);
-                    let nice: u64 = parts[2].parse().unwrap_or(0);
-                    let system: u64 = parts[3].parse().unwrap_or(0);
-                    let idle: u64 = parts[4].parse().unwrap_or(0);
-                    let iowait: u64 = parts[5].parse().unwrap_or(0);
-                    let irq: u64 = parts[6].parse().unwrap_or(0);
-                    let softirq: u64 = parts[7].parse().unwrap_or(0);
-                    let steal: u64 = parts.get(8).and_then(|s| s.parse().ok()).unwrap_or(0);
-
-                    let total_idle = idle + iowait;
-                    let total_non_idle = user + nice + system + irq + softirq + steal;
-                    let total = total_idle + total_non_idle;
-
-                    // For simplicity, return a basic calculation
-                    // In a real implementation, you'd need to track previous values
-                    let usage_percent = if total > 0 {
-                        (total_non_idle as f32 / total as f32) * 100.0
-                    } else {
-                        0.0
-                    };
-
-                    log::debug!("Read CPU usage: {:.1}%", usage_percent);
-                    return Ok(usage_percent);
-                }
-            }
-        }
-
-        Err(crate::errors::FanCurveError::Config(
-            "Could not read CPU usage from /proc/stat".to_string()
-        ))
-    }
-
     /// Check if System76 Power D-Bus client is initialized
     pub fn is_system76_power_initialized(&self) -> bool {
         self.system76_power_client.is_some()
     }
 
     /// Synchronous method to initialize System76 Power client
+    /// Runs on the shared runtime, see [`FanMonitor::block_on`]
     pub fn initialize_system76_power_sync(&mut self) -> Result<()> {
         log::debug!("FanMonitor::initialize_system76_power_sync called");
-        
-        match System76PowerClient::new_sync() {
-            Ok(client) => {
-                log::debug!("System76PowerClient::new_sync() succeeded");
-                
-                // Check if service is available by testing a simple call
-                // Use the same separate thread approach to avoid GUI conflicts
-                let (tx, rx) = std::sync::mpsc::channel();
-                let client_clone = client.clone();
-                
-                std::thread::spawn(move || {
-                    let rt = match tokio::runtime::Runtime::new() {
-                        Ok(rt) => {
-                            log::debug!("Created Tokio runtime for availability check in separate thread");
-                            rt
-                        }
-                        Err(e) => {
-                            log::error!("Failed to create Tokio runtime for availability check: {}", e);
-                            let _ = tx.send(Err(crate::errors::FanCurveError::Unknown(format!("Failed to create Tokio runtime: {}", e))));
-                            return;
-                        }
-                    };
-                    
-                    let is_available = rt.block_on(async {
-                        log::debug!("Checking if System76 Power service is available");
-                        client_clone.is_available().await
-                    });
-                    
-                    log::debug!("System76 Power service available: {}", is_available);
-                    let _ = tx.send(Ok(is_available));
-                });
-                
-                let is_available = rx.recv().map_err(|_| crate::errors::FanCurveError::Unknown("Failed to receive availability check result".to_string()))??;
-                
-                if is_available {
-                    self.system76_power_client = Some(client);
-                    info!("System76 Power client initialized and available");
-                } else {
-                    warn!("System76 Power service not available");
-                }
-                Ok(())
+
+        let result = self.block_on(async {
+            let client = System76PowerClient::new().await?;
+            let is_available = client.is_available().await;
+            Ok::<_, crate::errors::FanCurveError>((client, is_available))
+        })?;
+
+        match result {
+            Ok((client, true)) => {
+                self.system76_power_client = Some(client);
+                info!("System76 Power client initialized and available");
+            }
+            Ok((_, false)) => {
+                warn!("System76 Power service not available");
             }
             Err(e) => {
-                log::error!("System76PowerClient::new_sync() failed: {}", e);
                 warn!("Failed to initialize System76 Power client: {}", e);
-                Ok(()) // Don't fail initialization if System76 Power is not available
             }
         }
+        Ok(()) // Don't fail initialization if System76 Power is not available
+    }
+
+    /// Run a future to completion from synchronous (GUI) code
+    ///
+    /// All synchronous wrappers share one runtime, created on first use; an error is
+    /// returned if it can't be. When called from a thread that is already driving a
+    /// runtime, the future is run from a helper thread so blocking doesn't panic.
+    fn block_on<F>(&self, future: F) -> Result<F::Output>
+    where
+        F: std::future::Future + Send,
+        F::Output: Send,
+    {
+        crate::runtime::block_on(future)
     }
 }
 
@@ -1107,7 +2384,18 @@ impl Default for FanMonitor {
 }
 
 /// Test a fan curve by applying it and monitoring the results
-pub async fn test_fan_curve(curve_name: &str, duration_seconds: u64) -> Result<()> {
+/// Fails with `FanCurveError::FanCurveNotFound` if the config has no curve named `curve_name`
+pub async fn test_fan_curve(
+    curve_name: &str,
+    duration_seconds: u64,
+    poll_interval: Duration,
+) -> Result<()> {
+    let config = crate::fan::FanCurveConfig::load_from_file(
+        &crate::fan::FanCurveConfig::get_config_path(),
+    )
+    .unwrap_or_default();
+    let curve = config.curve(curve_name)?.clone();
+
     println!(
         "🚀 Starting fan curve test: '{}' for {} seconds",
         curve_name, duration_seconds
@@ -1123,6 +2411,9 @@ pub async fn test_fan_curve(curve_name: &str, duration_seconds: u64) -> Result<(
     println!("🎯 Test started! Press Ctrl+C to stop early.\n");
 
     let mut monitor = FanMonitor::new();
+    monitor.set_poll_interval(poll_interval);
+    monitor.configure(&config);
+    monitor.set_fan_curve(curve);
     monitor.initialize()?;
 
     // Initialize System76 Power client
@@ -1168,3 +2459,1035 @@ pub async fn test_fan_curve(curve_name: &str, duration_seconds: u64) -> Result<(
     Ok(())
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{ConfigPathOverride, EnvOverride, TempDir};
+    use crate::temperature::MockTemperatureSource;
+
+    #[test]
+    fn test_temp_source_selection() {
+        assert_eq!(TempSource::default(), TempSource::Cpu);
+        assert_eq!(TempSource::Cpu.select(50.0, Some(70.0)), 50.0);
+        assert_eq!(TempSource::Gpu.select(50.0, Some(70.0)), 70.0);
+        assert_eq!(TempSource::Max.select(80.0, Some(70.0)), 80.0);
+        assert_eq!(TempSource::Max.select(50.0, Some(70.0)), 70.0);
+        // Missing GPU reading falls back to CPU
+        assert_eq!(TempSource::Gpu.select(50.0, None), 50.0);
+    }
+
+    #[test]
+    fn test_fan_detector_scanned_once() {
+        let root = TempDir::new("monitor-scanned-once");
+        root.write("hwmon0/name", "coretemp\n");
+        root.write("hwmon0/temp1_label", "Package id 0\n");
+        root.write("hwmon0/temp1_input", "50000\n");
+        root.write("hwmon2/name", "system76_thelio_io\n");
+        root.write("hwmon2/fan1_label", "CPU Fan\n");
+        root.write("hwmon2/fan1_input", "1000\n");
+        root.write("hwmon2/pwm1", "128\n");
+        root.write("hwmon2/pwm1_enable", "2\n");
+        let _env = EnvOverride::new(crate::HWMON_ROOT_ENV, root.path());
+
+        let monitor = FanMonitor::new();
+        for _ in 0..100 {
+            monitor.read_current_fan_duty_from_pwm().unwrap();
+            monitor.get_current_fan_data_direct().unwrap();
+        }
+        assert_eq!(monitor.fan_detector().scan_count(), 1);
+        assert_eq!(monitor.fan_detector().fan_count(), 1);
+
+        // Clones share the already scanned detector
+        let clone = monitor.clone();
+        let _ = clone.read_current_fan_duty_from_pwm();
+        assert_eq!(clone.fan_detector().scan_count(), 1);
+    }
+
+    #[test]
+    fn test_percent_to_pwm() {
+        assert_eq!(percent_to_pwm(0).unwrap(), 0);
+        assert_eq!(percent_to_pwm(50).unwrap(), 127);
+        assert_eq!(percent_to_pwm(100).unwrap(), 255);
+        assert!(percent_to_pwm(101).is_err());
+    }
+
+    #[test]
+    fn test_unknown_curve_is_rejected() {
+        let dir = TempDir::new("monitor-test-curve");
+        let _config_override = ConfigPathOverride::new(&dir.path().join("config.json"));
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let result = runtime.block_on(test_fan_curve("No Such Curve", 1, Duration::from_secs(1)));
+
+        assert!(matches!(
+            result,
+            Err(crate::errors::FanCurveError::FanCurveNotFound { name }) if name == "No Such Curve"
+        ));
+    }
+
+    #[test]
+    fn test_cpu_thermal_zone_selection() {
+        let root = TempDir::new("monitor-thermal");
+        let add_zone = |number: u32, zone_type: &str| {
+            let zone = root.path().join(format!("thermal_zone{}", number));
+            std::fs::create_dir_all(&zone).unwrap();
+            std::fs::write(zone.join("type"), format!("{}\n", zone_type)).unwrap();
+            std::fs::write(zone.join("temp"), "45000\n").unwrap();
+        };
+        let zone_temp = |number: u32| {
+            Some(
+                root.path()
+                    .join(format!("thermal_zone{}", number))
+                    .join("temp"),
+            )
+        };
+
+        add_zone(0, "iwlwifi_1");
+        add_zone(1, "INT3400 Thermal");
+        assert_eq!(find_cpu_thermal_zone(root.path(), None), None);
+
+        add_zone(3, "acpitz");
+        assert_eq!(find_cpu_thermal_zone(root.path(), None), zone_temp(3));
+        add_zone(12, "x86_pkg_temp");
+        assert_eq!(find_cpu_thermal_zone(root.path(), None), zone_temp(12));
+
+        // An explicit zone overrides the type match
+        assert_eq!(find_cpu_thermal_zone(root.path(), Some(0)), zone_temp(0));
+    }
+
+    #[test]
+    fn test_data_feed_keeps_latest() {
+        let (temperatures, fetched) = mpsc::channel::<f32>();
+        let feed = FanDataFeed::spawn(Duration::from_millis(1), move || {
+            let temperature = fetched
+                .recv()
+                .map_err(|_| crate::errors::FanCurveError::Config("No more data".to_string()))?;
+            Ok(FanDataPoint {
+                timestamp: chrono::Local::now(),
+                temperature,
+                cpu_fan_speeds: Vec::new(),
+                intake_fan_speeds: Vec::new(),
+                gpu_fan_speeds: Vec::new(),
+                fan_duty: 4000,
+                cpu_usage: 0.0,
+                cpu_model: String::new(),
+                cpu_core_count: 0,
+            })
+        });
+
+        // Nothing has been read yet, and asking doesn't wait for the producer
+        assert!(feed.try_latest().is_none());
+
+        let wait_for = |expected: f32| {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while Instant::now() < deadline {
+                if let Some(result) = feed.try_latest() {
+                    if result.unwrap().temperature == expected {
+                        return;
+                    }
+                }
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            panic!("{} never arrived", expected);
+        };
+        temperatures.send(40.0).unwrap();
+        temperatures.send(41.0).unwrap();
+        wait_for(41.0);
+        assert!(feed.try_latest().is_none());
+
+        // Errors reach the consumer too
+        drop(temperatures);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let error = loop {
+            match feed.try_latest() {
+                Some(result) => break result.unwrap_err(),
+                None if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(1)),
+                None => panic!("error never arrived"),
+            }
+        };
+        assert!(error.to_string().contains("No more data"));
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut monitor = FanMonitor::new();
+        for i in 0..(HISTORY_CAPACITY * 3 + 7) {
+            monitor.record_sample(FanDataPoint {
+                timestamp: chrono::Local::now(),
+                temperature: i as f32,
+                cpu_fan_speeds: Vec::new(),
+                intake_fan_speeds: Vec::new(),
+                gpu_fan_speeds: Vec::new(),
+                fan_duty: 0,
+                cpu_usage: 0.0,
+                cpu_model: String::new(),
+                cpu_core_count: 0,
+            });
+            assert!(monitor.history().len() <= HISTORY_CAPACITY);
+        }
+
+        let history = monitor.history();
+        assert_eq!(history.len(), HISTORY_CAPACITY);
+        assert_eq!(history.front().unwrap().temperature, (HISTORY_CAPACITY * 2 + 7) as f32);
+        assert_eq!(history.back().unwrap().temperature, (HISTORY_CAPACITY * 3 + 6) as f32);
+
+        monitor.clear_history();
+        assert!(monitor.history().is_empty());
+    }
+
+    #[test]
+    fn test_observers_notified_on_changes() {
+        #[derive(Default)]
+        struct Counts {
+            duty: Vec<u16>,
+            temp: usize,
+            stalls: Vec<u8>,
+        }
+        struct Counting(Arc<Mutex<Counts>>);
+        impl EventObserver for Counting {
+            fn on_duty_changed(&mut self, duty: u16) {
+                self.0.lock().unwrap().duty.push(duty);
+            }
+            fn on_temp_changed(&mut self, _temp: f32) {
+                self.0.lock().unwrap().temp += 1;
+            }
+            fn on_stall(&mut self, fan: u8) {
+                self.0.lock().unwrap().stalls.push(fan);
+            }
+        }
+
+        let counts = Arc::new(Mutex::new(Counts::default()));
+        let mut monitor = FanMonitor::new();
+        let mut curve = crate::fan::FanCurve::standard();
+        curve.set_spin_up(5000, 1000);
+        monitor.set_fan_curve(curve);
+        monitor.add_observer(Box::new(Counting(counts.clone())));
+        monitor.add_observer(Box::new(crate::events::LoggingObserver));
+        monitor.add_observer(Box::new(crate::events::NoopObserver));
+
+        let sample = |temperature: f32, fan_duty: u16, rpms: &[u16]| FanDataPoint {
+            timestamp: chrono::Local::now(),
+            temperature,
+            cpu_fan_speeds: (1..).zip(rpms).map(|(n, &rpm)| (n, rpm, "CPU Fan".to_string())).collect(),
+            intake_fan_speeds: Vec::new(),
+            gpu_fan_speeds: Vec::new(),
+            fan_duty,
+            cpu_usage: 0.0,
+            cpu_model: String::new(),
+            cpu_core_count: 0,
+        };
+        monitor.record_sample(sample(50.0, 3000, &[1200, 1100]));
+        monitor.record_sample(sample(50.0, 3000, &[1200, 1100]));
+        monitor.record_sample(sample(55.0, 3000, &[1250, 1100]));
+        monitor.record_sample(sample(55.0, 4000, &[1300, 0]));
+        // Reported once while it stays stalled, and again after it recovers
+        monitor.record_sample(sample(55.0, 4000, &[1300, 0]));
+        monitor.record_sample(sample(55.0, 4000, &[1300, 900]));
+        monitor.record_sample(sample(55.0, 4000, &[1300, 0]));
+        // Stopped on purpose below the spin-up threshold
+        monitor.record_sample(sample(40.0, 800, &[0, 0]));
+
+        let counts = counts.lock().unwrap();
+        assert_eq!(counts.duty, [3000, 4000, 800]);
+        assert_eq!(counts.temp, 3);
+        assert_eq!(counts.stalls, [2, 2]);
+    }
+
+    #[test]
+    fn test_spin_up_kicks_once() {
+        let hwmon = TempDir::new("monitor-spin-up");
+        let mut monitor = FanMonitor::new();
+        let _ = monitor.fan_detector.set(FanDetector::fake(&hwmon));
+
+        let mut curve = crate::fan::FanCurve::new("Kick".to_string());
+        curve.add_point(40, 0);
+        curve.add_point(50, 2000);
+        curve.add_point(100, 10000);
+        curve.set_spin_up(5000, 0);
+        monitor.set_fan_curve(curve);
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let apply = |temperature: f32| {
+            runtime.block_on(monitor.apply_fan_curve(temperature)).unwrap();
+            std::fs::read_to_string(hwmon.path().join("pwm1"))
+                .unwrap()
+                .trim()
+                .parse::<u8>()
+                .unwrap()
+        };
+
+        let kick = Duty::from_ten_thousandths(5000).as_pwm();
+        let target = Duty::from_ten_thousandths(2000).as_pwm();
+        assert_eq!(apply(30.0), 0);
+        assert_eq!(apply(50.0), kick);
+        assert_eq!(apply(50.0), target);
+        assert_eq!(apply(50.0), target);
+
+        // Stopping and starting again kicks again
+        assert_eq!(apply(30.0), 0);
+        assert_eq!(apply(50.0), kick);
+    }
+
+    #[test]
+    fn test_auto_fan_skipped_by_curve() {
+        let hwmon = TempDir::new("monitor-fan-control");
+        let mut monitor = FanMonitor::new();
+        let _ = monitor
+            .fan_detector
+            .set(FanDetector::fake_with_labels(&hwmon, &["CPU Fan", "GPU Fan"]));
+        // The GPU fan was left in manual mode by an earlier run
+        hwmon.write("pwm2_enable", "1\n");
+
+        let mut curve = crate::fan::FanCurve::new("Flat".to_string());
+        curve.add_point(0, 6000);
+        curve.add_point(100, 6000);
+        monitor.set_fan_curve(curve);
+        monitor.set_fan_control(HashMap::from([("GPU Fan".to_string(), FanControlMode::Auto)]));
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let read = |file: &str| {
+            std::fs::read_to_string(hwmon.path().join(file))
+                .unwrap()
+                .trim()
+                .to_string()
+        };
+
+        runtime.block_on(monitor.apply_fan_curve(50.0)).unwrap();
+        assert_eq!(read("pwm1"), Duty::from_ten_thousandths(6000).as_pwm().to_string());
+        assert_eq!(read("pwm1_enable"), "1");
+        assert_eq!(read("pwm2"), "100");
+        assert_eq!(read("pwm2_enable"), "2");
+
+        monitor.set_fan_control(HashMap::from([(
+            "GPU Fan".to_string(),
+            FanControlMode::Manual(40),
+        )]));
+        runtime.block_on(monitor.apply_fan_curve(50.0)).unwrap();
+        assert_eq!(read("pwm1"), Duty::from_ten_thousandths(6000).as_pwm().to_string());
+        assert_eq!(read("pwm2"), Duty::from_percent(40.0).as_pwm().to_string());
+        assert_eq!(read("pwm2_enable"), "1");
+    }
+
+    #[test]
+    fn test_linked_fan_follows_leader() {
+        let hwmon = TempDir::new("monitor-fan-link");
+        let mut monitor = FanMonitor::new();
+        let _ = monitor
+            .fan_detector
+            .set(FanDetector::fake_with_labels(&hwmon, &["Intake Fan", "Exhaust Fan"]));
+        monitor.set_fan_curve(crate::fan::FanCurve::standard());
+        monitor.set_critical_override(100.0, 10000);
+        monitor.set_fan_links(vec![FanLink {
+            follower: "Exhaust Fan".to_string(),
+            leader: "Intake Fan".to_string(),
+            offset: 1000,
+        }]);
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let apply = |temperature: f32| {
+            runtime.block_on(monitor.apply_fan_curve(temperature)).unwrap();
+            let read = |file: &str| {
+                std::fs::read_to_string(hwmon.path().join(file))
+                    .unwrap()
+                    .trim()
+                    .parse::<u8>()
+                    .unwrap()
+            };
+            (read("pwm1"), read("pwm2"))
+        };
+        let pwm = |duty: u16| Duty::from_ten_thousandths(duty).as_pwm();
+
+        assert_eq!(apply(40.0), (pwm(3000), pwm(4000)));
+        assert_eq!(apply(65.0), (pwm(5500), pwm(6500)));
+        // Clamped at 100%
+        assert_eq!(apply(95.0), (pwm(9000), 255));
+        assert_eq!(apply(100.0), (255, 255));
+    }
+
+    #[test]
+    fn test_per_fan_control_bypasses_daemon() {
+        let mut monitor = FanMonitor::new();
+        assert!(!monitor.has_per_fan_control());
+
+        monitor.set_fan_control(HashMap::from([("CPU Fan".to_string(), FanControlMode::Curve)]));
+        assert!(!monitor.has_per_fan_control());
+
+        monitor.set_fan_control(HashMap::from([("GPU Fan".to_string(), FanControlMode::Auto)]));
+        assert!(monitor.has_per_fan_control());
+
+        monitor.set_fan_control(HashMap::new());
+        monitor.set_fan_links(vec![FanLink {
+            follower: "Exhaust Fan".to_string(),
+            leader: "Intake Fan".to_string(),
+            offset: 0,
+        }]);
+        assert!(monitor.has_per_fan_control());
+    }
+
+    #[test]
+    fn test_linked_follower_skips_its_own_mode() {
+        use crate::closed_loop::{RpmPoint, RpmTarget};
+
+        let hwmon = TempDir::new("monitor-fan-link-rpm");
+        let mut monitor = FanMonitor::new();
+        let _ = monitor
+            .fan_detector
+            .set(FanDetector::fake_with_labels(&hwmon, &["Intake Fan", "Exhaust Fan"]));
+        hwmon.write("fan2_input", "0\n");
+        monitor.set_fan_curve(crate::fan::FanCurve::standard());
+        monitor.set_fan_control(HashMap::from([(
+            "Exhaust Fan".to_string(),
+            FanControlMode::TargetRpm,
+        )]));
+        monitor.set_rpm_targets(HashMap::from([(
+            "Exhaust Fan".to_string(),
+            RpmTarget::new(vec![RpmPoint::new(40, 1000), RpmPoint::new(80, 2000)]),
+        )]));
+        monitor.set_fan_links(vec![FanLink {
+            follower: "Exhaust Fan".to_string(),
+            leader: "Intake Fan".to_string(),
+            offset: 1000,
+        }]);
+
+        assert_eq!(monitor.fan_duties(40.0, 3000), [(1, 3000), (2, 4000)]);
+        // The follower's PI controller never ran on a duty that isn't written
+        assert!(monitor.closed_loop.lock().unwrap().is_empty());
+
+        // A leader left to the firmware has no duty, so the follower uses its own mode again
+        monitor.set_fan_control(HashMap::from([
+            ("Intake Fan".to_string(), FanControlMode::Auto),
+            ("Exhaust Fan".to_string(), FanControlMode::TargetRpm),
+        ]));
+        let duties = monitor.fan_duties(40.0, 3000);
+        assert_eq!(duties.len(), 1);
+        assert_eq!(duties[0].0, 2);
+        assert!(monitor.closed_loop.lock().unwrap().contains_key(&2));
+    }
+
+    #[test]
+    fn test_rpm_mode_follows_measured_speed() {
+        use crate::closed_loop::{RpmPoint, RpmTarget};
+
+        let hwmon = TempDir::new("monitor-rpm-mode");
+        let mut monitor = FanMonitor::new();
+        let _ = monitor.fan_detector.set(FanDetector::fake(&hwmon));
+        let mut curve = crate::fan::FanCurve::new("Flat".to_string());
+        curve.add_point(0, 3000);
+        curve.add_point(100, 3000);
+        monitor.set_fan_curve(curve);
+        monitor.set_fan_control(HashMap::from([(
+            "CPU Fan".to_string(),
+            FanControlMode::TargetRpm,
+        )]));
+
+        // Without a target the fan follows the curve
+        assert_eq!(monitor.fan_duties(50.0, 3000), [(1, 3000)]);
+
+        monitor.set_rpm_targets(HashMap::from([(
+            "CPU Fan".to_string(),
+            RpmTarget::new(vec![RpmPoint::new(40, 1000), RpmPoint::new(80, 2000)]),
+        )]));
+        // Nor can it without a speed reading
+        assert_eq!(monitor.fan_duties(50.0, 3000), [(1, 3000)]);
+
+        // A stopped fan far below its 1250 RPM target is driven harder each second
+        hwmon.write("fan1_input", "0\n");
+        let fan = monitor.fan_detector().get_fans()[0].clone();
+        let start = Instant::now();
+        let duty_at = |seconds| {
+            monitor
+                .closed_loop_duty(&fan, 50.0, start + Duration::from_secs(seconds))
+                .unwrap()
+        };
+        let first = duty_at(0);
+        let second = duty_at(1);
+        assert!(first > 0);
+        assert!(second > first);
+
+        // Well above the target, it backs off
+        hwmon.write("fan1_input", "2500\n");
+        assert!(duty_at(2) < second);
+
+        // Setting the same targets again keeps the controller, changing them starts it over
+        monitor.set_rpm_targets(monitor.rpm_targets.clone());
+        assert!(monitor.closed_loop.lock().unwrap().contains_key(&1));
+        monitor.set_rpm_targets(HashMap::from([(
+            "CPU Fan".to_string(),
+            RpmTarget::new(vec![RpmPoint::new(40, 1200), RpmPoint::new(80, 2000)]),
+        )]));
+        assert!(monitor.closed_loop.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rpm_target_on_detected_case_fan() {
+        use crate::closed_loop::{RpmPoint, RpmTarget};
+
+        let root = TempDir::new("monitor-rpm-detected");
+        root.write("hwmon2/name", "system76_thelio_io\n");
+        for (n, label, rpm) in [(1, "Intake Fan", 0), (2, "CPU Fan", 1200)] {
+            root.write(&format!("hwmon2/fan{}_label", n), &format!("{}\n", label));
+            root.write(&format!("hwmon2/fan{}_input", n), &format!("{}\n", rpm));
+            root.write(&format!("hwmon2/pwm{}", n), "80\n");
+            root.write(&format!("hwmon2/pwm{}_enable", n), "2\n");
+        }
+        let mut detector = FanDetector::with_hwmon_root(root.path());
+        detector.initialize().unwrap();
+
+        let mut monitor = FanMonitor::new();
+        let _ = monitor.fan_detector.set(detector);
+        monitor.set_fan_curve(crate::fan::FanCurve::standard());
+        monitor.set_fan_control(HashMap::from([(
+            "Intake Fan".to_string(),
+            FanControlMode::TargetRpm,
+        )]));
+        let intake_target = RpmTarget::new(vec![RpmPoint::new(40, 1000), RpmPoint::new(80, 2000)]);
+        monitor.set_rpm_targets(HashMap::from([("Intake Fan".to_string(), intake_target.clone())]));
+
+        // The CPU fan comes first and follows the curve, the stopped intake fan is driven up
+        let duties = monitor.fan_duties(50.0, 3000);
+        assert_eq!(duties[0], (2, 3000));
+        assert_eq!(duties[1].0, 1);
+        assert!(duties[1].1 > 0);
+        assert!(monitor.closed_loop.lock().unwrap().contains_key(&1));
+
+        // Adding a target for the CPU fan leaves the intake fan's controller running
+        monitor.set_rpm_targets(HashMap::from([
+            ("Intake Fan".to_string(), intake_target),
+            (
+                "CPU Fan".to_string(),
+                RpmTarget::new(vec![RpmPoint::new(40, 900), RpmPoint::new(80, 1800)]),
+            ),
+        ]));
+        assert!(monitor.closed_loop.lock().unwrap().contains_key(&1));
+    }
+
+    #[test]
+    fn test_scripted_temperatures_drive_fans() {
+        let hwmon = TempDir::new("monitor-scripted");
+        let mut monitor = FanMonitor::new();
+        let _ = monitor.fan_detector.set(FanDetector::fake(&hwmon));
+        monitor.set_fan_curve(crate::fan::FanCurve::standard());
+        monitor.set_temperature_reader(Arc::new(MockTemperatureSource::new([
+            25.0, 35.0, 65.0, 100.0, 40.0,
+        ])));
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let step = || {
+            runtime.block_on(async {
+                let temperature = monitor.read_cpu_temperature_async().await.unwrap();
+                monitor.apply_fan_curve(temperature).await.unwrap();
+            });
+            std::fs::read_to_string(hwmon.path().join("pwm1"))
+                .unwrap()
+                .trim()
+                .parse::<u8>()
+                .unwrap()
+        };
+
+        let pwm = |duty: u16| Duty::from_ten_thousandths(duty).as_pwm();
+        let expected: Vec<u8> = [1667, 2500, 5500, 10000, 3000].into_iter().map(pwm).collect();
+        let observed: Vec<u8> = (0..expected.len()).map(|_| step()).collect();
+        assert_eq!(observed, expected);
+
+        // The last reading repeats once the script runs out
+        assert_eq!(step(), pwm(3000));
+    }
+
+    #[test]
+    fn test_read_failures_keep_fans_controlled() {
+        let hwmon = TempDir::new("monitor-read-failures");
+        let mut monitor = FanMonitor::new();
+        let _ = monitor.fan_detector.set(FanDetector::fake(&hwmon));
+        monitor.set_fan_curve(crate::fan::FanCurve::standard());
+
+        let sample = |temperature: f32| FanDataPoint {
+            timestamp: chrono::Local::now(),
+            temperature,
+            cpu_fan_speeds: vec![(1, 1200, "CPU Fan".to_string())],
+            intake_fan_speeds: Vec::new(),
+            gpu_fan_speeds: Vec::new(),
+            fan_duty: 0,
+            cpu_usage: 0.0,
+            cpu_model: String::new(),
+            cpu_core_count: 0,
+        };
+        let failure = || Err(crate::errors::FanCurveError::Config("read failed".to_string()));
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let start = Instant::now();
+        let mut step = |sample: Result<FanDataPoint>, secs: u64| {
+            let now = start + Duration::from_secs(secs);
+            let ok = runtime.block_on(monitor.control_from_sample(sample, now)).is_ok();
+            let pwm = std::fs::read_to_string(hwmon.path().join("pwm1"))
+                .unwrap()
+                .trim()
+                .parse::<u8>()
+                .unwrap();
+            (ok, pwm)
+        };
+
+        let curve_pwm = Duty::from_ten_thousandths(3000).as_pwm();
+        assert_eq!(step(Ok(sample(40.0)), 0), (true, curve_pwm));
+        // Intermittent failures keep following the last good reading
+        assert_eq!(step(failure(), 1), (false, curve_pwm));
+        assert_eq!(step(failure(), 2), (false, curve_pwm));
+        assert_eq!(step(Ok(sample(40.0)), 3), (true, curve_pwm));
+
+        // Too many failures in a row go to the critical duty
+        for secs in 4..4 + u64::from(MAX_READ_FAILURES) - 1 {
+            assert_eq!(step(failure(), secs), (false, curve_pwm));
+        }
+        assert_eq!(step(failure(), 8), (false, 255));
+        assert_eq!(step(Ok(sample(40.0)), 9), (true, curve_pwm));
+
+        // So does a single failure once the last reading is too old
+        let stale = 9 + MAX_SAMPLE_AGE.as_secs() + 1;
+        assert_eq!(step(failure(), stale), (false, 255));
+    }
+
+    #[test]
+    fn test_sample_without_system76_power_reads_local_sensors() {
+        let root = TempDir::new("monitor-local-sample");
+        root.write("hwmon0/name", "coretemp\n");
+        root.write("hwmon0/temp1_label", "Package id 0\n");
+        root.write("hwmon0/temp1_input", "55000\n");
+        let _env = EnvOverride::new(crate::HWMON_ROOT_ENV, root.path());
+        let hwmon = TempDir::new("monitor-local-sample-fans");
+        let mut monitor = FanMonitor::new();
+        let _ = monitor.fan_detector.set(FanDetector::fake(&hwmon));
+        hwmon.write("fan1_input", "1200\n");
+        monitor.set_fan_curve(crate::fan::FanCurve::standard());
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let sample = runtime.block_on(monitor.read_sample());
+        assert_eq!(sample.as_ref().unwrap().temperature, 55.0);
+        runtime
+            .block_on(monitor.control_from_sample(sample, Instant::now()))
+            .unwrap();
+        assert_eq!(monitor.read_failures.consecutive, 0);
+        let expected = crate::fan::FanCurve::standard().calculate_duty_for_temperature(55_000);
+        assert_eq!(
+            std::fs::read_to_string(hwmon.path().join("pwm1")).unwrap().trim(),
+            Duty::from_ten_thousandths(expected).as_pwm().to_string()
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_leave_monitor_state_alone() {
+        let root = TempDir::new("monitor-diagnostics");
+        root.write("hwmon0/name", "coretemp\n");
+        root.write("hwmon0/temp1_label", "Package id 0\n");
+        root.write("hwmon0/temp1_input", "90000\n");
+        let _env = EnvOverride::new(crate::HWMON_ROOT_ENV, root.path());
+        let hwmon = TempDir::new("monitor-diagnostics-fans");
+        let mut monitor = FanMonitor::new();
+        let _ = monitor.fan_detector.set(FanDetector::fake(&hwmon));
+        hwmon.write("fan1_input", "1200\n");
+        monitor.set_fan_curve(crate::fan::FanCurve::standard());
+        monitor.set_temp_filter_alpha(Some(0.5));
+        monitor.set_overheat_warning(Some(80.0));
+
+        let snapshot = monitor.capture_diagnostics();
+        assert_eq!(snapshot.fan_data.unwrap().temperature, 90.0);
+        assert_eq!(*monitor.smoothed_temp.lock().unwrap(), None);
+        // The alert hasn't seen the 90°C reading, so it still fires on the first sample
+        let alert = monitor.overheat.as_ref().unwrap();
+        assert!(alert.lock().unwrap().update(90.0, Instant::now()).is_some());
+    }
+
+    #[test]
+    fn test_gpu_temperature_read_once_per_sample() {
+        let root = TempDir::new("monitor-gpu-sample");
+        root.write("hwmon0/name", "coretemp\n");
+        root.write("hwmon0/temp1_label", "Package id 0\n");
+        root.write("hwmon0/temp1_input", "40000\n");
+        root.write("hwmon1/name", "amdgpu\n");
+        root.write("hwmon1/temp1_input", "80000\n");
+        let _env = EnvOverride::new(crate::HWMON_ROOT_ENV, root.path());
+        let hwmon = TempDir::new("monitor-gpu-sample-fans");
+        let mut monitor = FanMonitor::new();
+        let _ = monitor.fan_detector.set(FanDetector::fake(&hwmon));
+        hwmon.write("fan1_input", "1200\n");
+        monitor.set_fan_curve(crate::fan::FanCurve::standard());
+        monitor.set_temperature_source(TempSource::Max);
+        let standard = crate::fan::FanCurve::standard();
+
+        assert_eq!(monitor.get_current_fan_data_direct().unwrap().temperature, 40.0);
+        assert_eq!(monitor.expected_duty(40.0), standard.calculate_duty_for_temperature(80_000));
+
+        // Duty calculations reuse the sampled reading until the next sample
+        root.write("hwmon1/temp1_input", "60000\n");
+        assert_eq!(monitor.expected_duty(40.0), standard.calculate_duty_for_temperature(80_000));
+        monitor.get_current_fan_data_direct().unwrap();
+        assert_eq!(monitor.expected_duty(40.0), standard.calculate_duty_for_temperature(60_000));
+    }
+
+    #[test]
+    fn test_duty_read_back_in_fan_pwm_range() {
+        let root = TempDir::new("monitor-pwm-range");
+        root.write("hwmon1/name", "system76\n");
+        root.write("hwmon1/fan1_label", "CPU Fan\n");
+        root.write("hwmon1/fan1_input", "1500\n");
+        root.write("hwmon1/pwm1", "100\n");
+        root.write("hwmon1/pwm1_max", "100\n");
+        root.write("hwmon1/pwm1_enable", "1\n");
+        let mut detector = FanDetector::with_hwmon_root(root.path());
+        detector.initialize().unwrap();
+        let monitor = FanMonitor::new();
+        let _ = monitor.fan_detector.set(detector);
+
+        assert_eq!(monitor.read_current_fan_duty_from_pwm().unwrap(), 10000);
+        root.write("hwmon1/pwm1", "50\n");
+        assert_eq!(
+            monitor.read_current_fan_duty_from_pwm().unwrap(),
+            Duty::from_pwm(128).as_ten_thousandths()
+        );
+    }
+
+    #[test]
+    fn test_unchanged_duty_is_not_rewritten() {
+        let hwmon = TempDir::new("monitor-duty-cache");
+        let mut monitor = FanMonitor::new();
+        let _ = monitor.fan_detector.set(FanDetector::fake(&hwmon));
+        monitor.set_fan_curve(crate::fan::FanCurve::standard());
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let poll = |temperature: f32| {
+            runtime.block_on(monitor.apply_fan_curve(temperature)).unwrap();
+            std::fs::read_to_string(hwmon.path().join("pwm1"))
+                .unwrap()
+                .trim()
+                .parse::<u8>()
+                .unwrap()
+        };
+
+        let curve_pwm = Duty::from_ten_thousandths(3000).as_pwm();
+        assert_eq!(poll(40.0), curve_pwm);
+        // Anything written now shows whether the next polls write again
+        hwmon.write("pwm1", "7\n");
+        assert_eq!(poll(40.0), 7);
+        assert_eq!(poll(40.2), 7);
+
+        // A new duty is written
+        assert_eq!(poll(65.0), Duty::from_ten_thousandths(5500).as_pwm());
+
+        // Forcing rewrites the same duty
+        hwmon.write("pwm1", "7\n");
+        assert_eq!(poll(65.0), 7);
+        monitor.force_update();
+        assert_eq!(poll(65.0), Duty::from_ten_thousandths(5500).as_pwm());
+    }
+
+    #[test]
+    fn test_curve_duty_cache_matches_curve_degrees() {
+        let standard = crate::fan::FanCurve::standard();
+        let mut monitor = FanMonitor::new();
+        monitor.set_fan_curve(standard.clone());
+
+        // 40.6°C and 41.4°C round to the same degree but the curve reads different ones
+        assert_eq!(monitor.expected_duty(40.6), standard.calculate_duty_for_temperature(40_600));
+        assert_eq!(monitor.expected_duty(41.4), standard.calculate_duty_for_temperature(41_400));
+        assert_ne!(monitor.expected_duty(40.6), monitor.expected_duty(41.4));
+    }
+
+    #[test]
+    fn test_boost_overrides_then_expires() {
+        let hwmon = TempDir::new("monitor-boost");
+        let mut monitor = FanMonitor::new();
+        let _ = monitor.fan_detector.set(FanDetector::fake(&hwmon));
+        monitor.set_fan_curve(crate::fan::FanCurve::standard());
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let apply = |temperature: f32| {
+            runtime.block_on(monitor.apply_fan_curve(temperature)).unwrap();
+            std::fs::read_to_string(hwmon.path().join("pwm1"))
+                .unwrap()
+                .trim()
+                .parse::<u8>()
+                .unwrap()
+        };
+
+        let curve_pwm = Duty::from_ten_thousandths(3000).as_pwm();
+        assert_eq!(apply(40.0), curve_pwm);
+
+        monitor.set_boost(10000, Duration::from_millis(100));
+        assert!(monitor.boost_remaining().is_some());
+        assert_eq!(apply(40.0), 255);
+
+        std::thread::sleep(Duration::from_millis(150));
+        assert_eq!(apply(40.0), curve_pwm);
+        assert!(monitor.boost_remaining().is_none());
+
+        // Clearing ends a boost early
+        monitor.set_boost(10000, Duration::from_secs(60));
+        monitor.clear_boost();
+        assert_eq!(apply(40.0), curve_pwm);
+    }
+
+    #[test]
+    fn test_poll_interval() {
+        let mut monitor = FanMonitor::new();
+        assert_eq!(monitor.poll_interval(), Duration::from_secs(1));
+
+        monitor.set_poll_interval(Duration::from_millis(250));
+        assert_eq!(monitor.poll_interval(), Duration::from_millis(250));
+
+        monitor.set_poll_interval(Duration::ZERO);
+        assert_eq!(monitor.poll_interval(), MIN_POLL_INTERVAL);
+    }
+
+    #[test]
+    fn test_critical_temp_overrides_quiet_curve() {
+        let mut quiet = crate::fan::FanCurve::new("Too Quiet".to_string());
+        quiet.add_point(0, 2000);
+        quiet.add_point(100, 2000);
+
+        let mut monitor = FanMonitor::new();
+        monitor.set_fan_curve(quiet);
+        assert_eq!(monitor.calculate_fan_duty_from_curve(85.0), 2000);
+        assert_eq!(monitor.calculate_fan_duty_from_curve(95.0), 10000);
+        assert_eq!(monitor.calculate_fan_duty_from_curve(60.0), 2000);
+
+        // The override only raises the duty, a louder curve keeps its own
+        monitor.set_critical_override(80.0, 1500);
+        assert_eq!(monitor.calculate_fan_duty_from_curve(95.0), 2000);
+        monitor.set_critical_override(80.0, 6000);
+        assert_eq!(monitor.calculate_fan_duty_from_curve(85.0), 6000);
+        assert_eq!(monitor.critical_override(), (80.0, 6000));
+    }
+
+    #[test]
+    fn test_duty_calculation_leaves_overheat_warning_to_the_sample() {
+        let mut monitor = FanMonitor::new();
+        monitor.set_fan_curve(crate::fan::FanCurve::standard());
+        monitor.set_overheat_warning(Some(80.0));
+        monitor.calculate_fan_duty_from_curve(90.0);
+        monitor.calculate_fan_duty_from_curve(90.0);
+
+        // The warning is still pending for the once-per-sample check
+        let alert = monitor.overheat.as_ref().unwrap();
+        let event = alert.lock().unwrap().update(90.0, Instant::now());
+        assert_eq!(event, Some(crate::overheat::OverheatEvent::Overheated(90.0)));
+    }
+
+    #[test]
+    fn test_battery_caps_curve_duty() {
+        let supplies = TempDir::new("monitor-power-supply");
+        supplies.write("AC/online", "1\n");
+        supplies.write("BAT0/capacity", "80\n");
+
+        let mut loud = crate::fan::FanCurve::new("Loud".to_string());
+        loud.add_point(0, 8000);
+        loud.add_point(100, 8000);
+        let mut monitor = FanMonitor::new();
+        monitor.power_supply_root = supplies.path().to_path_buf();
+        monitor.set_fan_curve(loud);
+        monitor.set_battery_max_duty(Some(4000));
+
+        // On AC the full range is used
+        assert_eq!(monitor.calculate_fan_duty_from_curve(60.0), 8000);
+
+        supplies.write("AC/online", "0\n");
+        assert_eq!(monitor.calculate_fan_duty_from_curve(60.0), 4000);
+        // The critical override still wins
+        assert_eq!(monitor.calculate_fan_duty_from_curve(95.0), 10000);
+
+        monitor.set_battery_max_duty(None);
+        assert_eq!(monitor.calculate_fan_duty_from_curve(60.0), 8000);
+        assert_eq!(monitor.last_target(), Some((60.0, 8000)));
+
+        // Machines without power supplies count as on AC
+        assert!(!on_battery(&supplies.path().join("missing")));
+    }
+
+    #[test]
+    fn test_expected_duty_follows_curve() {
+        let mut monitor = FanMonitor::new();
+        monitor.set_fan_curve(crate::fan::FanCurve::standard());
+        monitor.set_critical_override(90.0, 9500);
+
+        // Standard: 50°C -> 40%, 60°C -> 50%, 100°C -> 100%
+        assert_eq!(monitor.expected_duty(50.0), 4000);
+        assert_eq!(monitor.expected_duty(55.0), 4500);
+        assert_eq!(monitor.expected_duty(95.0), 9500);
+        assert_eq!(monitor.expected_duty(100.0), 10000);
+
+        let mut quiet = crate::fan::FanCurve::new("Quiet".to_string());
+        quiet.add_point(0, 2000);
+        quiet.add_point(100, 2000);
+        monitor.set_fan_curve(quiet);
+        assert_eq!(monitor.expected_duty(85.0), 2000);
+        assert_eq!(monitor.expected_duty(91.0), 9500);
+        // Checking has no side effects on the critical state
+        assert!(!monitor.critical_active.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_configure_applies_overrides() {
+        let config = crate::fan::FanCurveConfig {
+            critical_temp: 80.0,
+            critical_duty: 9000,
+            temp_filter_alpha: Some(0.5),
+            ..Default::default()
+        };
+        let mut monitor = FanMonitor::new();
+        monitor.configure(&config);
+        monitor.set_fan_curve(crate::fan::FanCurve::standard());
+
+        assert_eq!(monitor.expected_duty(50.0), 4000);
+        assert_eq!(monitor.expected_duty(85.0), 9000);
+
+        // Temperatures are smoothed, and configuring again keeps the average
+        monitor.filter_temperature(40.0);
+        assert_eq!(monitor.filter_temperature(60.0), 50.0);
+        monitor.configure(&config);
+        assert_eq!(monitor.curve_temperature(70.0), 50.0);
+    }
+
+    #[test]
+    fn test_auto_curve_returns_fans_to_firmware() {
+        let hwmon = TempDir::new("monitor-auto-curve");
+        let mut monitor = FanMonitor::new();
+        let _ = monitor.fan_detector.set(FanDetector::fake(&hwmon));
+        let read = |file: &str| {
+            std::fs::read_to_string(hwmon.path().join(file))
+                .unwrap()
+                .trim()
+                .to_string()
+        };
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        monitor.set_fan_curve(crate::fan::FanCurve::standard());
+        runtime.block_on(monitor.apply_fan_curve(40.0)).unwrap();
+        assert_eq!(read("pwm1_enable"), "1");
+        let pwm = read("pwm1");
+
+        monitor.set_fan_curve(crate::fan::FanCurve::auto());
+        runtime.block_on(monitor.apply_fan_curve(95.0)).unwrap();
+        assert_eq!(read("pwm1_enable"), "2");
+        assert_eq!(read("pwm1"), pwm);
+
+        // Nothing is rewritten while the firmware is in control
+        hwmon.write("pwm1_enable", "5\n");
+        runtime.block_on(monitor.apply_fan_curve(95.0)).unwrap();
+        assert_eq!(read("pwm1_enable"), "5");
+    }
+
+    #[test]
+    fn test_temperature_filter_smooths_spikes() {
+        let variance = |values: &[f32]| {
+            let mean = values.iter().sum::<f32>() / values.len() as f32;
+            values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32
+        };
+        let noisy: Vec<f32> = (0..40)
+            .map(|i| match i % 8 {
+                3 => 85.0,
+                _ if i % 2 == 0 => 58.0,
+                _ => 62.0,
+            })
+            .collect();
+
+        let mut monitor = FanMonitor::new();
+        let raw: Vec<f32> = noisy.iter().map(|&t| monitor.filter_temperature(t)).collect();
+        assert_eq!(raw, noisy);
+
+        monitor.set_temp_filter_alpha(Some(0.2));
+        let smoothed: Vec<f32> = noisy.iter().map(|&t| monitor.filter_temperature(t)).collect();
+        assert_eq!(smoothed[0], noisy[0]);
+        assert!(variance(&smoothed) < variance(&noisy) / 4.0);
+        assert!(smoothed.iter().all(|&t| t < 70.0));
+
+        monitor.set_temp_filter_alpha(Some(1.5));
+        assert_eq!(monitor.filter_temperature(85.0), 85.0);
+    }
+
+    #[test]
+    fn test_filter_only_feeds_curve_lookup() {
+        let standard = crate::fan::FanCurve::standard();
+        let mut monitor = FanMonitor::new();
+        monitor.set_fan_curve(standard.clone());
+        monitor.set_temp_filter_alpha(Some(0.5));
+
+        monitor.filter_temperature(40.0);
+        assert_eq!(monitor.filter_temperature(60.0), 50.0);
+        assert_eq!(
+            monitor.calculate_fan_duty_from_curve(60.0),
+            standard.calculate_duty_for_temperature(50_000)
+        );
+
+        // A spike past the critical temperature isn't delayed by the average
+        assert!(monitor.filter_temperature(95.0) < crate::fan::DEFAULT_CRITICAL_TEMP);
+        assert_eq!(monitor.calculate_fan_duty_from_curve(95.0), crate::fan::DEFAULT_CRITICAL_DUTY);
+    }
+
+    #[test]
+    fn test_idle_floor_engages_and_disengages() {
+        let mut monitor = FanMonitor::new();
+        monitor.set_fan_curve(crate::fan::FanCurve::quiet());
+        let lowest = crate::fan::FanCurve::quiet().to_points()[0].duty;
+        let start = Instant::now();
+        let mut busy = 0;
+        // Cumulative CPU times after `second`, with `busy_ticks` of its 100 ticks busy
+        let mut sample = |monitor: &FanMonitor, second: u64, busy_ticks: u64, duty: u16| {
+            busy += busy_ticks;
+            let times = CpuTimes::new(busy, second * 100 - busy);
+            let now = start + Duration::from_secs(second);
+            monitor.record_cpu_times(times, now);
+            monitor.apply_idle_floor(duty, now)
+        };
+
+        // Off by default
+        assert_eq!(sample(&monitor, 1, 0, lowest), lowest);
+
+        monitor.set_idle_floor(Some(IdleFloor {
+            duty: 0,
+            cpu_percent: 5.0,
+            after: Duration::from_secs(30),
+        }));
+        // The first sample only sets the baseline, so idling is timed from the second
+        for second in 2..33 {
+            assert_eq!(sample(&monitor, second, 1, lowest), lowest, "{}", second);
+        }
+        assert_eq!(sample(&monitor, 33, 1, lowest), 0);
+        assert_eq!(sample(&monitor, 34, 2, lowest), 0);
+        // Above the curve's lowest duty the CPU is warm, so the curve wins
+        assert_eq!(sample(&monitor, 35, 1, lowest + 500), lowest + 500);
+
+        // A load spike restores the curve straight away
+        assert_eq!(sample(&monitor, 36, 90, lowest), lowest);
+        assert_eq!(sample(&monitor, 41, 5, lowest), lowest);
+
+        // Usage is measured between samples; a sample without new ticks reports nothing
+        let times = CpuTimes::new(busy + 25, 42 * 100 - busy - 25);
+        assert_eq!(monitor.record_cpu_times(times, start + Duration::from_secs(42)), 25.0);
+        assert_eq!(monitor.record_cpu_times(times, start + Duration::from_secs(43)), 0.0);
+    }
+
+    #[test]
+    fn test_ramp_down_delay_holds_duty() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut monitor = FanMonitor::new();
+
+        // Off by default
+        assert_eq!(monitor.hold_ramp_down(8000, at(0)), 8000);
+        assert_eq!(monitor.hold_ramp_down(3000, at(1)), 3000);
+
+        monitor.set_ramp_down_delay(Duration::from_secs(5));
+        // Spike, then idle: the spike duty is held for five seconds after the drop
+        assert_eq!(monitor.hold_ramp_down(3000, at(10)), 3000);
+        assert_eq!(monitor.hold_ramp_down(8000, at(11)), 8000);
+        assert_eq!(monitor.hold_ramp_down(3000, at(12)), 8000);
+        assert_eq!(monitor.hold_ramp_down(3500, at(14)), 8000);
+        assert_eq!(monitor.hold_ramp_down(3000, at(16)), 8000);
+        assert_eq!(monitor.hold_ramp_down(3000, at(17)), 3000);
+        assert_eq!(monitor.hold_ramp_down(2500, at(18)), 3000);
+
+        // Ramping up is never delayed, and a new spike restarts the hold
+        assert_eq!(monitor.hold_ramp_down(9000, at(19)), 9000);
+        assert_eq!(monitor.hold_ramp_down(6000, at(20)), 9000);
+        assert_eq!(monitor.hold_ramp_down(9500, at(21)), 9500);
+        assert_eq!(monitor.hold_ramp_down(2000, at(25)), 9500);
+        assert_eq!(monitor.hold_ramp_down(2000, at(30)), 2000);
+    }
+}
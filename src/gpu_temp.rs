@@ -0,0 +1,267 @@
+use crate::errors::FanCurveError;
+use crate::errors::Result;
+use log::info;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// hwmon driver names that report GPU temperatures
+const GPU_HWMON_NAMES: &[&str] = &["amdgpu", "nouveau"];
+
+/// Where the GPU temperature is read from
+#[derive(Debug, Clone, PartialEq)]
+pub enum GpuSensorKind {
+    /// A hwmon temperature input (amdgpu, nouveau)
+    Hwmon,
+    /// The proprietary NVIDIA driver via `nvidia-smi`
+    NvidiaSmi,
+}
+
+/// GPU temperature sensor information
+#[derive(Debug, Clone)]
+pub struct GpuTempSensor {
+    pub kind: GpuSensorKind,
+    pub hwmon_path: String,
+    pub temp_input_path: String,
+    pub sensor_name: String,
+}
+
+/// GPU temperature detector
+#[derive(Clone)]
+pub struct GpuTempDetector {
+    sensor: Option<GpuTempSensor>,
+}
+
+impl GpuTempDetector {
+    /// Create a new GPU temperature detector
+    pub fn new() -> Self {
+        Self { sensor: None }
+    }
+
+    /// Initialize the detector by scanning for GPU temperature sensors
+    pub fn initialize(&mut self) -> Result<()> {
+        info!("Initializing GPU temperature detector...");
+
+        let sensor = match self.find_gpu_temp_sensor(&crate::hwmon_root()) {
+            Ok(sensor) => sensor,
+            Err(e) => {
+                if Self::nvidia_smi_available() {
+                    info!("No GPU hwmon sensor found, using nvidia-smi");
+                    GpuTempSensor {
+                        kind: GpuSensorKind::NvidiaSmi,
+                        hwmon_path: String::new(),
+                        temp_input_path: String::new(),
+                        sensor_name: "nvidia-smi".to_string(),
+                    }
+                } else {
+                    return Err(e);
+                }
+            }
+        };
+        self.sensor = Some(sensor);
+
+        info!("GPU temperature sensor initialized: {:?}", self.sensor);
+        Ok(())
+    }
+
+    /// Find a GPU temperature sensor in the given hwmon directory
+    fn find_gpu_temp_sensor(&self, hwmon_dir: &Path) -> Result<GpuTempSensor> {
+        if !hwmon_dir.exists() {
+            return Err(FanCurveError::Config(
+                "Hardware monitoring directory not found".to_string(),
+            ));
+        }
+
+        let mut hwmon_paths = fs::read_dir(hwmon_dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_dir())
+            .collect::<Vec<_>>();
+        hwmon_paths.sort();
+
+        for hwmon_path in hwmon_paths {
+            let sensor_name = match fs::read_to_string(hwmon_path.join("name")) {
+                Ok(name) => name.trim().to_string(),
+                Err(_) => continue,
+            };
+
+            if !GPU_HWMON_NAMES.contains(&sensor_name.as_str()) {
+                continue;
+            }
+
+            if let Some(temp_input_path) = self.find_temp_input_file(&hwmon_path) {
+                return Ok(GpuTempSensor {
+                    kind: GpuSensorKind::Hwmon,
+                    hwmon_path: hwmon_path.to_string_lossy().to_string(),
+                    temp_input_path,
+                    sensor_name,
+                });
+            }
+        }
+
+        Err(FanCurveError::Config(
+            "Could not find GPU temperature sensor".to_string(),
+        ))
+    }
+
+    /// Find the GPU temperature input file, preferring the "edge" sensor on amdgpu
+    fn find_temp_input_file(&self, hwmon_path: &Path) -> Option<String> {
+        for temp_num in 1..=8 {
+            let label_path = hwmon_path.join(format!("temp{}_label", temp_num));
+            let input_path = hwmon_path.join(format!("temp{}_input", temp_num));
+
+            if let Ok(label) = fs::read_to_string(&label_path) {
+                if label.trim() == "edge" && input_path.exists() {
+                    return Some(input_path.to_string_lossy().to_string());
+                }
+            }
+        }
+
+        let temp1_input = hwmon_path.join("temp1_input");
+        if temp1_input.exists() {
+            Some(temp1_input.to_string_lossy().to_string())
+        } else {
+            None
+        }
+    }
+
+    /// Check whether `nvidia-smi` can be run
+    fn nvidia_smi_available() -> bool {
+        Command::new("nvidia-smi")
+            .arg("-L")
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    /// Query the GPU temperature from `nvidia-smi`
+    fn read_nvidia_smi_temperature(&self) -> Result<f32> {
+        let output = Command::new("nvidia-smi")
+            .args([
+                "--query-gpu=temperature.gpu",
+                "--format=csv,noheader,nounits",
+            ])
+            .output()?;
+
+        if !output.status.success() {
+            return Err(FanCurveError::Config(
+                "nvidia-smi failed to report GPU temperature".to_string(),
+            ));
+        }
+
+        Self::parse_nvidia_smi_output(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Parse `nvidia-smi` CSV output, using the first GPU listed
+    fn parse_nvidia_smi_output(output: &str) -> Result<f32> {
+        output
+            .lines()
+            .next()
+            .and_then(|line| line.trim().parse::<f32>().ok())
+            .ok_or_else(|| FanCurveError::Config("Failed to parse nvidia-smi output".to_string()))
+    }
+
+    /// Read the current GPU temperature
+    pub fn read_temperature(&self) -> Result<f32> {
+        let sensor = self.sensor.as_ref().ok_or_else(|| {
+            FanCurveError::Config("GPU temperature sensor not initialized".to_string())
+        })?;
+
+        let temp_celsius = match sensor.kind {
+            GpuSensorKind::NvidiaSmi => self.read_nvidia_smi_temperature()?,
+            GpuSensorKind::Hwmon => {
+                let temp_content = fs::read_to_string(&sensor.temp_input_path)?;
+
+                // Temperature is in millidegrees Celsius
+                let temp_millidegrees: i32 = temp_content.trim().parse().map_err(|_| {
+                    FanCurveError::Config("Failed to parse GPU temperature".to_string())
+                })?;
+
+                temp_millidegrees as f32 / 1000.0
+            }
+        };
+
+        if !(-50.0..=200.0).contains(&temp_celsius) {
+            return Err(FanCurveError::Config(format!(
+                "GPU temperature reading out of range: {:.1}°C",
+                temp_celsius
+            )));
+        }
+
+        Ok(temp_celsius)
+    }
+
+    /// Get sensor information
+    pub fn get_sensor_info(&self) -> Option<&GpuTempSensor> {
+        self.sensor.as_ref()
+    }
+
+    /// Check if the detector is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.sensor.is_some()
+    }
+}
+
+impl Default for GpuTempDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_amdgpu_edge_sensor_detection() {
+        let hwmon = TempDir::new("gpu-amdgpu");
+        hwmon.write("hwmon0/name", "k10temp\n");
+        hwmon.write("hwmon0/temp1_input", "45000\n");
+        hwmon.write("hwmon1/name", "amdgpu\n");
+        hwmon.write("hwmon1/temp1_label", "junction\n");
+        hwmon.write("hwmon1/temp1_input", "71000\n");
+        hwmon.write("hwmon1/temp2_label", "edge\n");
+        hwmon.write("hwmon1/temp2_input", "62000\n");
+
+        let mut detector = GpuTempDetector::new();
+        let sensor = detector.find_gpu_temp_sensor(hwmon.path()).unwrap();
+        assert_eq!(sensor.kind, GpuSensorKind::Hwmon);
+        assert_eq!(sensor.sensor_name, "amdgpu");
+        assert!(sensor.temp_input_path.ends_with("temp2_input"));
+
+        detector.sensor = Some(sensor);
+        assert_eq!(detector.read_temperature().unwrap(), 62.0);
+    }
+
+    #[test]
+    fn test_nouveau_falls_back_to_temp1() {
+        let hwmon = TempDir::new("gpu-nouveau");
+        hwmon.write("hwmon3/name", "nouveau\n");
+        hwmon.write("hwmon3/temp1_input", "50000\n");
+
+        let detector = GpuTempDetector::new();
+        let sensor = detector.find_gpu_temp_sensor(hwmon.path()).unwrap();
+        assert_eq!(sensor.sensor_name, "nouveau");
+        assert!(sensor.temp_input_path.ends_with("temp1_input"));
+    }
+
+    #[test]
+    fn test_no_gpu_sensor() {
+        let hwmon = TempDir::new("gpu-none");
+        hwmon.write("hwmon0/name", "coretemp\n");
+        hwmon.write("hwmon0/temp1_input", "40000\n");
+
+        let detector = GpuTempDetector::new();
+        assert!(detector.find_gpu_temp_sensor(hwmon.path()).is_err());
+        assert!(detector.read_temperature().is_err());
+    }
+
+    #[test]
+    fn test_parse_nvidia_smi_output() {
+        assert_eq!(
+            GpuTempDetector::parse_nvidia_smi_output("54\n61\n").unwrap(),
+            54.0
+        );
+        assert!(GpuTempDetector::parse_nvidia_smi_output("[N/A]\n").is_err());
+    }
+}
@@ -0,0 +1,138 @@
+//! Summary of the detected CPU temperature sensor and fans
+
+use crate::{cpu_temp::CpuTempDetector, fan_detector::FanDetector};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use zvariant::Type;
+
+/// Hardware detected by the application, as returned by `GetHardwareInfo`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct HardwareInfo {
+    /// CPU manufacturer (`Intel`, `Amd` or `Unknown`)
+    pub cpu_manufacturer: String,
+    /// hwmon name of the CPU temperature sensor, empty if none was found
+    pub cpu_sensor_name: String,
+    /// hwmon directory of the System76 fan controller, empty if none was found
+    pub fan_hwmon_path: String,
+    /// Detected fans
+    pub fans: Vec<FanInfo>,
+}
+
+/// A detected fan and its control files
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct FanInfo {
+    pub fan_number: u8,
+    pub label: String,
+    pub input_path: String,
+    pub pwm_path: String,
+    pub pwm_enable_path: String,
+    pub pwm_writable: bool,
+}
+
+impl HardwareInfo {
+    /// Scan the system, keeping whatever could be detected
+    pub fn detect() -> Self {
+        let mut cpu = CpuTempDetector::new();
+        if let Err(e) = cpu.initialize() {
+            warn!("CPU temperature sensor detection failed: {}", e);
+        }
+
+        let mut fans = FanDetector::new();
+        if let Err(e) = fans.initialize() {
+            warn!("Fan detection failed: {}", e);
+        }
+
+        Self::from_detectors(&cpu, &fans)
+    }
+
+    /// Build a summary from already initialized detectors
+    pub fn from_detectors(cpu: &CpuTempDetector, fans: &FanDetector) -> Self {
+        Self {
+            cpu_manufacturer: format!("{:?}", cpu.manufacturer()),
+            cpu_sensor_name: cpu
+                .get_sensor_info()
+                .map(|s| s.sensor_name.clone())
+                .unwrap_or_default(),
+            fan_hwmon_path: fans.hwmon_path().unwrap_or_default().to_string(),
+            fans: fans
+                .get_fans()
+                .iter()
+                .map(|fan| FanInfo {
+                    fan_number: fan.fan_number,
+                    label: fan.fan_label.clone(),
+                    input_path: fan.fan_input_path.clone(),
+                    pwm_path: fan.pwm_path.to_string_lossy().to_string(),
+                    pwm_enable_path: fan.pwm_enable_path.to_string_lossy().to_string(),
+                    pwm_writable: fan.pwm_writable,
+                })
+                .collect(),
+        }
+    }
+}
+
+fn or_none(value: &str) -> &str {
+    if value.is_empty() {
+        "(none)"
+    } else {
+        value
+    }
+}
+
+impl fmt::Display for HardwareInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "CPU manufacturer: {}", self.cpu_manufacturer)?;
+        writeln!(f, "CPU sensor:       {}", or_none(&self.cpu_sensor_name))?;
+        writeln!(f, "Fan controller:   {}", or_none(&self.fan_hwmon_path))?;
+        if self.fans.is_empty() {
+            write!(f, "Fans:             (none)")?;
+        } else {
+            write!(f, "Fans:")?;
+        }
+        for fan in &self.fans {
+            write!(
+                f,
+                "\n  fan{} '{}': input={} pwm={} ({})",
+                fan.fan_number,
+                fan.label,
+                fan.input_path,
+                fan.pwm_path,
+                if fan.pwm_writable {
+                    "writable"
+                } else {
+                    "read-only"
+                }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hardware_info_wire_format() {
+        // Changing this breaks `GetHardwareInfo` for existing clients
+        assert_eq!(HardwareInfo::signature().as_str(), "(sssa(yssssb))");
+
+        let info = HardwareInfo {
+            cpu_manufacturer: "Amd".to_string(),
+            cpu_sensor_name: String::new(),
+            fan_hwmon_path: "/sys/class/hwmon/hwmon3".to_string(),
+            fans: vec![FanInfo {
+                fan_number: 1,
+                label: "CPU Fan".to_string(),
+                input_path: "/sys/class/hwmon/hwmon3/fan1_input".to_string(),
+                pwm_path: "/sys/class/hwmon/hwmon3/pwm1".to_string(),
+                pwm_enable_path: "/sys/class/hwmon/hwmon3/pwm1_enable".to_string(),
+                pwm_writable: false,
+            }],
+        };
+        let text = info.to_string();
+        assert!(text.contains("CPU sensor:       (none)"));
+        assert!(text.contains("fan1 'CPU Fan'"));
+        assert!(text.contains("(read-only)"));
+    }
+}
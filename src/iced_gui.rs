@@ -1,16 +1,23 @@
-use crate::errors::Result;
-use crate::fan::{FanCurve, FanCurveConfig};
-use crate::fan_monitor::FanMonitor;
+use crate::curve_registry::{unique_tags, CurveRegistry};
+use crate::duty::Duty;
+use crate::errors::{FanCurveError, Result};
+use crate::fan::{FanCurve, FanCurveConfig, MIN_POINT_TEMP};
+use crate::fan_monitor::{FanMonitor, SensorOptions};
+use crate::temperature::TempUnit;
 use iced::{
     widget::{button, container, Column, Row, Text, text_input, pick_list},
     Application, Command, Element, Length, Settings, Theme,
     alignment::Alignment,
 };
 
+/// Tag filter entry that shows every curve
+const ALL_TAGS: &str = "All tags";
+
 #[derive(Debug, Clone)]
 pub enum Message {
     // Fan curve selection
     CurveSelected(FanCurve),
+    TagFilterSelected(String),
     
     // Fan curve editing
     AddPoint,
@@ -20,12 +27,19 @@ pub enum Message {
     EditDutyChanged(String),
     SaveEdit,
     CancelEdit,
+    ToggleLock,
+    ResetToPreset,
     
     // Actions
     ApplyFanCurve,
     SetFanDuty(u8),
     SaveAsNewProfile,
+    DeleteCurve(usize),
+    MoveCurve { from: usize, to: usize },
     SetAsDefault,
+    SaveConfig,
+    ToggleTempUnit,
+    ToggleDataSource,
     
     // Profile management
     NewProfileNameChanged(String),
@@ -42,6 +56,10 @@ pub struct FanCurveApp {
     fan_curves: Vec<FanCurve>,
     current_curve_index: usize,
     default_curve_index: Option<usize>,
+    /// Only offer curves with this tag in the profile list
+    tag_filter: Option<String>,
+    /// Curves as last loaded from or written to the config file
+    saved_curves: Vec<FanCurve>,
     
     // UI state
     status_message: Option<String>,
@@ -49,12 +67,18 @@ pub struct FanCurveApp {
     editing_point: Option<usize>,
     edit_temp_input: String,
     edit_duty_input: String,
+    /// Unit temperatures are shown in; points are still edited in Celsius
+    temp_unit: TempUnit,
     
     // Profile saving
     new_profile_name: String,
     
     // Fan monitoring
     fan_monitor: FanMonitor,
+    /// Read live data over DBus when the daemon is reachable, see [`data_source`]
+    use_dbus_data: bool,
+    /// Temperature (°C) for overheat notifications from the config
+    warn_temp: f32,
     current_data: Option<crate::fan_monitor::FanDataPoint>,
     data_error: Option<String>,
 }
@@ -63,54 +87,165 @@ impl FanCurveApp {
     pub fn new() -> Self {
         // Load existing config or use defaults
         let config_path = FanCurveConfig::get_config_path();
-        let (fan_curves, default_curve_index) = if config_path.exists() {
-            match FanCurveConfig::load_from_file(&config_path) {
-                Ok(config) => (config.curves, config.default_curve_index),
-                Err(_) => {
-                    let default_config = FanCurveConfig::new();
-                    (default_config.curves, default_config.default_curve_index)
-                }
-            }
+        let config = if config_path.exists() {
+            FanCurveConfig::load_from_file(&config_path).unwrap_or_default()
         } else {
-            let default_config = FanCurveConfig::new();
-            (default_config.curves, default_config.default_curve_index)
+            FanCurveConfig::new()
         };
 
         // Initialize fan monitor
-        let fan_monitor = FanMonitor::new();
+        let mut fan_monitor = FanMonitor::new();
+        fan_monitor.set_poll_interval(config.poll_interval());
+        fan_monitor.configure(&config);
         // Note: We'll initialize the System76 Power client later in the Application::new method
+
+        // Point out curves that may not cool a hot CPU enough
+        let lints: Vec<String> = config
+            .curves
+            .iter()
+            .flat_map(|curve| {
+                curve
+                    .safety_lint()
+                    .into_iter()
+                    .map(move |lint| format!("Fan curve '{}': {}", curve.name(), lint))
+            })
+            .collect();
+        for lint in &lints {
+            log::warn!("{}", lint);
+        }
         
         Self {
-            fan_curves,
-            current_curve_index: default_curve_index.unwrap_or(0),
-            default_curve_index,
-            status_message: None,
+            saved_curves: config.curves.to_vec(),
+            fan_curves: config.curves.into_vec(),
+            current_curve_index: config.default_curve_index.unwrap_or(0),
+            default_curve_index: config.default_curve_index,
+            tag_filter: None,
+            status_message: (!lints.is_empty()).then(|| lints.join("\n")),
             show_save_dialog: false,
             editing_point: None,
             edit_temp_input: String::new(),
+            temp_unit: TempUnit::default(),
             edit_duty_input: String::new(),
             new_profile_name: String::new(),
             fan_monitor,
+            use_dbus_data: config.gui_use_dbus,
+            warn_temp: config.warn_temp,
             current_data: None,
             data_error: None,
         }
     }
 
-    fn save_config(&self) -> Result<()> {
+    /// Connect the monitor to the daemon for DBus data; failures leave the GUI on sysfs
+    fn connect_daemon(&mut self) {
+        if self.fan_monitor.is_system76_power_initialized() {
+            return;
+        }
+        if let Err(e) = self.fan_monitor.initialize_system76_power_sync() {
+            log::warn!("Daemon unreachable, reading fan data from sysfs: {}", e);
+        }
+    }
+
+    /// Current fan data from the source picked by [`data_source`]
+    /// Falls back to sysfs if the DBus read fails
+    fn read_fan_data(&self) -> Result<crate::fan_monitor::FanDataPoint> {
+        let dbus_available = self.fan_monitor.is_system76_power_initialized();
+        match data_source(self.use_dbus_data, dbus_available) {
+            DataSource::Dbus => self.fan_monitor.get_current_fan_data_sync().or_else(|e| {
+                log::warn!("Failed to read fan data over DBus, reading sysfs: {}", e);
+                self.fan_monitor.get_current_fan_data_direct()
+            }),
+            DataSource::Direct => self.fan_monitor.get_current_fan_data_direct(),
+        }
+    }
+
+    fn save_config(&mut self) -> Result<()> {
+        self.save_config_remapping(Some)
+    }
+
+    /// Save like [`FanCurveApp::save_config`], moving the daemon's stored active curve index
+    /// with `remap` after curves were deleted or reordered
+    fn save_config_remapping(&mut self, remap: impl Fn(usize) -> Option<usize>) -> Result<()> {
         let config_path = FanCurveConfig::get_config_path();
         if let Some(parent) = config_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
 
-        let config = FanCurveConfig {
-            curves: self.fan_curves.clone(),
-            default_curve_index: self.default_curve_index,
-        };
+        // Start from the stored config so settings owned by the daemon are preserved; one
+        // that fails to load is left for the user to fix rather than replaced with defaults
+        let mut config = FanCurveConfig::load_or_default(&config_path)?;
+        config.active_curve_index = config.active_curve_index.and_then(remap);
+        config.curves = CurveRegistry::from(self.fan_curves.clone());
+        config.default_curve_index = self.default_curve_index;
+        config.gui_use_dbus = self.use_dbus_data;
 
         config.save_to_file(&config_path)?;
+        self.saved_curves = self.fan_curves.clone();
         Ok(())
     }
 
+    /// Save only the data source choice, leaving unsaved curve edits out of the file
+    fn save_data_source(&self) -> Result<()> {
+        let config_path = FanCurveConfig::get_config_path();
+        if let Some(parent) = config_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut config = FanCurveConfig::load_or_default(&config_path)?;
+        config.gui_use_dbus = self.use_dbus_data;
+        config.save_to_file(&config_path)
+    }
+
+    /// Check if the curves have changes that aren't saved yet
+    fn is_dirty(&self) -> bool {
+        self.fan_curves != self.saved_curves
+    }
+
+    /// Delete a curve, keeping the current and default selections on the same curves
+    fn delete_curve(&mut self, index: usize) -> Result<FanCurve> {
+        if self.fan_curves.len() <= 1 {
+            return Err(FanCurveError::Config("Cannot delete the last fan curve".to_string()));
+        }
+        let curve = self.fan_curves.get(index).ok_or_else(|| {
+            FanCurveError::Config(format!("No fan curve at index {}", index))
+        })?;
+        curve.ensure_unlocked()?;
+
+        let removed = self.fan_curves.remove(index);
+        self.current_curve_index = index_after_delete(self.current_curve_index, index)
+            .unwrap_or_else(|| index.min(self.fan_curves.len() - 1));
+        self.default_curve_index = self
+            .default_curve_index
+            .and_then(|default| index_after_delete(default, index));
+        self.save_config_remapping(|active| index_after_delete(active, index))?;
+        Ok(removed)
+    }
+
+    /// Move a curve to another position in the list, keeping the selections on the same curves
+    fn move_curve(&mut self, from: usize, to: usize) -> Result<()> {
+        if from >= self.fan_curves.len() || to >= self.fan_curves.len() {
+            return Err(FanCurveError::Config(format!("No fan curve at index {}", from.max(to))));
+        }
+        if from == to {
+            return Ok(());
+        }
+
+        let curve = self.fan_curves.remove(from);
+        self.fan_curves.insert(to, curve);
+        self.current_curve_index = index_after_move(self.current_curve_index, from, to);
+        self.default_curve_index = self
+            .default_curve_index
+            .map(|default| index_after_move(default, from, to));
+        self.save_config_remapping(|active| Some(index_after_move(active, from, to)))
+    }
+
+    /// Curves offered in the profile list under the current tag filter
+    fn filtered_curves(&self) -> Vec<FanCurve> {
+        self.fan_curves
+            .iter()
+            .filter(|curve| self.tag_filter.as_deref().map_or(true, |tag| curve.has_tag(tag)))
+            .cloned()
+            .collect()
+    }
+
     fn set_status(&mut self, message: String) {
         self.status_message = Some(message);
     }
@@ -125,10 +260,19 @@ impl Application for FanCurveApp {
     type Message = Message;
     type Theme = Theme;
     type Executor = iced::executor::Default;
-    type Flags = ();
+    /// Sensor overrides, display unit and `--notifications` from the command line
+    type Flags = (SensorOptions, TempUnit, bool);
 
-    fn new(_flags: ()) -> (Self, Command<Message>) {
-        let app = Self::new();
+    fn new((sensor_options, temp_unit, notifications): Self::Flags) -> (Self, Command<Message>) {
+        let mut app = Self::new();
+        app.fan_monitor.set_sensor_options(sensor_options);
+        app.temp_unit = temp_unit;
+        if notifications {
+            app.fan_monitor.set_overheat_warning(Some(app.warn_temp));
+        }
+        if app.use_dbus_data {
+            app.connect_daemon();
+        }
         
         // Start with a Tick message to begin automatic updates
         let init_command = Command::perform(
@@ -140,7 +284,11 @@ impl Application for FanCurveApp {
     }
 
     fn title(&self) -> String {
-        "Fan Curve Control".to_string()
+        if self.is_dirty() {
+            "Fan Curve Control *".to_string()
+        } else {
+            "Fan Curve Control".to_string()
+        }
     }
 
     fn update(&mut self, message: Message) -> Command<Message> {
@@ -155,6 +303,19 @@ impl Application for FanCurveApp {
                 Command::none()
             }
 
+            Message::TagFilterSelected(tag) => {
+                self.tag_filter = (tag != ALL_TAGS).then_some(tag);
+                // Jump to the first matching curve if the current one is filtered out
+                if let Some(tag) = self.tag_filter.as_deref() {
+                    if !self.fan_curves[self.current_curve_index].has_tag(tag) {
+                        if let Some(index) = self.fan_curves.iter().position(|c| c.has_tag(tag)) {
+                            self.current_curve_index = index;
+                        }
+                    }
+                }
+                Command::none()
+            }
+
             Message::AddPoint => {
                 log::info!("GUI: AddPoint button clicked - this proves GUI messages work!");
                 self.set_status("Add Point clicked (not implemented yet)".to_string());
@@ -182,9 +343,9 @@ impl Application for FanCurveApp {
                 if let Some(point) = self.fan_curves[self.current_curve_index].get_point(index) {
                     self.editing_point = Some(index);
                     self.edit_temp_input = point.temp.to_string();
-                    // Convert ten-thousandths to percentage for user input
-                    self.edit_duty_input = (point.duty as f32 / 100.0).to_string();
-                    self.set_status(format!("Editing point {}: {}°C -> {:.1}%", index + 1, point.temp, point.duty as f32 / 100.0));
+                    let duty = Duty::from_ten_thousandths(point.duty);
+                    self.edit_duty_input = duty.as_percent().to_string();
+                    self.set_status(format!("Editing point {}: {}°C -> {}", index + 1, point.temp, duty));
                 } else {
                     self.set_status(format!("Point {} not found", index + 1));
                 }
@@ -210,13 +371,12 @@ impl Application for FanCurveApp {
                     match (temp, duty_percent) {
                         (Ok(temp_val), Ok(duty_percent_val)) => {
                             // Validate ranges
-                            if temp_val < 0.0 || temp_val > 100.0 {
-                                self.set_status("Temperature must be between 0 and 100°C".to_string());
+                            if temp_val < MIN_POINT_TEMP as f32 || temp_val > 100.0 {
+                                self.set_status(format!("Temperature must be between {} and 100°C", MIN_POINT_TEMP));
                             } else if duty_percent_val < 0.0 || duty_percent_val > 100.0 {
                                 self.set_status("Duty must be between 0 and 100%".to_string());
                             } else {
-                                // Convert percentage to ten-thousandths for storage
-                                let duty_ten_thousandths = (duty_percent_val * 100.0) as u16;
+                                let duty_ten_thousandths = Duty::from_percent(duty_percent_val).as_ten_thousandths();
                                 
                                 // Update the point
                                 if let Some(point) = self.fan_curves[self.current_curve_index].get_point_mut(point_index) {
@@ -254,6 +414,38 @@ impl Application for FanCurveApp {
                 Command::none()
             }
 
+            Message::ToggleLock => {
+                let curve = &mut self.fan_curves[self.current_curve_index];
+                let locked = !curve.is_locked();
+                curve.set_locked(locked);
+                let name = curve.name().to_string();
+                if locked {
+                    // Drop any edit in progress, it can't be saved anymore
+                    self.editing_point = None;
+                    self.edit_temp_input.clear();
+                    self.edit_duty_input.clear();
+                }
+                match self.save_config() {
+                    Ok(()) if locked => self.set_status(format!("Locked '{}'", name)),
+                    Ok(()) => self.set_status(format!("Unlocked '{}'", name)),
+                    Err(e) => self.set_status(format!("Failed to save: {}", e)),
+                }
+                Command::none()
+            }
+
+            Message::ResetToPreset => {
+                let curve = &mut self.fan_curves[self.current_curve_index];
+                let name = curve.name().to_string();
+                match curve.reset_to_preset() {
+                    Ok(()) => {
+                        self.editing_point = None;
+                        self.set_status(format!("Reset '{}' to the built-in preset, save to keep it", name));
+                    }
+                    Err(e) => self.set_status(format!("Failed to reset '{}': {}", name, e)),
+                }
+                Command::none()
+            }
+
                     Message::ApplyFanCurve => {
                         log::info!("=== GUI: ApplyFanCurve button clicked ===");
                         
@@ -270,16 +462,17 @@ impl Application for FanCurveApp {
                             return Command::none();
                         };
                         
-                        log::info!("GUI: About to apply fan curve '{}' with {} points", curve_name, current_curve.points().len());
+                        log::info!("GUI: About to apply fan curve '{}' with {} points", curve_name, current_curve.len());
                         
                         // Now we can safely call methods that require &mut self
                         let result = self.fan_monitor.apply_fan_curve_from_gui(&current_curve, temperature);
                         
                         // Build status messages separately to avoid borrow issues
-                        let status_msg = if result.is_ok() {
-                            format!("Fan curve '{}' applied successfully! Temperature: {:.1}°C", curve_name, temperature)
-                        } else {
-                            format!("Failed to apply fan curve '{}': {}", curve_name, result.as_ref().unwrap_err())
+                        let status_msg = match &result {
+                            Ok(_) => format!("Fan curve '{}' applied successfully! Temperature: {:.1}°C", curve_name, temperature),
+                            // Tell the user how to get access instead of a generic failure
+                            Err(e) if e.is_permission_denied() => e.to_string(),
+                            Err(e) => format!("Failed to apply fan curve '{}': {}", curve_name, e),
                         };
                         
                         // Now set status (mutable borrow)
@@ -301,7 +494,7 @@ impl Application for FanCurveApp {
                 let pwm_value = if duty_percent == 0 {
                     0 // Auto mode
                 } else {
-                    ((duty_percent as f32 / 100.0) * 255.0) as u8
+                    crate::fan_monitor::percent_to_pwm(duty_percent).unwrap_or(255)
                 };
                 
                 // Set fan duty directly via D-Bus
@@ -341,6 +534,21 @@ impl Application for FanCurveApp {
                 Command::none()
             }
 
+            Message::DeleteCurve(index) => {
+                match self.delete_curve(index) {
+                    Ok(curve) => self.set_status(format!("Deleted '{}'", curve.name())),
+                    Err(e) => self.set_status(format!("Failed to delete: {}", e)),
+                }
+                Command::none()
+            }
+
+            Message::MoveCurve { from, to } => {
+                if let Err(e) = self.move_curve(from, to) {
+                    self.set_status(format!("Failed to move: {}", e));
+                }
+                Command::none()
+            }
+
             Message::SetAsDefault => {
                 self.default_curve_index = Some(self.current_curve_index);
                 if let Err(e) = self.save_config() {
@@ -351,6 +559,30 @@ impl Application for FanCurveApp {
                 Command::none()
             }
 
+            Message::SaveConfig => {
+                match self.save_config() {
+                    Ok(()) => self.set_status("Changes saved".to_string()),
+                    Err(e) => self.set_status(format!("Failed to save: {}", e)),
+                }
+                Command::none()
+            }
+
+            Message::ToggleTempUnit => {
+                self.temp_unit = self.temp_unit.toggled();
+                Command::none()
+            }
+
+            Message::ToggleDataSource => {
+                self.use_dbus_data = !self.use_dbus_data;
+                if self.use_dbus_data {
+                    self.connect_daemon();
+                }
+                if let Err(e) = self.save_data_source() {
+                    self.set_status(format!("Failed to save config: {}", e));
+                }
+                Command::none()
+            }
+
             Message::NewProfileNameChanged(name) => {
                 self.new_profile_name = name;
                 Command::none()
@@ -359,6 +591,8 @@ impl Application for FanCurveApp {
             Message::SaveNewProfile => {
                 if !self.new_profile_name.trim().is_empty() {
                     let mut new_curve = self.fan_curves[self.current_curve_index].clone();
+                    // The copy starts out editable even if the original is locked
+                    new_curve.set_locked(false);
                     new_curve.set_name(self.new_profile_name.trim().to_string());
                     self.fan_curves.push(new_curve);
                     self.set_status("Profile saved!".to_string());
@@ -395,14 +629,14 @@ impl Application for FanCurveApp {
                     }
 
                     Message::Tick => {
-                        // Get data using direct file reading (no D-Bus needed for display)
-                        match self.fan_monitor.get_current_fan_data_direct() {
+                        match self.read_fan_data() {
                             Ok(data) => {
+                                self.fan_monitor.record_sample(data.clone());
                                 self.current_data = Some(data);
                                 self.data_error = None;
-                                log::debug!("Auto refresh - Updated fan data: {:.1}°C, duty: {:.1}%", 
+                                log::debug!("Auto refresh - Updated fan data: {:.1}°C, duty: {}", 
                                     self.current_data.as_ref().unwrap().temperature,
-                                    self.current_data.as_ref().unwrap().fan_duty as f32 / 100.0
+                                    Duty::from_ten_thousandths(self.current_data.as_ref().unwrap().fan_duty)
                                 );
                             }
                             Err(e) => {
@@ -413,9 +647,10 @@ impl Application for FanCurveApp {
                         }
                         
                         // Schedule next update using std::thread::sleep
+                        let poll_interval = self.fan_monitor.poll_interval();
                         return Command::perform(
-                            async {
-                                std::thread::sleep(std::time::Duration::from_millis(500));
+                            async move {
+                                std::thread::sleep(poll_interval);
                                 Message::Tick
                             },
                             |msg| msg,
@@ -436,8 +671,10 @@ impl Application for FanCurveApp {
                 .size(28)
         );
 
+        let locked = self.fan_curves[self.current_curve_index].is_locked();
+
         // Fan curve selection card
-        let curve_selection = Row::new()
+        let mut curve_selection = Row::new()
             .spacing(15)
             .align_items(Alignment::Center)
             .push(
@@ -446,7 +683,17 @@ impl Application for FanCurveApp {
             )
             .push(
                 pick_list(
-                    self.fan_curves.as_slice(),
+                    std::iter::once(ALL_TAGS.to_string())
+                        .chain(unique_tags(&self.fan_curves))
+                        .collect::<Vec<_>>(),
+                    Some(self.tag_filter.clone().unwrap_or_else(|| ALL_TAGS.to_string())),
+                    Message::TagFilterSelected,
+                )
+                .width(120)
+            )
+            .push(
+                pick_list(
+                    self.filtered_curves(),
                     Some(self.fan_curves[self.current_curve_index].clone()),
                     Message::CurveSelected,
                 )
@@ -456,7 +703,41 @@ impl Application for FanCurveApp {
                 button("Set Default")
                     .padding([8, 16])
                     .on_press(Message::SetAsDefault)
+            )
+            .push(
+                button(if locked { "🔒 Unlock" } else { "🔓 Lock" })
+                    .padding([8, 16])
+                    .on_press(Message::ToggleLock)
+            )
+            .push(
+                button("▲")
+                    .padding([8, 12])
+                    .on_press_maybe((self.current_curve_index > 0).then(|| Message::MoveCurve {
+                        from: self.current_curve_index,
+                        to: self.current_curve_index - 1,
+                    }))
+            )
+            .push(
+                button("▼")
+                    .padding([8, 12])
+                    .on_press_maybe((self.current_curve_index + 1 < self.fan_curves.len()).then(|| Message::MoveCurve {
+                        from: self.current_curve_index,
+                        to: self.current_curve_index + 1,
+                    }))
+            )
+            .push(
+                button("Delete")
+                    .padding([8, 16])
+                    .on_press_maybe((!locked && self.fan_curves.len() > 1)
+                        .then_some(Message::DeleteCurve(self.current_curve_index)))
+            );
+        if self.fan_curves[self.current_curve_index].is_preset() {
+            curve_selection = curve_selection.push(
+                button("Reset to Preset")
+                    .padding([8, 16])
+                    .on_press_maybe((!locked).then_some(Message::ResetToPreset))
             );
+        }
 
         let curve_card = Column::new()
             .spacing(15)
@@ -474,24 +755,27 @@ impl Application for FanCurveApp {
         // Fan curve points card
         let mut points_content = Column::new().spacing(10);
         
-        for (i, point) in self.fan_curves[self.current_curve_index].points().iter().enumerate() {
+        for (i, point) in self.fan_curves[self.current_curve_index].to_points().iter().enumerate() {
             let point_row = Row::new()
                 .spacing(15)
                 .align_items(Alignment::Center)
                 .push(
-                    Text::new(format!("Point {}: {}°C → {:.1}%", i + 1, point.temp, point.duty as f32 / 100.0))
+                    Text::new(format!("Point {}: {} → {}{}", i + 1, self.temp_unit.format(f32::from(point.temp), 0), Duty::from_ten_thousandths(point.duty),
+                        self.fan_monitor.estimate_rpm(point.duty)
+                            .map(|rpm| format!(" (~{} RPM)", rpm))
+                            .unwrap_or_default()))
                         .size(14)
                 )
                 .push(
                     button("Edit")
                         .padding([8, 16])
-                        .on_press(Message::EditPoint(i))
+                        .on_press_maybe((!locked).then_some(Message::EditPoint(i)))
                 )
                 .push(
                     button("Remove")
                         .padding([8, 16])
                         .style(iced::theme::Button::Destructive)
-                        .on_press(Message::RemovePoint(i))
+                        .on_press_maybe((!locked).then_some(Message::RemovePoint(i)))
                 );
             
             points_content = points_content.push(point_row);
@@ -544,7 +828,7 @@ impl Application for FanCurveApp {
             .push(
                 button("Add Point")
                     .padding([8, 16])
-                    .on_press(Message::AddPoint)
+                    .on_press_maybe((!locked).then_some(Message::AddPoint))
             )
             .push(
                 button("Apply Fan Curve")
@@ -555,6 +839,11 @@ impl Application for FanCurveApp {
                 button("Save as New Profile")
                     .padding([8, 16])
                     .on_press(Message::SaveAsNewProfile)
+            )
+            .push(
+                button(if self.is_dirty() { "Save Changes *" } else { "Save Changes" })
+                    .padding([8, 16])
+                    .on_press_maybe(self.is_dirty().then_some(Message::SaveConfig))
             );
 
         // Fan Duty Control Section
@@ -608,19 +897,38 @@ impl Application for FanCurveApp {
         let live_data = Column::new()
             .spacing(8)
             .push(
-                Text::new("📊 Live Fan Data")
-                    .size(18)
+                Row::new()
+                    .spacing(15)
+                    .align_items(Alignment::Center)
+                    .push(
+                        Text::new("📊 Live Fan Data")
+                            .size(18)
+                    )
+                    .push(
+                        button(Text::new(format!("Show {}", self.temp_unit.toggled().symbol())).size(12))
+                            .padding([4, 10])
+                            .on_press(Message::ToggleTempUnit)
+                    )
+                    .push(
+                        button(Text::new(if self.use_dbus_data { "Read sysfs" } else { "Read via DBus" }).size(12))
+                            .padding([4, 10])
+                            .on_press(Message::ToggleDataSource)
+                    )
             )
             .push(
                 if let Some(ref data) = self.current_data {
                     Column::new()
                         .spacing(8)
                         .push(
-                            Text::new(format!("🌡️ CPU Temperature: {:.1}°C", data.temperature))
+                            Text::new(format!("🌡️ CPU Temperature: {}", self.temp_unit.format(data.temperature, 1)))
                                 .size(16)
                         )
                         .push(
-                            Text::new(format!("🌀 Fan Duty: {:.1}%", data.fan_duty as f32 / 100.0))
+                            Text::new(if self.fan_curves[self.current_curve_index].is_auto() {
+                                "🌀 Fan Duty: Automatic (firmware)".to_string()
+                            } else {
+                                format!("🌀 Fan Duty: {}", Duty::from_ten_thousandths(data.fan_duty))
+                            })
                                 .size(16)
                         )
                         .push(
@@ -656,7 +964,7 @@ impl Application for FanCurveApp {
                                 .size(12)
                         )
                         .push(
-                            Text::new(format!("💻 CPU: {}", data.cpu_model))
+                            Text::new(format!("💻 CPU: {} ({} cores)", data.cpu_model, data.cpu_core_count))
                                 .size(14)
                         )
                 } else if let Some(ref error) = self.data_error {
@@ -746,14 +1054,146 @@ impl Application for FanCurveApp {
     }
 }
 
-pub fn run_iced_gui() -> Result<()> {
+/// Where the curve at `index` ends up after the curve at `removed` is deleted
+/// None if it was the deleted curve
+fn index_after_delete(index: usize, removed: usize) -> Option<usize> {
+    match index.cmp(&removed) {
+        std::cmp::Ordering::Less => Some(index),
+        std::cmp::Ordering::Equal => None,
+        std::cmp::Ordering::Greater => Some(index - 1),
+    }
+}
+
+/// Where the curve at `index` ends up after the curve at `from` is moved to `to`
+fn index_after_move(index: usize, from: usize, to: usize) -> usize {
+    if index == from {
+        to
+    } else if from < index && index <= to {
+        index - 1
+    } else if to <= index && index < from {
+        index + 1
+    } else {
+        index
+    }
+}
+
+/// Where the GUI reads live fan data from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DataSource {
+    /// Through the daemon, so the GUI shows the duty the daemon applies
+    Dbus,
+    /// Straight from sysfs
+    Direct,
+}
+
+/// Use DBus when it is preferred and the daemon is reachable, otherwise sysfs
+fn data_source(prefer_dbus: bool, dbus_available: bool) -> DataSource {
+    if prefer_dbus && dbus_available {
+        DataSource::Dbus
+    } else {
+        DataSource::Direct
+    }
+}
+
+pub fn run_iced_gui(sensor_options: SensorOptions, temp_unit: TempUnit, notifications: bool) -> Result<()> {
     FanCurveApp::run(Settings {
         window: iced::window::Settings {
             size: iced::Size::new(800.0, 600.0),
             ..Default::default()
         },
-        ..Settings::with_flags(())
+        ..Settings::with_flags((sensor_options, temp_unit, notifications))
     })
         .map_err(|e| crate::errors::FanCurveError::Unknown(format!("GUI error: {}", e)))?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_after_delete() {
+        // Default before the deleted curve stays put
+        assert_eq!(index_after_delete(1, 3), Some(1));
+        // Default after the deleted curve moves up one
+        assert_eq!(index_after_delete(4, 3), Some(3));
+        // Deleting the default clears it
+        assert_eq!(index_after_delete(3, 3), None);
+        assert_eq!(index_after_delete(0, 0), None);
+    }
+
+    #[test]
+    fn test_index_after_move() {
+        let names = ["A", "B", "C", "D", "E"];
+        for from in 0..names.len() {
+            for to in 0..names.len() {
+                let mut moved = names.to_vec();
+                let curve = moved.remove(from);
+                moved.insert(to, curve);
+                for (index, name) in names.iter().enumerate() {
+                    assert_eq!(
+                        moved[index_after_move(index, from, to)], *name,
+                        "{} moving {} -> {}", name, from, to
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_data_source_selection() {
+        assert_eq!(data_source(true, true), DataSource::Dbus);
+        // An unreachable daemon falls back to sysfs
+        assert_eq!(data_source(true, false), DataSource::Direct);
+        assert_eq!(data_source(false, true), DataSource::Direct);
+        assert_eq!(data_source(false, false), DataSource::Direct);
+    }
+
+    #[test]
+    fn test_active_curve_follows_deletes_and_moves() {
+        let dir = crate::test_util::TempDir::new("gui-active-curve");
+        let config_path = dir.path().join("config.json");
+        let _config_override = crate::test_util::ConfigPathOverride::new(&config_path);
+        let mut config = FanCurveConfig::new();
+        config.active_curve_index = Some(2);
+        config.save_to_file(&config_path).unwrap();
+        let active_name = config.curves[2].name().to_string();
+        let active = || {
+            let saved = FanCurveConfig::load_from_file(&config_path).unwrap();
+            saved
+                .active_curve_index
+                .map(|index| saved.curves[index].name().to_string())
+        };
+
+        let mut app = FanCurveApp::new();
+        app.delete_curve(0).unwrap();
+        assert_eq!(active(), Some(active_name.clone()));
+        app.move_curve(1, 0).unwrap();
+        assert_eq!(active(), Some(active_name.clone()));
+        app.move_curve(1, 2).unwrap();
+        assert_eq!(active(), Some(active_name));
+
+        let index = app.fan_curves.iter().position(|c| c.name() == active().unwrap()).unwrap();
+        app.delete_curve(index).unwrap();
+        assert_eq!(active(), None);
+    }
+
+    #[test]
+    fn test_data_source_toggle_keeps_unsaved_curves_out() {
+        let dir = crate::test_util::TempDir::new("gui-data-source");
+        let config_path = dir.path().join("config.json");
+        let _config_override = crate::test_util::ConfigPathOverride::new(&config_path);
+        let mut config = FanCurveConfig::new();
+        config.gui_use_dbus = true;
+        config.save_to_file(&config_path).unwrap();
+
+        let mut app = FanCurveApp::new();
+        app.fan_curves[0].add_point(55, 4500);
+        let _ = app.update(Message::ToggleDataSource);
+
+        let saved = FanCurveConfig::load_from_file(&config_path).unwrap();
+        assert!(!saved.gui_use_dbus);
+        assert_eq!(saved.curves.to_vec(), config.curves.to_vec());
+        assert!(app.is_dirty());
+    }
+}
@@ -0,0 +1,172 @@
+//! Idle detection: drop the fans below the curve when the CPU has had nothing to do for a while
+
+use crate::errors::{FanCurveError, Result};
+use std::fs;
+use std::time::{Duration, Instant};
+
+/// Default CPU usage (%) below which the system counts as idle
+pub const DEFAULT_IDLE_CPU_PERCENT: f32 = 3.0;
+
+/// Default time the CPU has to stay below the threshold before the idle floor applies
+pub const DEFAULT_IDLE_AFTER: Duration = Duration::from_secs(60);
+
+/// Cumulative CPU time from the first line of `/proc/stat`, in clock ticks
+/// The default is the zero point at boot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CpuTimes {
+    busy: u64,
+    total: u64,
+}
+
+impl CpuTimes {
+    pub fn new(busy: u64, idle: u64) -> Self {
+        Self {
+            busy,
+            total: busy + idle,
+        }
+    }
+
+    /// Parse the aggregate `cpu` line of `/proc/stat`; idle and iowait count as idle
+    pub fn parse(stat: &str) -> Result<Self> {
+        let invalid = || FanCurveError::Config("Invalid /proc/stat format".to_string());
+        let line = stat
+            .lines()
+            .find(|line| line.starts_with("cpu "))
+            .ok_or_else(invalid)?;
+        let fields: Vec<u64> = line
+            .split_whitespace()
+            .skip(1)
+            .take(8)
+            .map(|field| field.parse().map_err(|_| invalid()))
+            .collect::<Result<_>>()?;
+        if fields.len() < 7 {
+            return Err(invalid());
+        }
+        let idle = fields[3] + fields[4];
+        Ok(Self::new(fields.iter().sum::<u64>() - idle, idle))
+    }
+
+    pub fn read() -> Result<Self> {
+        Self::parse(&fs::read_to_string("/proc/stat")?)
+    }
+
+    /// CPU usage (%) between `earlier` and these times; None when no time has passed
+    pub fn usage_since(&self, earlier: &CpuTimes) -> Option<f32> {
+        let total = self.total.saturating_sub(earlier.total);
+        if total == 0 {
+            return None;
+        }
+        let busy = self.busy.saturating_sub(earlier.busy);
+        Some((busy as f32 / total as f32 * 100.0).clamp(0.0, 100.0))
+    }
+}
+
+/// When the system counts as idle and how quiet the fans get then
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IdleFloor {
+    /// Duty (ten-thousandths) used instead of the curve's lowest duty while idle
+    pub duty: u16,
+    /// CPU usage (%) below which a sample counts as idle
+    pub cpu_percent: f32,
+    /// How long usage has to stay below `cpu_percent`
+    pub after: Duration,
+}
+
+/// Tracks CPU usage between samples to decide whether the idle floor applies
+///
+/// Any sample above the threshold ends idling at once, and the wait starts over.
+#[derive(Debug, Clone)]
+pub struct IdleDetector {
+    floor: IdleFloor,
+    previous: Option<CpuTimes>,
+    idle_since: Option<Instant>,
+}
+
+impl IdleDetector {
+    pub fn new(floor: IdleFloor) -> Self {
+        Self {
+            floor,
+            previous: None,
+            idle_since: None,
+        }
+    }
+
+    pub fn floor(&self) -> IdleFloor {
+        self.floor
+    }
+
+    /// Feed CPU times read at `now`; returns whether the system has been idle long enough
+    /// Times with no ticks since the previous sample carry no usage and change nothing
+    pub fn update(&mut self, times: CpuTimes, now: Instant) -> bool {
+        let Some(previous) = self.previous else {
+            self.previous = Some(times);
+            return false;
+        };
+        let Some(usage) = times.usage_since(&previous) else {
+            return self.is_idle(now);
+        };
+        self.previous = Some(times);
+        if usage >= self.floor.cpu_percent {
+            self.idle_since = None;
+            return false;
+        }
+        self.idle_since.get_or_insert(now);
+        self.is_idle(now)
+    }
+
+    /// Whether usage has stayed below the threshold for long enough at `now`
+    pub fn is_idle(&self, now: Instant) -> bool {
+        self.idle_since
+            .is_some_and(|since| now.duration_since(since) >= self.floor.after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_detector() {
+        let stat = "cpu  100 0 50 800 50 0 0 0 0 0\ncpu0 50 0 25 400 25 0 0 0 0 0\n";
+        assert_eq!(CpuTimes::parse(stat).unwrap(), CpuTimes::new(150, 850));
+        assert!(CpuTimes::parse("intr 1 2 3\n").is_err());
+        assert_eq!(
+            CpuTimes::new(300, 700).usage_since(&CpuTimes::new(100, 400)),
+            Some(40.0)
+        );
+        assert_eq!(
+            CpuTimes::new(10, 90).usage_since(&CpuTimes::new(10, 90)),
+            None
+        );
+
+        let mut detector = IdleDetector::new(IdleFloor {
+            duty: 0,
+            cpu_percent: 5.0,
+            after: Duration::from_secs(10),
+        });
+        let start = Instant::now();
+        let at = |seconds| start + Duration::from_secs(seconds);
+        // Cumulative times at `second`, after `busy_ticks` of the last 100 ticks per second
+        let mut busy = 0;
+        let mut idle_second = |detector: &mut IdleDetector, second, busy_ticks| {
+            busy += busy_ticks;
+            detector.update(CpuTimes::new(busy, second * 100 - busy), at(second))
+        };
+
+        assert!(!idle_second(&mut detector, 1, 1));
+        for second in 2..12 {
+            assert!(!idle_second(&mut detector, second, 1), "{}", second);
+        }
+        assert!(idle_second(&mut detector, 12, 1));
+        // One busy second ends it and the wait starts over
+        assert!(!idle_second(&mut detector, 13, 60));
+        assert!(!idle_second(&mut detector, 20, 7));
+        assert!(idle_second(&mut detector, 30, 10));
+
+        // Samples without new ticks don't count as idle time
+        assert!(!idle_second(&mut detector, 31, 90));
+        let times = CpuTimes::new(busy, 31 * 100 - busy);
+        assert!(!detector.update(times, at(60)));
+        assert!(!detector.is_idle(at(60)));
+    }
+}
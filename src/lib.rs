@@ -4,22 +4,97 @@
 
 pub mod args;
 pub mod client;
+pub mod closed_loop;
+pub mod cpu_info;
 pub mod cpu_temp;
+pub mod curve_registry;
 pub mod daemon;
+pub mod diagnostics;
+pub mod doctor;
+pub mod duty;
 pub mod errors;
+pub mod events;
 pub mod fan;
+#[cfg(feature = "egui")]
+pub mod fan_curve_gui;
 pub mod iced_gui;
+pub mod idle;
 pub mod fan_detector;
 pub mod fan_monitor;
+pub mod gpu_temp;
+pub mod hardware;
+pub mod lm_sensors;
 pub mod logging;
+pub mod overheat;
+pub mod runtime;
+pub mod schedule;
 pub mod system76_power_client;
+pub mod temperature;
 pub mod thelio_io;
+#[cfg(feature = "tui")]
+pub mod tui;
+
+#[cfg(test)]
+mod test_util;
 
 // DBus constants following System76 Power patterns
 pub const DBUS_SERVICE_NAME: &str = "com.system76.FanCurveDaemon";
 pub const DBUS_OBJECT_PATH: &str = "/com/system76/FanCurveDaemon";
 pub const DBUS_INTERFACE_NAME: &str = "com.system76.FanCurveDaemon";
 
+/// Crate version of this build
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Short git hash this build was made from, or "unknown" outside a git checkout
+pub const GIT_HASH: &str = or_unknown(option_env!("GIT_HASH"));
+
+/// `git describe` output for this build, or "unknown"
+pub const GIT_DESC: &str = or_unknown(option_env!("GIT_DESC"));
+
+/// When this build was made (RFC 3339), or "unknown"
+pub const BUILD_TIME: &str = or_unknown(option_env!("BUILD_TIME"));
+
+const fn or_unknown(value: Option<&'static str>) -> &'static str {
+    match value {
+        Some(value) => value,
+        None => "unknown",
+    }
+}
+
+/// Version and build metadata of a binary, e.g. the running daemon
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BuildInfo {
+    pub version: String,
+    pub git_hash: String,
+    pub build_time: String,
+}
+
+impl BuildInfo {
+    /// Metadata of this binary
+    pub fn current() -> Self {
+        Self {
+            version: VERSION.to_string(),
+            git_hash: GIT_HASH.to_string(),
+            build_time: BUILD_TIME.to_string(),
+        }
+    }
+}
+
+/// Where the kernel lists hardware monitoring devices
+pub const HWMON_ROOT: &str = "/sys/class/hwmon";
+
+/// Environment variable pointing the sensor and fan detectors at another hwmon tree
+pub const HWMON_ROOT_ENV: &str = "FAN_APP_HWMON_ROOT";
+
+/// Directory to scan for hwmon devices: `$FAN_APP_HWMON_ROOT` if set, otherwise [`HWMON_ROOT`]
+pub fn hwmon_root() -> std::path::PathBuf {
+    std::env::var_os(HWMON_ROOT_ENV)
+        .filter(|root| !root.is_empty())
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| std::path::PathBuf::from(HWMON_ROOT))
+}
+
 // Re-export commonly used types
+pub use curve_registry::CurveRegistry;
 pub use errors::{FanCurveError, Result};
 pub use fan::{FanCurve, FanCurveConfig, FanPoint};
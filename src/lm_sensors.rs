@@ -0,0 +1,263 @@
+//! Temperatures and fan speeds from lm-sensors' `sensors -j`
+//!
+//! An alternative to reading hwmon directly, for machines where `sensors` is already
+//! configured with good labels. Selected with `--source lm-sensors`.
+
+use crate::cpu_temp::{AMD_LABELS, INTEL_LABELS};
+use crate::errors::{FanCurveError, Result};
+use crate::temperature::TemperatureSource;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Name of the lm-sensors binary, looked up on `PATH`
+pub const SENSORS_COMMAND: &str = "sensors";
+
+/// One temperature (°C) or fan (RPM) input
+#[derive(Debug, Clone, PartialEq)]
+pub struct SensorReading {
+    /// Chip the input belongs to, e.g. `k10temp-pci-00c3`
+    pub chip: String,
+    /// Feature label, e.g. `Tctl` or `cpu_fan`
+    pub label: String,
+    pub value: f32,
+}
+
+/// Everything one `sensors -j` run reported, sorted by chip, then label
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LmSensorsReadings {
+    pub temperatures: Vec<SensorReading>,
+    pub fans: Vec<SensorReading>,
+}
+
+impl LmSensorsReadings {
+    /// Parse the output of `sensors -j`
+    ///
+    /// Each chip maps feature labels to subfeatures such as `temp1_input` or `fan2_input`;
+    /// only the `_input` values are kept.
+    pub fn parse(json: &str) -> Result<Self> {
+        let chips: serde_json::Map<String, Value> = serde_json::from_str(json)?;
+        let mut readings = Self::default();
+        for (chip, features) in &chips {
+            let Some(features) = features.as_object() else {
+                continue;
+            };
+            for (label, subfeatures) in features {
+                let Some(subfeatures) = subfeatures.as_object() else {
+                    // e.g. "Adapter": "PCI adapter"
+                    continue;
+                };
+                for (name, value) in subfeatures {
+                    let Some(value) = value.as_f64() else {
+                        continue;
+                    };
+                    let reading = SensorReading {
+                        chip: chip.clone(),
+                        label: label.clone(),
+                        value: value as f32,
+                    };
+                    if name.starts_with("temp") && name.ends_with("_input") {
+                        readings.temperatures.push(reading);
+                    } else if name.starts_with("fan") && name.ends_with("_input") {
+                        readings.fans.push(reading);
+                    }
+                }
+            }
+        }
+        for list in [&mut readings.temperatures, &mut readings.fans] {
+            list.sort_by(|a, b| (&a.chip, &a.label).cmp(&(&b.chip, &b.label)));
+        }
+        Ok(readings)
+    }
+
+    /// Temperature with the given label on any chip
+    pub fn temperature(&self, label: &str) -> Option<f32> {
+        self.temperatures
+            .iter()
+            .find(|reading| reading.label == label)
+            .map(|reading| reading.value)
+    }
+
+    /// CPU temperature, using the same label preference as the hwmon sensor detection
+    /// Labels match by prefix, and `tctl_offset` is subtracted from `Tctl` readings
+    pub fn cpu_temperature(&self, tctl_offset: f32) -> Option<f32> {
+        let reading = AMD_LABELS.iter().chain(INTEL_LABELS).find_map(|prefix| {
+            self.temperatures
+                .iter()
+                .find(|reading| reading.label.starts_with(prefix))
+        })?;
+        if reading.label.starts_with("Tctl") {
+            Some(reading.value - tctl_offset)
+        } else {
+            Some(reading.value)
+        }
+    }
+
+    /// Fan speeds as (fan number, RPM, label), numbered from 1
+    pub fn fan_speeds(&self) -> Vec<(u8, u16, String)> {
+        self.fans
+            .iter()
+            .zip(1..=u8::MAX)
+            .map(|(fan, number)| (number, fan.value.round() as u16, fan.label.clone()))
+            .collect()
+    }
+}
+
+/// Runs `sensors -j` for each reading
+#[derive(Debug, Clone)]
+pub struct LmSensorsSource {
+    command: PathBuf,
+    /// Degrees subtracted from `Tctl` readings, as for the hwmon sensor
+    tctl_offset: f32,
+}
+
+impl LmSensorsSource {
+    /// Use `sensors` from `PATH`; None if lm-sensors isn't installed
+    pub fn detect() -> Option<Self> {
+        let path = env::var_os("PATH")?;
+        env::split_paths(&path)
+            .map(|dir| dir.join(SENSORS_COMMAND))
+            .find(|candidate| candidate.is_file())
+            .map(Self::with_command)
+    }
+
+    /// Use a specific `sensors` binary
+    pub fn with_command(command: impl Into<PathBuf>) -> Self {
+        Self {
+            command: command.into(),
+            tctl_offset: 0.0,
+        }
+    }
+
+    /// Subtract `offset` degrees from `Tctl` readings
+    pub fn with_tctl_offset(mut self, offset: f32) -> Self {
+        self.tctl_offset = offset;
+        self
+    }
+
+    /// Run `sensors -j` and parse its output
+    pub fn read(&self) -> Result<LmSensorsReadings> {
+        let output = Command::new(&self.command)
+            .arg("-j")
+            .output()
+            .map_err(|e| {
+                FanCurveError::Io(e).context(format!("Failed to run {}", self.command.display()))
+            })?;
+        if !output.status.success() {
+            return Err(FanCurveError::Config(format!(
+                "{} -j failed ({}): {}",
+                self.command.display(),
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        LmSensorsReadings::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Read the CPU temperature in °C
+    pub fn read_cpu_temperature(&self) -> Result<f32> {
+        self.read()?
+            .cpu_temperature(self.tctl_offset)
+            .ok_or_else(|| FanCurveError::Config("sensors reported no CPU temperature".to_string()))
+    }
+}
+
+#[async_trait]
+impl TemperatureSource for LmSensorsSource {
+    async fn read_celsius(&self) -> Result<f32> {
+        self.read_cpu_temperature()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `sensors -j` on a Ryzen desktop with an nct6798 Super I/O chip
+    const SENSORS_JSON: &str = r#"{
+   "k10temp-pci-00c3":{
+      "Adapter": "PCI adapter",
+      "Tctl":{
+         "temp1_input": 52.875
+      },
+      "Tccd1":{
+         "temp3_input": 44.250
+      }
+   },
+   "nct6798-isa-0290":{
+      "Adapter": "ISA adapter",
+      "in0":{
+         "in0_input": 0.936,
+         "in0_min": 0.000
+      },
+      "cpu_fan":{
+         "fan2_input": 1187.000,
+         "fan2_min": 0.000,
+         "fan2_alarm": 0.000
+      },
+      "chassis_fan":{
+         "fan1_input": 824.000,
+         "fan1_min": 0.000
+      },
+      "SYSTIN":{
+         "temp1_input": 31.000,
+         "temp1_max": 80.000,
+         "temp1_max_hyst": 75.000
+      }
+   }
+}"#;
+
+    #[test]
+    fn test_parse_sensors_json() {
+        let readings = LmSensorsReadings::parse(SENSORS_JSON).unwrap();
+        assert_eq!(readings.temperatures.len(), 3);
+        assert_eq!(readings.temperature("Tccd1"), Some(44.25));
+        assert_eq!(readings.temperature("SYSTIN"), Some(31.0));
+        assert_eq!(readings.cpu_temperature(0.0), Some(52.875));
+        assert_eq!(readings.cpu_temperature(10.0), Some(42.875));
+        assert_eq!(readings.temperatures[0].chip, "k10temp-pci-00c3");
+
+        // Limits and alarms are skipped, as are voltages
+        assert_eq!(
+            readings.fan_speeds(),
+            [
+                (1, 824, "chassis_fan".to_string()),
+                (2, 1187, "cpu_fan".to_string()),
+            ]
+        );
+
+        assert_eq!(
+            LmSensorsReadings::parse("{}").unwrap(),
+            LmSensorsReadings::default()
+        );
+        assert!(LmSensorsReadings::parse("No sensors found!").is_err());
+    }
+
+    #[test]
+    fn test_cpu_temperature_label_prefix() {
+        let readings = LmSensorsReadings::parse(
+            r#"{
+   "coretemp-isa-0000":{
+      "Core 0":{ "temp2_input": 48.000 },
+      "Package id 0:":{ "temp1_input": 51.000 }
+   }
+}"#,
+        )
+        .unwrap();
+        // The package wins over a core, and the offset only applies to Tctl
+        assert_eq!(readings.cpu_temperature(10.0), Some(51.0));
+
+        let readings = LmSensorsReadings::parse(
+            r#"{
+   "k10temp-pci-00c3":{
+      "Tctl":{ "temp1_input": 60.000 },
+      "Tdie (CCD1)":{ "temp2_input": 50.000 }
+   }
+}"#,
+        )
+        .unwrap();
+        assert_eq!(readings.cpu_temperature(10.0), Some(50.0));
+    }
+}
@@ -2,17 +2,89 @@
 
 use fern::Dispatch;
 use log::LevelFilter;
+use std::str::FromStr;
+
+/// Environment variable holding a log filter, used when `--log-filter` isn't given
+pub const LOG_FILTER_ENV: &str = "FAN_APP_LOG";
+
+/// Log levels parsed from a `RUST_LOG`-style filter
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogLevels {
+    /// Level for targets without their own entry
+    pub default: LevelFilter,
+    /// (target, level) pairs, e.g. ("zbus", Off)
+    pub targets: Vec<(String, LevelFilter)>,
+}
+
+impl LogLevels {
+    /// Levels for `-v` counts: warn, info, debug, then trace
+    pub fn from_verbosity(verbosity: u8) -> Self {
+        let default = match verbosity {
+            0 => LevelFilter::Warn,
+            1 => LevelFilter::Info,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        };
+        Self {
+            default,
+            targets: Vec::new(),
+        }
+    }
+
+    /// Parse a filter like `info,zbus=off,fan_curve_app::fan_monitor=debug`
+    ///
+    /// A bare level sets the default; entries without one keep `default`.
+    pub fn parse(filter: &str, default: LevelFilter) -> Result<Self, String> {
+        let mut levels = Self {
+            default,
+            targets: Vec::new(),
+        };
+
+        for directive in filter.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((target, level)) => {
+                    let target = target.trim();
+                    if target.is_empty() {
+                        return Err(format!("missing target in '{}'", directive));
+                    }
+                    let level = LevelFilter::from_str(level.trim())
+                        .map_err(|_| format!("unknown level in '{}'", directive))?;
+                    levels.targets.push((target.to_string(), level));
+                }
+                None => {
+                    levels.default = LevelFilter::from_str(directive)
+                        .map_err(|_| format!("unknown level '{}'", directive))?;
+                }
+            }
+        }
+
+        Ok(levels)
+    }
+
+    /// Levels from `filter`, falling back to the verbosity default if it doesn't parse
+    /// Returns the parse error alongside so it can be logged once logging is up
+    pub fn resolve(verbosity: u8, filter: Option<&str>) -> (Self, Option<String>) {
+        let fallback = Self::from_verbosity(verbosity);
+        match filter {
+            Some(filter) => match Self::parse(filter, fallback.default) {
+                Ok(levels) => (levels, None),
+                Err(e) => (
+                    fallback,
+                    Some(format!("Ignoring log filter '{}': {}", filter, e)),
+                ),
+            },
+            None => (fallback, None),
+        }
+    }
+}
 
 /// Setup logging with the specified verbosity level
-pub fn setup(verbosity: u8) -> Result<(), fern::InitError> {
-    let level = match verbosity {
-        0 => LevelFilter::Warn,
-        1 => LevelFilter::Info,
-        2 => LevelFilter::Debug,
-        _ => LevelFilter::Trace,
-    };
-
-    Dispatch::new()
+/// A `filter` (or `$FAN_APP_LOG` when it is None) can set levels per target
+pub fn setup(verbosity: u8, filter: Option<&str>) -> Result<(), fern::InitError> {
+    let env_filter = std::env::var(LOG_FILTER_ENV).ok();
+    let (levels, error) = LogLevels::resolve(verbosity, filter.or(env_filter.as_deref()));
+
+    let mut dispatch = Dispatch::new()
         .format(|out, message, record| {
             out.finish(format_args!(
                 "{} [{}] {}: {}",
@@ -22,9 +94,48 @@ pub fn setup(verbosity: u8) -> Result<(), fern::InitError> {
                 message
             ))
         })
-        .level(level)
-        .chain(std::io::stdout())
-        .apply()?;
+        .level(levels.default);
+    for (target, level) in levels.targets {
+        dispatch = dispatch.level_for(target, level);
+    }
+    dispatch.chain(std::io::stdout()).apply()?;
+
+    if let Some(error) = error {
+        log::warn!("{}", error);
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_filter_parsing() {
+        let (levels, error) =
+            LogLevels::resolve(0, Some("info, zbus=off,fan_curve_app::fan_monitor=debug"));
+        assert!(error.is_none());
+        assert_eq!(levels.default, LevelFilter::Info);
+        assert_eq!(
+            levels.targets,
+            vec![
+                ("zbus".to_string(), LevelFilter::Off),
+                ("fan_curve_app::fan_monitor".to_string(), LevelFilter::Debug),
+            ]
+        );
+
+        // Target entries alone keep the verbosity default
+        let (levels, _) = LogLevels::resolve(2, Some("zbus=warn"));
+        assert_eq!(levels.default, LevelFilter::Debug);
+
+        // Invalid filters fall back to the verbosity default
+        for invalid in ["zbus=loud", "=debug", "chatty"] {
+            let (levels, error) = LogLevels::resolve(1, Some(invalid));
+            assert_eq!(levels, LogLevels::from_verbosity(1), "{}", invalid);
+            assert!(error.is_some(), "{}", invalid);
+        }
+
+        assert_eq!(LogLevels::resolve(3, None).0, LogLevels::from_verbosity(3));
+    }
+}
@@ -2,28 +2,32 @@
 
 use clap::Parser;
 use fan_curve_app::{
-    args::Args, client::FanCurveClient, daemon::FanCurveDaemon, iced_gui, logging,
+    args::{Args, Commands, FanCurveCommands},
+    client::FanCurveClient,
+    daemon::FanCurveDaemon,
+    doctor,
+    fan_monitor::SensorOptions,
+    iced_gui, logging,
+    temperature::TempUnit,
+    BUILD_TIME, GIT_DESC, GIT_HASH, VERSION,
 };
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Print version and build metadata for binary identity verification
-    let pkg_version = env!("CARGO_PKG_VERSION");
-    let git_hash = option_env!("GIT_HASH").unwrap_or("unknown");
-    let git_desc = option_env!("GIT_DESC").unwrap_or("unknown");
-    let build_time = option_env!("BUILD_TIME").unwrap_or("unknown");
     eprintln!(
         "fan-curve-app v{} (git {} / {}) built {}",
-        pkg_version, git_hash, git_desc, build_time
+        VERSION, GIT_HASH, GIT_DESC, BUILD_TIME
     );
     // Parse command line arguments
     let args = Args::parse();
 
     // Setup logging
-    logging::setup(args.verbose).map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?;
+    logging::setup(args.verbose, args.log_filter.as_deref())
+        .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?;
 
     // Handle GUI mode
     if args.gui {
-        run_gui()?;
+        run_gui(args.sensor_options(), args.units, args.notifications)?;
         return Ok(());
     }
 
@@ -36,9 +40,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
 async fn async_main(args: Args) -> Result<(), Box<dyn std::error::Error>> {
     // Handle daemon mode
-    if let Some(fan_curve_app::args::Commands::Daemon) = args.command {
-        let daemon =
+    if let Some(fan_curve_app::args::Commands::Daemon {
+        metrics_addr,
+        restore_auto_on_exit,
+    }) = args.command
+    {
+        let mut daemon =
             FanCurveDaemon::new().map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?;
+        daemon.set_metrics_addr(metrics_addr);
+        daemon.set_restore_auto_on_exit(restore_auto_on_exit);
         daemon
             .run()
             .await
@@ -46,10 +56,24 @@ async fn async_main(args: Args) -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
+    // The self-test reports an unreachable DBus as a failed check instead of bailing out
+    if let Some(Commands::FanCurve {
+        command: FanCurveCommands::Doctor,
+    }) = args.command
+    {
+        doctor::run(args.sensor_options())
+            .await
+            .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?;
+        return Ok(());
+    }
+
     // Handle client mode
-    let client = FanCurveClient::new()
+    let mut client = FanCurveClient::new()
         .await
         .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?;
+    client.set_sensor_options(args.sensor_options());
+    client.set_temp_unit(args.units);
+    client.set_notifications(args.notifications);
     client
         .handle_args(args)
         .await
@@ -59,7 +83,11 @@ async fn async_main(args: Args) -> Result<(), Box<dyn std::error::Error>> {
 }
 
 /// Run the GUI application
-fn run_gui() -> Result<(), Box<dyn std::error::Error>> {
-    iced_gui::run_iced_gui()?;
+fn run_gui(
+    sensor_options: SensorOptions,
+    temp_unit: TempUnit,
+    notifications: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    iced_gui::run_iced_gui(sensor_options, temp_unit, notifications)?;
     Ok(())
 }
@@ -0,0 +1,162 @@
+//! Desktop notifications when the CPU runs hot, through the freedesktop Notifications service
+
+use crate::errors::Result;
+use log::debug;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use zbus::Connection;
+
+/// Shortest time between two notifications
+pub const NOTIFICATION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Degrees below the warning temperature the CPU has to drop to count as recovered
+pub const RECOVERY_HYSTERESIS: f32 = 3.0;
+
+const NOTIFICATIONS_SERVICE: &str = "org.freedesktop.Notifications";
+const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+
+/// A change worth telling the user about
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverheatEvent {
+    /// The CPU went above the warning temperature; holds the reading in °C
+    Overheated(f32),
+    /// The CPU cooled down again; holds the reading in °C
+    Recovered(f32),
+}
+
+impl OverheatEvent {
+    pub fn summary(&self) -> &'static str {
+        match self {
+            OverheatEvent::Overheated(_) => "CPU temperature high",
+            OverheatEvent::Recovered(_) => "CPU temperature back to normal",
+        }
+    }
+
+    pub fn body(&self, warn_temp: f32) -> String {
+        match self {
+            OverheatEvent::Overheated(temp) => {
+                format!(
+                    "CPU at {:.1}°C, above the {:.1}°C warning level",
+                    temp, warn_temp
+                )
+            }
+            OverheatEvent::Recovered(temp) => format!("CPU down to {:.1}°C", temp),
+        }
+    }
+}
+
+/// Tracks whether the CPU is above `warn_temp` and decides when to notify
+///
+/// Changes that happen within [`NOTIFICATION_INTERVAL`] of the last notification are held
+/// back; once the interval has passed, only the state at that time is reported.
+#[derive(Debug, Clone)]
+pub struct OverheatAlert {
+    warn_temp: f32,
+    hot: bool,
+    /// Whether the last notification said the CPU was hot
+    notified_hot: bool,
+    last_sent: Option<Instant>,
+}
+
+impl OverheatAlert {
+    pub fn new(warn_temp: f32) -> Self {
+        Self {
+            warn_temp,
+            hot: false,
+            notified_hot: false,
+            last_sent: None,
+        }
+    }
+
+    pub fn warn_temp(&self) -> f32 {
+        self.warn_temp
+    }
+
+    /// Feed a reading taken at `now`; returns the event to notify about, if any
+    pub fn update(&mut self, temperature: f32, now: Instant) -> Option<OverheatEvent> {
+        if temperature > self.warn_temp {
+            self.hot = true;
+        } else if temperature < self.warn_temp - RECOVERY_HYSTERESIS {
+            self.hot = false;
+        }
+
+        if self.hot == self.notified_hot {
+            return None;
+        }
+        if self
+            .last_sent
+            .is_some_and(|last| now.duration_since(last) < NOTIFICATION_INTERVAL)
+        {
+            debug!("Holding back overheat notification, one was sent recently");
+            return None;
+        }
+
+        self.notified_hot = self.hot;
+        self.last_sent = Some(now);
+        Some(if self.hot {
+            OverheatEvent::Overheated(temperature)
+        } else {
+            OverheatEvent::Recovered(temperature)
+        })
+    }
+}
+
+/// Show a desktop notification on the session bus
+pub async fn send_desktop_notification(summary: &str, body: &str) -> Result<()> {
+    let connection = Connection::session().await?;
+    connection
+        .call_method(
+            Some(NOTIFICATIONS_SERVICE),
+            NOTIFICATIONS_PATH,
+            Some(NOTIFICATIONS_SERVICE),
+            "Notify",
+            &(
+                "Fan Curve Control",
+                0u32,
+                "dialog-warning",
+                summary,
+                body,
+                Vec::<&str>::new(),
+                HashMap::<&str, zvariant::Value>::new(),
+                -1i32,
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overheat_alert_throttles() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut alert = OverheatAlert::new(85.0);
+
+        assert_eq!(alert.update(80.0, at(0)), None);
+        assert_eq!(
+            alert.update(86.0, at(1)),
+            Some(OverheatEvent::Overheated(86.0))
+        );
+        assert_eq!(alert.update(90.0, at(2)), None);
+
+        // Within the hysteresis band the CPU still counts as hot
+        assert_eq!(alert.update(83.0, at(70)), None);
+
+        // Recovering soon after the warning is held back until the interval has passed
+        let mut alert = OverheatAlert::new(85.0);
+        assert!(alert.update(86.0, at(0)).is_some());
+        assert_eq!(alert.update(70.0, at(10)), None);
+        assert_eq!(
+            alert.update(70.0, at(61)),
+            Some(OverheatEvent::Recovered(70.0))
+        );
+
+        // Flapping within the interval ends up reporting nothing if it settles back
+        assert_eq!(alert.update(90.0, at(62)), None);
+        assert_eq!(alert.update(70.0, at(63)), None);
+        assert_eq!(alert.update(70.0, at(200)), None);
+    }
+}
@@ -0,0 +1,84 @@
+//! Shared Tokio runtime for synchronous callers such as the GUI
+//!
+//! Threading model: a single multi-threaded runtime is created on first use and
+//! lives for the rest of the process. Synchronous code bridges into async code
+//! through [`block_on`], which runs the future on that runtime. zbus connections
+//! spawn their socket tasks on the runtime they were created on, so sharing one
+//! runtime also keeps D-Bus connections alive between calls.
+
+use crate::errors::{FanCurveError, Result};
+use std::future::Future;
+use std::sync::OnceLock;
+use tokio::runtime::{Builder, Handle, Runtime};
+
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+/// Get a handle to the shared runtime, creating it on first use
+pub fn shared_handle() -> Result<Handle> {
+    if let Some(runtime) = RUNTIME.get() {
+        return Ok(runtime.handle().clone());
+    }
+
+    let runtime = Builder::new_multi_thread()
+        .worker_threads(2)
+        .thread_name("fan-curve-runtime")
+        .enable_all()
+        .build()
+        .map_err(|e| FanCurveError::Io(e).context("Failed to create Tokio runtime"))?;
+
+    // Another thread may have won the race; the spare runtime is simply dropped
+    Ok(RUNTIME.get_or_init(|| runtime).handle().clone())
+}
+
+/// Run a future to completion on `handle` from synchronous code
+///
+/// Blocking is not allowed on a thread that is already driving a runtime, so in
+/// that case the future is run from a short-lived helper thread instead.
+pub fn block_on_handle<F>(handle: &Handle, future: F) -> Result<F::Output>
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    if Handle::try_current().is_err() {
+        return Ok(handle.block_on(future));
+    }
+
+    std::thread::scope(|scope| scope.spawn(|| handle.block_on(future)).join())
+        .map_err(|_| FanCurveError::Unknown("Blocking task panicked".to_string()))
+}
+
+/// Run a future to completion on the shared runtime from synchronous code
+pub fn block_on<F>(future: F) -> Result<F::Output>
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    block_on_handle(&shared_handle()?, future)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn worker_thread_name() -> Option<String> {
+        block_on(async {
+            tokio::spawn(async { std::thread::current().name().map(str::to_string) })
+                .await
+                .unwrap()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_block_on_uses_shared_runtime() {
+        assert_eq!(worker_thread_name().as_deref(), Some("fan-curve-runtime"));
+        assert_eq!(worker_thread_name().as_deref(), Some("fan-curve-runtime"));
+    }
+
+    #[tokio::test]
+    async fn test_block_on_inside_runtime() {
+        // Would panic if the future were blocked on from this worker thread
+        let value = block_on(async { 42 }).unwrap();
+        assert_eq!(value, 42);
+    }
+}
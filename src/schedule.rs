@@ -0,0 +1,132 @@
+//! Time-of-day windows that switch the active fan curve, e.g. a quiet curve at night
+
+use crate::errors::{FanCurveError, Result};
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use zvariant::Type;
+
+/// How often the daemon checks whether a new schedule window has started
+pub const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Format of schedule times in the config file and over DBus
+const TIME_FORMAT: &str = "%H:%M";
+
+/// Use `curve_name` from `start` until `end`, local time
+///
+/// A window whose end is before its start wraps around midnight, e.g. 22:00-07:00.
+/// A window starting and ending at the same time covers the whole day.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleEntry {
+    #[serde(with = "hh_mm")]
+    pub start: NaiveTime,
+    #[serde(with = "hh_mm")]
+    pub end: NaiveTime,
+    pub curve_name: String,
+}
+
+// Sent over DBus as ("HH:MM", "HH:MM", curve name)
+impl Type for ScheduleEntry {
+    fn signature() -> zvariant::Signature<'static> {
+        <(String, String, String)>::signature()
+    }
+}
+
+impl ScheduleEntry {
+    pub fn new(start: NaiveTime, end: NaiveTime, curve_name: impl Into<String>) -> Self {
+        Self {
+            start,
+            end,
+            curve_name: curve_name.into(),
+        }
+    }
+
+    /// The window as `HH:MM-HH:MM`
+    pub fn window(&self) -> String {
+        format!(
+            "{}-{}",
+            self.start.format(TIME_FORMAT),
+            self.end.format(TIME_FORMAT)
+        )
+    }
+
+    /// Whether `time` falls in this window; the start is included and the end is not
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start == self.end || (self.start <= time && time < self.end)
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// Position of the first entry whose window contains `time`
+pub fn active_entry(entries: &[ScheduleEntry], time: NaiveTime) -> Option<usize> {
+    entries.iter().position(|entry| entry.contains(time))
+}
+
+/// Parse a time of day written as `HH:MM`
+pub fn parse_time(text: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(text.trim(), TIME_FORMAT)
+        .map_err(|_| FanCurveError::Config(format!("Invalid time '{}', expected HH:MM", text)))
+}
+
+mod hh_mm {
+    use super::TIME_FORMAT;
+    use chrono::NaiveTime;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(time: &NaiveTime, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&time.format(TIME_FORMAT))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveTime, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        super::parse_time(&text).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> NaiveTime {
+        parse_time(text).unwrap()
+    }
+
+    #[test]
+    fn test_schedule_windows() {
+        let schedule = [
+            ScheduleEntry::new(at("22:00"), at("07:00"), "Quiet"),
+            ScheduleEntry::new(at("09:00"), at("17:30"), "Performance"),
+            // Overlaps the working day; the earlier entry wins
+            ScheduleEntry::new(at("12:00"), at("23:00"), "Standard"),
+        ];
+
+        assert_eq!(active_entry(&schedule, at("23:30")), Some(0));
+        assert_eq!(active_entry(&schedule, at("00:00")), Some(0));
+        assert_eq!(active_entry(&schedule, at("06:59")), Some(0));
+        assert_eq!(active_entry(&schedule, at("07:00")), None);
+        assert_eq!(active_entry(&schedule, at("09:00")), Some(1));
+        assert_eq!(active_entry(&schedule, at("13:00")), Some(1));
+        assert_eq!(active_entry(&schedule, at("17:30")), Some(2));
+        assert_eq!(active_entry(&schedule, at("22:00")), Some(0));
+
+        let all_day = ScheduleEntry::new(at("08:00"), at("08:00"), "Quiet");
+        assert!(all_day.contains(at("07:59")));
+        assert!(all_day.contains(at("08:00")));
+
+        assert!(parse_time("25:00").is_err());
+        assert!(parse_time("7pm").is_err());
+
+        let json = serde_json::to_string(&schedule[0]).unwrap();
+        assert_eq!(
+            json,
+            r#"{"start":"22:00","end":"07:00","curve_name":"Quiet"}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<ScheduleEntry>(&json).unwrap(),
+            schedule[0]
+        );
+    }
+}
@@ -1,71 +1,39 @@
 //! System76 Power DBus client for fan control integration
 
-use crate::errors::Result;
+use crate::errors::{FanCurveError, Result};
+use crate::fan::FanCurveDto;
 use log::{debug, info, warn};
-use zbus::Connection;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::time::{sleep, Duration};
+use zbus::{Connection, Message};
+use zvariant::Type;
+
+const POWER_DAEMON_SERVICE: &str = "com.system76.PowerDaemon";
+const POWER_DAEMON_PATH: &str = "/com/system76/PowerDaemon";
+const FAN_PATH: &str = "/com/system76/PowerDaemon/Fan";
+const FAN_INTERFACE: &str = "com.system76.PowerDaemon.Fan";
+
+/// Reconnect attempts made before a failed call is given up
+const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+/// Delay before the first reconnect attempt, doubled on each retry
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+/// Upper bound for the reconnect delay
+const MAX_BACKOFF: Duration = Duration::from_secs(2);
 
 /// System76 Power DBus client
+/// Clones share the connection, so a reconnect made through one clone is seen by all
 #[derive(Clone)]
 pub struct System76PowerClient {
-    connection: Connection,
+    connection: Arc<Mutex<Connection>>,
 }
 
 impl System76PowerClient {
     /// Create a new System76 Power client (synchronous)
+    /// The connection lives on the shared runtime, see [`crate::runtime`]
     pub fn new_sync() -> Result<Self> {
         log::debug!("System76PowerClient::new_sync() called");
-        
-        // Try to use existing runtime first
-        if let Ok(handle) = tokio::runtime::Handle::try_current() {
-            log::debug!("Found existing Tokio runtime, using it");
-            return handle.block_on(async {
-                log::debug!("About to call Connection::system()");
-                let connection = Connection::system()
-                    .await
-                    .map_err(crate::errors::FanCurveError::DBus)?;
-
-                log::debug!("Connection::system() succeeded");
-                info!("Connected to System76 Power DBus service");
-                Ok(Self { connection })
-            });
-        }
-        
-        // No existing runtime, create one in a separate thread to avoid GUI conflicts
-        log::debug!("No existing Tokio runtime found, creating in separate thread");
-        
-        let (tx, rx) = std::sync::mpsc::channel();
-        
-        std::thread::spawn(move || {
-            let rt = match tokio::runtime::Runtime::new() {
-                Ok(rt) => {
-                    log::debug!("Successfully created Tokio runtime in separate thread");
-                    rt
-                }
-                Err(e) => {
-                    log::error!("Failed to create Tokio runtime in separate thread: {}", e);
-                    let _ = tx.send(Err(crate::errors::FanCurveError::Unknown(format!("Failed to create Tokio runtime: {}", e))));
-                    return;
-                }
-            };
-            
-            let result = rt.block_on(async {
-                log::debug!("About to call Connection::system()");
-                let connection = Connection::system()
-                    .await
-                    .map_err(crate::errors::FanCurveError::DBus)?;
-
-                log::debug!("Connection::system() succeeded");
-                info!("Connected to System76 Power DBus service");
-                Ok(Self { connection })
-            });
-            
-            let _ = tx.send(result);
-        });
-        
-        let result = rx.recv().map_err(|_| crate::errors::FanCurveError::Unknown("Failed to receive result from D-Bus initialization thread".to_string()))?;
-        
-        log::debug!("System76PowerClient::new_sync() completed with result: {:?}", result.is_ok());
-        result
+        crate::runtime::block_on(Self::new())?
     }
 
     /// Create a new System76 Power client
@@ -75,20 +43,70 @@ impl System76PowerClient {
             .map_err(crate::errors::FanCurveError::DBus)?;
 
         info!("Connected to System76 Power DBus service");
-        Ok(Self { connection })
+        Ok(Self::from_connection(connection))
     }
 
-    /// Check if System76 Power service is available
-    pub async fn is_available(&self) -> bool {
-        // Check if the service is available by trying to get a proxy
-        match zbus::Proxy::new(
-            &self.connection,
-            "com.system76.PowerDaemon",
-            "/com/system76/PowerDaemon",
-            "com.system76.PowerDaemon",
+    fn from_connection(connection: Connection) -> Self {
+        Self {
+            connection: Arc::new(Mutex::new(connection)),
+        }
+    }
+
+    /// Get the current connection
+    fn connection(&self) -> Connection {
+        self.connection.lock().unwrap().clone()
+    }
+
+    /// Replace the connection with a fresh one to the system bus
+    pub async fn reconnect(&self) -> Result<()> {
+        let connection = Connection::system().await.map_err(FanCurveError::DBus)?;
+        *self.connection.lock().unwrap() = connection;
+        info!("Reconnected to System76 Power DBus service");
+        Ok(())
+    }
+
+    /// Call a System76 Power method, reconnecting with exponential backoff on transport errors
+    async fn call_method<B>(
+        &self,
+        path: &str,
+        interface: &str,
+        method: &str,
+        body: &B,
+    ) -> Result<Arc<Message>>
+    where
+        B: serde::Serialize + zvariant::DynamicType,
+    {
+        retry_with_backoff(
+            || async move {
+                let connection = self.connection();
+                let proxy =
+                    zbus::Proxy::new(&connection, POWER_DAEMON_SERVICE, path, interface).await?;
+                proxy.call_method(method, body).await
+            },
+            || self.reconnect(),
         )
         .await
-        {
+    }
+
+    /// Call a method on the System76 Power fan interface
+    async fn call_fan_method<B>(&self, method: &str, body: &B) -> Result<Arc<Message>>
+    where
+        B: serde::Serialize + zvariant::DynamicType,
+    {
+        self.call_method(FAN_PATH, FAN_INTERFACE, method, body).await
+    }
+
+    /// Check if System76 Power service is available
+    /// A dead connection is replaced once before giving up
+    pub async fn is_available(&self) -> bool {
+        let mut result = self.ping().await;
+        if let Err(ref e) = result {
+            if is_transport_error(e) && self.reconnect().await.is_ok() {
+                result = self.ping().await;
+            }
+        }
+
+        match result {
             Ok(_) => {
                 debug!("System76 Power service is available");
                 true
@@ -100,22 +118,24 @@ impl System76PowerClient {
         }
     }
 
-    /// Get current temperature from System76 Power daemon
-    /// Returns temperature in thousandths of Celsius (e.g., 35000 = 35.0°C)
-    pub async fn get_current_temperature_from_daemon(&self) -> Result<u32> {
+    /// Ping the System76 Power service without retrying
+    async fn ping(&self) -> zbus::Result<()> {
+        let connection = self.connection();
         let proxy = zbus::Proxy::new(
-            &self.connection,
-            "com.system76.PowerDaemon",
-            "/com/system76/PowerDaemon/Fan",
-            "com.system76.PowerDaemon.Fan",
+            &connection,
+            POWER_DAEMON_SERVICE,
+            POWER_DAEMON_PATH,
+            "org.freedesktop.DBus.Peer",
         )
-        .await
-        .map_err(crate::errors::FanCurveError::DBus)?;
+        .await?;
+        proxy.call_method("Ping", &()).await?;
+        Ok(())
+    }
 
-        let response = proxy
-            .call_method("GetCurrentTemperature", &())
-            .await
-            .map_err(crate::errors::FanCurveError::DBus)?;
+    /// Get current temperature from System76 Power daemon
+    /// Returns temperature in thousandths of Celsius (e.g., 35000 = 35.0°C)
+    pub async fn get_current_temperature_from_daemon(&self) -> Result<u32> {
+        let response = self.call_fan_method("GetCurrentTemperature", &()).await?;
 
         let temp: u32 = response.body::<u32>()?;
         Ok(temp)
@@ -124,21 +144,7 @@ impl System76PowerClient {
     /// Get current fan duty from System76 Power daemon
     /// Returns duty as PWM value (0-255)
     pub async fn get_current_duty_from_daemon(&self) -> Result<u8> {
-        let proxy = zbus::Proxy::new(
-            &self.connection,
-            "com.system76.PowerDaemon",
-            "/com/system76/PowerDaemon/Fan",
-            "com.system76.PowerDaemon.Fan",
-        )
-        .await
-        .map_err(crate::errors::FanCurveError::DBus)?;
-
-
-
-        let response = proxy
-            .call_method("GetCurrentDuty", &())
-            .await
-            .map_err(crate::errors::FanCurveError::DBus)?;
+        let response = self.call_fan_method("GetCurrentDuty", &()).await?;
 
         let duty: u8 = response.body::<u8>()?;
         Ok(duty)
@@ -147,44 +153,30 @@ impl System76PowerClient {
     /// Get fan speeds from System76 Power daemon
     /// Returns fan speeds in RPM as Vec<u32>
     pub async fn get_fan_speeds_from_daemon(&self) -> Result<Vec<u32>> {
-        let proxy = zbus::Proxy::new(
-            &self.connection,
-            "com.system76.PowerDaemon",
-            "/com/system76/PowerDaemon/Fan",
-            "com.system76.PowerDaemon.Fan",
-        )
-        .await
-        .map_err(crate::errors::FanCurveError::DBus)?;
-
-       
-
-        let response = proxy
-            .call_method("GetFanSpeeds", &())
-            .await
-            .map_err(crate::errors::FanCurveError::DBus)?;
+        let response = self.call_fan_method("GetFanSpeeds", &()).await?;
 
         let speeds: Vec<u32> = response.body::<Vec<u32>>()?;
         Ok(speeds)
     }
 
+    /// Get the active fan curve, with its name, from System76 Power daemon
+    pub async fn get_fan_curve(&self) -> Result<FanCurveDto> {
+        let response = self.call_fan_method("GetFanCurve", &()).await?;
+        decode_fan_curve(&response)
+    }
+
+    /// Send a fan curve, with its name, to System76 Power daemon
+    pub async fn set_fan_curve(&self, curve: &FanCurveDto) -> Result<()> {
+        self.call_fan_method("SetFanCurve", &(curve,)).await?;
+
+        Ok(())
+    }
+
     /// Get fan curve from System76 Power daemon
     /// Returns fan curve points as Vec<(i16, u16)> (temp, duty pairs)
+    #[deprecated(note = "use `get_fan_curve`, which keeps the curve name")]
     pub async fn get_fan_curve_from_daemon(&self) -> Result<Vec<(i16, u16)>> {
-        let proxy = zbus::Proxy::new(
-            &self.connection,
-            "com.system76.PowerDaemon",
-            "/com/system76/PowerDaemon/Fan",
-            "com.system76.PowerDaemon.Fan",
-        )
-        .await
-        .map_err(crate::errors::FanCurveError::DBus)?;
-
- 
-        
-        let response = proxy
-            .call_method("GetFanCurve", &())
-            .await
-            .map_err(crate::errors::FanCurveError::DBus)?;
+        let response = self.call_fan_method("GetFanCurve", &()).await?;
         
         let curve_points: Vec<(i16, u16)> = response.body::<Vec<(i16, u16)>>()?;
         Ok(curve_points)
@@ -192,20 +184,9 @@ impl System76PowerClient {
 
     /// Set fan curve to System76 Power daemon
     /// Takes fan curve points as Vec<(i16, u16)> (temp, duty pairs)
+    #[deprecated(note = "use `set_fan_curve`, which keeps the curve name")]
     pub async fn set_fan_curve_to_daemon(&self, points: Vec<(i16, u16)>) -> Result<()> {
-        let proxy = zbus::Proxy::new(
-            &self.connection,
-            "com.system76.PowerDaemon",
-            "/com/system76/PowerDaemon/Fan",
-            "com.system76.PowerDaemon.Fan",
-        )
-        .await
-        .map_err(crate::errors::FanCurveError::DBus)?;
-
-        proxy
-            .call_method("SetFanCurve", &(points,))
-            .await
-            .map_err(crate::errors::FanCurveError::DBus)?;
+        self.call_fan_method("SetFanCurve", &(points,)).await?;
 
         Ok(())
     }
@@ -226,19 +207,7 @@ impl System76PowerClient {
         );
 
         // Use the new D-Bus method to apply the fan curve
-        let proxy = zbus::Proxy::new(
-            &self.connection,
-            "com.system76.PowerDaemon",
-            "/com/system76/PowerDaemon/Fan",
-            "com.system76.PowerDaemon.Fan",
-        )
-        .await
-        .map_err(crate::errors::FanCurveError::DBus)?;
-
-        proxy
-            .call_method("ApplyFanCurve", &())
-            .await
-            .map_err(crate::errors::FanCurveError::DBus)?;
+        self.call_fan_method("ApplyFanCurve", &()).await?;
 
         info!("Fan curve applied successfully via daemon");
         Ok(())
@@ -246,33 +215,10 @@ impl System76PowerClient {
 
     /// Set power profile via System76 Power
     async fn set_power_profile(&self, profile: &str) -> Result<()> {
-        let proxy = zbus::Proxy::new(
-            &self.connection,
-            "com.system76.PowerDaemon",
-            "/com/system76/PowerDaemon",
-            "com.system76.PowerDaemon",
-        )
-        .await
-        .map_err(crate::errors::FanCurveError::DBus)?;
-
         match profile {
-            "Battery" => {
-                proxy
-                    .call_method("Battery", &())
-                    .await
-                    .map_err(crate::errors::FanCurveError::DBus)?;
-            }
-            "Balanced" => {
-                proxy
-                    .call_method("Balanced", &())
-                    .await
-                    .map_err(crate::errors::FanCurveError::DBus)?;
-            }
-            "Performance" => {
-                proxy
-                    .call_method("Performance", &())
-                    .await
-                    .map_err(crate::errors::FanCurveError::DBus)?;
+            "Battery" | "Balanced" | "Performance" => {
+                self.call_method(POWER_DAEMON_PATH, "com.system76.PowerDaemon", profile, &())
+                    .await?;
             }
             _ => {
                 return Err(crate::errors::FanCurveError::Config(format!(
@@ -306,16 +252,7 @@ impl System76PowerClient {
     pub async fn set_fan_duty(&self, duty: u8) -> Result<()> {
         log::debug!("System76PowerClient::set_fan_duty called with duty={}", duty);
         
-        let proxy = zbus::Proxy::new(
-            &self.connection,
-            "com.system76.PowerDaemon",
-            "/com/system76/PowerDaemon/Fan",
-            "com.system76.PowerDaemon.Fan",
-        ).await.map_err(crate::errors::FanCurveError::DBus)?;
-
-        proxy.call_method("SetDuty", &(duty,))
-            .await
-            .map_err(crate::errors::FanCurveError::DBus)?;
+        self.call_fan_method("SetDuty", &(duty,)).await?;
 
         log::debug!("System76PowerClient::set_fan_duty completed successfully");
         Ok(())
@@ -329,3 +266,191 @@ impl Default for System76PowerClient {
         panic!("System76PowerClient::default() should not be called. Use System76PowerClient::new() instead.");
     }
 }
+
+/// Check whether a D-Bus error means the connection went away
+/// A service that isn't running (`ServiceUnknown`, `NameHasNoOwner`) won't be back within the
+/// retries, so those fail right away
+fn is_transport_error(error: &zbus::Error) -> bool {
+    match error {
+        zbus::Error::InputOutput(_) => true,
+        zbus::Error::MethodError(name, _, _) => matches!(
+            name.as_str(),
+            "org.freedesktop.DBus.Error.NoReply" | "org.freedesktop.DBus.Error.Disconnected"
+        ),
+        _ => false,
+    }
+}
+
+/// Decode a `GetFanCurve` reply
+/// Daemons that still reply with bare `a(nq)` points get the curve name "Custom"
+fn decode_fan_curve(message: &Message) -> Result<FanCurveDto> {
+    if message.body_signature()? == <Vec<(i16, u16)>>::signature() {
+        let points: Vec<(i16, u16)> = message.body()?;
+        return Ok(FanCurveDto {
+            name: "Custom".to_string(),
+            points,
+        });
+    }
+
+    Ok(message.body::<FanCurveDto>()?)
+}
+
+/// Delay before the given (zero-based) reconnect attempt
+fn backoff_delay(attempt: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(1 << attempt.min(16))
+        .min(MAX_BACKOFF)
+}
+
+/// Run `operation`, reconnecting and retrying with exponential backoff on transport errors
+async fn retry_with_backoff<T, Op, OpFut, Re, ReFut>(
+    mut operation: Op,
+    mut reconnect: Re,
+) -> Result<T>
+where
+    Op: FnMut() -> OpFut,
+    OpFut: Future<Output = zbus::Result<T>>,
+    Re: FnMut() -> ReFut,
+    ReFut: Future<Output = Result<()>>,
+{
+    let mut attempt = 0;
+    loop {
+        match operation().await {
+            Ok(value) => return Ok(value),
+            Err(e) if is_transport_error(&e) && attempt < MAX_RECONNECT_ATTEMPTS => {
+                let delay = backoff_delay(attempt);
+                attempt += 1;
+                warn!(
+                    "D-Bus call failed ({}), reconnecting in {:?} (attempt {}/{})",
+                    e, delay, attempt, MAX_RECONNECT_ATTEMPTS
+                );
+                sleep(delay).await;
+                if let Err(e) = reconnect().await {
+                    warn!("Reconnect attempt {} failed: {}", attempt, e);
+                }
+            }
+            Err(e) => return Err(FanCurveError::DBus(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn dropped_connection() -> zbus::Error {
+        zbus::Error::InputOutput(Arc::new(std::io::Error::new(
+            std::io::ErrorKind::BrokenPipe,
+            "connection closed",
+        )))
+    }
+
+    fn reply(body: &(impl serde::Serialize + zvariant::DynamicType)) -> Message {
+        Message::method(
+            None::<&str>,
+            None::<&str>,
+            FAN_PATH,
+            Some(FAN_INTERFACE),
+            "GetFanCurve",
+            body,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_fan_curve_dto_round_trip() {
+        let curve = crate::fan::FanCurve::standard();
+        let dto = FanCurveDto::from(&curve);
+        assert_eq!(FanCurveDto::signature().as_str(), "(sa(nq))");
+        assert_eq!(dto.name, "Standard");
+
+        let decoded = decode_fan_curve(&reply(&(dto.clone(),))).unwrap();
+        assert_eq!(decode_fan_curve(&reply(&dto)).unwrap(), decoded);
+        assert_eq!(decoded, dto);
+        assert_eq!(crate::fan::FanCurve::from(decoded), curve);
+
+        // Sub-percent duty survives the trip
+        let precise = FanCurveDto {
+            name: "Precise".to_string(),
+            points: vec![(40, 2549), (90, 10000)],
+        };
+        assert_eq!(decode_fan_curve(&reply(&precise)).unwrap(), precise);
+
+        // Legacy replies carry only the points
+        let legacy = decode_fan_curve(&reply(&(precise.points.clone(),))).unwrap();
+        assert_eq!(legacy.name, "Custom");
+        assert_eq!(legacy.points, precise.points);
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        assert_eq!(backoff_delay(0), Duration::from_millis(100));
+        assert_eq!(backoff_delay(1), Duration::from_millis(200));
+        assert_eq!(backoff_delay(2), Duration::from_millis(400));
+        assert_eq!(backoff_delay(5), Duration::from_secs(2));
+        assert_eq!(backoff_delay(40), Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_dropped_connection() {
+        let calls = AtomicU32::new(0);
+        let reconnects = AtomicU32::new(0);
+
+        let result = retry_with_backoff(
+            || async {
+                if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                    Err(dropped_connection())
+                } else {
+                    Ok(42u32)
+                }
+            },
+            || async {
+                reconnects.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            },
+        )
+        .await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(reconnects.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_other_errors_are_not_retried() {
+        let calls = AtomicU32::new(0);
+        let reconnects = AtomicU32::new(0);
+
+        let result: Result<u32> = retry_with_backoff(
+            || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(zbus::Error::Failure("bad arguments".to_string()))
+            },
+            || async {
+                reconnects.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            },
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(reconnects.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_missing_service_is_not_retried() {
+        let method_error = |name: &str| {
+            zbus::Error::MethodError(
+                zbus::names::OwnedErrorName::try_from(name).unwrap(),
+                None,
+                Arc::new(reply(&())),
+            )
+        };
+        assert!(!is_transport_error(&method_error("org.freedesktop.DBus.Error.ServiceUnknown")));
+        assert!(!is_transport_error(&method_error("org.freedesktop.DBus.Error.NameHasNoOwner")));
+        assert!(is_transport_error(&method_error("org.freedesktop.DBus.Error.NoReply")));
+        assert!(is_transport_error(&dropped_connection()));
+    }
+}
@@ -0,0 +1,149 @@
+//! Temperature sources feeding the fan curve
+//!
+//! [`FanMonitor`](crate::fan_monitor::FanMonitor) reads temperatures through
+//! [`TemperatureSource`], so the duty logic can be driven by sysfs, the
+//! system76-power daemon or, in tests, a scripted `MockTemperatureSource`.
+
+use crate::cpu_temp::CpuTempDetector;
+use crate::errors::Result;
+use crate::system76_power_client::System76PowerClient;
+use async_trait::async_trait;
+#[cfg(test)]
+use std::collections::VecDeque;
+#[cfg(test)]
+use std::sync::Mutex;
+
+/// Unit temperatures are shown in; curves and all internal math stay in Celsius
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum TempUnit {
+    #[default]
+    #[value(name = "c", alias = "celsius")]
+    Celsius,
+    #[value(name = "f", alias = "fahrenheit")]
+    Fahrenheit,
+}
+
+impl TempUnit {
+    /// Convert a Celsius temperature to this unit
+    pub fn from_celsius(self, celsius: f32) -> f32 {
+        match self {
+            TempUnit::Celsius => celsius,
+            TempUnit::Fahrenheit => celsius * 9.0 / 5.0 + 32.0,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            TempUnit::Celsius => "°C",
+            TempUnit::Fahrenheit => "°F",
+        }
+    }
+
+    /// Format a Celsius temperature in this unit with `decimals` digits, e.g. "140.0°F"
+    pub fn format(self, celsius: f32, decimals: usize) -> String {
+        format!("{:.*}{}", decimals, self.from_celsius(celsius), self.symbol())
+    }
+
+    /// The other unit, for a display toggle
+    pub fn toggled(self) -> Self {
+        match self {
+            TempUnit::Celsius => TempUnit::Fahrenheit,
+            TempUnit::Fahrenheit => TempUnit::Celsius,
+        }
+    }
+}
+
+/// Something that can report a temperature in degrees Celsius
+#[async_trait]
+pub trait TemperatureSource: Send + Sync {
+    /// Read the current temperature in °C
+    async fn read_celsius(&self) -> Result<f32>;
+}
+
+#[async_trait]
+impl TemperatureSource for CpuTempDetector {
+    async fn read_celsius(&self) -> Result<f32> {
+        self.read_temperature()
+    }
+}
+
+#[async_trait]
+impl TemperatureSource for System76PowerClient {
+    async fn read_celsius(&self) -> Result<f32> {
+        let temp_thousandths = self.get_current_temperature_from_daemon().await?;
+        Ok(temp_thousandths as f32 / 1000.0)
+    }
+}
+
+/// Replays a fixed sequence of temperatures, repeating the last one once exhausted
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct MockTemperatureSource {
+    readings: Mutex<VecDeque<f32>>,
+    last: Mutex<Option<f32>>,
+}
+
+#[cfg(test)]
+impl MockTemperatureSource {
+    /// Source returning `readings` in order
+    pub fn new(readings: impl IntoIterator<Item = f32>) -> Self {
+        Self {
+            readings: Mutex::new(readings.into_iter().collect()),
+            last: Mutex::new(None),
+        }
+    }
+
+    /// Queue more readings after the current ones
+    pub fn push(&self, celsius: f32) {
+        self.readings.lock().unwrap().push_back(celsius);
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl TemperatureSource for MockTemperatureSource {
+    async fn read_celsius(&self) -> Result<f32> {
+        let mut last = self.last.lock().unwrap();
+        if let Some(reading) = self.readings.lock().unwrap().pop_front() {
+            *last = Some(reading);
+        }
+        last.ok_or_else(|| {
+            crate::errors::FanCurveError::Config("No mock temperature readings".to_string())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_source_replays_readings() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let read = |source: &MockTemperatureSource| runtime.block_on(source.read_celsius());
+
+        assert!(read(&MockTemperatureSource::default()).is_err());
+
+        let source = MockTemperatureSource::new([40.0, 55.5]);
+        assert_eq!(read(&source).unwrap(), 40.0);
+        assert_eq!(read(&source).unwrap(), 55.5);
+        assert_eq!(read(&source).unwrap(), 55.5);
+
+        source.push(70.0);
+        assert_eq!(read(&source).unwrap(), 70.0);
+    }
+
+    #[test]
+    fn test_temp_unit_conversion() {
+        let f = TempUnit::Fahrenheit;
+        assert_eq!(f.from_celsius(0.0), 32.0);
+        assert_eq!(f.from_celsius(100.0), 212.0);
+        assert_eq!(f.from_celsius(-40.0), -40.0);
+        assert_eq!(f.from_celsius(-10.0), 14.0);
+        assert_eq!(f.format(37.0, 1), "98.6°F");
+        assert_eq!(f.format(-17.5, 0), "0°F");
+
+        assert_eq!(TempUnit::default().format(-5.25, 1), "-5.2°C");
+        assert_eq!(TempUnit::Celsius.toggled(), f);
+    }
+}
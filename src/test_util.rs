@@ -0,0 +1,104 @@
+//! Shared helpers for unit tests
+
+use crate::fan::FanCurveConfig;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+/// Serialize tests that read or modify process environment variables
+pub fn env_lock() -> MutexGuard<'static, ()> {
+    ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Sets environment variables until dropped, holding the env lock meanwhile
+pub struct EnvOverride {
+    saved: Vec<(&'static str, Option<OsString>)>,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl EnvOverride {
+    pub fn new(name: &'static str, value: impl AsRef<OsStr>) -> Self {
+        Self {
+            saved: Vec::new(),
+            _lock: env_lock(),
+        }
+        .with(name, value)
+    }
+
+    /// Also set `name`, under the same lock
+    pub fn with(mut self, name: &'static str, value: impl AsRef<OsStr>) -> Self {
+        self.saved.push((name, std::env::var_os(name)));
+        std::env::set_var(name, value);
+        self
+    }
+}
+
+impl Drop for EnvOverride {
+    fn drop(&mut self) {
+        for (name, saved) in self.saved.drain(..).rev() {
+            match saved {
+                Some(value) => std::env::set_var(name, value),
+                None => std::env::remove_var(name),
+            }
+        }
+    }
+}
+
+/// Points `$FAN_APP_CONFIG` at a test file until dropped, holding the env lock meanwhile
+pub struct ConfigPathOverride {
+    _env: EnvOverride,
+}
+
+impl ConfigPathOverride {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            _env: EnvOverride::new(FanCurveConfig::CONFIG_PATH_ENV, path),
+        }
+    }
+}
+
+/// Temporary directory that is removed when dropped
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Create a new, empty temporary directory
+    pub fn new(prefix: &str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "fan-curve-app-{}-{}-{}",
+            prefix,
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::SeqCst)
+        ));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+
+    /// Get the directory path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write a file relative to the directory, creating parent directories as needed
+    pub fn write(&self, relative: &str, contents: &str) -> PathBuf {
+        let path = self.path.join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(&path, contents).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
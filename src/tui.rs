@@ -0,0 +1,288 @@
+//! Terminal UI for headless machines, built on [`FanMonitor`]
+//!
+//! Shows the live temperature, duty, fan speeds and a temperature sparkline, and switches
+//! curves with the arrow keys. Enabled with the `tui` cargo feature.
+
+use crate::duty::Duty;
+use crate::errors::{FanCurveError, Result};
+use crate::fan::FanCurve;
+use crate::fan_monitor::{FanDataPoint, FanMonitor};
+use crate::temperature::TempUnit;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use log::debug;
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, List, Paragraph, Sparkline, Tabs};
+use ratatui::{Frame, Terminal};
+use std::collections::VecDeque;
+use std::io::{self, Stdout};
+use std::time::Instant;
+
+/// Everything one frame shows
+pub struct TuiView<'a> {
+    pub curve_names: &'a [String],
+    pub selected: usize,
+    /// Samples to show, oldest first; the last one is the live data
+    pub history: &'a VecDeque<FanDataPoint>,
+    pub temp_unit: TempUnit,
+    /// Error from the last sample, if it failed
+    pub status: Option<&'a str>,
+}
+
+/// Draw one frame
+pub fn render(frame: &mut Frame, view: &TuiView<'_>) {
+    let areas = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(4),
+            Constraint::Min(3),
+            Constraint::Length(6),
+            Constraint::Length(1),
+        ])
+        .split(frame.size());
+
+    let tabs = Tabs::new(view.curve_names.iter().map(String::as_str))
+        .select(view.selected)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Fan curve (←/→ to switch, q to quit)"),
+        );
+    frame.render_widget(tabs, areas[0]);
+
+    let latest = view.history.back();
+    let summary = match latest {
+        Some(data) => vec![
+            Line::from(format!(
+                "CPU: {} ({} cores), {:.0}% busy",
+                data.cpu_model, data.cpu_core_count, data.cpu_usage
+            )),
+            Line::from(format!(
+                "Temperature: {}   Duty: {}",
+                view.temp_unit.format(data.temperature, 1),
+                Duty::from_ten_thousandths(data.fan_duty)
+            )),
+        ],
+        None => vec![Line::from("Waiting for the first sample...")],
+    };
+    frame.render_widget(
+        Paragraph::new(summary).block(Block::default().borders(Borders::ALL).title("Live")),
+        areas[1],
+    );
+
+    let fans: Vec<String> = latest
+        .map(|data| {
+            data.cpu_fan_speeds
+                .iter()
+                .chain(&data.intake_fan_speeds)
+                .chain(&data.gpu_fan_speeds)
+                .map(|(number, rpm, label)| format!("Fan {} {}: {} RPM", number, label, rpm))
+                .collect()
+        })
+        .unwrap_or_default();
+    frame.render_widget(
+        List::new(fans).block(Block::default().borders(Borders::ALL).title("Fans")),
+        areas[2],
+    );
+
+    let temperatures: Vec<u64> = view
+        .history
+        .iter()
+        .map(|data| data.temperature.max(0.0).round() as u64)
+        .collect();
+    frame.render_widget(
+        Sparkline::default().data(&temperatures).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Temperature history"),
+        ),
+        areas[3],
+    );
+
+    if let Some(status) = view.status {
+        frame.render_widget(Paragraph::new(status), areas[4]);
+    }
+}
+
+/// Puts the terminal back to normal however the UI exits
+struct TerminalGuard(Terminal<CrosstermBackend<Stdout>>);
+
+impl TerminalGuard {
+    fn new() -> io::Result<Self> {
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        Ok(Self(Terminal::new(CrosstermBackend::new(io::stdout()))?))
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(self.0.backend_mut(), LeaveAlternateScreen);
+        let _ = self.0.show_cursor();
+    }
+}
+
+/// Read the fans, record the sample and apply the monitor's curve
+async fn sample(monitor: &mut FanMonitor) -> Result<()> {
+    let data = match monitor.get_current_fan_data().await {
+        Ok(data) => data,
+        Err(e) => {
+            debug!("Reading fan data via D-Bus failed ({}), reading sysfs", e);
+            monitor.get_current_fan_data_direct()?
+        }
+    };
+    let temperature = data.temperature;
+    monitor.record_sample(data);
+    monitor.apply_fan_curve(temperature).await
+}
+
+/// Run the UI until q or Esc is pressed, applying the selected curve every poll interval
+/// `monitor` should already be initialized
+pub async fn run(
+    mut monitor: FanMonitor,
+    curves: Vec<FanCurve>,
+    selected: usize,
+    temp_unit: TempUnit,
+) -> Result<()> {
+    if curves.is_empty() {
+        return Err(FanCurveError::Config(
+            "No fan curves configured".to_string(),
+        ));
+    }
+    let names: Vec<String> = curves
+        .iter()
+        .map(|curve| curve.name().to_string())
+        .collect();
+    let mut selected = selected.min(curves.len() - 1);
+    monitor.set_fan_curve(curves[selected].clone());
+
+    let mut terminal = TerminalGuard::new()?;
+    let mut next_sample = Instant::now();
+    let mut status = None;
+    loop {
+        if Instant::now() >= next_sample {
+            next_sample = Instant::now() + monitor.poll_interval();
+            status = sample(&mut monitor).await.err().map(|e| e.to_string());
+        }
+
+        terminal.0.draw(|frame| {
+            render(
+                frame,
+                &TuiView {
+                    curve_names: &names,
+                    selected,
+                    history: monitor.history(),
+                    temp_unit,
+                    status: status.as_deref(),
+                },
+            )
+        })?;
+
+        let timeout = next_sample.saturating_duration_since(Instant::now());
+        let event = tokio::task::block_in_place(|| -> io::Result<Option<Event>> {
+            if event::poll(timeout)? {
+                event::read().map(Some)
+            } else {
+                Ok(None)
+            }
+        })?;
+        let Some(Event::Key(key)) = event else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        let previous = selected;
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => break,
+            KeyCode::Left | KeyCode::Up => selected = (selected + curves.len() - 1) % curves.len(),
+            KeyCode::Right | KeyCode::Down => selected = (selected + 1) % curves.len(),
+            _ => {}
+        }
+        if selected != previous {
+            monitor.set_fan_curve(curves[selected].clone());
+            // Apply the new curve right away
+            next_sample = Instant::now();
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+
+    #[test]
+    fn test_render_sample() {
+        let mut history = VecDeque::new();
+        for temperature in [48.0, 52.5] {
+            history.push_back(FanDataPoint {
+                timestamp: chrono::Local::now(),
+                temperature,
+                cpu_fan_speeds: vec![(1, 1450, "CPU Fan".to_string())],
+                intake_fan_speeds: vec![(2, 900, "Intake Fan".to_string())],
+                gpu_fan_speeds: Vec::new(),
+                fan_duty: 4500,
+                cpu_usage: 12.0,
+                cpu_model: "Test CPU".to_string(),
+                cpu_core_count: 8,
+            });
+        }
+        let names = ["Standard".to_string(), "Quiet".to_string()];
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 20)).unwrap();
+        terminal
+            .draw(|frame| {
+                render(
+                    frame,
+                    &TuiView {
+                        curve_names: &names,
+                        selected: 1,
+                        history: &history,
+                        temp_unit: TempUnit::Celsius,
+                        status: Some("Sensor unavailable"),
+                    },
+                )
+            })
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let text: String = (0..buffer.area.height)
+            .map(|y| {
+                let row: String = (0..buffer.area.width)
+                    .map(|x| buffer.get(x, y).symbol())
+                    .collect();
+                row + "\n"
+            })
+            .collect();
+        for expected in [
+            "Quiet",
+            "Test CPU (8 cores)",
+            "52.5°C",
+            "45.0%",
+            "Fan 1 CPU Fan: 1450 RPM",
+            "Fan 2 Intake Fan: 900 RPM",
+            "Temperature history",
+            "Sensor unavailable",
+        ] {
+            assert!(
+                text.contains(expected),
+                "{} missing from\n{}",
+                expected,
+                text
+            );
+        }
+    }
+}
@@ -3,7 +3,7 @@ use fan_curve_app::fan::FanCurve;
 use fan_curve_app::logging;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    logging::setup(1); // INFO level
+    logging::setup(1, None); // INFO level
     
     println!("=== Testing Fan Curve Application ===");
     